@@ -0,0 +1,240 @@
+//! Tracking of queries currently in flight per channel, and of agent idleness
+
+use crate::slack::ChannelId;
+use dashmap::DashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// A query in flight and the signal used to cancel it
+#[derive(Debug)]
+struct ActiveQuery {
+    started_at: Instant,
+    cancel: Arc<Notify>,
+}
+
+/// Tracks which channels have a query being processed by their agent
+#[derive(Debug, Clone, Default)]
+pub struct ActiveQueries {
+    started: Arc<DashMap<ChannelId, ActiveQuery>>,
+}
+
+impl ActiveQueries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark a query as started; it is marked finished when the guard is dropped
+    pub fn start(&self, channel_id: &ChannelId) -> ActiveQueryGuard {
+        let cancel = Arc::new(Notify::new());
+        self.started.insert(
+            channel_id.clone(),
+            ActiveQuery {
+                started_at: Instant::now(),
+                cancel: Arc::clone(&cancel),
+            },
+        );
+        ActiveQueryGuard {
+            started: Arc::clone(&self.started),
+            channel_id: channel_id.clone(),
+            cancel,
+        }
+    }
+
+    /// Check if a query is in flight for the channel
+    pub fn is_active(&self, channel_id: &ChannelId) -> bool {
+        self.started.contains_key(channel_id)
+    }
+
+    /// How long the in-flight query has been running, if any
+    pub fn elapsed(&self, channel_id: &ChannelId) -> Option<Duration> {
+        self.started
+            .get(channel_id)
+            .map(|query| query.started_at.elapsed())
+    }
+
+    /// Ask the in-flight query for the channel to stop. Returns false if none is running.
+    pub fn cancel(&self, channel_id: &ChannelId) -> bool {
+        match self.started.get(channel_id) {
+            Some(query) => {
+                // notify_one stores a permit, so the query sees it even if it isn't
+                // waiting at this exact moment
+                query.cancel.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Marks a query as finished when dropped (including on error paths)
+pub struct ActiveQueryGuard {
+    started: Arc<DashMap<ChannelId, ActiveQuery>>,
+    channel_id: ChannelId,
+    cancel: Arc<Notify>,
+}
+
+impl ActiveQueryGuard {
+    /// Resolves once the query has been cancelled via [`ActiveQueries::cancel`]
+    pub async fn cancelled(&self) {
+        self.cancel.notified().await
+    }
+}
+
+impl Drop for ActiveQueryGuard {
+    fn drop(&mut self) {
+        self.started.remove(&self.channel_id);
+    }
+}
+
+/// What the idle reaper should do with an agent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleAction {
+    /// Recently active (or already warned and not yet expired)
+    None,
+    /// Past the warning threshold: tell the channel the agent will go idle soon
+    Warn { remaining: Duration },
+    /// Past the full timeout: disconnect the agent
+    Reap,
+}
+
+/// When an agent was last used, and whether its channel was warned about going idle
+#[derive(Debug, Clone, Copy)]
+pub struct IdleState {
+    last_activity: Instant,
+    warned: bool,
+}
+
+impl IdleState {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            last_activity: now,
+            warned: false,
+        }
+    }
+
+    /// Record activity, which also re-arms the idle warning
+    pub fn touch(&mut self, now: Instant) {
+        self.last_activity = now;
+        self.warned = false;
+    }
+
+    /// How long the agent has been idle as of `now`
+    pub fn idle_for(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.last_activity)
+    }
+
+    /// Whether the idle warning has been sent since the last activity
+    pub fn warned(&self) -> bool {
+        self.warned
+    }
+
+    /// Decide what to do as of `now`. A `Warn` is returned at most once per idle
+    /// period; `warn_after` of `None` disables the warning.
+    pub fn check(
+        &mut self,
+        now: Instant,
+        warn_after: Option<Duration>,
+        timeout: Duration,
+    ) -> IdleAction {
+        let idle = self.idle_for(now);
+        if idle > timeout {
+            return IdleAction::Reap;
+        }
+        match warn_after {
+            Some(warn_after) if !self.warned && idle >= warn_after => {
+                self.warned = true;
+                IdleAction::Warn {
+                    remaining: timeout.saturating_sub(idle),
+                }
+            }
+            _ => IdleAction::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_tracked_while_guard_alive() {
+        let queries = ActiveQueries::new();
+        let channel = ChannelId::new("C123");
+        let other = ChannelId::new("C456");
+
+        assert!(!queries.is_active(&channel));
+
+        let guard = queries.start(&channel);
+        assert!(queries.is_active(&channel));
+        assert!(!queries.is_active(&other));
+        assert!(queries.elapsed(&channel).is_some());
+
+        drop(guard);
+        assert!(!queries.is_active(&channel));
+        assert!(queries.elapsed(&channel).is_none());
+    }
+
+    #[test]
+    fn test_idle_warns_once_then_reaps() {
+        let start = Instant::now();
+        let timeout = Duration::from_secs(100);
+        let warn_after = Some(Duration::from_secs(80));
+        let mut idle = IdleState::new(start);
+
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert_eq!(idle.check(at(50), warn_after, timeout), IdleAction::None);
+        assert_eq!(
+            idle.check(at(80), warn_after, timeout),
+            IdleAction::Warn {
+                remaining: Duration::from_secs(20)
+            }
+        );
+        assert!(idle.warned());
+        // The warning isn't repeated
+        assert_eq!(idle.check(at(90), warn_after, timeout), IdleAction::None);
+        assert_eq!(idle.check(at(101), warn_after, timeout), IdleAction::Reap);
+    }
+
+    #[test]
+    fn test_activity_rearms_idle_warning() {
+        let start = Instant::now();
+        let timeout = Duration::from_secs(100);
+        let warn_after = Some(Duration::from_secs(80));
+        let mut idle = IdleState::new(start);
+
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(matches!(
+            idle.check(at(85), warn_after, timeout),
+            IdleAction::Warn { .. }
+        ));
+        idle.touch(at(90));
+        assert!(!idle.warned());
+        assert_eq!(idle.check(at(150), warn_after, timeout), IdleAction::None);
+        assert!(matches!(
+            idle.check(at(170), warn_after, timeout),
+            IdleAction::Warn { .. }
+        ));
+
+        // Without a warning threshold the agent is reaped silently
+        let mut silent = IdleState::new(start);
+        assert_eq!(silent.check(at(99), None, timeout), IdleAction::None);
+        assert_eq!(silent.check(at(101), None, timeout), IdleAction::Reap);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_wakes_guard() {
+        let queries = ActiveQueries::new();
+        let channel = ChannelId::new("C123");
+
+        assert!(!queries.cancel(&channel));
+
+        let guard = queries.start(&channel);
+        assert!(queries.cancel(&channel));
+        tokio::time::timeout(Duration::from_secs(1), guard.cancelled())
+            .await
+            .expect("cancellation should be observed");
+    }
+}
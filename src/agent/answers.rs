@@ -0,0 +1,133 @@
+//! Which messages the agent answered, so an edited request can be re-run in place
+
+use crate::session::SessionId;
+use crate::slack::{ChannelId, MessageTs, ThreadTs};
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// Answers remembered per channel; older ones can no longer be re-run by editing
+pub const MAX_ANSWERS_PER_CHANNEL: usize = 50;
+
+/// A user message and the reply that answered it
+#[derive(Debug, Clone)]
+pub struct Answer {
+    /// The user's message
+    pub request_ts: MessageTs,
+    /// The thread the request was posted in, if any (it names the agent session)
+    pub thread_ts: Option<ThreadTs>,
+    /// The first message of the reply, which a re-run edits
+    pub response_ts: MessageTs,
+    /// The session the query ran in, which feedback on the reply is recorded against
+    pub session_id: Option<SessionId>,
+}
+
+/// Bounded per-channel log of answered requests
+#[derive(Debug, Clone, Default)]
+pub struct AnsweredRequests {
+    answers: Arc<DashMap<ChannelId, VecDeque<Answer>>>,
+}
+
+impl AnsweredRequests {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Remember a reply, replacing any earlier answer to the same request
+    pub fn record(&self, channel_id: &ChannelId, answer: Answer) {
+        let mut answers = self.answers.entry(channel_id.clone()).or_default();
+        answers.retain(|a| a.request_ts.as_str() != answer.request_ts.as_str());
+        if answers.len() >= MAX_ANSWERS_PER_CHANNEL {
+            answers.pop_front();
+        }
+        answers.push_back(answer);
+    }
+
+    /// The answer to a request, if it's still remembered
+    pub fn find(&self, channel_id: &ChannelId, request_ts: &str) -> Option<Answer> {
+        self.answers
+            .get(channel_id)?
+            .iter()
+            .find(|a| a.request_ts.as_str() == request_ts)
+            .cloned()
+    }
+
+    /// The answer whose reply starts with a given message
+    pub fn find_response(&self, channel_id: &ChannelId, response_ts: &str) -> Option<Answer> {
+        self.answers
+            .get(channel_id)?
+            .iter()
+            .find(|a| a.response_ts.as_str() == response_ts)
+            .cloned()
+    }
+
+    /// Whether a message is one of the bot's replies (so editing it must not re-run
+    /// anything)
+    pub fn is_response(&self, channel_id: &ChannelId, ts: &str) -> bool {
+        self.answers
+            .get(channel_id)
+            .is_some_and(|answers| answers.iter().any(|a| a.response_ts.as_str() == ts))
+    }
+
+    /// Forget the channel's answers (when the bot leaves it)
+    pub fn clear(&self, channel_id: &ChannelId) {
+        self.answers.remove(channel_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel() -> ChannelId {
+        ChannelId::new("C12345")
+    }
+
+    fn answer(request: &str, response: &str) -> Answer {
+        Answer {
+            request_ts: MessageTs::new(request),
+            thread_ts: None,
+            response_ts: MessageTs::new(response),
+            session_id: Some(format!("session-{request}")),
+        }
+    }
+
+    #[test]
+    fn test_edit_maps_to_previous_response() {
+        let answers = AnsweredRequests::new();
+        answers.record(&channel(), answer("1.0001", "1.0002"));
+
+        let found = answers.find(&channel(), "1.0001").unwrap();
+        assert_eq!(found.response_ts.as_str(), "1.0002");
+        assert!(answers.find(&channel(), "1.0002").is_none());
+        assert!(answers.find(&ChannelId::new("C99999"), "1.0001").is_none());
+
+        // The bot's own reply is recognized so its edits are ignored
+        assert!(answers.is_response(&channel(), "1.0002"));
+        assert!(!answers.is_response(&channel(), "1.0001"));
+
+        // Feedback on the reply finds the session it ran in
+        let rated = answers.find_response(&channel(), "1.0002").unwrap();
+        assert_eq!(rated.session_id.as_deref(), Some("session-1.0001"));
+        assert!(answers.find_response(&channel(), "1.0001").is_none());
+
+        // A re-run replaces the mapping rather than adding a second one
+        answers.record(&channel(), answer("1.0001", "1.0003"));
+        let found = answers.find(&channel(), "1.0001").unwrap();
+        assert_eq!(found.response_ts.as_str(), "1.0003");
+        assert_eq!(answers.answers.get(&channel()).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_answers_are_bounded() {
+        let answers = AnsweredRequests::new();
+        for i in 0..MAX_ANSWERS_PER_CHANNEL + 1 {
+            answers.record(&channel(), answer(&format!("{i}.1"), &format!("{i}.2")));
+        }
+        assert!(answers.find(&channel(), "0.1").is_none());
+        assert!(answers.find(&channel(), "1.1").is_some());
+
+        answers.clear(&channel());
+        assert!(answers.find(&channel(), "1.1").is_none());
+    }
+}
@@ -0,0 +1,114 @@
+//! The Claude connection behind a repo agent, as a trait so tests can stand in for the CLI
+
+use claude_agent_sdk_rs::{ClaudeClient, ClaudeError, Message};
+use futures::Stream;
+use std::future::Future;
+
+type ClientResult<T> = std::result::Result<T, ClaudeError>;
+
+/// What `RepoAgent` needs from a Claude client
+pub trait AgentClient: Send {
+    fn connect(&mut self) -> impl Future<Output = ClientResult<()>> + Send;
+
+    /// Send a prompt in a session. `user` is the attributed requester
+    /// (`CLAUDE_USER_ATTRIBUTION`), if any.
+    fn query(
+        &mut self,
+        prompt: &str,
+        session_id: String,
+        user: Option<&str>,
+    ) -> impl Future<Output = ClientResult<()>> + Send;
+
+    fn receive_response(&mut self) -> impl Stream<Item = ClientResult<Message>> + Send + '_;
+
+    fn interrupt(&mut self) -> impl Future<Output = ClientResult<()>> + Send;
+
+    fn disconnect(&mut self) -> impl Future<Output = ClientResult<()>> + Send;
+}
+
+impl AgentClient for ClaudeClient {
+    async fn connect(&mut self) -> ClientResult<()> {
+        ClaudeClient::connect(self).await
+    }
+
+    async fn query(
+        &mut self,
+        prompt: &str,
+        session_id: String,
+        _user: Option<&str>,
+    ) -> ClientResult<()> {
+        // The SDK's query carries only the prompt and session: it has no user or
+        // metadata field, so the attribution can't be passed on to Claude
+        self.query_with_session(prompt, session_id).await
+    }
+
+    fn receive_response(&mut self) -> impl Stream<Item = ClientResult<Message>> + Send + '_ {
+        ClaudeClient::receive_response(self)
+    }
+
+    async fn interrupt(&mut self) -> ClientResult<()> {
+        ClaudeClient::interrupt(self).await
+    }
+
+    async fn disconnect(&mut self) -> ClientResult<()> {
+        ClaudeClient::disconnect(self).await
+    }
+}
+
+/// A client that records what it's sent, for tests
+#[cfg(test)]
+pub(crate) mod stub {
+    use super::*;
+    use std::collections::VecDeque;
+
+    #[derive(Debug, Default)]
+    pub(crate) struct StubClient {
+        /// Errors returned by the next connects, in order; later connects succeed
+        pub connect_errors: VecDeque<ClaudeError>,
+        pub connect_attempts: u32,
+        /// `(prompt, session_id, user)` of every query
+        pub queries: Vec<(String, String, Option<String>)>,
+    }
+
+    impl StubClient {
+        pub(crate) fn failing_connects(errors: impl IntoIterator<Item = ClaudeError>) -> Self {
+            Self {
+                connect_errors: errors.into_iter().collect(),
+                ..Self::default()
+            }
+        }
+    }
+
+    impl AgentClient for StubClient {
+        async fn connect(&mut self) -> ClientResult<()> {
+            self.connect_attempts += 1;
+            match self.connect_errors.pop_front() {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        }
+
+        async fn query(
+            &mut self,
+            prompt: &str,
+            session_id: String,
+            user: Option<&str>,
+        ) -> ClientResult<()> {
+            self.queries
+                .push((prompt.to_string(), session_id, user.map(str::to_string)));
+            Ok(())
+        }
+
+        fn receive_response(&mut self) -> impl Stream<Item = ClientResult<Message>> + Send + '_ {
+            futures::stream::empty()
+        }
+
+        async fn interrupt(&mut self) -> ClientResult<()> {
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> ClientResult<()> {
+            Ok(())
+        }
+    }
+}
@@ -10,7 +10,7 @@ pub fn create_todo_hooks(
     channel_id: ChannelId,
 ) -> Hooks {
     tracing::debug!(
-        channel = %channel_id.as_str(),
+        channel = %channel_id,
         "Creating TodoWrite hooks"
     );
     let mut hooks = Hooks::new();
@@ -63,7 +63,7 @@ pub fn create_todo_hooks(
 
                             // Update Slack progress display with plan that includes timing
                             tracing::debug!(
-                                channel = %channel.as_str(),
+                                channel = %channel,
                                 "Updating Slack progress"
                             );
                             match tracker.update_progress(&channel, &plan_to_display).await {
@@ -91,14 +91,13 @@ pub fn create_todo_hooks(
     );
 
     tracing::debug!(
-        channel = %channel_clone.as_str(),
+        channel = %channel_clone,
         "TodoWrite hooks registered"
     );
     hooks
 }
 
-/// Format plan for display with timing information
-#[allow(dead_code)]
+/// Plain-text snapshot of a plan: progress, the current task, then every task
 pub fn format_plan_summary(plan: &Plan) -> String {
     let completed = plan.get_completed_count();
     let total = plan.get_total_count();
@@ -128,3 +127,38 @@ pub fn format_plan_summary(plan: &Plan) -> String {
 
     lines.join("\n")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Task;
+
+    fn task(content: &str, active_form: &str, status: TaskStatus) -> Task {
+        Task {
+            content: content.to_string(),
+            active_form: active_form.to_string(),
+            status,
+            start_time: None,
+            completion_time: None,
+        }
+    }
+
+    #[test]
+    fn test_format_plan_summary() {
+        let mut plan = Plan::new();
+        plan.todos = vec![
+            task("Read the code", "Reading the code", TaskStatus::Completed),
+            task("Fix the bug", "Fixing the bug", TaskStatus::InProgress),
+            task("Run the tests", "Running the tests", TaskStatus::Pending),
+        ];
+
+        assert_eq!(
+            format_plan_summary(&plan),
+            "Progress: 1/3\n\
+             Current: Fixing the bug\n\
+             ✅ Read the code\n\
+             ⏳ Fixing the bug\n\
+             ⬜ Run the tests"
+        );
+    }
+}
@@ -1,36 +1,44 @@
-use crate::agent::{Plan, create_todo_hooks};
-use crate::config::Settings;
+use crate::agent::{ConnectRetry, Plan, create_todo_hooks};
+use crate::config::{AgentConfig, RepoSpec, Settings};
 use crate::error::{Result, SlackCoderError};
 use crate::slack::{ChannelId, ProgressTracker};
-use crate::storage::Workspace;
-use claude_agent_sdk_rs::{ClaudeAgentOptions, ClaudeClient, PermissionMode, SystemPrompt};
-use futures::StreamExt;
+use crate::storage::{SetupSteps, Workspace};
+use claude_agent_sdk_rs::{ClaudeAgentOptions, ClaudeClient, ClaudeError, Message, SystemPrompt};
+use futures::{Stream, StreamExt};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Longest repository summary shown when setup completes, in characters
+const MAX_SUMMARY_CHARS: usize = 400;
 
 pub struct MainAgent {
     client: ClaudeClient,
     plan: Arc<Mutex<Plan>>,
+    connect_retry: ConnectRetry,
 }
 
 impl MainAgent {
     /// Create new main agent with TodoWrite hook
     pub async fn new(
-        _settings: Arc<Settings>,
+        settings: Arc<Settings>,
         workspace: Arc<Workspace>,
         progress_tracker: Arc<ProgressTracker>,
         channel_id: ChannelId,
     ) -> Result<Self> {
         let plan = Arc::new(Mutex::new(Plan::new()));
 
-        // Load main agent system prompt
-        let system_prompt = include_str!("../../prompts/main-agent-system-prompt.md").to_string();
+        let system_prompt = Self::build_system_prompt(&settings.agent);
 
         // Create hooks
         let hooks = create_todo_hooks(Arc::clone(&plan), progress_tracker, channel_id.clone());
 
         // Build agent options
         let options = ClaudeAgentOptions::builder()
-            .permission_mode(PermissionMode::BypassPermissions)
+            // Setup clones, runs `gh` and writes the system prompt, so it needs tool
+            // access; a restrictive global mode will make setup fail rather than prompt
+            .permission_mode(settings.agent.permission_mode.to_sdk())
+            .model(settings.claude.model.clone())
+            .env(settings.claude.cli_env())
             .system_prompt(SystemPrompt::Text(system_prompt))
             .cwd(workspace.base_path())
             .hooks(hooks.build())
@@ -38,41 +46,42 @@ impl MainAgent {
 
         let client = ClaudeClient::new(options);
 
-        Ok(Self { client, plan })
+        Ok(Self {
+            client,
+            plan,
+            connect_retry: ConnectRetry::from_config(&settings.agent),
+        })
+    }
+
+    /// The main agent's system prompt, after the global overlay if one is set (the same
+    /// order as repo agents, so organization rules always come first)
+    fn build_system_prompt(agent: &AgentConfig) -> String {
+        let main_prompt = include_str!("../../prompts/main-agent-system-prompt.md");
+        match &agent.global_prompt_overlay {
+            Some(overlay) => format!("{}\n\n---\n\n{}", overlay, main_prompt),
+            None => main_prompt.to_string(),
+        }
     }
 
-    /// Connect to Claude API
+    /// Connect to Claude API, retrying transient failures
     pub async fn connect(&mut self) -> Result<()> {
-        self.client
-            .connect()
-            .await
-            .map_err(|e| SlackCoderError::ClaudeAgent(e.to_string()))?;
+        let mut attempts = self.connect_retry.attempts("main agent");
+        while let Err(e) = self.client.connect().await {
+            attempts.retry_after(e).await?;
+        }
+        attempts.connected();
         Ok(())
     }
 
-    /// Run repository setup process
+    /// Run the remaining repository setup `steps`, giving up after `timeout`
     pub async fn setup_repository(
         &mut self,
-        repo_name: &str,
+        repo: &RepoSpec,
         channel_id: &ChannelId,
-    ) -> Result<()> {
-        let prompt = format!(
-            r#"Please set up the repository {} for channel {}.
-
-Tasks:
-1. Validate the repository exists and is accessible using gh CLI
-2. Clone it to ~/.slack_coder/repos/{}
-3. Analyze the codebase comprehensively
-4. Generate a system prompt for this repository
-5. Save the system prompt to ~/.slack_coder/system/{}/system_prompt.md
-
-The repository name provided by the user is: {}"#,
-            repo_name,
-            channel_id.as_str(),
-            channel_id.as_str(),
-            channel_id.as_str(),
-            repo_name
-        );
+        steps: SetupSteps,
+        timeout: Duration,
+    ) -> Result<String> {
+        let prompt = setup_prompt(repo, channel_id, steps);
 
         self.client
             .query(&prompt)
@@ -80,26 +89,14 @@ The repository name provided by the user is: {}"#,
             .map_err(|e| SlackCoderError::ClaudeAgent(e.to_string()))?;
 
         // Receive response stream
-        let mut stream = self.client.receive_response();
-        let mut final_result = String::new();
-
-        // Consume ENTIRE stream to ensure all hooks fire and progress updates work
-        while let Some(message) = stream.next().await {
-            let message = message.map_err(|e| SlackCoderError::ClaudeAgent(e.to_string()))?;
-
-            // Capture result but DON'T break - continue processing stream
-            if let claude_agent_sdk_rs::Message::Result(res) = message {
-                final_result = res.result.unwrap_or_default();
-                tracing::debug!("Received result message, continuing stream processing for hooks");
-            }
-            // Stream continues until it naturally ends, allowing all hooks to execute
-        }
+        let stream = self.client.receive_response();
+        let final_result = consume_setup_stream(stream, timeout).await?;
 
         tracing::info!(
             result_len = final_result.len(),
             "Setup stream processing complete"
         );
-        Ok(())
+        Ok(final_result)
     }
 
     /// Get current plan state
@@ -116,3 +113,272 @@ The repository name provided by the user is: {}"#,
         Ok(())
     }
 }
+
+/// The setup request for the main agent, listing only the steps still to run
+fn setup_prompt(repo: &RepoSpec, channel_id: &ChannelId, steps: SetupSteps) -> String {
+    let channel = channel_id.as_str();
+    let mut tasks = Vec::new();
+    let mut notes = Vec::new();
+
+    if steps.clone {
+        tasks.push("Validate the repository exists and is accessible using gh CLI".to_string());
+        tasks.push(format!("Clone it to ~/.slack_coder/repos/{}", channel));
+        if !repo.is_github_com() {
+            notes.push(format!(
+                "The repository is hosted on {host}, not github.com. For GitHub Enterprise use `gh` with the host (`gh repo view {repo}`, `gh repo clone {repo}`); for other hosts validate with `git ls-remote {url}.git` and clone with `git clone {url}.git`.",
+                host = repo.host,
+                repo = repo,
+                url = repo.web_url()
+            ));
+        }
+    } else {
+        notes.push(format!(
+            "The repository is already cloned at ~/.slack_coder/repos/{} from an earlier setup attempt. Do NOT clone it again.",
+            channel
+        ));
+    }
+    if steps.generate_prompt {
+        tasks.push("Analyze the codebase comprehensively".to_string());
+        tasks.push("Generate a system prompt for this repository".to_string());
+        tasks.push(format!(
+            "Save the system prompt to ~/.slack_coder/system/{}/system_prompt.md",
+            channel
+        ));
+    } else {
+        notes.push(format!(
+            "The system prompt at ~/.slack_coder/system/{}/system_prompt.md is already written from an earlier setup. Do NOT regenerate it.",
+            channel
+        ));
+    }
+
+    let mut prompt = format!(
+        "Please set up the repository {} for channel {}.\n\nTasks:\n",
+        repo, channel
+    );
+    for (i, task) in tasks.iter().enumerate() {
+        prompt.push_str(&format!("{}. {}\n", i + 1, task));
+    }
+    if !notes.is_empty() {
+        prompt.push_str("\nNotes:\n");
+        for note in &notes {
+            prompt.push_str(&format!("- {}\n", note));
+        }
+    }
+    prompt.push_str(&format!(
+        "\nThe repository name provided by the user is: {}",
+        repo
+    ));
+    prompt.push_str(
+        "\n\nWhen you are done, reply with one short paragraph describing what the repository is and its main technologies, without headings or lists. It is shown to the channel.",
+    );
+    prompt
+}
+
+/// A short description of the repository for the setup completion message: the
+/// agent's closing prose paragraph (the prompt asks it to end with one), or else the
+/// first prose paragraph of the system prompt
+pub fn repo_summary(setup_result: &str, system_prompt: Option<&str>) -> Option<String> {
+    prose_paragraphs(setup_result)
+        .pop()
+        .or_else(|| system_prompt.and_then(|prompt| prose_paragraphs(prompt).into_iter().next()))
+        .map(|paragraph| shorten_summary(&paragraph))
+}
+
+/// The prose paragraphs of markdown, each joined onto one line. Headings, list items,
+/// tables, quotes and code blocks are skipped.
+fn prose_paragraphs(markdown: &str) -> Vec<String> {
+    let mut paragraphs = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let mut in_code = false;
+    for line in markdown.lines().map(str::trim) {
+        if line.starts_with("```") {
+            in_code = !in_code;
+        }
+        let numbered = line
+            .split_once(". ")
+            .is_some_and(|(n, _)| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()));
+        // `*emphasis*` starts prose; only a marker followed by a space is a bullet
+        let bullet = ["- ", "* ", "+ "]
+            .iter()
+            .any(|marker| line.starts_with(marker));
+        let prose = !in_code
+            && !line.is_empty()
+            && !line.starts_with("```")
+            && !line.starts_with(['#', '|', '>'])
+            && !bullet
+            && !numbered;
+        if prose {
+            paragraph.push(line);
+        } else if !paragraph.is_empty() {
+            paragraphs.push(paragraph.join(" "));
+            paragraph.clear();
+        }
+    }
+    if !paragraph.is_empty() {
+        paragraphs.push(paragraph.join(" "));
+    }
+    paragraphs
+}
+
+/// Cut a summary at a word boundary to `MAX_SUMMARY_CHARS`
+fn shorten_summary(text: &str) -> String {
+    if text.chars().count() <= MAX_SUMMARY_CHARS {
+        return text.to_string();
+    }
+    let cut: String = text.chars().take(MAX_SUMMARY_CHARS).collect();
+    let cut = cut
+        .rsplit_once(char::is_whitespace)
+        .map_or(cut.as_str(), |(head, _)| head);
+    format!("{}…", cut.trim_end_matches([',', ';', ':', ' ']))
+}
+
+/// Consume the whole setup stream, returning the final result text.
+/// Fails with `SetupTimeout` if the stream hasn't finished within `timeout`.
+async fn consume_setup_stream<S>(stream: S, timeout: Duration) -> Result<String>
+where
+    S: Stream<Item = std::result::Result<Message, ClaudeError>>,
+{
+    let consume = async {
+        let mut stream = std::pin::pin!(stream);
+        let mut final_result = String::new();
+
+        // Consume ENTIRE stream to ensure all hooks fire and progress updates work
+        while let Some(message) = stream.next().await {
+            let message = message.map_err(|e| SlackCoderError::ClaudeAgent(e.to_string()))?;
+
+            // Capture result but DON'T break - continue processing stream
+            if let Message::Result(res) = message {
+                final_result = res.result.unwrap_or_default();
+                tracing::debug!("Received result message, continuing stream processing for hooks");
+            }
+            // Stream continues until it naturally ends, allowing all hooks to execute
+        }
+
+        Ok(final_result)
+    };
+
+    tokio::time::timeout(timeout, consume).await.map_err(|_| {
+        tracing::error!(
+            timeout_secs = timeout.as_secs(),
+            "Repository setup timed out"
+        );
+        SlackCoderError::SetupTimeout {
+            timeout_secs: timeout.as_secs(),
+        }
+    })?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str) -> RepoSpec {
+        RepoSpec::parse(name, crate::config::DEFAULT_GIT_HOST).unwrap()
+    }
+
+    #[test]
+    fn test_setup_prompt_full() {
+        let prompt = setup_prompt(
+            &spec("owner/repo"),
+            &ChannelId::new("C123"),
+            SetupSteps::ALL,
+        );
+        assert!(prompt.contains("2. Clone it to ~/.slack_coder/repos/C123"));
+        assert!(prompt.contains("5. Save the system prompt"));
+        assert!(!prompt.contains("Notes:"));
+        assert!(prompt.ends_with("It is shown to the channel."));
+    }
+
+    #[test]
+    fn test_repo_summary() {
+        let result = "A Rust web framework focused on ergonomics,\nbuilt on tokio and hyper.\n\n- extra detail";
+        assert_eq!(
+            repo_summary(result, None).as_deref(),
+            Some("A Rust web framework focused on ergonomics, built on tokio and hyper.")
+        );
+
+        // Nothing usable from the agent: fall back to the system prompt's first paragraph
+        let system_prompt = "# Project\n\n```\ncode here\n```\n\n1. Step\n\nThis CLI converts images.\n\n## Conventions";
+        assert_eq!(
+            repo_summary("## Done\n- cloned", Some(system_prompt)).as_deref(),
+            Some("This CLI converts images.")
+        );
+        assert_eq!(repo_summary("", None), None);
+
+        // The agent's closing paragraph, after whatever it reported first
+        let result = "I cloned the repo and wrote the prompt.\n\n- cloned\n- saved\n\n*Acme* is a Go\nservice for billing.";
+        assert_eq!(
+            repo_summary(result, None).as_deref(),
+            Some("*Acme* is a Go service for billing.")
+        );
+
+        let long = "word ".repeat(200);
+        let summary = repo_summary(&long, None).unwrap();
+        assert!(summary.ends_with("word…"));
+        assert!(summary.chars().count() <= MAX_SUMMARY_CHARS + 1);
+    }
+
+    #[test]
+    fn test_setup_prompt_enterprise_host() {
+        let repo = spec("ghe.company.com/platform/api");
+        let prompt = setup_prompt(&repo, &ChannelId::new("C123"), SetupSteps::ALL);
+        assert!(prompt.starts_with("Please set up the repository ghe.company.com/platform/api"));
+        assert!(prompt.contains("hosted on ghe.company.com"));
+        assert!(prompt.contains("gh repo clone ghe.company.com/platform/api"));
+        assert!(prompt.contains("git clone https://ghe.company.com/platform/api.git"));
+    }
+
+    #[test]
+    fn test_setup_prompt_resumes_partial_setup() {
+        let channel = ChannelId::new("C123");
+
+        let skip_clone = SetupSteps {
+            clone: false,
+            ..SetupSteps::ALL
+        };
+        let prompt = setup_prompt(&spec("owner/repo"), &channel, skip_clone);
+        assert!(prompt.contains("1. Analyze the codebase"));
+        assert!(!prompt.contains("Clone it to"));
+        assert!(prompt.contains("Do NOT clone it again"));
+
+        let skip_prompt = SetupSteps {
+            generate_prompt: false,
+            ..SetupSteps::ALL
+        };
+        let prompt = setup_prompt(&spec("owner/repo"), &channel, skip_prompt);
+        assert!(prompt.contains("2. Clone it to"));
+        assert!(!prompt.contains("Analyze the codebase"));
+        assert!(prompt.contains("Do NOT regenerate it"));
+    }
+
+    #[tokio::test]
+    async fn test_setup_stream_times_out_without_result() {
+        let stream = futures::stream::pending::<std::result::Result<Message, ClaudeError>>();
+
+        let result = consume_setup_stream(stream, Duration::from_millis(20)).await;
+        assert!(matches!(
+            result,
+            Err(SlackCoderError::SetupTimeout { timeout_secs: 0 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_setup_stream_completes_when_stream_ends() {
+        let stream = futures::stream::empty::<std::result::Result<Message, ClaudeError>>();
+
+        let result = consume_setup_stream(stream, Duration::from_secs(1)).await;
+        assert_eq!(result.unwrap(), "");
+    }
+
+    #[test]
+    fn test_system_prompt_starts_with_global_overlay() {
+        let mut agent = crate::config::test_settings().agent;
+        let main_prompt = include_str!("../../prompts/main-agent-system-prompt.md");
+        assert_eq!(MainAgent::build_system_prompt(&agent), main_prompt);
+
+        agent.global_prompt_overlay = Some("ORG RULES".to_string());
+        let prompt = MainAgent::build_system_prompt(&agent);
+        assert!(prompt.starts_with("ORG RULES\n\n---\n\n"));
+        assert!(prompt.ends_with(main_prompt));
+    }
+}
@@ -1,56 +1,331 @@
-use crate::agent::{MainAgent, RepoAgent};
-use crate::config::Settings;
+use crate::agent::{
+    ActiveQueries, AnsweredRequests, ChannelSyncReport, IdleAction, MainAgent, QueryQueues,
+    RecentErrors, RepoAgent, SetupReport, repo_summary,
+};
+use crate::config::{RepoSpec, Settings, SharedSettings};
 use crate::error::{Result, SlackCoderError};
-use crate::slack::{ChannelId, ProgressTracker, SlackClient};
-use crate::storage::Workspace;
+use crate::session::SessionId;
+use crate::slack::{
+    ChannelId, ProgressTracker, SessionUsage, SlackClient, UsageMetrics, display_command,
+};
+use crate::storage::{BranchSwitch, SetupStatus, Workspace, checkout_branch};
 use dashmap::DashMap;
+use dashmap::mapref::entry::Entry;
+use futures::StreamExt;
+use std::collections::HashSet;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::Mutex;
+use tokio::time::timeout;
+
+/// How long `restart_agent` waits for a cancelled query to release the agent
+const RESTART_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Bounds for a channel's idle timeout set with `/timeout`
+pub const MIN_AGENT_TIMEOUT: Duration = Duration::from_secs(60);
+pub const MAX_AGENT_TIMEOUT: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How long after `/teardown` a `/teardown confirm` is accepted
+pub const TEARDOWN_CONFIRM_WINDOW: Duration = Duration::from_secs(2 * 60);
+
+/// A running repo agent, as listed by `/agents`
+#[derive(Debug, Clone)]
+pub struct ActiveAgent {
+    pub channel_id: ChannelId,
+    /// The channel session, or None while the agent is busy with a query
+    pub session_id: Option<SessionId>,
+    /// Time since the agent last did anything, or None while it's busy
+    pub idle: Option<Duration>,
+}
 
 pub struct AgentManager {
     repo_agents: Arc<DashMap<ChannelId, Arc<Mutex<RepoAgent>>>>,
     workspace: Arc<Workspace>,
-    settings: Arc<Settings>,
+    settings: SharedSettings,
     progress_tracker: Arc<ProgressTracker>,
+    active_queries: ActiveQueries,
+    query_queues: QueryQueues,
+    /// Running usage totals for each channel's current session (persisted to disk)
+    session_usage: DashMap<ChannelId, SessionUsage>,
+    recent_errors: RecentErrors,
+    /// Replies to each request, so edited requests can be re-run in place
+    answered_requests: AnsweredRequests,
+    /// Idle timeouts set with `/timeout`, in place of `AGENT_TIMEOUT_SECS` (not persisted)
+    timeout_overrides: DashMap<ChannelId, Duration>,
+    /// When `/teardown` was last shown in each channel, awaiting `/teardown confirm`
+    teardown_requests: DashMap<ChannelId, Instant>,
 }
 
 impl AgentManager {
     /// Create new agent manager with empty repo agent pool
     pub async fn new(
-        settings: Arc<Settings>,
+        settings: SharedSettings,
         workspace: Arc<Workspace>,
         progress_tracker: Arc<ProgressTracker>,
     ) -> Result<Self> {
         // Ensure workspace directories exist
         workspace.ensure_workspace().await?;
 
+        let startup_settings = settings.load();
+        let query_queues = QueryQueues::new(startup_settings.agent.max_queued_messages);
+        let recent_errors = RecentErrors::new(startup_settings.filters.secrets.clone());
+
         Ok(Self {
             repo_agents: Arc::new(DashMap::new()),
             workspace,
             settings,
             progress_tracker,
+            active_queries: ActiveQueries::new(),
+            query_queues,
+            session_usage: DashMap::new(),
+            recent_errors,
+            answered_requests: AnsweredRequests::new(),
+            timeout_overrides: DashMap::new(),
+            teardown_requests: DashMap::new(),
         })
     }
 
-    /// Scan Slack channels and restore existing agents from disk (in parallel)
+    /// Snapshot of the current settings (they may be reloaded at any time)
+    pub fn settings(&self) -> Arc<Settings> {
+        self.settings.load()
+    }
+
+    /// Get the reloadable settings
+    pub fn shared_settings(&self) -> &SharedSettings {
+        &self.settings
+    }
+
+    /// Get the workspace
+    pub fn workspace(&self) -> &Arc<Workspace> {
+        &self.workspace
+    }
+
+    /// Get the progress tracker
+    pub fn progress_tracker(&self) -> &Arc<ProgressTracker> {
+        &self.progress_tracker
+    }
+
+    /// Get the tracker of in-flight queries
+    pub fn active_queries(&self) -> &ActiveQueries {
+        &self.active_queries
+    }
+
+    /// Get the per-channel queues of messages waiting for their agent
+    pub fn query_queues(&self) -> &QueryQueues {
+        &self.query_queues
+    }
+
+    /// Get the per-channel log of recent errors
+    pub fn recent_errors(&self) -> &RecentErrors {
+        &self.recent_errors
+    }
+
+    /// Get the per-channel log of answered requests
+    pub fn answered_requests(&self) -> &AnsweredRequests {
+        &self.answered_requests
+    }
+
+    /// Record that `/teardown` was shown, so a `/teardown confirm` within
+    /// `TEARDOWN_CONFIRM_WINDOW` can proceed
+    pub fn request_teardown(&self, channel_id: &ChannelId) {
+        self.teardown_requests
+            .insert(channel_id.clone(), Instant::now());
+    }
+
+    /// Consume the channel's pending teardown request. Returns false if there was
+    /// none or it has expired.
+    pub fn take_teardown_request(&self, channel_id: &ChannelId) -> bool {
+        self.teardown_requests
+            .remove(channel_id)
+            .is_some_and(|(_, requested)| requested.elapsed() <= TEARDOWN_CONFIRM_WINDOW)
+    }
+
+    /// The channel's idle timeout: its `/timeout` override, or the global default
+    pub fn agent_timeout(&self, channel_id: &ChannelId) -> Duration {
+        self.agent_timeout_override(channel_id)
+            .unwrap_or_else(|| Duration::from_secs(self.settings().agent.agent_timeout_secs))
+    }
+
+    /// The channel's `/timeout` override, if any
+    pub fn agent_timeout_override(&self, channel_id: &ChannelId) -> Option<Duration> {
+        self.timeout_overrides
+            .get(channel_id)
+            .map(|timeout| *timeout)
+    }
+
+    /// Override the channel's idle timeout until the bot restarts (`None` restores the
+    /// default). Fails if the timeout is outside `MIN_AGENT_TIMEOUT..=MAX_AGENT_TIMEOUT`.
+    pub fn set_agent_timeout(
+        &self,
+        channel_id: &ChannelId,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        let Some(timeout) = timeout else {
+            self.timeout_overrides.remove(channel_id);
+            return Ok(());
+        };
+        if !(MIN_AGENT_TIMEOUT..=MAX_AGENT_TIMEOUT).contains(&timeout) {
+            return Err(SlackCoderError::Config(format!(
+                "Timeout must be between {} and {} seconds",
+                MIN_AGENT_TIMEOUT.as_secs(),
+                MAX_AGENT_TIMEOUT.as_secs()
+            )));
+        }
+        self.timeout_overrides.insert(channel_id.clone(), timeout);
+        Ok(())
+    }
+
+    /// When to warn (if at all) and when to reap the channel's idle agent
+    fn idle_limits(&self, channel_id: &ChannelId) -> (Option<Duration>, Duration) {
+        let timeout = self.agent_timeout(channel_id);
+        (self.settings().agent.idle_warning_for(timeout), timeout)
+    }
+
+    /// Usage totals for the channel's current session. Totals left over from a session
+    /// the agent no longer has (after a reset or restart) don't count.
+    pub async fn session_usage(&self, channel_id: &ChannelId) -> Result<Option<SessionUsage>> {
+        let usage = self.stored_session_usage(channel_id).await?;
+        Ok(usage.filter(|usage| self.session_is_live(channel_id, &usage.session_id)))
+    }
+
+    /// Whether `session_id` is one of the channel agent's sessions. An agent busy with a
+    /// query is taken to be working in it, rather than waiting for the query to finish.
+    fn session_is_live(&self, channel_id: &ChannelId, session_id: &str) -> bool {
+        let Some(agent) = self.repo_agents.get(channel_id).map(|r| r.clone()) else {
+            return false;
+        };
+        match agent.try_lock() {
+            Ok(agent) => agent.has_session(session_id),
+            Err(_) => true,
+        }
+    }
+
+    /// The last recorded usage totals, loading them from disk on first access
+    async fn stored_session_usage(&self, channel_id: &ChannelId) -> Result<Option<SessionUsage>> {
+        if let Some(usage) = self.session_usage.get(channel_id) {
+            return Ok(Some(usage.clone()));
+        }
+
+        let loaded = self.workspace.load_session_usage(channel_id).await?;
+        if let Some(usage) = &loaded {
+            self.session_usage.insert(channel_id.clone(), usage.clone());
+        }
+        Ok(loaded)
+    }
+
+    /// Add a query's metrics to the session totals, starting fresh if the session changed
+    pub async fn record_usage(
+        &self,
+        channel_id: &ChannelId,
+        session_id: &str,
+        metrics: &UsageMetrics,
+    ) -> Result<SessionUsage> {
+        let mut usage = self
+            .stored_session_usage(channel_id)
+            .await?
+            .filter(|u| u.session_id == session_id)
+            .unwrap_or_else(|| SessionUsage::new(session_id));
+        usage.record(metrics);
+
+        self.session_usage.insert(channel_id.clone(), usage.clone());
+        self.workspace
+            .save_session_usage(channel_id, &usage)
+            .await?;
+        Ok(usage)
+    }
+
+    /// Reset the session totals when a new session starts
+    pub async fn reset_session_usage(
+        &self,
+        channel_id: &ChannelId,
+        session_id: &str,
+    ) -> Result<()> {
+        let usage = SessionUsage::new(session_id);
+        self.session_usage.insert(channel_id.clone(), usage.clone());
+        self.workspace.save_session_usage(channel_id, &usage).await
+    }
+
+    /// Whether the access lists allow serving a channel. The channel name is only looked
+    /// up when a `#name` pattern needs it.
+    async fn channel_allowed(&self, slack_client: &SlackClient, channel_id: &ChannelId) -> bool {
+        let channel_name = async {
+            match slack_client.get_channel_info(channel_id.as_str()).await {
+                Ok(info) => Some(info.name),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to look up channel name {}", channel_id.log_format());
+                    None
+                }
+            }
+        };
+        self.settings()
+            .access
+            .allows(channel_id.as_str(), channel_name)
+            .await
+    }
+
+    /// Scan Slack channels and restore existing agents from disk on startup, then let
+    /// the restored channels know the agent is back
     pub async fn scan_and_restore_channels(&self, slack_client: &SlackClient) -> Result<()> {
-        let span = tracing::info_span!("scan_and_restore_channels");
+        let report = self.sync_channels(slack_client).await?;
+
+        // Let restored channels know the agent is back
+        if report.added > 0 {
+            self.send_startup_notifications().await;
+        }
+
+        Ok(())
+    }
+
+    /// Reconcile live agents with the channels the bot is a member of: create agents for
+    /// set-up channels that have none (in parallel, bounded by `max_concurrent_restores`)
+    /// and drop agents for channels the bot was removed from.
+    ///
+    /// Dropped channels keep their data on disk, so inviting the bot back restores them.
+    pub async fn sync_channels(&self, slack_client: &SlackClient) -> Result<ChannelSyncReport> {
+        let span = tracing::info_span!("sync_channels");
         let _guard = span.enter();
 
         let start = std::time::Instant::now();
         let channels = slack_client.list_channels().await?;
+        let mut report = ChannelSyncReport::default();
 
         tracing::info!(
             total_channels = channels.len(),
             "Scanning for existing setups"
         );
 
-        // Filter to channels that are setup
+        let members: HashSet<ChannelId> = channels.iter().cloned().collect();
+        let live: Vec<ChannelId> = self.repo_agents.iter().map(|e| e.key().clone()).collect();
+        for channel_id in channels_to_prune(&live, &members) {
+            self.release_channel(&channel_id).await;
+            report.removed += 1;
+        }
+
+        // Filter to channels that are setup and don't have an agent yet
         let mut setup_channels = Vec::new();
         for channel_id in channels {
-            if self.workspace.is_channel_setup(&channel_id).await {
+            if self.has_agent(&channel_id) {
+                continue;
+            }
+            let status = self.workspace.is_channel_setup(&channel_id).await;
+            if (status.is_ready() || status.is_partial())
+                && !self.channel_allowed(slack_client, &channel_id).await
+            {
+                report.disallowed += 1;
+                tracing::info!(
+                    "Skipping restore {}: not allowed by the channel access lists",
+                    channel_id.log_format()
+                );
+            } else if status.is_ready() {
                 setup_channels.push(channel_id);
+            } else if status.is_partial() {
+                report.incomplete += 1;
+                tracing::warn!(
+                    status = ?status,
+                    "Skipping restore {}: {}. Re-run setup in the channel to fix it",
+                    channel_id.log_format(),
+                    status.missing().unwrap_or_default()
+                );
             }
         }
 
@@ -60,58 +335,56 @@ impl AgentManager {
         );
 
         if setup_channels.is_empty() {
-            tracing::info!("No channels to restore");
-            return Ok(());
+            tracing::info!(removed = report.removed, "No channels to restore");
+            return Ok(report);
         }
 
-        // Restore all agents in parallel
-        let restore_futures: Vec<_> = setup_channels
-            .into_iter()
-            .map(|channel_id| {
-                let workspace = self.workspace.clone();
-                let settings = self.settings.clone();
-                let progress_tracker = self.progress_tracker.clone();
-
-                async move {
-                    Self::create_repo_agent_static(
-                        channel_id.clone(),
-                        workspace,
-                        settings,
-                        progress_tracker,
-                    )
-                    .await
-                    .map(|agent| (channel_id.clone(), agent))
-                    .map_err(|e| (channel_id, e))
-                }
-            })
-            .collect();
-
+        // Restore agents in parallel, but only a few at a time - each one starts a
+        // Claude process, and hundreds at once can overwhelm the host
+        let concurrency = self.settings().agent.max_concurrent_restores;
         tracing::info!(
-            agent_count = restore_futures.len(),
+            agent_count = setup_channels.len(),
+            concurrency = concurrency,
             "Restoring agents in parallel"
         );
 
-        let results = futures::future::join_all(restore_futures).await;
+        let results = run_bounded(setup_channels, concurrency, |channel_id| {
+            let workspace = self.workspace.clone();
+            let settings = self.settings();
+            let progress_tracker = self.progress_tracker.clone();
 
-        // Process results
-        let mut restored_count = 0;
-        let mut failed_count = 0;
+            async move {
+                Self::create_repo_agent_static(
+                    channel_id.clone(),
+                    workspace,
+                    settings,
+                    progress_tracker,
+                )
+                .await
+                .map(|agent| (channel_id.clone(), agent))
+                .map_err(|e| (channel_id, e))
+            }
+        })
+        .await;
 
+        // Process results
         for result in results {
             match result {
                 Ok((channel_id, agent)) => {
-                    self.repo_agents
-                        .insert(channel_id.clone(), Arc::new(Mutex::new(agent)));
-                    restored_count += 1;
+                    // A setup may have finished for this channel while we were restoring
+                    if !self.insert_agent_if_absent(&channel_id, agent).await {
+                        continue;
+                    }
+                    report.added += 1;
                     tracing::debug!(
-                        channel_id = %channel_id.as_str(),
+                        channel_id = %channel_id,
                         "Agent restored"
                     );
                 }
                 Err((channel_id, e)) => {
-                    failed_count += 1;
+                    report.failed += 1;
                     tracing::warn!(
-                        channel_id = %channel_id.as_str(),
+                        channel_id = %channel_id,
                         error = %e,
                         "Failed to restore agent"
                     );
@@ -121,98 +394,70 @@ impl AgentManager {
 
         let duration = start.elapsed();
         tracing::info!(
-            restored = restored_count,
-            failed = failed_count,
+            restored = report.added,
+            removed = report.removed,
+            failed = report.failed,
             duration_ms = duration.as_millis() as u64,
             "Agent restoration complete"
         );
 
-        // Send startup notification to all restored channels in parallel
-        if restored_count > 0 {
-            self.send_startup_notifications().await;
-        }
-
-        Ok(())
+        Ok(report)
     }
 
-    /// Send startup notifications to all channels with restored agents (in parallel)
+    /// Send startup notifications to channels with restored agents.
+    ///
+    /// Skipped entirely when disabled in config. Channels notified within the cooldown
+    /// are left alone. The notices go out in the background, spaced out to stay under
+    /// chat.postMessage limits, so events are handled while they're sent.
     async fn send_startup_notifications(&self) {
+        let settings = self.settings();
+        let agent_config = &settings.agent;
+        if !agent_config.startup_notifications {
+            tracing::info!("Startup notifications disabled");
+            return;
+        }
+
         tracing::info!("Sending startup notifications to restored channels");
 
         // Collect channel IDs and session IDs
-        let mut channel_sessions = Vec::new();
-        for entry in self.repo_agents.iter() {
-            let channel_id = entry.key().clone();
-
-            // Try to get session ID
-            if let Ok(agent) =
-                tokio::time::timeout(Duration::from_millis(100), entry.value().lock()).await
-            {
-                channel_sessions.push((channel_id, agent.get_session_id()));
-            }
+        let candidates = self.get_all_active_agents().await;
+        let mut with_last_notice = Vec::with_capacity(candidates.len());
+        for (channel_id, session_id) in candidates {
+            let last = self.workspace.load_last_startup_notice(&channel_id).await;
+            with_last_notice.push((channel_id, session_id, last));
         }
 
+        let targets = startup_notice_targets(
+            with_last_notice,
+            SystemTime::now(),
+            agent_config.startup_notice_cooldown(),
+        );
+
         tracing::debug!(
-            channel_count = channel_sessions.len(),
+            channel_count = targets.len(),
             "Prepared startup notifications"
         );
 
-        // Send notifications in parallel
-        let slack_client = self.progress_tracker.slack_client_ref();
-        let notification_futures: Vec<_> = channel_sessions
-            .into_iter()
-            .map(|(channel_id, session_id)| {
-                let client = slack_client.clone();
-                async move {
-                    let notification = format!(
-                        "🤖 *Agent Ready*\n\nSession ID: `{}`\n\nI'm ready to help with this repository! Type `/help` for available commands.",
-                        session_id
-                    );
-
-                    match client.send_message(&channel_id, &notification, None).await {
-                        Ok(_) => {
-                            tracing::debug!(
-                                channel_id = %channel_id.as_str(),
-                                session_id = %session_id,
-                                "Startup notification sent"
-                            );
-                            Ok(())
-                        }
-                        Err(e) => {
-                            tracing::warn!(
-                                channel_id = %channel_id.as_str(),
-                                error = %e,
-                                "Failed to send startup notification"
-                            );
-                            Err(e)
-                        }
-                    }
-                }
-            })
-            .collect();
-
-        let results = futures::future::join_all(notification_futures).await;
-
-        let success_count = results.iter().filter(|r| r.is_ok()).count();
-        tracing::info!(
-            sent = success_count,
-            total = results.len(),
-            "Startup notifications sent"
-        );
+        tokio::spawn(send_startup_notices(
+            self.progress_tracker.slack_client_ref(),
+            self.workspace.clone(),
+            targets,
+            self.settings().slack.command_prefix.clone(),
+        ));
     }
 
     /// Setup a new channel - invokes main agent to validate, clone, analyze, generate prompt
-    pub async fn setup_channel(&self, channel_id: ChannelId, repo_name: String) -> Result<()> {
-        tracing::info!(
-            "🎬 Setting up {} repo={}",
-            channel_id.log_format(),
-            repo_name
-        );
+    pub async fn setup_channel(
+        &self,
+        channel_id: ChannelId,
+        repo: RepoSpec,
+    ) -> Result<SetupReport> {
+        tracing::info!("🎬 Setting up {} repo={}", channel_id.log_format(), repo);
 
         // Create and run main agent
         tracing::debug!("Creating main agent...");
         let mut main_agent = MainAgent::new(
-            self.settings.clone(),
+            self.settings(),
             self.workspace.clone(),
             self.progress_tracker.clone(),
             channel_id.clone(),
@@ -224,27 +469,106 @@ impl AgentManager {
         main_agent.connect().await?;
         tracing::info!("✅ Connected to Claude");
 
-        tracing::info!("🚀 Running repository setup (this may take 1-2 minutes)...");
-        main_agent.setup_repository(&repo_name, &channel_id).await?;
+        // Rerunning an interrupted setup reuses whatever it already finished
+        let steps = self.workspace.setup_steps(&channel_id, &repo).await;
+        if steps.discard_clone {
+            tracing::info!("Removing leftover clone before setup");
+            self.workspace.remove_repo(&channel_id).await?;
+        }
+
+        tracing::info!(
+            skipped = ?steps.skipped(),
+            "🚀 Running repository setup (this may take 1-2 minutes)..."
+        );
+        let setup_timeout = Duration::from_secs(self.settings().agent.setup_timeout_secs);
+        let setup_result = match main_agent
+            .setup_repository(&repo, &channel_id, steps, setup_timeout)
+            .await
+        {
+            Ok(setup_result) => setup_result,
+            Err(e) => {
+                tracing::error!(error = %e, "Repository setup failed {}", channel_id.log_format());
+                if let Err(disconnect_err) = main_agent.disconnect().await {
+                    tracing::warn!(error = %disconnect_err, "Failed to disconnect main agent");
+                }
+                // A timed-out setup may leave a partial clone behind (a reused one is fine)
+                if matches!(e, SlackCoderError::SetupTimeout { .. })
+                    && steps.clone
+                    && let Err(remove_err) = self.workspace.remove_repo(&channel_id).await
+                {
+                    tracing::warn!(error = %remove_err, "Failed to remove partial clone");
+                }
+                return Err(e);
+            }
+        };
         tracing::info!("✅ Repository setup completed");
 
         tracing::debug!("Disconnecting main agent...");
         main_agent.disconnect().await?;
 
+        // Enforce the disk quota before keeping the clone around
+        let repo_size_bytes = self.check_repo_size(&channel_id).await?;
+
+        // Remember which repository this channel is set up for
+        let mut channel_config = self.workspace.load_channel_config(&channel_id).await?;
+        let repo_name = repo.to_string();
+        // A working branch chosen for another repository doesn't carry over
+        if channel_config.repo_name.as_ref() != Some(&repo_name) {
+            channel_config.branch = None;
+        }
+        channel_config.repo_name = Some(repo_name);
+        self.workspace
+            .save_channel_config(&channel_id, &channel_config)
+            .await?;
+
         // Create repository agent
         tracing::info!(
             "🤖 Creating repository-specific agent {}...",
             channel_id.log_format()
         );
         let repo_agent = self.create_repo_agent(channel_id.clone()).await?;
-        self.repo_agents
-            .insert(channel_id.clone(), Arc::new(Mutex::new(repo_agent)));
+        // Anything restored before the setup finished runs the old prompt
+        self.replace_agent(&channel_id, repo_agent).await;
         tracing::info!(
             "✅ Repository agent created and cached {}",
             channel_id.log_format()
         );
 
-        Ok(())
+        let system_prompt = self.workspace.load_system_prompt(&channel_id).await.ok();
+        Ok(SetupReport {
+            repo_size_bytes,
+            skipped: steps.skipped(),
+            summary: repo_summary(&setup_result, system_prompt.as_deref()),
+        })
+    }
+
+    /// Measure the cloned repository and remove it if it exceeds `max_repo_size_mb`
+    async fn check_repo_size(&self, channel_id: &ChannelId) -> Result<u64> {
+        let size_bytes = self.workspace.repo_size_bytes(channel_id).await?;
+        let limit_mb = self.settings().workspace.max_repo_size_mb;
+        let size_mb = size_bytes / (1024 * 1024);
+
+        tracing::info!(
+            channel_id = %channel_id,
+            size_bytes = size_bytes,
+            limit_mb = limit_mb,
+            "Measured repository size"
+        );
+
+        if size_bytes > limit_mb * 1024 * 1024 {
+            tracing::warn!(
+                channel_id = %channel_id,
+                size_mb = size_mb,
+                limit_mb = limit_mb,
+                "Repository exceeds size limit, removing clone"
+            );
+            if let Err(e) = self.workspace.remove_repo(channel_id).await {
+                tracing::error!(error = %e, "Failed to remove oversized repository");
+            }
+            return Err(SlackCoderError::RepoTooLarge { size_mb, limit_mb });
+        }
+
+        Ok(size_bytes)
     }
 
     /// Create a new repository agent (instance method)
@@ -252,7 +576,7 @@ impl AgentManager {
         Self::create_repo_agent_static(
             channel_id,
             self.workspace.clone(),
-            self.settings.clone(),
+            self.settings(),
             self.progress_tracker.clone(),
         )
         .await
@@ -266,21 +590,40 @@ impl AgentManager {
         progress_tracker: Arc<ProgressTracker>,
     ) -> Result<RepoAgent> {
         tracing::debug!(
-            channel_id = %channel_id.as_str(),
+            channel_id = %channel_id,
             "Creating repo agent"
         );
 
+        // A re-clone or a manual checkout may have left the repository on another branch
+        if let Some(branch) = workspace.load_channel_config(&channel_id).await?.branch {
+            match checkout_branch(&workspace.repo_path(&channel_id), &branch).await {
+                Ok(BranchSwitch::Unchanged) => {}
+                Ok(switch) => tracing::info!(
+                    branch = %branch,
+                    switch = ?switch,
+                    "Checked out working branch {}",
+                    channel_id.log_format()
+                ),
+                Err(e) => tracing::warn!(
+                    branch = %branch,
+                    error = %e,
+                    "Failed to check out working branch {}",
+                    channel_id.log_format()
+                ),
+            }
+        }
+
         let mut agent =
             RepoAgent::new(channel_id.clone(), workspace, settings, progress_tracker).await?;
 
         tracing::debug!(
-            channel_id = %channel_id.as_str(),
+            channel_id = %channel_id,
             "Connecting agent to Claude"
         );
         agent.connect().await?;
 
         tracing::debug!(
-            channel_id = %channel_id.as_str(),
+            channel_id = %channel_id,
             session_id = %agent.get_session_id(),
             "Agent connected"
         );
@@ -290,6 +633,53 @@ impl AgentManager {
         Ok(agent)
     }
 
+    /// Cache a newly created agent unless the channel already has one, in which case the
+    /// new agent is disconnected and the existing one kept. Returns whether it was stored.
+    async fn insert_agent_if_absent(&self, channel_id: &ChannelId, agent: RepoAgent) -> bool {
+        let duplicate = match self.repo_agents.entry(channel_id.clone()) {
+            Entry::Occupied(_) => Some(agent),
+            Entry::Vacant(entry) => {
+                entry.insert(Arc::new(Mutex::new(agent)));
+                None
+            }
+        };
+        // The entry guard is released before anything is awaited
+        let Some(duplicate) = duplicate else {
+            return true;
+        };
+
+        tracing::warn!(
+            "Agent already exists, disconnecting the duplicate {}",
+            channel_id.log_format()
+        );
+        if let Err(e) = duplicate.disconnect().await {
+            tracing::warn!(error = %e, "Failed to disconnect duplicate agent");
+        }
+        false
+    }
+
+    /// Cache a newly created agent, disconnecting any agent it replaces
+    async fn replace_agent(&self, channel_id: &ChannelId, agent: RepoAgent) {
+        if let Some(previous) = self
+            .repo_agents
+            .insert(channel_id.clone(), Arc::new(Mutex::new(agent)))
+        {
+            tracing::info!("Replaced existing agent {}", channel_id.log_format());
+            if let Err(e) = Self::disconnect_shared(previous).await {
+                tracing::warn!(error = %e, "Failed to disconnect replaced agent");
+            }
+        }
+    }
+
+    /// Disconnect an agent taken out of the cache. One still in use by a query is left
+    /// to be dropped when that query finishes.
+    async fn disconnect_shared(agent_mutex: Arc<Mutex<RepoAgent>>) -> Result<()> {
+        if let Ok(mutex) = Arc::try_unwrap(agent_mutex) {
+            mutex.into_inner().disconnect().await?;
+        }
+        Ok(())
+    }
+
     /// Get repository agent for a channel
     pub async fn get_repo_agent(&self, channel_id: &ChannelId) -> Result<Arc<Mutex<RepoAgent>>> {
         self.repo_agents
@@ -306,33 +696,222 @@ impl AgentManager {
     /// Remove agent for a channel
     pub async fn remove_agent(&self, channel_id: &ChannelId) -> Result<()> {
         if let Some((_, agent_mutex)) = self.repo_agents.remove(channel_id) {
-            // Try to unwrap and disconnect if we have sole ownership
-            if let Ok(mutex) = Arc::try_unwrap(agent_mutex) {
-                let agent = mutex.into_inner();
-                agent.disconnect().await?;
-            }
+            Self::disconnect_shared(agent_mutex).await?;
         }
         Ok(())
     }
 
+    /// Stop serving a channel the bot is no longer in: cancel any running query and drop
+    /// its agent, queue and task board. Nothing is posted to the channel, and its data
+    /// stays on disk so inviting the bot back restores it.
+    ///
+    /// Returns whether the channel had an agent.
+    pub async fn release_channel(&self, channel_id: &ChannelId) -> bool {
+        let had_agent = self.has_agent(channel_id);
+        tracing::info!(
+            had_agent = had_agent,
+            "Bot is no longer in channel, releasing it {}",
+            channel_id.log_format()
+        );
+
+        self.active_queries.cancel(channel_id);
+        self.query_queues.clear(channel_id);
+        if let Err(e) = self.remove_agent(channel_id).await {
+            tracing::warn!(
+                channel_id = %channel_id,
+                error = %e,
+                "Failed to disconnect agent"
+            );
+        }
+        self.progress_tracker.forget_progress(channel_id);
+        self.session_usage.remove(channel_id);
+        self.recent_errors.clear(channel_id);
+        self.answered_requests.clear(channel_id);
+        self.timeout_overrides.remove(channel_id);
+        self.teardown_requests.remove(channel_id);
+        had_agent
+    }
+
+    /// Decommission a channel: drop its agent, queue and task board, then delete its
+    /// repository clone and system directory. Returns the bytes reclaimed on disk.
+    ///
+    /// Callers should make sure no query is running; the agent is disconnected as-is.
+    pub async fn teardown_channel(&self, channel_id: &ChannelId) -> Result<u64> {
+        tracing::info!("Tearing down channel {}", channel_id.log_format());
+
+        self.query_queues.clear(channel_id);
+        if let Err(e) = self.remove_agent(channel_id).await {
+            tracing::warn!(error = %e, "Failed to disconnect agent");
+        }
+        if let Err(e) = self.progress_tracker.clear_progress(channel_id).await {
+            tracing::warn!(error = %e, "Failed to clear progress board");
+        }
+        self.session_usage.remove(channel_id);
+        self.recent_errors.clear(channel_id);
+        self.answered_requests.clear(channel_id);
+        self.timeout_overrides.remove(channel_id);
+
+        let reclaimed = self.workspace.remove_channel(channel_id).await?;
+        tracing::info!(
+            reclaimed_bytes = reclaimed,
+            "Channel torn down {}",
+            channel_id.log_format()
+        );
+        Ok(reclaimed)
+    }
+
+    /// Tear down the channel's agent and create a fresh one, for recovering a wedged
+    /// Claude connection. Any in-flight query is cancelled first so its lock is released.
+    ///
+    /// Sessions live in the Claude process and aren't persisted, so the new agent starts
+    /// a new session. Returns the new session id.
+    pub async fn restart_agent(&self, channel_id: &ChannelId) -> Result<SessionId> {
+        self.workspace
+            .is_channel_setup(channel_id)
+            .await
+            .ensure_ready(channel_id)?;
+
+        tracing::info!("Restarting agent {}", channel_id.log_format());
+
+        if self.active_queries.cancel(channel_id) {
+            tracing::info!("Cancelled in-flight query {}", channel_id.log_format());
+        }
+
+        // Wait for the cancelled query to drop the lock so the old agent can be disconnected
+        let old_agent = self.repo_agents.get(channel_id).map(|r| r.clone());
+        let released = match &old_agent {
+            Some(agent_mutex) => timeout(RESTART_LOCK_TIMEOUT, agent_mutex.lock())
+                .await
+                .is_ok(),
+            None => true,
+        };
+        self.repo_agents.remove(channel_id);
+        if let Some(old_agent) = old_agent {
+            if released {
+                if let Err(e) = Self::disconnect_shared(old_agent).await {
+                    tracing::warn!(error = %e, "Failed to disconnect old agent");
+                }
+            } else {
+                // Don't hold up the restart; the old agent goes once its query lets go
+                tracing::warn!(
+                    "Agent lock still held after {:?}, disconnecting old agent in the background {}",
+                    RESTART_LOCK_TIMEOUT,
+                    channel_id.log_format()
+                );
+                tokio::spawn(async move {
+                    drop(old_agent.lock().await);
+                    if let Err(e) = Self::disconnect_shared(old_agent).await {
+                        tracing::warn!(error = %e, "Failed to disconnect old agent");
+                    }
+                });
+            }
+        }
+        if let Err(e) = self.progress_tracker.clear_progress(channel_id).await {
+            tracing::warn!(error = %e, "Failed to clear progress board");
+        }
+
+        let agent = self.create_repo_agent(channel_id.clone()).await?;
+        let session_id = agent.get_session_id();
+        self.replace_agent(channel_id, agent).await;
+        self.reset_session_usage(channel_id, &session_id).await?;
+
+        tracing::info!(
+            session_id = %session_id,
+            "Agent restarted {}",
+            channel_id.log_format()
+        );
+        Ok(session_id)
+    }
+
+    /// Recreate the agent for a channel that is set up on disk but has no live agent
+    /// (e.g. after it was reaped for inactivity).
+    ///
+    /// Returns the channel's setup status; an agent exists only when it's `FullySetup`.
+    pub async fn restore_agent(&self, channel_id: &ChannelId) -> Result<SetupStatus> {
+        if self.has_agent(channel_id) {
+            return Ok(SetupStatus::FullySetup);
+        }
+        let status = self.workspace.is_channel_setup(channel_id).await;
+        if !status.is_ready() {
+            return Ok(status);
+        }
+
+        tracing::info!("Restoring agent on demand {}", channel_id.log_format());
+        let agent = self.create_repo_agent(channel_id.clone()).await?;
+        self.insert_agent_if_absent(channel_id, agent).await;
+        Ok(status)
+    }
+
     /// Cleanup inactive agents (background task)
-    pub async fn cleanup_inactive_agents(&self) -> Result<()> {
-        let timeout = Duration::from_secs(self.settings.agent.agent_timeout_secs);
+    ///
+    /// Warns channels whose agent is close to the idle timeout, and sends an
+    /// idle-timeout notice to each expired agent's channel before disconnecting it.
+    /// Returns the number of agents reaped.
+    pub async fn cleanup_inactive_agents(&self) -> Result<usize> {
+        let mut to_warn = Vec::new();
         let mut to_remove = Vec::new();
 
         for entry in self.repo_agents.iter() {
-            let agent = entry.value().lock().await;
-            if agent.is_expired(timeout) {
-                to_remove.push(entry.key().clone());
+            // A locked agent is busy processing a query, so it can't be idle
+            let Ok(agent) = entry.value().try_lock() else {
+                continue;
+            };
+            let (warn_after, timeout) = self.idle_limits(entry.key());
+            match agent.check_idle(warn_after, timeout) {
+                IdleAction::None => {}
+                IdleAction::Warn { remaining } => {
+                    to_warn.push((entry.key().clone(), remaining));
+                }
+                IdleAction::Reap => {
+                    to_remove.push((entry.key().clone(), agent.get_session_id(), timeout));
+                }
+            }
+        }
+
+        let slack_client = self.progress_tracker.slack_client_ref();
+
+        for (channel_id, remaining) in to_warn {
+            tracing::info!(
+                remaining_secs = remaining.as_secs(),
+                "Warning idle agent {}",
+                channel_id.log_format()
+            );
+            if let Err(e) = slack_client.send_idle_warning(&channel_id, remaining).await {
+                tracing::warn!(
+                    channel_id = %channel_id,
+                    error = %e,
+                    "Failed to send idle warning"
+                );
             }
         }
 
-        for channel_id in to_remove {
+        let mut reaped = 0;
+
+        for (channel_id, session_id, timeout) in to_remove {
             tracing::info!("Removing expired agent for channel {}", channel_id.as_str());
-            self.remove_agent(&channel_id).await?;
+
+            if let Err(e) = slack_client
+                .send_idle_timeout_notice(&channel_id, &session_id, timeout)
+                .await
+            {
+                tracing::warn!(
+                    channel_id = %channel_id,
+                    error = %e,
+                    "Failed to send idle timeout notice"
+                );
+            }
+
+            match self.remove_agent(&channel_id).await {
+                Ok(()) => reaped += 1,
+                Err(e) => tracing::warn!(
+                    channel_id = %channel_id,
+                    error = %e,
+                    "Failed to disconnect expired agent"
+                ),
+            }
         }
 
-        Ok(())
+        Ok(reaped)
     }
 
     /// Check if channel has a configured agent
@@ -340,23 +919,397 @@ impl AgentManager {
         self.repo_agents.contains_key(channel_id)
     }
 
+    /// Number of live repository agents
+    pub fn agent_count(&self) -> usize {
+        self.repo_agents.len()
+    }
+
     /// Get all active agents and their session IDs
     /// Returns a list of (channel_id, session_id) tuples
     pub async fn get_all_active_agents(&self) -> Vec<(ChannelId, String)> {
-        let mut result = Vec::new();
+        self.active_agents()
+            .await
+            .into_iter()
+            .filter_map(|agent| Some((agent.channel_id, agent.session_id?)))
+            .collect()
+    }
 
-        for entry in self.repo_agents.iter() {
-            let channel_id = entry.key().clone();
+    /// Every running repo agent, including ones busy with a query (whose session and
+    /// idle time can't be read without waiting for the query, so they're left out)
+    pub async fn active_agents(&self) -> Vec<ActiveAgent> {
+        let agents: Vec<_> = self
+            .repo_agents
+            .iter()
+            .map(|entry| (entry.key().clone(), entry.value().clone()))
+            .collect();
 
-            // Try to lock with short timeout
-            if let Ok(agent) =
-                tokio::time::timeout(Duration::from_millis(100), entry.value().lock()).await
-            {
-                let session_id = agent.get_session_id();
-                result.push((channel_id, session_id));
+        agents
+            .into_iter()
+            .map(|(channel_id, agent)| {
+                let (session_id, idle) = match agent.try_lock() {
+                    Ok(agent) => (Some(agent.get_session_id()), Some(agent.idle_duration())),
+                    Err(_) => (None, None),
+                };
+                ActiveAgent {
+                    channel_id,
+                    session_id,
+                    idle,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Run `task` for every item with at most `limit` running at once, collecting the
+/// results in completion order
+async fn run_bounded<T, R, F, Fut>(items: Vec<T>, limit: usize, task: F) -> Vec<R>
+where
+    F: FnMut(T) -> Fut,
+    Fut: Future<Output = R>,
+{
+    futures::stream::iter(items)
+        .map(task)
+        .buffer_unordered(limit.max(1))
+        .collect()
+        .await
+}
+
+/// Channels with a live agent that the bot is no longer a member of
+fn channels_to_prune(live: &[ChannelId], members: &HashSet<ChannelId>) -> Vec<ChannelId> {
+    live.iter()
+        .filter(|channel_id| !members.contains(*channel_id))
+        .cloned()
+        .collect()
+}
+
+/// Post the "Agent Ready" notices one at a time, spaced out so large workspaces don't
+/// hit rate limits
+async fn send_startup_notices(
+    slack_client: Arc<SlackClient>,
+    workspace: Arc<Workspace>,
+    targets: Vec<(ChannelId, SessionId)>,
+    command_prefix: String,
+) {
+    let interval = slack_client.limits().min_post_interval;
+    let total = targets.len();
+    let mut success_count = 0;
+
+    for (i, (channel_id, session_id)) in targets.into_iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(interval).await;
+        }
+
+        let notification = format!(
+            "🤖 *Agent Ready*\n\nSession ID: `{}`\n\nI'm ready to help with this repository! Type `{}` for available commands.",
+            session_id,
+            display_command("/help", &command_prefix)
+        );
+
+        match slack_client
+            .send_message(&channel_id, &notification, None)
+            .await
+        {
+            Ok(_) => {
+                success_count += 1;
+                tracing::debug!(
+                    channel_id = %channel_id,
+                    session_id = %session_id,
+                    "Startup notification sent"
+                );
+                if let Err(e) = workspace
+                    .save_last_startup_notice(&channel_id, SystemTime::now())
+                    .await
+                {
+                    tracing::warn!(
+                        channel_id = %channel_id,
+                        error = %e,
+                        "Failed to record startup notification"
+                    );
+                }
+            }
+            Err(e) => {
+                tracing::warn!(
+                    channel_id = %channel_id,
+                    error = %e,
+                    "Failed to send startup notification"
+                );
             }
         }
+    }
+
+    tracing::info!(
+        sent = success_count,
+        total = total,
+        "Startup notifications sent"
+    );
+}
+
+/// Pick which restored channels get an "Agent Ready" notice.
+///
+/// Channels notified less than `cooldown` ago (e.g. before a quick restart) are
+/// skipped.
+fn startup_notice_targets(
+    candidates: Vec<(ChannelId, SessionId, Option<SystemTime>)>,
+    now: SystemTime,
+    cooldown: Duration,
+) -> Vec<(ChannelId, SessionId)> {
+    candidates
+        .into_iter()
+        .filter(|(_, _, last)| match last {
+            // A notice "in the future" (clock went backwards) counts as recent
+            Some(last) => now
+                .duration_since(*last)
+                .map(|elapsed| elapsed >= cooldown)
+                .unwrap_or(false),
+            None => true,
+        })
+        .map(|(channel_id, session_id, _)| (channel_id, session_id))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_settings;
+    use crate::storage::test_workspace;
 
-        result
+    /// A manager over a temporary workspace, with what its agents are built from
+    struct TestManager {
+        _dir: tempfile::TempDir,
+        manager: Arc<AgentManager>,
+        settings: SharedSettings,
+        workspace: Arc<Workspace>,
+        progress_tracker: Arc<ProgressTracker>,
+    }
+
+    impl TestManager {
+        /// A manager whose workspace has a system prompt for each of `channels`
+        async fn new(channels: &[&ChannelId]) -> Self {
+            let (dir, workspace) = test_workspace(channels, "prompt").await;
+            let settings = SharedSettings::new(test_settings());
+            let workspace = Arc::new(workspace);
+            let slack_client = Arc::new(SlackClient::new(test_settings().slack).unwrap());
+            let progress_tracker = Arc::new(ProgressTracker::new(slack_client, Default::default()));
+            let manager = AgentManager::new(
+                settings.clone(),
+                workspace.clone(),
+                progress_tracker.clone(),
+            )
+            .await
+            .unwrap();
+            Self {
+                _dir: dir,
+                manager: Arc::new(manager),
+                settings,
+                workspace,
+                progress_tracker,
+            }
+        }
+
+        /// A repo agent for `channel`, built without connecting
+        async fn agent(&self, channel: &ChannelId) -> RepoAgent {
+            RepoAgent::new(
+                channel.clone(),
+                self.workspace.clone(),
+                self.settings.load(),
+                self.progress_tracker.clone(),
+            )
+            .await
+            .unwrap()
+        }
+    }
+
+    fn candidates(now: SystemTime) -> Vec<(ChannelId, SessionId, Option<SystemTime>)> {
+        vec![
+            (ChannelId::new("C1"), "session-C1".to_string(), None),
+            (
+                ChannelId::new("C2"),
+                "session-C2".to_string(),
+                Some(now - Duration::from_secs(5 * 60)),
+            ),
+            (
+                ChannelId::new("C3"),
+                "session-C3".to_string(),
+                Some(now - Duration::from_secs(2 * 60 * 60)),
+            ),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_run_bounded_limits_concurrency() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_seen = Arc::new(AtomicUsize::new(0));
+
+        let results = run_bounded((0..20).collect(), 3, |i: usize| {
+            let running = running.clone();
+            let max_seen = max_seen.clone();
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                max_seen.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                if i % 4 == 0 { Err(i) } else { Ok(i) }
+            }
+        })
+        .await;
+
+        assert_eq!(max_seen.load(Ordering::SeqCst), 3);
+        // Every task still reports, successes and failures alike
+        assert_eq!(results.len(), 20);
+        assert_eq!(results.iter().filter(|r| r.is_err()).count(), 5);
+    }
+
+    #[test]
+    fn test_channels_to_prune() {
+        let live = vec![
+            ChannelId::new("C1"),
+            ChannelId::new("C2"),
+            ChannelId::new("C3"),
+        ];
+        let members: HashSet<ChannelId> = [ChannelId::new("C1"), ChannelId::new("C3")]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            channels_to_prune(&live, &members),
+            vec![ChannelId::new("C2")]
+        );
+        assert!(channels_to_prune(&[], &members).is_empty());
+    }
+
+    #[test]
+    fn test_startup_notice_targets_cooldown() {
+        let now = SystemTime::now();
+        let targets = startup_notice_targets(candidates(now), now, Duration::from_secs(30 * 60));
+        let channels: Vec<_> = targets.iter().map(|(c, _)| c.as_str()).collect();
+        assert_eq!(channels, vec!["C1", "C3"]);
+
+        // No cooldown: everyone is notified
+        let targets = startup_notice_targets(candidates(now), now, Duration::ZERO);
+        assert_eq!(targets.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_teardown_needs_a_recent_request() {
+        let channel = ChannelId::new("C12345");
+        let test = TestManager::new(&[&channel]).await;
+        let manager = &test.manager;
+
+        // Confirming without a prompt is refused
+        assert!(!manager.take_teardown_request(&channel));
+
+        // A request can be confirmed once
+        manager.request_teardown(&channel);
+        assert!(manager.take_teardown_request(&channel));
+        assert!(!manager.take_teardown_request(&channel));
+
+        // An expired request is refused
+        let expired = Instant::now()
+            .checked_sub(TEARDOWN_CONFIRM_WINDOW + Duration::from_secs(1))
+            .unwrap();
+        manager.teardown_requests.insert(channel.clone(), expired);
+        assert!(!manager.take_teardown_request(&channel));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_inserts_keep_one_agent() {
+        let channel = ChannelId::new("C12345");
+        let test = TestManager::new(&[&channel]).await;
+        let manager = &test.manager;
+
+        // Agents are built without connecting, as a setup and a restore would race
+        let mut inserts = Vec::new();
+        for _ in 0..8 {
+            let agent = test.agent(&channel).await;
+            let manager = manager.clone();
+            let channel = channel.clone();
+            inserts.push(tokio::spawn(async move {
+                manager.insert_agent_if_absent(&channel, agent).await
+            }));
+        }
+        let stored = futures::future::join_all(inserts)
+            .await
+            .into_iter()
+            .filter(|stored| *stored.as_ref().unwrap())
+            .count();
+
+        assert_eq!(stored, 1);
+        assert_eq!(manager.agent_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_active_agents_lists_every_channel() {
+        let channels = [ChannelId::new("C1"), ChannelId::new("C2")];
+        let test = TestManager::new(&[&channels[0], &channels[1]]).await;
+        let manager = &test.manager;
+
+        for channel in &channels {
+            let agent = test.agent(channel).await;
+            assert!(manager.insert_agent_if_absent(channel, agent).await);
+        }
+
+        // A busy agent is still listed, without the details its lock guards
+        let busy = manager.get_repo_agent(&channels[1]).await.unwrap();
+        let _query = busy.lock().await;
+
+        let mut agents = manager.active_agents().await;
+        agents.sort_by(|a, b| a.channel_id.as_str().cmp(b.channel_id.as_str()));
+        assert_eq!(agents.len(), 2);
+        assert_eq!(agents[0].channel_id, channels[0]);
+        assert!(agents[0].session_id.is_some() && agents[0].idle.is_some());
+        assert_eq!(agents[1].channel_id, channels[1]);
+        assert!(agents[1].session_id.is_none() && agents[1].idle.is_none());
+
+        // Shutdown only sees the agents it can reach
+        assert_eq!(manager.get_all_active_agents().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_override_changes_expiry() {
+        use crate::agent::IdleState;
+        use std::time::Instant;
+
+        let test = TestManager::new(&[]).await;
+        let manager = &test.manager;
+        let channel = ChannelId::new("C12345");
+
+        // Idle for 40 minutes, past the 30 minute default
+        let start = Instant::now();
+        let now = start + Duration::from_secs(40 * 60);
+        let (warn_after, timeout) = manager.idle_limits(&channel);
+        assert_eq!(timeout, Duration::from_secs(1800));
+        assert_eq!(
+            IdleState::new(start).check(now, warn_after, timeout),
+            IdleAction::Reap
+        );
+
+        manager
+            .set_agent_timeout(&channel, Some(Duration::from_secs(2 * 60 * 60)))
+            .unwrap();
+        let (warn_after, timeout) = manager.idle_limits(&channel);
+        assert_eq!(warn_after, Some(Duration::from_secs(96 * 60)));
+        assert_eq!(
+            IdleState::new(start).check(now, warn_after, timeout),
+            IdleAction::None
+        );
+        // Other channels keep the default
+        assert_eq!(
+            manager.agent_timeout(&ChannelId::new("C99999")),
+            Duration::from_secs(1800)
+        );
+
+        assert!(
+            manager
+                .set_agent_timeout(&channel, Some(MAX_AGENT_TIMEOUT + Duration::from_secs(1)))
+                .is_err()
+        );
+        assert!(
+            manager
+                .set_agent_timeout(&channel, Some(Duration::from_secs(5)))
+                .is_err()
+        );
+        manager.set_agent_timeout(&channel, None).unwrap();
+        assert_eq!(manager.agent_timeout_override(&channel), None);
     }
 }
@@ -1,11 +1,27 @@
+mod activity;
+mod answers;
+mod client;
+mod errors;
 mod hooks;
 mod main_agent;
 mod manager;
+mod queue;
 mod repo_agent;
+mod response;
+mod retry;
 mod types;
 
-pub use hooks::create_todo_hooks;
-pub use main_agent::MainAgent;
-pub use manager::AgentManager;
+pub use activity::{ActiveQueries, ActiveQueryGuard, IdleAction, IdleState};
+pub use answers::{Answer, AnsweredRequests, MAX_ANSWERS_PER_CHANNEL};
+pub use client::AgentClient;
+pub use errors::{ErrorRecord, MAX_RECENT_ERRORS, RecentErrors};
+pub use hooks::{create_todo_hooks, format_plan_summary};
+pub use main_agent::{MainAgent, repo_summary};
+pub use manager::{
+    ActiveAgent, AgentManager, MAX_AGENT_TIMEOUT, MIN_AGENT_TIMEOUT, TEARDOWN_CONFIRM_WINDOW,
+};
+pub use queue::{Enqueued, QueryQueues, QueuedQuery};
 pub use repo_agent::RepoAgent;
-pub use types::{Plan, Task, TaskStatus};
+pub use response::{AgentResponse, ToolAction};
+pub use retry::{ConnectAttempts, ConnectRetry};
+pub use types::{ChannelSyncReport, Plan, SetupReport, Task, TaskStatus};
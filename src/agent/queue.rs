@@ -0,0 +1,171 @@
+//! Per-channel FIFO of queries waiting for the channel's agent
+
+use crate::slack::{ChannelId, MessageTs, ThreadTs, UserId};
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+/// A user message waiting to be sent to the agent
+#[derive(Debug, Clone)]
+pub struct QueuedQuery {
+    /// What is sent to the agent (commands such as `/summarize` expand into a prompt)
+    pub text: String,
+    /// The message as the user wrote it
+    pub request: String,
+    /// Who sent it
+    pub user: UserId,
+    pub thread_ts: Option<ThreadTs>,
+    pub ts: MessageTs,
+    /// The earlier answer to edit in place, when the user edited an answered request
+    pub replaces: Option<MessageTs>,
+}
+
+/// Outcome of adding a query to a channel's queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Enqueued {
+    /// Nothing was running; the caller must start a worker to drain the queue
+    Started,
+    /// Waiting behind the running query; position 1 runs next
+    Queued { position: usize },
+    /// The queue is at capacity and the query was dropped
+    Full,
+}
+
+#[derive(Debug, Default)]
+struct ChannelQueue {
+    pending: VecDeque<QueuedQuery>,
+    /// Whether a worker is currently draining this channel's queue
+    running: bool,
+}
+
+/// Bounded per-channel queues, so messages that arrive while the agent is busy are
+/// processed in order instead of being rejected
+#[derive(Debug, Clone)]
+pub struct QueryQueues {
+    queues: Arc<DashMap<ChannelId, ChannelQueue>>,
+    /// Maximum number of queries waiting behind the running one
+    capacity: usize,
+}
+
+impl QueryQueues {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            queues: Arc::new(DashMap::new()),
+            capacity,
+        }
+    }
+
+    /// Add a query to the channel's queue
+    pub fn push(&self, channel_id: &ChannelId, query: QueuedQuery) -> Enqueued {
+        let mut queue = self.queues.entry(channel_id.clone()).or_default();
+
+        if !queue.running {
+            queue.running = true;
+            queue.pending.push_back(query);
+            return Enqueued::Started;
+        }
+
+        if queue.pending.len() >= self.capacity {
+            return Enqueued::Full;
+        }
+
+        queue.pending.push_back(query);
+        Enqueued::Queued {
+            position: queue.pending.len(),
+        }
+    }
+
+    /// Take the next query for the channel's worker. Returns `None` (and marks the
+    /// channel idle) once the queue is drained, at which point the worker should exit.
+    pub fn next(&self, channel_id: &ChannelId) -> Option<QueuedQuery> {
+        let mut queue = self.queues.get_mut(channel_id)?;
+        let query = queue.pending.pop_front();
+        if query.is_none() {
+            queue.running = false;
+            drop(queue);
+            self.queues
+                .remove_if(channel_id, |_, q| !q.running && q.pending.is_empty());
+        }
+        query
+    }
+
+    /// Drop every query waiting for the channel (the running one is unaffected).
+    /// Returns how many were dropped.
+    pub fn clear(&self, channel_id: &ChannelId) -> usize {
+        self.queues
+            .get_mut(channel_id)
+            .map(|mut queue| {
+                let dropped = queue.pending.len();
+                queue.pending.clear();
+                dropped
+            })
+            .unwrap_or(0)
+    }
+
+    /// Number of queries waiting for the channel
+    pub fn len(&self, channel_id: &ChannelId) -> usize {
+        self.queues
+            .get(channel_id)
+            .map(|queue| queue.pending.len())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(text: &str) -> QueuedQuery {
+        QueuedQuery {
+            text: text.to_string(),
+            request: text.to_string(),
+            user: UserId::new("U123"),
+            thread_ts: None,
+            ts: MessageTs::new("1.0"),
+            replaces: None,
+        }
+    }
+
+    #[test]
+    fn test_queue_runs_in_order_and_reports_position() {
+        let queues = QueryQueues::new(2);
+        let channel = ChannelId::new("C123");
+
+        assert_eq!(queues.push(&channel, query("a")), Enqueued::Started);
+        // The worker picks up the first query; later ones wait behind it
+        assert_eq!(queues.next(&channel).unwrap().text, "a");
+        assert_eq!(
+            queues.push(&channel, query("b")),
+            Enqueued::Queued { position: 1 }
+        );
+        assert_eq!(
+            queues.push(&channel, query("c")),
+            Enqueued::Queued { position: 2 }
+        );
+        assert_eq!(queues.push(&channel, query("d")), Enqueued::Full);
+
+        assert_eq!(queues.next(&channel).unwrap().text, "b");
+        assert_eq!(queues.next(&channel).unwrap().text, "c");
+        assert!(queues.next(&channel).is_none());
+
+        // Once drained, the next query starts a new worker
+        assert_eq!(queues.push(&channel, query("e")), Enqueued::Started);
+    }
+
+    #[test]
+    fn test_clear_drops_waiting_queries() {
+        let queues = QueryQueues::new(5);
+        let channel = ChannelId::new("C123");
+
+        queues.push(&channel, query("a"));
+        queues.next(&channel);
+        queues.push(&channel, query("b"));
+        queues.push(&channel, query("c"));
+        assert_eq!(queues.len(&channel), 2);
+
+        assert_eq!(queues.clear(&channel), 2);
+        assert_eq!(queues.len(&channel), 0);
+        assert!(queues.next(&channel).is_none());
+        assert_eq!(queues.clear(&ChannelId::new("C999")), 0);
+    }
+}
@@ -1,22 +1,64 @@
-use crate::agent::{Plan, create_todo_hooks};
-use crate::config::Settings;
+use crate::agent::{AgentClient, ConnectRetry, IdleAction, IdleState, Plan, create_todo_hooks};
+use crate::config::{AgentConfig, ResponseLocale, Settings};
 use crate::error::{Result, SlackCoderError};
-use crate::session::{SessionId, generate_session_id};
-use crate::slack::{ChannelId, ProgressTracker};
+use crate::session::{SessionId, SessionRegistry};
+use crate::slack::{ChannelId, ProgressTracker, ThreadTs};
 use crate::storage::Workspace;
-use claude_agent_sdk_rs::{
-    ClaudeAgentOptions, ClaudeClient, Message, PermissionMode, SystemPrompt,
-};
-use futures::Stream;
+use claude_agent_sdk_rs::{ClaudeAgentOptions, ClaudeClient, Message, SystemPrompt};
+use futures::{Stream, StreamExt};
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::{Duration, Instant};
 
-pub struct RepoAgent {
-    client: ClaudeClient,
+/// Replaces the end of a repository prompt cut to fit the budget
+const PROMPT_TRUNCATION_NOTICE: &str =
+    "\n\n_[Repository prompt truncated to fit MAX_SYSTEM_PROMPT_CHARS]_";
+
+/// Character budget for a repo agent's system prompt
+#[derive(Debug, Clone, Copy)]
+struct PromptBudget {
+    /// 0 disables the check
+    max_chars: usize,
+    /// Cut the repository prompt to fit, rather than only warning
+    truncate: bool,
+}
+
+impl PromptBudget {
+    fn from_config(config: &AgentConfig) -> Self {
+        Self {
+            max_chars: config.max_system_prompt_chars,
+            truncate: config.truncate_system_prompt,
+        }
+    }
+
+    fn is_exceeded(&self, chars: usize) -> bool {
+        self.max_chars > 0 && chars > self.max_chars
+    }
+
+    /// The repository prompt cut (at a line break where possible) so that, with
+    /// `other_chars` of other sections and the notice, the whole prompt fits
+    fn truncate_repo_prompt(&self, repo_prompt: &str, other_chars: usize) -> String {
+        let notice_chars = PROMPT_TRUNCATION_NOTICE.chars().count();
+        let keep = self
+            .max_chars
+            .saturating_sub(other_chars)
+            .saturating_sub(notice_chars);
+        let end = repo_prompt
+            .char_indices()
+            .nth(keep)
+            .map_or(repo_prompt.len(), |(i, _)| i);
+        let kept = &repo_prompt[..end];
+        let kept = kept.rfind('\n').map_or(kept, |i| &kept[..i]);
+        format!("{}{}", kept, PROMPT_TRUNCATION_NOTICE)
+    }
+}
+
+pub struct RepoAgent<C = ClaudeClient> {
+    client: C,
     plan: Arc<Mutex<Plan>>,
     channel_id: ChannelId,
-    current_session_id: Arc<RwLock<SessionId>>,
-    last_activity: Arc<RwLock<Instant>>,
+    sessions: Arc<RwLock<SessionRegistry>>,
+    idle: Arc<RwLock<IdleState>>,
+    connect_retry: ConnectRetry,
 }
 
 impl RepoAgent {
@@ -24,35 +66,40 @@ impl RepoAgent {
     pub async fn new(
         channel_id: ChannelId,
         workspace: Arc<Workspace>,
-        _settings: Arc<Settings>,
+        settings: Arc<Settings>,
         progress_tracker: Arc<ProgressTracker>,
     ) -> Result<Self> {
         let plan = Arc::new(Mutex::new(Plan::new()));
 
-        // Start with common workflow requirements (so they're seen first!)
-        let mut system_prompt = String::new();
-        system_prompt.push_str(include_str!("../../prompts/repo-agent-workflow.md"));
-        system_prompt.push_str("\n\n---\n\n");
+        // Channel overrides (`/permissions`, `/lang`, `/config import`) win over global settings
+        let channel_config = workspace.load_channel_config(&channel_id).await?;
+        let permission_mode = channel_config
+            .permission_mode
+            .unwrap_or(settings.agent.permission_mode);
+        let locale = channel_config
+            .response_locale
+            .as_ref()
+            .or(settings.agent.response_locale.as_ref());
 
-        // Append repository-specific system prompt from disk
-        let repo_prompt = workspace
-            .load_system_prompt(&channel_id)
-            .await
-            .map_err(|e| {
-                SlackCoderError::Config(format!(
-                    "Failed to load system prompt for channel {}: {}",
-                    channel_id.as_str(),
-                    e
-                ))
-            })?;
-        system_prompt.push_str(&repo_prompt);
+        let system_prompt =
+            Self::build_system_prompt(&workspace, &channel_id, locale, &settings.agent).await?;
+        let claude = settings.claude.with_overrides(&channel_config);
+        tracing::debug!(
+            permission_mode = %permission_mode,
+            model = %claude.model,
+            max_tokens = claude.max_tokens,
+            "Creating repo agent {}",
+            channel_id.log_format()
+        );
 
         // Create hooks
         let hooks = create_todo_hooks(Arc::clone(&plan), progress_tracker, channel_id.clone());
 
         // Build agent options
         let options = ClaudeAgentOptions::builder()
-            .permission_mode(PermissionMode::BypassPermissions)
+            .permission_mode(permission_mode.to_sdk())
+            .model(claude.model.clone())
+            .env(claude.cli_env())
             .system_prompt(SystemPrompt::Text(system_prompt))
             .cwd(workspace.repo_path(&channel_id))
             .hooks(hooks.build())
@@ -60,56 +107,196 @@ impl RepoAgent {
 
         let client = ClaudeClient::new(options);
 
+        Ok(Self::with_client(client, plan, channel_id, &settings.agent))
+    }
+
+    /// Assemble the agent's system prompt. Sections, in order:
+    ///
+    /// 1. The global overlay (`GLOBAL_PROMPT_OVERLAY_PATH`) - organization-wide rules,
+    ///    seen first
+    /// 2. Common workflow requirements (`prompts/repo-agent-workflow.md`)
+    /// 3. The repository-specific prompt generated during setup
+    /// 4. The response language, if one is set
+    /// 5. The channel's optional `overlay.md` - team standing instructions, last so they
+    ///    can refine anything above without editing the generated prompt
+    ///
+    /// Only the generated repository prompt can run away in size, so it's the section
+    /// cut when the whole prompt is over budget and truncation is on.
+    async fn build_system_prompt(
+        workspace: &Workspace,
+        channel_id: &ChannelId,
+        locale: Option<&ResponseLocale>,
+        agent: &AgentConfig,
+    ) -> Result<String> {
+        let budget = PromptBudget::from_config(agent);
+        let mut system_prompt = String::new();
+        if let Some(overlay) = &agent.global_prompt_overlay {
+            system_prompt.push_str(overlay);
+            system_prompt.push_str("\n\n---\n\n");
+        }
+        system_prompt.push_str(include_str!("../../prompts/repo-agent-workflow.md"));
+        system_prompt.push_str("\n\n---\n\n");
+
+        // Append repository-specific system prompt from disk
+        let mut repo_prompt = match workspace.load_system_prompt(channel_id).await {
+            Ok(prompt) => prompt,
+            Err(e) => {
+                // Usually an interrupted setup rather than an I/O problem - say what's missing
+                let status = workspace.is_channel_setup(channel_id).await;
+                status.ensure_ready(channel_id)?;
+                return Err(SlackCoderError::Config(format!(
+                    "Failed to load system prompt for channel {}: {}",
+                    channel_id.as_str(),
+                    e
+                )));
+            }
+        };
+
+        // Sections after the repository prompt
+        let mut tail = String::new();
+        if let Some(locale) = locale {
+            tracing::debug!(
+                locale = %locale,
+                "Setting response language {}",
+                channel_id.log_format()
+            );
+            tail.push_str("\n\n---\n\n");
+            tail.push_str(&locale.instruction());
+        }
+
+        // Append the channel's standing instructions, if any
+        if let Some(overlay) = workspace.load_prompt_overlay(channel_id).await {
+            tracing::info!(
+                overlay_len = overlay.len(),
+                "Applying prompt overlay {}",
+                channel_id.log_format()
+            );
+            tail.push_str("\n\n---\n\n");
+            tail.push_str(&overlay);
+        }
+
+        let other_chars = system_prompt.chars().count() + tail.chars().count();
+        let repo_chars = repo_prompt.chars().count();
+        tracing::debug!(
+            repo_prompt_chars = repo_chars,
+            other_chars = other_chars,
+            total_chars = repo_chars + other_chars,
+            max_chars = budget.max_chars,
+            "System prompt size {}",
+            channel_id.log_format()
+        );
+        if budget.is_exceeded(repo_chars + other_chars) {
+            tracing::warn!(
+                repo_prompt_chars = repo_chars,
+                total_chars = repo_chars + other_chars,
+                max_chars = budget.max_chars,
+                truncate = budget.truncate,
+                "System prompt exceeds MAX_SYSTEM_PROMPT_CHARS {}",
+                channel_id.log_format()
+            );
+            if budget.truncate {
+                repo_prompt = budget.truncate_repo_prompt(&repo_prompt, other_chars);
+            }
+        }
+
+        system_prompt.push_str(&repo_prompt);
+        system_prompt.push_str(&tail);
+        Ok(system_prompt)
+    }
+}
+
+impl<C: AgentClient> RepoAgent<C> {
+    /// An agent around an already configured client
+    fn with_client(
+        client: C,
+        plan: Arc<Mutex<Plan>>,
+        channel_id: ChannelId,
+        agent: &AgentConfig,
+    ) -> Self {
         // Generate initial session ID
-        let session_id = generate_session_id(&channel_id);
+        let sessions = SessionRegistry::new(channel_id.clone(), agent.session_scope);
         tracing::info!(
             "Generated session ID: {} for {}",
-            session_id,
+            sessions.channel_session(),
             channel_id.log_format()
         );
 
-        Ok(Self {
+        Self {
             client,
             plan,
             channel_id,
-            current_session_id: Arc::new(RwLock::new(session_id)),
-            last_activity: Arc::new(RwLock::new(Instant::now())),
-        })
+            sessions: Arc::new(RwLock::new(sessions)),
+            idle: Arc::new(RwLock::new(IdleState::new(Instant::now()))),
+            connect_retry: ConnectRetry::from_config(agent),
+        }
     }
 
     /// Connect to Claude API
     pub async fn connect(&mut self) -> Result<()> {
-        self.client
-            .connect()
-            .await
-            .map_err(|e| SlackCoderError::ClaudeAgent(e.to_string()))?;
+        let what = format!("repo agent {}", self.channel_id.log_format());
+        let mut attempts = self.connect_retry.attempts(&what);
+        while let Err(e) = self.client.connect().await {
+            attempts.retry_after(e).await?;
+        }
+        attempts.connected();
         self.update_activity();
         Ok(())
     }
 
-    /// Send query to agent with session management
-    pub async fn query(&mut self, message: &str) -> Result<()> {
-        let session_id = self.current_session_id.read().unwrap().clone();
+    /// Send query to agent with session management. `thread` is the Slack thread the
+    /// message belongs to; with `SESSION_SCOPE=thread` it picks the session. `user` is
+    /// the attributed requester (`CLAUDE_USER_ATTRIBUTION`). Returns the session the
+    /// query was sent in.
+    pub async fn query(
+        &mut self,
+        message: &str,
+        thread: Option<&ThreadTs>,
+        user: Option<&str>,
+    ) -> Result<SessionId> {
+        let session_id = self.sessions.write().unwrap().session_for(thread);
 
         tracing::debug!("Sending query with session_id: {}", session_id);
 
         self.client
-            .query_with_session(message, session_id)
+            .query(message, session_id.clone(), user)
             .await
             .map_err(|e| SlackCoderError::ClaudeAgent(e.to_string()))?;
 
         self.update_activity();
-        Ok(())
+        Ok(session_id)
     }
 
     /// Get response stream from agent
     pub fn receive_response(
         &mut self,
-    ) -> impl Stream<Item = std::result::Result<Message, claude_agent_sdk_rs::ClaudeError>> + '_
+    ) -> impl Stream<Item = std::result::Result<Message, claude_agent_sdk_rs::ClaudeError>> + Send + '_
     {
         self.client.receive_response()
     }
 
+    /// Stop the running turn and discard the rest of its output, so the next query
+    /// doesn't read a stale response. Returns `false` if the agent didn't settle within
+    /// `grace`; it should then be restarted.
+    pub async fn interrupt(&mut self, grace: Duration) -> bool {
+        if let Err(e) = self.client.interrupt().await {
+            tracing::warn!(error = %e, "Failed to interrupt agent {}", self.channel_id.log_format());
+            return false;
+        }
+
+        let drain = async {
+            let mut stream = std::pin::pin!(self.client.receive_response());
+            while let Some(message) = stream.next().await {
+                if matches!(message, Ok(Message::Result(_))) {
+                    break;
+                }
+            }
+        };
+        let settled = tokio::time::timeout(grace, drain).await.is_ok();
+
+        self.update_activity();
+        settled
+    }
+
     /// Get current plan state
     pub fn get_plan(&self) -> Plan {
         self.plan.lock().unwrap().clone()
@@ -120,15 +307,34 @@ impl RepoAgent {
         Arc::clone(&self.plan)
     }
 
-    /// Update last activity timestamp
+    /// Reset the todo plan to empty
+    pub fn reset_plan(&self) {
+        if let Ok(mut plan) = self.plan.lock() {
+            *plan = Plan::new();
+        }
+    }
+
+    /// Update last activity timestamp (re-arms the idle warning)
     fn update_activity(&self) {
-        *self.last_activity.write().unwrap() = Instant::now();
+        self.idle.write().unwrap().touch(Instant::now());
+    }
+
+    /// How long the agent has been idle
+    pub fn idle_duration(&self) -> Duration {
+        self.idle.read().unwrap().idle_for(Instant::now())
+    }
+
+    /// Whether the channel has been warned that this agent is about to go idle
+    pub fn idle_warned(&self) -> bool {
+        self.idle.read().unwrap().warned()
     }
 
-    /// Check if agent is expired based on timeout
-    pub fn is_expired(&self, timeout: Duration) -> bool {
-        let last = *self.last_activity.read().unwrap();
-        last.elapsed() > timeout
+    /// Decide whether to warn about or reap this agent; see [`IdleState::check`]
+    pub fn check_idle(&self, warn_after: Option<Duration>, timeout: Duration) -> IdleAction {
+        self.idle
+            .write()
+            .unwrap()
+            .check(Instant::now(), warn_after, timeout)
     }
 
     /// Get channel ID
@@ -136,9 +342,10 @@ impl RepoAgent {
         &self.channel_id
     }
 
-    /// Start a new session (clears conversation context)
-    pub async fn start_new_session(&mut self) -> Result<SessionId> {
-        let new_session_id = generate_session_id(&self.channel_id);
+    /// Start a new session (clears conversation context, and the todo plan unless `keep_plan`).
+    /// With thread-scoped sessions every thread starts over too.
+    pub async fn start_new_session(&mut self, keep_plan: bool) -> Result<SessionId> {
+        let new_session_id = self.sessions.write().unwrap().reset();
 
         tracing::info!(
             "Starting new session: {} for {}",
@@ -146,20 +353,24 @@ impl RepoAgent {
             self.channel_id.log_format()
         );
 
-        *self.current_session_id.write().unwrap() = new_session_id.clone();
-
-        // Clear the todo plan for the new session
-        if let Ok(mut plan) = self.plan.lock() {
-            *plan = Plan::new();
+        // Clear the todo plan for the new session unless asked to carry it over
+        if !keep_plan {
+            self.reset_plan();
         }
 
         self.update_activity();
         Ok(new_session_id)
     }
 
-    /// Get current session ID
+    /// Get current (channel-wide) session ID
     pub fn get_session_id(&self) -> SessionId {
-        self.current_session_id.read().unwrap().clone()
+        self.sessions.read().unwrap().channel_session().clone()
+    }
+
+    /// Whether `session_id` is one of the agent's current sessions (it hasn't been
+    /// reset or replaced by a restart)
+    pub fn has_session(&self, session_id: &str) -> bool {
+        self.sessions.read().unwrap().contains(session_id)
     }
 
     /// Disconnect from Claude API
@@ -171,3 +382,206 @@ impl RepoAgent {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::client::stub::StubClient;
+    use crate::config::{SessionScope, UserAttribution, test_settings};
+    use crate::storage::test_workspace;
+    use claude_agent_sdk_rs::ClaudeError;
+
+    fn stub_agent(client: StubClient) -> RepoAgent<StubClient> {
+        RepoAgent::with_client(
+            client,
+            Arc::new(Mutex::new(Plan::new())),
+            ChannelId::new("C123"),
+            &test_settings().agent,
+        )
+    }
+
+    /// An agent whose first connects fail with `errors`, retrying up to 3 times
+    fn failing_agent(errors: Vec<ClaudeError>) -> RepoAgent<StubClient> {
+        RepoAgent::with_client(
+            StubClient::failing_connects(errors),
+            Arc::new(Mutex::new(Plan::new())),
+            ChannelId::new("C123"),
+            &AgentConfig {
+                connect_max_attempts: 3,
+                connect_backoff_ms: 1,
+                ..test_settings().agent
+            },
+        )
+    }
+
+    fn io_error(kind: std::io::ErrorKind) -> ClaudeError {
+        ClaudeError::from(std::io::Error::from(kind))
+    }
+
+    #[tokio::test]
+    async fn test_connect_retries_transient_failure() {
+        let mut agent = failing_agent(vec![io_error(std::io::ErrorKind::ConnectionReset)]);
+        agent.connect().await.unwrap();
+        assert_eq!(agent.client.connect_attempts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_connect_gives_up_after_max_attempts() {
+        let errors = (0..5)
+            .map(|_| io_error(std::io::ErrorKind::ConnectionReset))
+            .collect();
+        let mut agent = failing_agent(errors);
+        let err = agent.connect().await;
+        assert!(matches!(err, Err(SlackCoderError::ClaudeAgent(_))));
+        assert_eq!(agent.client.connect_attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn test_connect_does_not_retry_permanent_failure() {
+        let mut agent = failing_agent(vec![io_error(std::io::ErrorKind::NotFound)]);
+        assert!(agent.connect().await.is_err());
+        assert_eq!(agent.client.connect_attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_query_returns_thread_session() {
+        let mut agent = RepoAgent::with_client(
+            StubClient::default(),
+            Arc::new(Mutex::new(Plan::new())),
+            ChannelId::new("C123"),
+            &AgentConfig {
+                session_scope: SessionScope::Thread,
+                ..test_settings().agent
+            },
+        );
+        let thread = ThreadTs::new("1.0001");
+
+        let session = agent.query("hi", Some(&thread), None).await.unwrap();
+        assert_ne!(session, agent.get_session_id());
+        assert_eq!(agent.client.queries[0].1, session);
+        // The same thread keeps its session
+        assert_eq!(
+            agent.query("again", Some(&thread), None).await.unwrap(),
+            session
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_passes_attribution_to_client() {
+        let mut agent = stub_agent(StubClient::default());
+        let user = UserAttribution::Hashed.attribute("U123");
+
+        agent.query("fix it", None, user.as_deref()).await.unwrap();
+        agent.query("and this", None, None).await.unwrap();
+
+        let session = agent.get_session_id();
+        assert_eq!(
+            agent.client.queries,
+            [
+                ("fix it".to_string(), session.clone(), user),
+                ("and this".to_string(), session, None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_system_prompt_includes_overlay_last() {
+        let agent = test_settings().agent;
+        let channel = ChannelId::new("C123");
+        let (_dir, workspace) = test_workspace(&[&channel], "REPO PROMPT").await;
+
+        let prompt = RepoAgent::build_system_prompt(&workspace, &channel, None, &agent)
+            .await
+            .unwrap();
+        assert!(prompt.ends_with("REPO PROMPT"));
+
+        tokio::fs::write(workspace.prompt_overlay_path(&channel), "TEAM OVERLAY")
+            .await
+            .unwrap();
+
+        let prompt = RepoAgent::build_system_prompt(&workspace, &channel, None, &agent)
+            .await
+            .unwrap();
+        let repo_pos = prompt.find("REPO PROMPT").unwrap();
+        let overlay_pos = prompt.find("TEAM OVERLAY").unwrap();
+        assert!(repo_pos < overlay_pos);
+        assert!(prompt.ends_with("TEAM OVERLAY"));
+    }
+
+    #[tokio::test]
+    async fn test_system_prompt_includes_response_language() {
+        let agent = test_settings().agent;
+        let channel = ChannelId::new("C123");
+        let (_dir, workspace) = test_workspace(&[&channel], "REPO PROMPT").await;
+        tokio::fs::write(workspace.prompt_overlay_path(&channel), "TEAM OVERLAY")
+            .await
+            .unwrap();
+
+        let locale: ResponseLocale = "ja".parse().unwrap();
+        let prompt = RepoAgent::build_system_prompt(&workspace, &channel, Some(&locale), &agent)
+            .await
+            .unwrap();
+        let language_pos = prompt.find("Respond in Japanese").unwrap();
+        assert!(prompt.find("REPO PROMPT").unwrap() < language_pos);
+        assert!(language_pos < prompt.find("TEAM OVERLAY").unwrap());
+
+        let prompt = RepoAgent::build_system_prompt(&workspace, &channel, None, &agent)
+            .await
+            .unwrap();
+        assert!(!prompt.contains("Response Language"));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_repo_prompt_is_truncated() {
+        let channel = ChannelId::new("C123");
+        let runaway = "generated line\n".repeat(10_000);
+        let (_dir, workspace) = test_workspace(&[&channel], &runaway).await;
+        tokio::fs::write(workspace.prompt_overlay_path(&channel), "TEAM OVERLAY")
+            .await
+            .unwrap();
+
+        let workflow_chars = include_str!("../../prompts/repo-agent-workflow.md")
+            .chars()
+            .count();
+        let max_chars = workflow_chars + 1000;
+
+        // Over budget but only warned about: the prompt is left whole
+        let mut agent = test_settings().agent;
+        agent.max_system_prompt_chars = max_chars;
+        let prompt = RepoAgent::build_system_prompt(&workspace, &channel, None, &agent)
+            .await
+            .unwrap();
+        assert!(PromptBudget::from_config(&agent).is_exceeded(prompt.chars().count()));
+        assert!(prompt.contains(&runaway));
+
+        // Truncated: fits the budget, keeps the sections around it and says it was cut
+        agent.truncate_system_prompt = true;
+        let prompt = RepoAgent::build_system_prompt(&workspace, &channel, None, &agent)
+            .await
+            .unwrap();
+        assert!(prompt.chars().count() <= max_chars);
+        assert!(prompt.contains("generated line\n"));
+        assert!(prompt.contains(PROMPT_TRUNCATION_NOTICE));
+        assert!(prompt.ends_with("TEAM OVERLAY"));
+        // Cut at a line break, not mid-line
+        assert!(prompt.contains(&format!("generated line{}", PROMPT_TRUNCATION_NOTICE)));
+    }
+
+    #[tokio::test]
+    async fn test_system_prompt_starts_with_global_overlay() {
+        let mut agent = test_settings().agent;
+        agent.global_prompt_overlay = Some("ORG RULES: never force-push".to_string());
+        let channel = ChannelId::new("C123");
+        let (_dir, workspace) = test_workspace(&[&channel], "REPO PROMPT").await;
+        tokio::fs::write(workspace.prompt_overlay_path(&channel), "TEAM OVERLAY")
+            .await
+            .unwrap();
+
+        let prompt = RepoAgent::build_system_prompt(&workspace, &channel, None, &agent)
+            .await
+            .unwrap();
+        assert!(prompt.starts_with("ORG RULES: never force-push\n\n---\n\n"));
+        assert!(prompt.contains(include_str!("../../prompts/repo-agent-workflow.md")));
+        assert!(prompt.ends_with("TEAM OVERLAY"));
+    }
+}
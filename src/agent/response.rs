@@ -0,0 +1,320 @@
+//! What the agent produced for a query: its final text plus the actions it took
+
+use claude_agent_sdk_rs::{ContentBlock, Message, ResultMessage};
+use std::path::Path;
+
+/// Files or commands listed per line of the summary before the rest are counted
+const MAX_LISTED: usize = 5;
+
+/// Longest command shown in the summary, in characters
+const MAX_COMMAND_CHARS: usize = 60;
+
+/// A tool call worth telling the user about
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolAction {
+    /// A file created or edited (`Write`, `Edit`, `MultiEdit`, `NotebookEdit`)
+    FileWritten(String),
+    /// A shell command (`Bash`)
+    CommandRun(String),
+}
+
+impl ToolAction {
+    /// The action for a tool call, or `None` for tools that only read
+    pub fn from_tool_use(name: &str, input: &serde_json::Value) -> Option<Self> {
+        let field = |key: &str| input.get(key)?.as_str().map(str::to_string);
+        match name {
+            "Write" | "Edit" | "MultiEdit" => field("file_path").map(Self::FileWritten),
+            "NotebookEdit" => field("notebook_path").map(Self::FileWritten),
+            "Bash" => field("command").map(Self::CommandRun),
+            _ => None,
+        }
+    }
+
+    /// One compact line for the verbose feed, e.g. "✏️ `src/main.rs`"
+    pub fn describe(&self, repo_root: Option<&Path>) -> String {
+        match self {
+            Self::FileWritten(path) => format!("✏️ `{}`", display_path(path, repo_root)),
+            Self::CommandRun(command) => format!("💻 `{}`", shorten_command(command)),
+        }
+    }
+}
+
+/// An agent response gathered from the message stream
+#[derive(Debug, Default)]
+pub struct AgentResponse {
+    /// Text blocks the agent wrote while working, in order
+    pub interim_text: Vec<String>,
+    /// Files written and commands run, in the order the agent called the tools
+    pub actions: Vec<ToolAction>,
+    /// The final result message, if the stream got that far
+    pub result: Option<ResultMessage>,
+}
+
+impl AgentResponse {
+    /// Add a streamed message. Returns `true` once the final result has arrived.
+    pub fn record(&mut self, message: Message) -> bool {
+        match message {
+            Message::Assistant(msg) => {
+                for block in msg.message.content {
+                    match block {
+                        ContentBlock::Text(text) => self.interim_text.push(text.text),
+                        ContentBlock::ToolUse(tool) => {
+                            if let Some(action) = ToolAction::from_tool_use(&tool.name, &tool.input)
+                            {
+                                tracing::debug!(action = ?action, "Agent tool action");
+                                self.actions.push(action);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                false
+            }
+            Message::Result(res) => {
+                self.result = Some(res);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The final answer, empty if there's no result (or it had no text)
+    pub fn text(&self) -> String {
+        self.result
+            .as_ref()
+            .and_then(|res| res.result.clone())
+            .unwrap_or_default()
+    }
+
+    /// Everything the agent wrote so far (for responses cut off before the result)
+    pub fn partial_text(&self) -> String {
+        self.interim_text.join("\n\n")
+    }
+
+    /// Distinct files written, in first-touched order, relative to `repo_root` if under it
+    pub fn files_changed(&self, repo_root: Option<&Path>) -> Vec<String> {
+        let mut files: Vec<String> = Vec::new();
+        for action in &self.actions {
+            if let ToolAction::FileWritten(path) = action {
+                let shown = display_path(path, repo_root);
+                if !files.contains(&shown) {
+                    files.push(shown);
+                }
+            }
+        }
+        files
+    }
+
+    /// Commands run, in order
+    pub fn commands_run(&self) -> Vec<&str> {
+        self.actions
+            .iter()
+            .filter_map(|action| match action {
+                ToolAction::CommandRun(command) => Some(command.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// A compact "Actions taken" section for Slack, or `None` if the agent only read
+    pub fn actions_summary(&self, repo_root: Option<&Path>) -> Option<String> {
+        let files = self.files_changed(repo_root);
+        let commands: Vec<String> = self
+            .commands_run()
+            .into_iter()
+            .map(|command| shorten_command(command))
+            .collect();
+        if files.is_empty() && commands.is_empty() {
+            return None;
+        }
+
+        let mut summary = "*Actions taken*".to_string();
+        if !files.is_empty() {
+            summary.push_str(&format!(
+                "\n• Changed {}: {}",
+                plural(files.len(), "file"),
+                list_items(&files)
+            ));
+        }
+        if !commands.is_empty() {
+            summary.push_str(&format!(
+                "\n• Ran {}: {}",
+                plural(commands.len(), "command"),
+                list_items(&commands)
+            ));
+        }
+        Some(summary)
+    }
+}
+
+/// `path` relative to `repo_root` if it's under it
+fn display_path(path: &str, repo_root: Option<&Path>) -> String {
+    repo_root
+        .and_then(|root| Path::new(path).strip_prefix(root).ok())
+        .map(|relative| relative.display().to_string())
+        .unwrap_or_else(|| path.to_string())
+}
+
+fn plural(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// `a`, `b`, `c` +2 more
+fn list_items(items: &[String]) -> String {
+    let mut listed = items
+        .iter()
+        .take(MAX_LISTED)
+        .map(|item| format!("`{}`", item))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if items.len() > MAX_LISTED {
+        listed.push_str(&format!(" +{} more", items.len() - MAX_LISTED));
+    }
+    listed
+}
+
+/// First line of a command, trimmed to `MAX_COMMAND_CHARS` and safe inside backticks
+fn shorten_command(command: &str) -> String {
+    let line = command
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .replace('`', "'");
+    let multiline = command.trim().contains('\n');
+    if line.chars().count() > MAX_COMMAND_CHARS || multiline {
+        let kept: String = line.chars().take(MAX_COMMAND_CHARS).collect();
+        format!("{}…", kept.trim_end())
+    } else {
+        line
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Messages as the Claude CLI streams them
+    fn synthetic_stream() -> Vec<Message> {
+        [
+            json!({
+                "type": "assistant",
+                "message": {
+                    "model": "claude",
+                    "content": [
+                        {"type": "text", "text": "Let me look at the code."},
+                        {"type": "tool_use", "id": "t1", "name": "Read",
+                         "input": {"file_path": "/repos/C1/src/lib.rs"}},
+                    ]
+                }
+            }),
+            json!({
+                "type": "assistant",
+                "message": {
+                    "model": "claude",
+                    "content": [
+                        {"type": "tool_use", "id": "t2", "name": "Edit",
+                         "input": {"file_path": "/repos/C1/src/lib.rs", "old_string": "a", "new_string": "b"}},
+                        {"type": "tool_use", "id": "t3", "name": "Write",
+                         "input": {"file_path": "/tmp/notes.md", "content": "x"}},
+                        {"type": "tool_use", "id": "t4", "name": "Edit",
+                         "input": {"file_path": "/repos/C1/src/lib.rs", "old_string": "b", "new_string": "c"}},
+                        {"type": "tool_use", "id": "t5", "name": "Bash",
+                         "input": {"command": "cargo test --workspace"}},
+                    ]
+                }
+            }),
+            json!({
+                "type": "result",
+                "subtype": "success",
+                "duration_ms": 1200,
+                "duration_api_ms": 1000,
+                "is_error": false,
+                "num_turns": 3,
+                "session_id": "s1",
+                "result": "Fixed the bug."
+            }),
+        ]
+        .into_iter()
+        .map(|value| serde_json::from_value(value).unwrap())
+        .collect()
+    }
+
+    #[test]
+    fn test_record_synthetic_stream() {
+        let mut response = AgentResponse::default();
+        let finished: Vec<bool> = synthetic_stream()
+            .into_iter()
+            .map(|message| response.record(message))
+            .collect();
+
+        assert_eq!(finished, [false, false, true]);
+        assert_eq!(response.text(), "Fixed the bug.");
+        assert_eq!(response.partial_text(), "Let me look at the code.");
+        // Read is not an action
+        assert_eq!(response.actions.len(), 4);
+        assert_eq!(
+            response.files_changed(Some(Path::new("/repos/C1"))),
+            ["src/lib.rs", "/tmp/notes.md"]
+        );
+        assert_eq!(response.commands_run(), ["cargo test --workspace"]);
+
+        let summary = response
+            .actions_summary(Some(Path::new("/repos/C1")))
+            .unwrap();
+        assert_eq!(
+            summary,
+            "*Actions taken*\n• Changed 2 files: `src/lib.rs`, `/tmp/notes.md`\n• Ran 1 command: `cargo test --workspace`"
+        );
+    }
+
+    #[test]
+    fn test_read_only_response_has_no_summary() {
+        let response = AgentResponse::default();
+        assert_eq!(response.actions_summary(None), None);
+        assert_eq!(response.text(), "");
+    }
+
+    #[test]
+    fn test_tool_action_from_tool_use() {
+        assert_eq!(
+            ToolAction::from_tool_use("NotebookEdit", &json!({"notebook_path": "a.ipynb"})),
+            Some(ToolAction::FileWritten("a.ipynb".to_string()))
+        );
+        assert_eq!(
+            ToolAction::from_tool_use("Grep", &json!({"pattern": "x"})),
+            None
+        );
+        assert_eq!(ToolAction::from_tool_use("Bash", &json!({})), None);
+        assert_eq!(
+            ToolAction::FileWritten("/repos/C1/src/lib.rs".to_string())
+                .describe(Some(Path::new("/repos/C1"))),
+            "✏️ `src/lib.rs`"
+        );
+    }
+
+    #[test]
+    fn test_summary_lists_are_capped() {
+        let response = AgentResponse {
+            actions: (0..7)
+                .map(|i| ToolAction::FileWritten(format!("f{i}.rs")))
+                .chain([ToolAction::CommandRun(format!(
+                    "echo {}\necho done",
+                    "x".repeat(80)
+                ))])
+                .collect(),
+            ..Default::default()
+        };
+        let summary = response.actions_summary(None).unwrap();
+        assert!(summary.contains("Changed 7 files: `f0.rs`"));
+        assert!(summary.contains("`f4.rs` +2 more"));
+        assert!(!summary.contains("f5.rs"));
+        assert!(summary.contains("…`"));
+        assert!(!summary.contains("echo done"));
+    }
+}
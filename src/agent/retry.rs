@@ -0,0 +1,128 @@
+//! Retrying a Claude connection that fails for a transient reason
+
+use crate::config::AgentConfig;
+use crate::error::{Result, SlackCoderError};
+use claude_agent_sdk_rs::ClaudeError;
+use std::io::ErrorKind;
+use std::time::Duration;
+
+/// Longest pause between two connection attempts
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How often connecting is attempted, with the pause doubling after each failure
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectRetry {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+}
+
+impl ConnectRetry {
+    /// The policy set by `CONNECT_MAX_ATTEMPTS` and `CONNECT_BACKOFF_MS`
+    pub fn from_config(config: &AgentConfig) -> Self {
+        Self {
+            max_attempts: config.connect_max_attempts,
+            initial_backoff: Duration::from_millis(config.connect_backoff_ms),
+        }
+    }
+
+    /// Pause after the given failed attempt (1-based)
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(MAX_BACKOFF)
+    }
+
+    /// Start counting attempts to connect; `what` names the agent in logs. Call
+    /// `retry_after` with each failure and `connected` once it succeeds.
+    pub fn attempts<'a>(&self, what: &'a str) -> ConnectAttempts<'a> {
+        ConnectAttempts {
+            retry: *self,
+            what,
+            attempt: 1,
+        }
+    }
+}
+
+/// Attempts made so far to connect one agent
+#[derive(Debug)]
+pub struct ConnectAttempts<'a> {
+    retry: ConnectRetry,
+    what: &'a str,
+    attempt: u32,
+}
+
+impl ConnectAttempts<'_> {
+    /// Handle a failed attempt: wait out the backoff if it's worth another try, or
+    /// give up with the error if it's permanent or attempts have run out
+    pub async fn retry_after(&mut self, error: ClaudeError) -> Result<()> {
+        let max_attempts = self.retry.max_attempts.max(1);
+        if is_permanent(&error) || self.attempt >= max_attempts {
+            tracing::error!(
+                attempts = self.attempt,
+                error = %error,
+                "Failed to connect {}",
+                self.what
+            );
+            return Err(SlackCoderError::ClaudeAgent(error.to_string()));
+        }
+
+        let backoff = self.retry.backoff(self.attempt);
+        tracing::warn!(
+            attempt = self.attempt,
+            max_attempts = max_attempts,
+            backoff_ms = backoff.as_millis() as u64,
+            error = %error,
+            "Connecting {} failed, retrying",
+            self.what
+        );
+        tokio::time::sleep(backoff).await;
+        self.attempt += 1;
+        Ok(())
+    }
+
+    /// Log a connection that needed retries
+    pub fn connected(&self) {
+        if self.attempt > 1 {
+            tracing::info!(
+                attempts = self.attempt,
+                "Connected {} after retrying",
+                self.what
+            );
+        }
+    }
+}
+
+/// Whether a connection error would recur however often it's retried: the CLI is
+/// missing, can't be run, or rejects the options
+fn is_permanent(error: &ClaudeError) -> bool {
+    match error {
+        ClaudeError::CliNotFound { .. } | ClaudeError::InvalidConfig { .. } => true,
+        ClaudeError::Io(e) => matches!(e.kind(), ErrorKind::NotFound | ErrorKind::PermissionDenied),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_permanent() {
+        let io = |kind: ErrorKind| ClaudeError::from(std::io::Error::from(kind));
+        assert!(is_permanent(&io(ErrorKind::NotFound)));
+        assert!(is_permanent(&io(ErrorKind::PermissionDenied)));
+        assert!(!is_permanent(&io(ErrorKind::ConnectionReset)));
+        assert!(!is_permanent(&io(ErrorKind::TimedOut)));
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let retry = ConnectRetry {
+            max_attempts: 10,
+            initial_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(retry.backoff(1), Duration::from_secs(1));
+        assert_eq!(retry.backoff(3), Duration::from_secs(4));
+        assert_eq!(retry.backoff(9), MAX_BACKOFF);
+    }
+}
@@ -2,6 +2,58 @@ use serde::{Deserialize, Serialize};
 use std::hash::{Hash, Hasher};
 use std::time::Instant;
 
+/// Outcome of a successful channel setup
+#[derive(Debug, Clone, Default)]
+pub struct SetupReport {
+    /// Size of the cloned repository on disk
+    pub repo_size_bytes: u64,
+    /// Steps skipped because an interrupted earlier setup already finished them
+    pub skipped: Vec<&'static str>,
+    /// One-paragraph description of the repository (markdown), if one could be found
+    pub summary: Option<String>,
+}
+
+/// Outcome of reconciling live agents with the channels the bot is in
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelSyncReport {
+    /// Agents created for set-up channels that had none
+    pub added: usize,
+    /// Agents dropped because the bot is no longer in their channel
+    pub removed: usize,
+    /// Set-up channels whose agent failed to start
+    pub failed: usize,
+    /// Channels skipped because their setup is incomplete
+    pub incomplete: usize,
+    /// Set-up channels skipped because the access lists don't allow them
+    pub disallowed: usize,
+}
+
+impl ChannelSyncReport {
+    /// Format for Slack
+    pub fn format_slack_message(&self) -> String {
+        let mut message = format!(
+            "🔄 *Channels synced*\n\n• Agents added: {}\n• Agents removed: {}",
+            self.added, self.removed
+        );
+        if self.failed > 0 {
+            message.push_str(&format!("\n• Failed to start: {} (see logs)", self.failed));
+        }
+        if self.incomplete > 0 {
+            message.push_str(&format!(
+                "\n• Skipped with incomplete setup: {}",
+                self.incomplete
+            ));
+        }
+        if self.disallowed > 0 {
+            message.push_str(&format!(
+                "\n• Skipped as not allowed (`CHANNEL_ALLOWLIST`/`CHANNEL_DENYLIST`): {}",
+                self.disallowed
+            ));
+        }
+        message
+    }
+}
+
 /// Represents the status of a task in the plan
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Hash)]
 #[serde(rename_all = "snake_case")]
@@ -110,6 +162,31 @@ impl Plan {
         self.todos.len()
     }
 
+    /// Average duration in seconds of the completed tasks, if any have been timed
+    pub fn average_completion_time(&self) -> Option<f64> {
+        let durations: Vec<f64> = self
+            .todos
+            .iter()
+            .filter(|t| t.status == TaskStatus::Completed)
+            .filter_map(|t| t.completion_time)
+            .collect();
+
+        if durations.is_empty() {
+            None
+        } else {
+            Some(durations.iter().sum::<f64>() / durations.len() as f64)
+        }
+    }
+
+    /// Estimated seconds remaining, based on the average completed-task duration
+    pub fn estimated_remaining_time(&self) -> Option<f64> {
+        let remaining = self
+            .get_total_count()
+            .saturating_sub(self.get_completed_count());
+        self.average_completion_time()
+            .map(|avg| avg * remaining as f64)
+    }
+
     pub fn is_complete(&self) -> bool {
         !self.todos.is_empty() && self.todos.iter().all(|t| t.status == TaskStatus::Completed)
     }
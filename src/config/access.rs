@@ -0,0 +1,235 @@
+//! Which channels the bot operates in (`CHANNEL_ALLOWLIST` / `CHANNEL_DENYLIST`)
+
+use crate::error::{Result, SlackCoderError};
+use std::str::FromStr;
+
+/// One allow- or deny-list entry: a channel id (`C0123ABCD`) or a `#name` pattern where
+/// `*` matches any run of characters (`#eng-*`)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelPattern {
+    Id(String),
+    Name(String),
+}
+
+impl ChannelPattern {
+    /// Whether the pattern matches a channel. Name patterns never match a channel whose
+    /// name is unknown.
+    pub fn matches(&self, channel_id: &str, channel_name: Option<&str>) -> bool {
+        match self {
+            Self::Id(id) => id == channel_id,
+            Self::Name(pattern) => {
+                channel_name.is_some_and(|name| glob_match(pattern, &name.to_lowercase()))
+            }
+        }
+    }
+}
+
+impl FromStr for ChannelPattern {
+    type Err = SlackCoderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some(name) = s.strip_prefix('#') {
+            if name.is_empty() {
+                return Err(SlackCoderError::Config(
+                    "Empty channel name pattern '#'".to_string(),
+                ));
+            }
+            return Ok(Self::Name(name.to_lowercase()));
+        }
+
+        // Channel, private channel and DM ids
+        let is_id = s.len() >= 9
+            && s.starts_with(['C', 'G', 'D'])
+            && s.chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+        if !is_id {
+            return Err(SlackCoderError::Config(format!(
+                "Invalid channel pattern: '{}'. Expected a channel id (C0123ABCD) or #name pattern (#eng-*)",
+                s
+            )));
+        }
+        Ok(Self::Id(s.to_string()))
+    }
+}
+
+/// Allow- and deny-lists deciding where the bot sets up and answers.
+///
+/// A channel on the deny-list is never served. If the allow-list is non-empty, only
+/// channels on it are served; an empty allow-list allows every channel not denied.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelAccess {
+    pub allow: Vec<ChannelPattern>,
+    pub deny: Vec<ChannelPattern>,
+    /// Reply with a short "not enabled here" note in disallowed channels, rather than
+    /// staying silent
+    pub notify_disallowed: bool,
+}
+
+impl ChannelAccess {
+    /// Parse comma-separated allow and deny lists
+    pub fn from_lists(allow: &str, deny: &str) -> Result<Self> {
+        Ok(Self {
+            allow: parse_patterns(allow)?,
+            deny: parse_patterns(deny)?,
+            notify_disallowed: true,
+        })
+    }
+
+    /// Whether every channel is allowed, so no check (or name lookup) is needed
+    pub fn is_unrestricted(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Whether any pattern needs the channel name
+    pub fn needs_name(&self) -> bool {
+        self.allow
+            .iter()
+            .chain(&self.deny)
+            .any(|pattern| matches!(pattern, ChannelPattern::Name(_)))
+    }
+
+    pub fn is_allowed(&self, channel_id: &str, channel_name: Option<&str>) -> bool {
+        let matches = |patterns: &[ChannelPattern]| {
+            patterns
+                .iter()
+                .any(|pattern| pattern.matches(channel_id, channel_name))
+        };
+        !matches(&self.deny) && (self.allow.is_empty() || matches(&self.allow))
+    }
+
+    /// Check a channel, awaiting `channel_name` (a lookup) only when a name pattern
+    /// needs it
+    pub async fn allows(
+        &self,
+        channel_id: &str,
+        channel_name: impl Future<Output = Option<String>>,
+    ) -> bool {
+        if self.is_unrestricted() {
+            return true;
+        }
+        let name = if self.needs_name() {
+            channel_name.await
+        } else {
+            None
+        };
+        self.is_allowed(channel_id, name.as_deref())
+    }
+}
+
+fn parse_patterns(list: &str) -> Result<Vec<ChannelPattern>> {
+    list.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Match `text` against a pattern where `*` matches any run of characters
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        // No `*`: the whole text must match
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_patterns() {
+        assert_eq!(
+            parse_patterns(" C0123ABCD, #Eng-* ,").unwrap(),
+            vec![
+                ChannelPattern::Id("C0123ABCD".to_string()),
+                ChannelPattern::Name("eng-*".to_string())
+            ]
+        );
+        assert!(parse_patterns("").unwrap().is_empty());
+        assert!(parse_patterns("engineering").is_err());
+        assert!(parse_patterns("#").is_err());
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("eng-*", "eng-backend"));
+        assert!(glob_match("eng-*", "eng-"));
+        assert!(!glob_match("eng-*", "design"));
+        assert!(glob_match("*-bots", "ci-bots"));
+        assert!(glob_match("team-*-dev", "team-payments-dev"));
+        assert!(!glob_match("team-*-dev", "team-payments-prod"));
+        assert!(glob_match("general", "general"));
+        assert!(!glob_match("general", "general-2"));
+        assert!(glob_match("*", "anything"));
+    }
+
+    #[test]
+    fn test_allow_list() {
+        let access = ChannelAccess::from_lists("C0123ABCD, #eng-*", "").unwrap();
+        assert!(!access.is_unrestricted());
+        assert!(access.needs_name());
+
+        // By id, with or without a name
+        assert!(access.is_allowed("C0123ABCD", None));
+        // By name pattern (case-insensitive)
+        assert!(access.is_allowed("C0999ZZZZ", Some("Eng-Backend")));
+        assert!(!access.is_allowed("C0999ZZZZ", Some("marketing")));
+        // An unknown name can't match a name pattern
+        assert!(!access.is_allowed("C0999ZZZZ", None));
+    }
+
+    #[test]
+    fn test_deny_list() {
+        let access = ChannelAccess::from_lists("", "C0123ABCD, #*-social").unwrap();
+        assert!(!access.is_allowed("C0123ABCD", Some("eng")));
+        assert!(!access.is_allowed("C0999ZZZZ", Some("eng-social")));
+        assert!(access.is_allowed("C0999ZZZZ", Some("eng")));
+        assert!(access.is_allowed("C0999ZZZZ", None));
+
+        // Deny wins over allow
+        let access = ChannelAccess::from_lists("#eng-*", "#eng-social").unwrap();
+        assert!(access.is_allowed("C0999ZZZZ", Some("eng-backend")));
+        assert!(!access.is_allowed("C0999ZZZZ", Some("eng-social")));
+    }
+
+    #[test]
+    fn test_unrestricted() {
+        let access = ChannelAccess::default();
+        assert!(access.is_unrestricted());
+        assert!(!access.needs_name());
+        assert!(access.is_allowed("C0123ABCD", None));
+    }
+
+    #[tokio::test]
+    async fn test_allows_looks_up_name_only_when_needed() {
+        let lookup = |name: &'static str| async move { Some(name.to_string()) };
+
+        // Unrestricted and id-only lists never need the name
+        let open = ChannelAccess::from_lists("", "").unwrap();
+        assert!(open.allows("C1", async { unreachable!() }).await);
+        let by_id = ChannelAccess::from_lists("C1", "").unwrap();
+        assert!(by_id.allows("C1", async { unreachable!() }).await);
+        assert!(!by_id.allows("C2", async { unreachable!() }).await);
+
+        let by_name = ChannelAccess::from_lists("#eng-*", "").unwrap();
+        assert!(by_name.allows("C2", lookup("eng-backend")).await);
+        assert!(!by_name.allows("C2", lookup("random")).await);
+        // A failed lookup can't match a name pattern
+        assert!(!by_name.allows("C2", async { None }).await);
+    }
+}
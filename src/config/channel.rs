@@ -0,0 +1,151 @@
+//! Per-channel configuration overrides
+
+use crate::config::{AgentPermissionMode, DEFAULT_GIT_HOST, RepoSpec, ResponseLocale};
+use crate::error::{Result, SlackCoderError};
+use serde::{Deserialize, Serialize};
+
+/// Per-channel configuration, persisted at `~/.slack_coder/system/{channel_id}/channel_config.json`
+///
+/// Every field is an optional override of the global settings. The struct never holds
+/// secrets (tokens stay in the global `SlackConfig`), so exports are safe to share.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChannelConfig {
+    /// Repository the channel is set up for (`owner/repo`)
+    pub repo_name: Option<String>,
+
+    /// Claude model override
+    pub model: Option<String>,
+
+    /// Max tokens override
+    pub max_tokens: Option<usize>,
+
+    /// Permission mode override (see `AgentPermissionMode` for the security trade-offs)
+    pub permission_mode: Option<AgentPermissionMode>,
+
+    /// Post the agent's tool calls while it works (`/verbose`); off when unset
+    pub verbose: Option<bool>,
+
+    /// Branch the agent works on (`/branch`), checked out again whenever the agent starts
+    pub branch: Option<String>,
+
+    /// Language the agent answers in (`/lang`), in place of `RESPONSE_LOCALE`
+    pub response_locale: Option<ResponseLocale>,
+}
+
+impl ChannelConfig {
+    /// Serialize for `/config export`
+    pub fn to_export_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse and validate a `/config import` payload
+    ///
+    /// Accepts raw JSON or JSON wrapped in a ``` fence, as pasted into Slack.
+    pub fn from_import_json(input: &str) -> Result<Self> {
+        let json = Self::strip_code_fence(input)
+            .replace("&quot;", "\"")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&");
+
+        let config: Self = serde_json::from_str(&json)
+            .map_err(|e| SlackCoderError::Config(format!("Invalid channel config JSON: {}", e)))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Validate field values
+    pub fn validate(&self) -> Result<()> {
+        if let Some(repo) = &self.repo_name {
+            // Hosts are checked against the allow-list when the repository is set up
+            RepoSpec::parse(repo, DEFAULT_GIT_HOST)?;
+        }
+
+        if let Some(model) = &self.model
+            && model.trim().is_empty()
+        {
+            return Err(SlackCoderError::Config("model cannot be empty".to_string()));
+        }
+
+        if let Some(branch) = &self.branch
+            && (branch.trim().is_empty() || branch.starts_with('-'))
+        {
+            return Err(SlackCoderError::Config(format!(
+                "Invalid branch: '{}'",
+                branch
+            )));
+        }
+
+        if self.max_tokens == Some(0) {
+            return Err(SlackCoderError::Config(
+                "max_tokens must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn strip_code_fence(input: &str) -> &str {
+        let trimmed = input.trim();
+        match trimmed
+            .strip_prefix("```")
+            .and_then(|rest| rest.strip_suffix("```"))
+        {
+            Some(inner) => inner.trim_start_matches("json").trim(),
+            None => trimmed,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> ChannelConfig {
+        ChannelConfig {
+            repo_name: Some("tyrchen/slack-coder".to_string()),
+            model: Some("claude-opus-4".to_string()),
+            max_tokens: Some(32768),
+            permission_mode: Some(AgentPermissionMode::AcceptEdits),
+            verbose: Some(true),
+            branch: Some("feature/login".to_string()),
+            response_locale: Some("ja".parse().unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_export_import_round_trip() {
+        let config = sample();
+        let exported = config.to_export_json().unwrap();
+        let imported = ChannelConfig::from_import_json(&exported).unwrap();
+        assert_eq!(imported, config);
+    }
+
+    #[test]
+    fn test_import_from_code_fence() {
+        let exported = sample().to_export_json().unwrap();
+        let pasted = format!("```json\n{}\n```", exported);
+        assert_eq!(ChannelConfig::from_import_json(&pasted).unwrap(), sample());
+    }
+
+    #[test]
+    fn test_import_rejects_malformed_json() {
+        assert!(ChannelConfig::from_import_json("{ not json").is_err());
+    }
+
+    #[test]
+    fn test_import_rejects_unknown_fields() {
+        let err = ChannelConfig::from_import_json(r#"{"bot_token": "xoxb-123"}"#);
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_import_rejects_invalid_values() {
+        assert!(ChannelConfig::from_import_json(r#"{"max_tokens": 0}"#).is_err());
+        assert!(ChannelConfig::from_import_json(r#"{"model": "  "}"#).is_err());
+        assert!(ChannelConfig::from_import_json(r#"{"repo_name": "no-slash"}"#).is_err());
+        assert!(ChannelConfig::from_import_json(r#"{"permission_mode": "yolo"}"#).is_err());
+        assert!(ChannelConfig::from_import_json(r#"{"response_locale": "japanese"}"#).is_err());
+    }
+}
@@ -0,0 +1,186 @@
+//! The language agents answer in (`RESPONSE_LOCALE`, `/lang`)
+
+use crate::error::{Result, SlackCoderError};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Names of common languages, used in the prompt instruction. Other valid codes are
+/// passed to the agent as codes.
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("zh-CN", "Simplified Chinese"),
+    ("zh-Hans", "Simplified Chinese"),
+    ("zh-TW", "Traditional Chinese"),
+    ("zh-Hant", "Traditional Chinese"),
+    ("pt-BR", "Brazilian Portuguese"),
+    ("ar", "Arabic"),
+    ("de", "German"),
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("he", "Hebrew"),
+    ("hi", "Hindi"),
+    ("id", "Indonesian"),
+    ("it", "Italian"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("nl", "Dutch"),
+    ("pl", "Polish"),
+    ("pt", "Portuguese"),
+    ("ru", "Russian"),
+    ("sv", "Swedish"),
+    ("th", "Thai"),
+    ("tr", "Turkish"),
+    ("uk", "Ukrainian"),
+    ("vi", "Vietnamese"),
+    ("zh", "Chinese"),
+];
+
+/// A language tag such as `ja` or `pt-BR`: a 2-3 letter language code, optionally
+/// followed by a region (`BR`, `419`) or script (`Hant`). Stored in canonical case.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ResponseLocale(String);
+
+impl ResponseLocale {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The language's English name, if it's a common one
+    pub fn language_name(&self) -> Option<&'static str> {
+        let language = self.0.split('-').next().unwrap_or_default();
+        LANGUAGE_NAMES
+            .iter()
+            .find(|(code, _)| *code == self.0)
+            .or_else(|| LANGUAGE_NAMES.iter().find(|(code, _)| *code == language))
+            .map(|(_, name)| *name)
+    }
+
+    /// Standing instruction added to the agent's system prompt
+    pub fn instruction(&self) -> String {
+        let language = match self.language_name() {
+            Some(name) => format!("{} (`{}`)", name, self.0),
+            None => format!("the language with locale code `{}`", self.0),
+        };
+        format!(
+            "## Response Language\n\nRespond in {}, whatever language the request or the \
+             repository is written in. Keep code, identifiers, file paths, commands and \
+             quoted output unchanged.",
+            language
+        )
+    }
+}
+
+impl FromStr for ResponseLocale {
+    type Err = SlackCoderError;
+
+    /// Accepts `-` or `_` between the parts, in any case (`pt_br` becomes `pt-BR`)
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || {
+            SlackCoderError::Config(format!(
+                "Invalid locale: '{}'. Expected a language code such as `ja`, `de` or `pt-BR`",
+                s.trim()
+            ))
+        };
+
+        let mut parts = s.trim().split(['-', '_']);
+        let language = parts.next().unwrap_or_default();
+        if !(2..=3).contains(&language.len()) || !language.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return Err(invalid());
+        }
+        let mut canonical = language.to_ascii_lowercase();
+
+        if let Some(subtag) = parts.next() {
+            let subtag = match subtag.len() {
+                // Region: `BR`, or a numeric area such as `419`
+                2 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => subtag.to_ascii_uppercase(),
+                3 if subtag.chars().all(|c| c.is_ascii_digit()) => subtag.to_string(),
+                // Script: `Hant`
+                4 if subtag.chars().all(|c| c.is_ascii_alphabetic()) => {
+                    let lower = subtag.to_ascii_lowercase();
+                    lower[..1].to_ascii_uppercase() + &lower[1..]
+                }
+                _ => return Err(invalid()),
+            };
+            canonical.push('-');
+            canonical.push_str(&subtag);
+        }
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        Ok(Self(canonical))
+    }
+}
+
+impl TryFrom<String> for ResponseLocale {
+    type Error = SlackCoderError;
+
+    fn try_from(value: String) -> Result<Self> {
+        value.parse()
+    }
+}
+
+impl From<ResponseLocale> for String {
+    fn from(locale: ResponseLocale) -> Self {
+        locale.0
+    }
+}
+
+impl fmt::Display for ResponseLocale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_normalizes_case() {
+        for (input, expected) in [
+            ("ja", "ja"),
+            ("DE", "de"),
+            ("pt_br", "pt-BR"),
+            ("es-419", "es-419"),
+            ("zh-hant", "zh-Hant"),
+            (" fil ", "fil"),
+        ] {
+            assert_eq!(input.parse::<ResponseLocale>().unwrap().as_str(), expected);
+        }
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_codes() {
+        for input in [
+            "", "j", "japanese", "ja-", "ja-JPN", "ja-JP-x", "1a", "en US",
+        ] {
+            assert!(
+                input.parse::<ResponseLocale>().is_err(),
+                "expected '{}' to be rejected",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_instruction_names_language() {
+        let ja: ResponseLocale = "ja".parse().unwrap();
+        assert!(ja.instruction().contains("Respond in Japanese (`ja`)"));
+
+        let regional: ResponseLocale = "de-AT".parse().unwrap();
+        assert_eq!(regional.language_name(), Some("German"));
+        let traditional: ResponseLocale = "zh-TW".parse().unwrap();
+        assert_eq!(traditional.language_name(), Some("Traditional Chinese"));
+
+        let unknown: ResponseLocale = "tlh".parse().unwrap();
+        assert!(
+            unknown
+                .instruction()
+                .contains("the language with locale code `tlh`")
+        );
+    }
+}
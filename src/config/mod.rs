@@ -1,5 +1,20 @@
+mod access;
+mod channel;
+mod locale;
+mod reload;
+mod repo;
 mod settings;
 
+pub use access::{ChannelAccess, ChannelPattern};
+pub use channel::ChannelConfig;
+pub use locale::ResponseLocale;
+pub use reload::{ReloadReport, SharedSettings};
+pub use repo::{DEFAULT_GIT_HOST, GitHosts, RepoSpec};
+#[cfg(test)]
+pub(crate) use settings::test_settings;
 pub use settings::{
-    AgentConfig, ClaudeConfig, Settings, SlackConfig, WorkspaceConfig, load_settings,
+    AgentConfig, AgentPermissionMode, BusySessionPolicy, ClaudeConfig, DisplayConfig, FilterConfig,
+    InputFilterKind, LongResponseMode, OutputFilterMode, ProgressEmoji, ProgressFinishMode,
+    ServerConfig, SessionScope, Settings, SlackConfig, UserAttribution, WorkspaceConfig,
+    load_dotenv, load_settings, reload_settings,
 };
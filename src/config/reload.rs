@@ -0,0 +1,198 @@
+//! Settings that can be swapped at runtime (reloaded on SIGHUP)
+
+use crate::config::Settings;
+use arc_swap::ArcSwap;
+use std::sync::Arc;
+
+/// The current settings, shared by every component and replaced as a whole on reload.
+///
+/// Readers take a snapshot with [`SharedSettings::load`], so one operation sees one
+/// consistent version even if a reload lands halfway through it.
+#[derive(Debug, Clone)]
+pub struct SharedSettings {
+    current: Arc<ArcSwap<Settings>>,
+}
+
+impl SharedSettings {
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            current: Arc::new(ArcSwap::from_pointee(settings)),
+        }
+    }
+
+    /// Snapshot of the current settings
+    pub fn load(&self) -> Arc<Settings> {
+        self.current.load_full()
+    }
+
+    /// Swap in freshly loaded settings.
+    ///
+    /// Settings that are only read at startup keep their running values, so what
+    /// `load` returns always matches what the bot is doing. The report names them, and
+    /// the changed settings that only reach agents created after the reload.
+    pub fn reload(&self, mut new: Settings) -> ReloadReport {
+        let current = self.load();
+        let report = ReloadReport {
+            restart_required: keep_startup_settings(&current, &mut new),
+            new_agents_only: agent_creation_changes(&current, &new),
+        };
+        self.current.store(Arc::new(new));
+        report
+    }
+}
+
+/// Changed settings a reload couldn't apply to everything already running
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ReloadReport {
+    /// Startup-only settings, which kept their running values
+    pub restart_required: Vec<&'static str>,
+    /// Settings read when an agent is created: new agents use them, but running agents
+    /// keep the old values until `/restart` or a bot restart
+    pub new_agents_only: Vec<&'static str>,
+}
+
+/// Names of the changed settings that are only read when an agent is created
+fn agent_creation_changes(current: &Settings, new: &Settings) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    macro_rules! check {
+        ($name:literal, $($field:ident).+) => {
+            if current.$($field).+ != new.$($field).+ {
+                changed.push($name);
+            }
+        };
+    }
+
+    check!("CLAUDE_MODEL", claude.model);
+    check!("CLAUDE_MAX_TOKENS", claude.max_tokens);
+    check!("PERMISSION_MODE", agent.permission_mode);
+    check!("SESSION_SCOPE", agent.session_scope);
+    check!("RESPONSE_LOCALE", agent.response_locale);
+    check!("GLOBAL_PROMPT_OVERLAY_PATH", agent.global_prompt_overlay);
+    check!("MAIN_AGENT_PROMPT_PATH", agent.main_agent_prompt_path);
+    changed
+}
+
+/// Copy the startup-only settings from `current` into `new`, returning the names of
+/// those that differed
+fn keep_startup_settings(current: &Settings, new: &mut Settings) -> Vec<&'static str> {
+    let mut changed = Vec::new();
+    macro_rules! keep {
+        ($name:literal, $($field:ident).+) => {
+            if current.$($field).+ != new.$($field).+ {
+                changed.push($name);
+                new.$($field).+.clone_from(&current.$($field).+);
+            }
+        };
+    }
+
+    // Connections and the workspace location
+    keep!("SLACK_BOT_TOKEN", slack.bot_token);
+    keep!("SLACK_APP_TOKEN", slack.app_token);
+    keep!("SLACK_SIGNING_SECRET", slack.signing_secret);
+    keep!("WORKSPACE_BASE_PATH", workspace.base_path);
+    keep!("HEALTH_PORT", server.health_port);
+    // Read when long-lived components are built
+    keep!(
+        "EVENT_DEDUP_RETENTION_SECS",
+        slack.event_dedup_retention_secs
+    );
+    keep!("EVENT_DEDUP_MAX_ENTRIES", slack.event_dedup_max_entries);
+    keep!("RATE_LIMIT_PER_MINUTE", slack.rate_limit_per_minute);
+    keep!(
+        "METADATA_NEGATIVE_TTL_SECS",
+        slack.metadata_negative_ttl_secs
+    );
+    keep!("CLEANUP_INTERVAL_SECS", workspace.cleanup_interval_secs);
+    keep!("MAX_QUEUED_MESSAGES", agent.max_queued_messages);
+    keep!("MAX_MESSAGE_BYTES", display.max_message_bytes);
+    keep!("PROGRESS_ON_FINISH", display.progress_finish_mode);
+    keep!("PROGRESS_EMOJI_COMPLETED", display.progress_emoji.completed);
+    keep!(
+        "PROGRESS_EMOJI_IN_PROGRESS",
+        display.progress_emoji.in_progress
+    );
+    keep!("PROGRESS_EMOJI_PENDING", display.progress_emoji.pending);
+    keep!("INPUT_FILTERS", filters.input_filters);
+    keep!("OUTPUT_FILTER", filters.output_filter);
+
+    let patterns = |settings: &Settings| -> Vec<(String, String)> {
+        settings
+            .filters
+            .secrets
+            .patterns()
+            .iter()
+            .map(|pattern| (pattern.name.clone(), pattern.regex.as_str().to_string()))
+            .collect()
+    };
+    if patterns(current) != patterns(new) {
+        changed.push("SECRET_PATTERNS");
+        new.filters.secrets = current.filters.secrets.clone();
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SessionScope;
+    use crate::config::settings::test_settings;
+    use std::time::Duration;
+
+    #[test]
+    fn test_reload_updates_timeout_seen_by_consumer() {
+        let shared = SharedSettings::new(test_settings());
+        // A consumer holding its own handle, like the agent manager
+        let consumer = shared.clone();
+        assert_eq!(
+            consumer.load().agent.query_timeout(),
+            Duration::from_secs(900)
+        );
+
+        let mut new = test_settings();
+        new.agent.query_timeout_secs = 60;
+        new.display.reply_in_thread = false;
+        assert_eq!(shared.reload(new), ReloadReport::default());
+
+        assert_eq!(
+            consumer.load().agent.query_timeout(),
+            Duration::from_secs(60)
+        );
+        assert!(!consumer.load().display.reply_in_thread);
+    }
+
+    #[test]
+    fn test_reload_keeps_startup_settings() {
+        let shared = SharedSettings::new(test_settings());
+        let snapshot = shared.load();
+
+        let mut new = test_settings();
+        new.slack.bot_token = "xoxb-rotated".to_string();
+        new.server.health_port = 9090;
+        new.agent.setup_timeout_secs = 30;
+        let report = shared.reload(new);
+
+        assert_eq!(report.restart_required, ["SLACK_BOT_TOKEN", "HEALTH_PORT"]);
+        assert!(report.new_agents_only.is_empty());
+        let current = shared.load();
+        assert_eq!(current.slack.bot_token, "xoxb-test");
+        assert_eq!(current.server.health_port, 8080);
+        assert_eq!(current.agent.setup_timeout_secs, 30);
+        // Snapshots taken before the reload are unchanged
+        assert_eq!(snapshot.agent.setup_timeout_secs, 600);
+    }
+
+    #[test]
+    fn test_reload_reports_agent_creation_settings() {
+        let shared = SharedSettings::new(test_settings());
+
+        let mut new = test_settings();
+        new.claude.model = "claude-opus-4".to_string();
+        new.agent.session_scope = SessionScope::Thread;
+        let report = shared.reload(new);
+
+        // Applied for new agents, but reported since running agents keep the old values
+        assert!(report.restart_required.is_empty());
+        assert_eq!(report.new_agents_only, ["CLAUDE_MODEL", "SESSION_SCOPE"]);
+        assert_eq!(shared.load().claude.model, "claude-opus-4");
+    }
+}
@@ -0,0 +1,348 @@
+//! Repository specs (`owner/repo` or `host/owner/repo`) and the git hosts allowed
+//! for them (`GIT_DEFAULT_HOST` / `GIT_ALLOWED_HOSTS`)
+
+use crate::error::{Result, SlackCoderError};
+use std::fmt;
+
+/// Host that the `owner/repo` shorthand refers to unless configured otherwise
+pub const DEFAULT_GIT_HOST: &str = "github.com";
+
+/// Longest host name accepted in a repository spec
+const MAX_HOST_LEN: usize = 253;
+
+/// GitHub limits for user/organization names and repository names
+const MAX_OWNER_LEN: usize = 39;
+const MAX_REPO_LEN: usize = 100;
+
+/// A repository on a git host.
+///
+/// Displays as `owner/repo` on github.com and `host/owner/repo` elsewhere, which is
+/// also the `[HOST/]OWNER/REPO` form `gh` accepts, so saved specs stay unambiguous
+/// if the default host changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoSpec {
+    pub host: String,
+    pub owner: String,
+    pub repo: String,
+}
+
+impl RepoSpec {
+    /// Parse `owner/repo` (on `default_host`) or `host/owner/repo`. A leading
+    /// `https://` and trailing `.git` are accepted so pasted clone URLs work. The host
+    /// is only checked for syntax here; see [`GitHosts::resolve`] for the allow-list.
+    pub fn parse(spec: &str, default_host: &str) -> Result<Self> {
+        let trimmed = spec.trim();
+        let path = trimmed.strip_prefix("https://").unwrap_or(trimmed);
+        let path = path.trim_end_matches('/');
+        let path = path.strip_suffix(".git").unwrap_or(path);
+
+        let (host, owner_repo) = match path.split('/').count() {
+            2 => (default_host.to_string(), path),
+            3 => {
+                let (host, rest) = path.split_once('/').unwrap_or_default();
+                (validate_host(host)?, rest)
+            }
+            _ => {
+                return Err(SlackCoderError::Config(format!(
+                    "Invalid repository format: '{}'. Expected format: owner/repo-name or host/owner/repo-name",
+                    spec.trim()
+                )));
+            }
+        };
+
+        let (owner, repo) = validate_repo_name_format(owner_repo)?;
+        Ok(Self { host, owner, repo })
+    }
+
+    /// Whether the repository is on github.com
+    pub fn is_github_com(&self) -> bool {
+        self.host == DEFAULT_GIT_HOST
+    }
+
+    /// `owner/repo`, without the host
+    pub fn name_with_owner(&self) -> String {
+        format!("{}/{}", self.owner, self.repo)
+    }
+
+    /// Browser URL of the repository
+    pub fn web_url(&self) -> String {
+        format!("https://{}/{}/{}", self.host, self.owner, self.repo)
+    }
+
+    /// Whether a `host/owner/repo` path (as read from a clone's remote) is this repository
+    pub fn matches_remote(&self, remote: &str) -> bool {
+        remote.eq_ignore_ascii_case(&format!("{}/{}/{}", self.host, self.owner, self.repo))
+    }
+}
+
+impl fmt::Display for RepoSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_github_com() {
+            write!(f, "{}/{}", self.owner, self.repo)
+        } else {
+            write!(f, "{}/{}/{}", self.host, self.owner, self.repo)
+        }
+    }
+}
+
+/// Lowercase a host name, accepting only letters, digits, `-` and `.` between labels
+fn validate_host(host: &str) -> Result<String> {
+    let host = host.trim().to_ascii_lowercase();
+    let valid = !host.is_empty()
+        && host.len() <= MAX_HOST_LEN
+        && host.contains('.')
+        && host.split('.').all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    if !valid {
+        return Err(SlackCoderError::Config(format!(
+            "Invalid git host '{}'. Expected a host name such as github.example.com",
+            host
+        )));
+    }
+    Ok(host)
+}
+
+/// The git hosts repositories may be set up from.
+///
+/// `owner/repo` maps to the default host, which is always allowed. Other hosts must be
+/// named in `GIT_ALLOWED_HOSTS`, so a channel can't point the bot at an arbitrary server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitHosts {
+    pub default_host: String,
+    /// Allowed hosts besides the default, lowercased
+    pub allowed: Vec<String>,
+}
+
+impl Default for GitHosts {
+    fn default() -> Self {
+        Self {
+            default_host: DEFAULT_GIT_HOST.to_string(),
+            allowed: Vec::new(),
+        }
+    }
+}
+
+impl GitHosts {
+    /// Build from the `GIT_DEFAULT_HOST` value (empty = github.com) and the
+    /// comma-separated `GIT_ALLOWED_HOSTS` list
+    pub fn from_config(default_host: &str, allowed: &str) -> Result<Self> {
+        let default_host = match default_host.trim() {
+            "" => DEFAULT_GIT_HOST.to_string(),
+            host => validate_host(host)?,
+        };
+        let allowed = allowed
+            .split(',')
+            .filter(|host| !host.trim().is_empty())
+            .map(validate_host)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            default_host,
+            allowed,
+        })
+    }
+
+    /// Whether repositories may be set up from `host`
+    pub fn is_allowed(&self, host: &str) -> bool {
+        host == self.default_host || self.allowed.iter().any(|allowed| allowed == host)
+    }
+
+    /// Parse a repository spec and check its host against the allow-list
+    pub fn resolve(&self, spec: &str) -> Result<RepoSpec> {
+        let repo = RepoSpec::parse(spec, &self.default_host)?;
+        if !self.is_allowed(&repo.host) {
+            let mut hosts = vec![self.default_host.as_str()];
+            hosts.extend(self.allowed.iter().map(String::as_str));
+            return Err(SlackCoderError::Config(format!(
+                "Git host '{}' is not allowed. Allowed hosts: {}",
+                repo.host,
+                hosts.join(", ")
+            )));
+        }
+        Ok(repo)
+    }
+}
+
+/// Validate repository name format (owner/repo, without a host; see
+/// [`RepoSpec`] for `host/owner/repo`)
+///
+/// The name ends up in filesystem paths and in `gh`/`git` commands run by the
+/// agent, so only a conservative character set is accepted.
+pub fn validate_repo_name_format(name: &str) -> Result<(String, String)> {
+    let parts: Vec<&str> = name.trim().split('/').collect();
+
+    if parts.len() != 2 {
+        return Err(SlackCoderError::Config(format!(
+            "Invalid repository format: '{}'. Expected format: owner/repo-name",
+            name
+        )));
+    }
+
+    let owner = parts[0].trim();
+    let repo = parts[1].trim();
+
+    if owner.is_empty() || repo.is_empty() {
+        return Err(SlackCoderError::Config(
+            "Owner and repository name cannot be empty".to_string(),
+        ));
+    }
+
+    validate_repo_name_part("Owner", owner, MAX_OWNER_LEN)?;
+    validate_repo_name_part("Repository name", repo, MAX_REPO_LEN)?;
+
+    Ok((owner.to_string(), repo.to_string()))
+}
+
+/// Validate one side of `owner/repo`
+fn validate_repo_name_part(label: &str, part: &str, max_len: usize) -> Result<()> {
+    if part.len() > max_len {
+        return Err(SlackCoderError::Config(format!(
+            "{} '{}' is too long (max {} characters)",
+            label, part, max_len
+        )));
+    }
+
+    if let Some(c) = part
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')))
+    {
+        return Err(SlackCoderError::Config(format!(
+            "{} '{}' contains invalid character '{}'. Only letters, digits, '.', '_' and '-' are allowed",
+            label, part, c
+        )));
+    }
+
+    if part.starts_with('.') || part.contains("..") {
+        return Err(SlackCoderError::Config(format!(
+            "{} '{}' cannot start with '.' or contain '..'",
+            label, part
+        )));
+    }
+
+    // A leading dash could be parsed as a command-line flag by git/gh
+    if part.starts_with('-') {
+        return Err(SlackCoderError::Config(format!(
+            "{} '{}' cannot start with '-'",
+            label, part
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hosts() -> GitHosts {
+        GitHosts::from_config("", "GHE.Company.com, gitlab.example.org").unwrap()
+    }
+
+    #[test]
+    fn test_shorthand_maps_to_github() {
+        let repo = hosts().resolve("tyrchen/slack-coder").unwrap();
+        assert_eq!(repo.host, "github.com");
+        assert_eq!(repo.name_with_owner(), "tyrchen/slack-coder");
+        assert_eq!(repo.to_string(), "tyrchen/slack-coder");
+        assert_eq!(repo.web_url(), "https://github.com/tyrchen/slack-coder");
+        assert!(repo.is_github_com());
+    }
+
+    #[test]
+    fn test_enterprise_host_form() {
+        let repo = hosts().resolve("ghe.company.com/platform/api").unwrap();
+        assert_eq!(repo.host, "ghe.company.com");
+        assert_eq!(repo.to_string(), "ghe.company.com/platform/api");
+        assert_eq!(repo.web_url(), "https://ghe.company.com/platform/api");
+        assert!(!repo.is_github_com());
+        assert!(repo.matches_remote("GHE.company.com/Platform/api"));
+        assert!(!repo.matches_remote("github.com/platform/api"));
+
+        // Pasted clone URLs and upper-case hosts work too
+        let pasted = hosts()
+            .resolve("https://GHE.company.com/platform/api.git")
+            .unwrap();
+        assert_eq!(pasted, repo);
+    }
+
+    #[test]
+    fn test_host_allow_list() {
+        let err = hosts().resolve("evil.example.com/owner/repo").unwrap_err();
+        assert!(err.to_string().contains("not allowed"));
+        assert!(err.to_string().contains("github.com, ghe.company.com"));
+
+        assert!(hosts().resolve("localhost/owner/repo").is_err());
+        assert!(hosts().resolve("a/b/c/d").is_err());
+        assert!(hosts().resolve("no-slash").is_err());
+        assert!(GitHosts::from_config("", "not a host").is_err());
+    }
+
+    #[test]
+    fn test_custom_default_host() {
+        let hosts = GitHosts::from_config("ghe.company.com", "").unwrap();
+        let repo = hosts.resolve("platform/api").unwrap();
+        assert_eq!(repo.to_string(), "ghe.company.com/platform/api");
+        // github.com is only allowed when it's the default or listed
+        assert!(hosts.resolve("github.com/tyrchen/slack-coder").is_err());
+    }
+
+    #[test]
+    fn test_validate_repo_name_accepts_valid_names() {
+        let (owner, repo) = validate_repo_name_format("owner/my-repo.rs").unwrap();
+        assert_eq!(owner, "owner");
+        assert_eq!(repo, "my-repo.rs");
+
+        assert!(validate_repo_name_format(" tyrchen/slack_coder ").is_ok());
+    }
+
+    #[test]
+    fn test_validate_repo_name_rejects_traversal() {
+        for name in [
+            "../../etc",
+            "owner/..",
+            "owner/../passwd",
+            "../repo",
+            "owner/.git",
+            "owner/repo/extra",
+            "/etc/passwd",
+            "owner\\..\\repo/x",
+        ] {
+            assert!(
+                validate_repo_name_format(name).is_err(),
+                "expected '{}' to be rejected",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_repo_name_rejects_shell_metacharacters() {
+        for name in [
+            "owner/repo;rm -rf ~",
+            "owner/$(whoami)",
+            "owner/repo`id`",
+            "owner/repo|cat",
+            "owner/repo && ls",
+            "-oProxyCommand=x/repo",
+            "owner/repo\nnext",
+        ] {
+            assert!(
+                validate_repo_name_format(name).is_err(),
+                "expected '{}' to be rejected",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate_repo_name_rejects_long_names() {
+        let name = format!("owner/{}", "a".repeat(MAX_REPO_LEN + 1));
+        assert!(validate_repo_name_format(&name).is_err());
+
+        let name = format!("{}/repo", "a".repeat(MAX_OWNER_LEN + 1));
+        assert!(validate_repo_name_format(&name).is_err());
+    }
+}
@@ -1,5 +1,12 @@
+use crate::config::{ChannelAccess, ChannelConfig, GitHosts, ResponseLocale};
 use crate::error::{Result, SlackCoderError};
+use crate::filter::SecretScanner;
+use claude_agent_sdk_rs::PermissionMode;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::OnceLock;
 
 #[derive(Debug, Clone)]
 pub struct Settings {
@@ -7,6 +14,13 @@ pub struct Settings {
     pub claude: ClaudeConfig,
     pub workspace: WorkspaceConfig,
     pub agent: AgentConfig,
+    pub display: DisplayConfig,
+    pub server: ServerConfig,
+    pub filters: FilterConfig,
+    /// Channels the bot sets up and answers in
+    pub access: ChannelAccess,
+    /// Git hosts repositories can be set up from
+    pub git: GitHosts,
 }
 
 #[derive(Debug, Clone)]
@@ -14,6 +28,29 @@ pub struct SlackConfig {
     pub bot_token: String,
     pub app_token: String,
     pub signing_secret: String,
+    /// Slack user IDs allowed to run admin commands
+    pub admin_user_ids: Vec<String>,
+    /// How long processed event keys are remembered for deduplication
+    pub event_dedup_retention_secs: u64,
+    /// Cap on remembered event keys; the oldest are dropped first
+    pub event_dedup_max_entries: usize,
+    /// What bot commands start with (`/help`, `!help`, ...). Anything other than `/`
+    /// avoids clashing with Slack's own slash commands.
+    pub command_prefix: String,
+    /// Agent queries each user may send per minute (0 = unlimited). Admins are exempt.
+    pub rate_limit_per_minute: u32,
+    /// Channel id that gets a record of every answered query (None = no audit trail)
+    pub audit_channel_id: Option<String>,
+    /// How long a failed user or channel lookup is remembered before Slack is asked
+    /// again (None = until restart, 0 = not remembered)
+    pub metadata_negative_ttl_secs: Option<u64>,
+}
+
+impl SlackConfig {
+    /// Check if a user is allowed to run admin commands
+    pub fn is_admin(&self, user_id: &str) -> bool {
+        self.admin_user_ids.iter().any(|id| id == user_id)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +59,30 @@ pub struct ClaudeConfig {
     pub max_tokens: usize,
 }
 
+/// Environment variable the Claude Code CLI reads its output token limit from
+const MAX_OUTPUT_TOKENS_ENV: &str = "CLAUDE_CODE_MAX_OUTPUT_TOKENS";
+
+impl ClaudeConfig {
+    /// Apply a channel's `model`/`max_tokens` overrides on top of the global config
+    pub fn with_overrides(&self, channel: &ChannelConfig) -> Self {
+        Self {
+            model: channel.model.clone().unwrap_or_else(|| self.model.clone()),
+            max_tokens: channel.max_tokens.unwrap_or(self.max_tokens),
+        }
+    }
+
+    /// Environment for the Claude CLI subprocess.
+    ///
+    /// The SDK options have no max-tokens field; the CLI takes the limit from the
+    /// environment instead, so it's passed through here.
+    pub fn cli_env(&self) -> HashMap<String, String> {
+        HashMap::from([(
+            MAX_OUTPUT_TOKENS_ENV.to_string(),
+            self.max_tokens.to_string(),
+        )])
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct WorkspaceConfig {
     pub base_path: PathBuf,
@@ -32,13 +93,562 @@ pub struct WorkspaceConfig {
 #[derive(Debug, Clone)]
 pub struct AgentConfig {
     pub main_agent_prompt_path: PathBuf,
+    /// Standing instructions for every agent in every channel, read once from
+    /// `GLOBAL_PROMPT_OVERLAY_PATH` when settings load (None = no global overlay)
+    pub global_prompt_overlay: Option<String>,
     pub agent_timeout_secs: u64,
+    /// Warn the channel once an agent has been idle for this percentage of
+    /// `agent_timeout_secs` (0 disables the warning)
+    pub idle_warning_percent: u8,
+    /// Maximum time a repository setup (clone + analysis) may run before it's aborted
+    pub setup_timeout_secs: u64,
+    /// Maximum time one query may stream before it's stopped
+    pub query_timeout_secs: u64,
     pub max_concurrent_requests: usize,
+    /// How many agents are restored at once on startup (each starts a Claude process)
+    pub max_concurrent_restores: usize,
+    /// Messages that may wait per channel while the agent is busy
+    pub max_queued_messages: usize,
+    pub busy_session_policy: BusySessionPolicy,
+    /// Permission mode for agents; channels can override it with `/permissions`
+    pub permission_mode: AgentPermissionMode,
+    /// Language agents answer in (None = the language of the request); channels can
+    /// override it with `/lang`
+    pub response_locale: Option<ResponseLocale>,
+    /// Whether a channel shares one conversation or each thread gets its own
+    pub session_scope: SessionScope,
+    /// Which Slack user each query is attributed to (handed to the agent client, though
+    /// the SDK has no field to pass it on in yet)
+    pub user_attribution: UserAttribution,
+    /// Post an "Agent Ready" message to each restored channel on startup
+    pub startup_notifications: bool,
+    /// Skip the startup notice for channels notified within this many minutes
+    pub startup_notice_cooldown_mins: u64,
+    /// Attempts to connect an agent to Claude before giving up (transient errors only)
+    pub connect_max_attempts: u32,
+    /// Pause before the first connect retry; it doubles after each failure
+    pub connect_backoff_ms: u64,
+    /// Warn when a repo agent's system prompt is longer than this many characters
+    /// (0 disables the check)
+    pub max_system_prompt_chars: usize,
+    /// Cut the repository prompt to fit `max_system_prompt_chars`, rather than only warning
+    pub truncate_system_prompt: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct DisplayConfig {
+    pub progress_finish_mode: ProgressFinishMode,
+    pub long_response_mode: LongResponseMode,
+    /// Largest message posted to Slack; longer text is chunked or truncated
+    pub max_message_bytes: usize,
+    /// In truncate mode, also upload the full response as a snippet
+    pub truncate_upload_full: bool,
+    /// Responses longer than this are uploaded as a snippet (in snippet mode)
+    pub snippet_threshold_bytes: usize,
+    /// Responses containing a code block longer than this are uploaded as a snippet
+    pub snippet_code_block_bytes: usize,
+    /// `diff` blocks longer than this are uploaded as `.diff` files instead of posted
+    /// inline (0 keeps every diff inline)
+    pub diff_snippet_bytes: usize,
+    /// Reply in a thread under the user's message rather than at channel level.
+    ///
+    /// Threads keep busy channels readable; top-level replies are easier to follow in
+    /// quiet channels. Messages already in a thread are always answered in that thread.
+    pub reply_in_thread: bool,
+    /// Mark the channel topic while the agent is working. Off by default: it needs the
+    /// `channels:write.topic` scope and edits channel state.
+    pub busy_topic: bool,
+    /// Turn `@name` references to known users in agent output into Slack mentions
+    /// (which notify those users)
+    pub resolve_mentions: bool,
+    /// Posted as soon as a query starts and edited into the answer (None = no placeholder)
+    pub placeholder_message: Option<String>,
+    /// List the files the agent changed and the commands it ran under each reply
+    pub show_actions: bool,
+    /// Start each reply with a blockquote of the request it answers
+    pub quote_request: bool,
+    /// Re-run a request when its author edits it, editing the earlier answer in place
+    pub rerun_on_edit: bool,
+    /// Write common unicode emoji in replies as Slack `:name:` codes, so answers look
+    /// the same whichever form the agent used
+    pub emoji_shortcodes: bool,
+    /// Emoji shown next to each task on the progress board
+    pub progress_emoji: ProgressEmoji,
+}
+
+/// Emoji for each task status on the progress board, as `:name:` codes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgressEmoji {
+    pub completed: String,
+    pub in_progress: String,
+    pub pending: String,
+}
+
+impl Default for ProgressEmoji {
+    fn default() -> Self {
+        Self {
+            completed: ":ballot_box_with_check:".to_string(),
+            in_progress: ":arrows_counterclockwise:".to_string(),
+            pending: ":white_medium_square:".to_string(),
+        }
+    }
+}
+
+/// Read an emoji code from `var`, falling back to `default` when unset.
+///
+/// Only `:name:` codes are accepted; they render the same on every client and work
+/// with a workspace's custom emoji.
+fn parse_emoji(var: &str, value: Option<&str>, default: &str) -> Result<String> {
+    let Some(value) = value.map(str::trim) else {
+        return Ok(default.to_string());
+    };
+    let valid = value
+        .strip_prefix(':')
+        .and_then(|rest| rest.strip_suffix(':'))
+        .is_some_and(|name| {
+            !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '+' | '\''))
+        });
+    if valid {
+        Ok(value.to_string())
+    } else {
+        Err(SlackCoderError::Config(format!(
+            "Invalid {}: '{}'. Expected an emoji code such as `:white_check_mark:`",
+            var, value
+        )))
+    }
+}
+
+impl AgentConfig {
+    /// Minimum time between two startup notices to the same channel
+    pub fn startup_notice_cooldown(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.startup_notice_cooldown_mins * 60)
+    }
+
+    /// Maximum time one query may stream before it's stopped
+    pub fn query_timeout(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.query_timeout_secs)
+    }
+
+    /// Idle time after which the channel is warned, or `None` if warnings are disabled
+    pub fn idle_warning_after(&self) -> Option<std::time::Duration> {
+        self.idle_warning_for(std::time::Duration::from_secs(self.agent_timeout_secs))
+    }
+
+    /// Like `idle_warning_after`, for a channel whose idle timeout is overridden
+    pub fn idle_warning_for(&self, timeout: std::time::Duration) -> Option<std::time::Duration> {
+        if self.idle_warning_percent == 0 {
+            return None;
+        }
+        let timeout_ms = timeout.as_millis() as u64;
+        Some(std::time::Duration::from_millis(
+            timeout_ms * u64::from(self.idle_warning_percent) / 100,
+        ))
+    }
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            progress_finish_mode: ProgressFinishMode::default(),
+            long_response_mode: LongResponseMode::default(),
+            max_message_bytes: MAX_MESSAGE_BYTES,
+            truncate_upload_full: true,
+            snippet_threshold_bytes: 39000,
+            snippet_code_block_bytes: 8000,
+            diff_snippet_bytes: 4000,
+            reply_in_thread: true,
+            busy_topic: false,
+            resolve_mentions: false,
+            placeholder_message: Some(DEFAULT_PLACEHOLDER_MESSAGE.to_string()),
+            show_actions: true,
+            quote_request: false,
+            rerun_on_edit: false,
+            emoji_shortcodes: false,
+            progress_emoji: ProgressEmoji::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Port for the `/healthz`, `/readyz` and `/metrics` endpoints (0 disables the server)
+    pub health_port: u16,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FilterConfig {
+    /// Filters run on user messages before they reach the agent, in order
+    pub input_filters: Vec<InputFilterKind>,
+    /// What happens to agent responses containing secrets
+    pub output_filter: OutputFilterMode,
+    /// Secret patterns shared by the input and output filters
+    pub secrets: SecretScanner,
+}
+
+/// Built-in input filters that can be named in `INPUT_FILTERS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFilterKind {
+    /// Block messages containing credentials such as AWS keys or API tokens
+    Secrets,
+}
+
+impl FromStr for InputFilterKind {
+    type Err = SlackCoderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "secrets" => Ok(Self::Secrets),
+            other => Err(SlackCoderError::Config(format!(
+                "Invalid INPUT_FILTERS entry: '{}'. Expected `secrets`",
+                other
+            ))),
+        }
+    }
+}
+
+/// What the output filter does with a response containing a secret
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFilterMode {
+    /// Post the response unchanged
+    Off,
+    /// Replace each secret with its prefix and `[REDACTED]`
+    #[default]
+    Mask,
+    /// Withhold the whole response and post a notice instead
+    Block,
+}
+
+impl FromStr for OutputFilterMode {
+    type Err = SlackCoderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "mask" => Ok(Self::Mask),
+            "block" => Ok(Self::Block),
+            other => Err(SlackCoderError::Config(format!(
+                "Invalid OUTPUT_FILTER: '{}'. Expected `mask`, `block` or `off`",
+                other
+            ))),
+        }
+    }
+}
+
+/// Parse a comma-separated filter list; empty or `none` disables filtering
+fn parse_input_filters(s: &str) -> Result<Vec<InputFilterKind>> {
+    if s.trim().eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    s.split(',')
+        .filter(|name| !name.trim().is_empty())
+        .map(str::parse)
+        .collect()
+}
+
+/// Unset uses the default of 60 seconds; `never` (or `-1`) remembers failed lookups
+/// until restart
+fn parse_negative_ttl(value: Option<&str>) -> Result<Option<u64>> {
+    match value.map(str::trim) {
+        None => Ok(Some(60)),
+        Some(ttl) if ttl.eq_ignore_ascii_case("never") || ttl == "-1" => Ok(None),
+        Some(ttl) => ttl.parse().map(Some).map_err(|_| {
+            SlackCoderError::Config(format!(
+                "Invalid METADATA_NEGATIVE_TTL_SECS: '{}'. Use seconds, or `never`",
+                ttl
+            ))
+        }),
+    }
+}
+
+/// Posted while the agent works on a query, unless `PLACEHOLDER_MESSAGE` overrides it
+const DEFAULT_PLACEHOLDER_MESSAGE: &str = "🤔 Working on it…";
+
+/// Unset uses the default placeholder; empty or `none` disables it
+fn parse_placeholder_message(value: Option<&str>) -> Option<String> {
+    match value.map(str::trim) {
+        None => Some(DEFAULT_PLACEHOLDER_MESSAGE.to_string()),
+        Some(text) if text.is_empty() || text.eq_ignore_ascii_case("none") => None,
+        Some(text) => Some(text.to_string()),
+    }
+}
+
+/// Read the global prompt overlay. Unset or empty means none; a path that can't be read
+/// is an error, so a typo doesn't silently drop the organization's instructions.
+fn load_global_overlay(path: Option<&str>) -> Result<Option<String>> {
+    let Some(path) = path.map(str::trim).filter(|path| !path.is_empty()) else {
+        return Ok(None);
+    };
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        SlackCoderError::Config(format!(
+            "Failed to read GLOBAL_PROMPT_OVERLAY_PATH '{}': {}",
+            path, e
+        ))
+    })?;
+    let content = content.trim();
+    if content.is_empty() {
+        tracing::warn!(path = path, "Global prompt overlay is empty, ignoring it");
+        return Ok(None);
+    }
+    tracing::info!(
+        path = path,
+        overlay_len = content.len(),
+        "Loaded global prompt overlay"
+    );
+    Ok(Some(content.to_string()))
+}
+
+/// Slack rejects messages over 40KB; this leaves room for what Slack adds around the text
+const MAX_MESSAGE_BYTES: usize = 39000;
+
+/// Smallest `MAX_MESSAGE_BYTES` that still leaves room for a useful message
+const MIN_MESSAGE_BYTES: usize = 500;
+
+/// How responses too long for a single message are posted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LongResponseMode {
+    /// Split into several numbered messages
+    #[default]
+    Chunk,
+    /// Upload the full text as a file snippet with a short summary message
+    Snippet,
+    /// Post only what fits in one message, with a note saying how much was cut
+    Truncate,
+}
+
+impl FromStr for LongResponseMode {
+    type Err = SlackCoderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "chunk" => Ok(Self::Chunk),
+            "snippet" => Ok(Self::Snippet),
+            "truncate" => Ok(Self::Truncate),
+            other => Err(SlackCoderError::Config(format!(
+                "Invalid LONG_RESPONSE_MODE: '{}'. Expected `chunk`, `snippet` or `truncate`",
+                other
+            ))),
+        }
+    }
+}
+
+/// What happens to the progress board when a task completes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProgressFinishMode {
+    /// Replace the board with a compact "Done in Xs" summary
+    #[default]
+    Summary,
+    /// Delete the board entirely
+    Delete,
+}
+
+impl FromStr for ProgressFinishMode {
+    type Err = SlackCoderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "summary" => Ok(Self::Summary),
+            "delete" => Ok(Self::Delete),
+            other => Err(SlackCoderError::Config(format!(
+                "Invalid PROGRESS_ON_FINISH: '{}'. Expected `summary` or `delete`",
+                other
+            ))),
+        }
+    }
+}
+
+/// How Claude conversation context is shared within a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SessionScope {
+    /// One session for the whole channel; every thread sees the same context
+    #[default]
+    Channel,
+    /// A separate session per Slack thread, so unrelated conversations don't mix
+    Thread,
+}
+
+impl FromStr for SessionScope {
+    type Err = SlackCoderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "channel" => Ok(Self::Channel),
+            "thread" => Ok(Self::Thread),
+            other => Err(SlackCoderError::Config(format!(
+                "Invalid SESSION_SCOPE: '{}'. Expected `channel` or `thread`",
+                other
+            ))),
+        }
+    }
+}
+
+/// How the requesting Slack user is attributed on Claude queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UserAttribution {
+    /// Queries carry no user
+    #[default]
+    Off,
+    /// The Slack user id, as is
+    Id,
+    /// A stable hash of the user id, for usage reports that mustn't name people
+    Hashed,
+}
+
+impl UserAttribution {
+    /// The user a query is attributed to, if attribution is on
+    pub fn attribute(self, user_id: &str) -> Option<String> {
+        match self {
+            Self::Off => None,
+            Self::Id => Some(user_id.to_string()),
+            Self::Hashed => {
+                // FNV-1a, so the same user hashes the same way across restarts and builds
+                let hash = user_id
+                    .bytes()
+                    .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+                    });
+                Some(format!("slack-{:016x}", hash))
+            }
+        }
+    }
+}
+
+impl FromStr for UserAttribution {
+    type Err = SlackCoderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "off" => Ok(Self::Off),
+            "id" => Ok(Self::Id),
+            "hashed" => Ok(Self::Hashed),
+            other => Err(SlackCoderError::Config(format!(
+                "Invalid CLAUDE_USER_ATTRIBUTION: '{}'. Expected `off`, `id` or `hashed`",
+                other
+            ))),
+        }
+    }
+}
+
+/// What `/new-session` does when the agent is in the middle of a query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BusySessionPolicy {
+    /// Tell the user and reset the session once the current task finishes
+    #[default]
+    Defer,
+    /// Refuse and ask the user to retry once the current task finishes
+    Reject,
+}
+
+impl FromStr for BusySessionPolicy {
+    type Err = SlackCoderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "defer" => Ok(Self::Defer),
+            "reject" => Ok(Self::Reject),
+            other => Err(SlackCoderError::Config(format!(
+                "Invalid NEW_SESSION_WHILE_BUSY: '{}'. Expected `defer` or `reject`",
+                other
+            ))),
+        }
+    }
+}
+
+/// How much the agent may do without asking, mapped onto the SDK's `PermissionMode`.
+///
+/// Agents run headless - there is nobody at a terminal to approve a prompt - so any
+/// mode other than `BypassPermissions` means tool calls needing approval are refused
+/// rather than asked about. `BypassPermissions` lets the agent run arbitrary shell
+/// commands, edit any file and push to GitHub with the bot's credentials; only use it
+/// for channels whose members you'd trust with that access.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AgentPermissionMode {
+    /// Ask before running tools (unapproved tool calls are refused)
+    Default,
+    /// Allow file edits without asking, ask for everything else
+    AcceptEdits,
+    /// Read-only planning; no edits or commands
+    Plan,
+    /// Run every tool without asking (the historical behavior)
+    #[default]
+    BypassPermissions,
+}
+
+impl AgentPermissionMode {
+    /// Name used in config and the `/permissions` command
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Default => "default",
+            Self::AcceptEdits => "accept-edits",
+            Self::Plan => "plan",
+            Self::BypassPermissions => "bypass-permissions",
+        }
+    }
+
+    /// The equivalent SDK permission mode
+    pub fn to_sdk(self) -> PermissionMode {
+        match self {
+            Self::Default => PermissionMode::Default,
+            Self::AcceptEdits => PermissionMode::AcceptEdits,
+            Self::Plan => PermissionMode::Plan,
+            Self::BypassPermissions => PermissionMode::BypassPermissions,
+        }
+    }
+}
+
+impl std::fmt::Display for AgentPermissionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AgentPermissionMode {
+    type Err = SlackCoderError;
+
+    /// Accepts kebab-case, snake_case or the SDK's camelCase spelling
+    fn from_str(s: &str) -> Result<Self> {
+        let normalized: String = s
+            .trim()
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .collect::<String>()
+            .to_lowercase();
+        match normalized.as_str() {
+            "default" => Ok(Self::Default),
+            "acceptedits" => Ok(Self::AcceptEdits),
+            "plan" => Ok(Self::Plan),
+            "bypasspermissions" | "bypass" => Ok(Self::BypassPermissions),
+            _ => Err(SlackCoderError::Config(format!(
+                "Invalid permission mode: '{}'. Expected `default`, `accept-edits`, `plan` or `bypass-permissions`",
+                s.trim()
+            ))),
+        }
+    }
+}
+
+/// Names of the variables set by the real environment, before `.env` was read
+static PROCESS_ENV: OnceLock<HashSet<String>> = OnceLock::new();
+
+fn process_env() -> &'static HashSet<String> {
+    PROCESS_ENV.get_or_init(|| {
+        std::env::vars_os()
+            .filter_map(|(name, _)| name.into_string().ok())
+            .collect()
+    })
+}
+
+/// Load `.env` if present. Variables already set in the environment win over the file.
+pub fn load_dotenv() {
+    process_env();
+    dotenvy::dotenv().ok();
 }
 
 pub fn load_settings() -> Result<Settings> {
     // Load .env file if present
-    dotenvy::dotenv().ok();
+    load_dotenv();
 
     // Load Slack config
     let slack = SlackConfig {
@@ -48,7 +658,49 @@ pub fn load_settings() -> Result<Settings> {
             .map_err(|_| SlackCoderError::Config("SLACK_APP_TOKEN not set".to_string()))?,
         signing_secret: std::env::var("SLACK_SIGNING_SECRET")
             .map_err(|_| SlackCoderError::Config("SLACK_SIGNING_SECRET not set".to_string()))?,
+        admin_user_ids: std::env::var("SLACK_ADMIN_USER_IDS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .collect(),
+        event_dedup_retention_secs: std::env::var("EVENT_DEDUP_RETENTION_SECS")
+            .unwrap_or_else(|_| "3600".to_string())
+            .parse()
+            .map_err(|_| {
+                SlackCoderError::Config("Invalid EVENT_DEDUP_RETENTION_SECS".to_string())
+            })?,
+        event_dedup_max_entries: std::env::var("EVENT_DEDUP_MAX_ENTRIES")
+            .unwrap_or_else(|_| "10000".to_string())
+            .parse()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| {
+                SlackCoderError::Config("Invalid EVENT_DEDUP_MAX_ENTRIES".to_string())
+            })?,
+        command_prefix: std::env::var("COMMAND_PREFIX")
+            .map(|prefix| prefix.trim().to_string())
+            .unwrap_or_else(|_| "/".to_string()),
+        rate_limit_per_minute: std::env::var("RATE_LIMIT_PER_MINUTE")
+            .unwrap_or_else(|_| "0".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid RATE_LIMIT_PER_MINUTE".to_string()))?,
+        audit_channel_id: std::env::var("AUDIT_CHANNEL_ID")
+            .ok()
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty()),
+        metadata_negative_ttl_secs: parse_negative_ttl(
+            std::env::var("METADATA_NEGATIVE_TTL_SECS").ok().as_deref(),
+        )?,
     };
+    if slack.command_prefix.is_empty() || slack.command_prefix.chars().any(|c| c.is_alphanumeric())
+    {
+        return Err(SlackCoderError::Config(format!(
+            "Invalid COMMAND_PREFIX: '{}'. Use punctuation such as `/` or `!`",
+            slack.command_prefix
+        )));
+    }
 
     // Load Claude config
     let claude = ClaudeConfig {
@@ -61,12 +713,19 @@ pub fn load_settings() -> Result<Settings> {
 
     // Load workspace config
     let workspace = WorkspaceConfig {
-        base_path: std::env::var("WORKSPACE_BASE_PATH")
-            .unwrap_or_else(|_| {
-                let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-                format!("{}/.slack_coder", home)
-            })
-            .into(),
+        base_path: match std::env::var("WORKSPACE_BASE_PATH") {
+            // `~/` isn't expanded by the shell when read from `.env`
+            Ok(path) => match (path.strip_prefix("~/"), std::env::var("HOME")) {
+                (Some(rest), Ok(home)) => PathBuf::from(home).join(rest),
+                _ => PathBuf::from(path),
+            },
+            // Without HOME (some service units and containers), next to the working
+            // directory; still absolute, as `ensure_workspace` requires
+            Err(_) => match std::env::var("HOME") {
+                Ok(home) => PathBuf::from(home).join(".slack_coder"),
+                Err(_) => std::env::current_dir()?.join(".slack_coder"),
+            },
+        },
         max_repo_size_mb: std::env::var("MAX_REPO_SIZE_MB")
             .unwrap_or_else(|_| "1024".to_string())
             .parse()
@@ -82,20 +741,549 @@ pub fn load_settings() -> Result<Settings> {
         main_agent_prompt_path: std::env::var("MAIN_AGENT_PROMPT_PATH")
             .unwrap_or_else(|_| "specs/0003-system-prompt.md".to_string())
             .into(),
+        global_prompt_overlay: load_global_overlay(
+            std::env::var("GLOBAL_PROMPT_OVERLAY_PATH").ok().as_deref(),
+        )?,
         agent_timeout_secs: std::env::var("AGENT_TIMEOUT_SECS")
             .unwrap_or_else(|_| "1800".to_string())
             .parse()
             .map_err(|_| SlackCoderError::Config("Invalid AGENT_TIMEOUT_SECS".to_string()))?,
+        idle_warning_percent: std::env::var("IDLE_WARNING_PERCENT")
+            .unwrap_or_else(|_| "80".to_string())
+            .parse()
+            .ok()
+            .filter(|pct| *pct <= 100)
+            .ok_or_else(|| SlackCoderError::Config("Invalid IDLE_WARNING_PERCENT".to_string()))?,
+        setup_timeout_secs: std::env::var("SETUP_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "600".to_string())
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid SETUP_TIMEOUT_SECS".to_string()))?,
+        query_timeout_secs: std::env::var("QUERY_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "900".to_string())
+            .parse()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .ok_or_else(|| SlackCoderError::Config("Invalid QUERY_TIMEOUT_SECS".to_string()))?,
         max_concurrent_requests: std::env::var("MAX_CONCURRENT_REQUESTS")
             .unwrap_or_else(|_| "10".to_string())
             .parse()
             .map_err(|_| SlackCoderError::Config("Invalid MAX_CONCURRENT_REQUESTS".to_string()))?,
+        max_concurrent_restores: std::env::var("MAX_CONCURRENT_RESTORES")
+            .unwrap_or_else(|_| "8".to_string())
+            .parse()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| {
+                SlackCoderError::Config("Invalid MAX_CONCURRENT_RESTORES".to_string())
+            })?,
+        max_queued_messages: std::env::var("MAX_QUEUED_MESSAGES")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid MAX_QUEUED_MESSAGES".to_string()))?,
+        busy_session_policy: std::env::var("NEW_SESSION_WHILE_BUSY")
+            .unwrap_or_else(|_| "defer".to_string())
+            .parse()?,
+        permission_mode: std::env::var("PERMISSION_MODE")
+            .unwrap_or_else(|_| "bypass-permissions".to_string())
+            .parse()?,
+        response_locale: match std::env::var("RESPONSE_LOCALE") {
+            Ok(code) if !code.trim().is_empty() => Some(
+                code.parse()
+                    .map_err(|e| SlackCoderError::Config(format!("RESPONSE_LOCALE: {}", e)))?,
+            ),
+            _ => None,
+        },
+        session_scope: std::env::var("SESSION_SCOPE")
+            .unwrap_or_else(|_| "channel".to_string())
+            .parse()?,
+        user_attribution: std::env::var("CLAUDE_USER_ATTRIBUTION")
+            .unwrap_or_else(|_| "off".to_string())
+            .parse()?,
+        startup_notifications: std::env::var("STARTUP_NOTIFICATIONS")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid STARTUP_NOTIFICATIONS".to_string()))?,
+        startup_notice_cooldown_mins: std::env::var("STARTUP_NOTICE_COOLDOWN_MINS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .map_err(|_| {
+                SlackCoderError::Config("Invalid STARTUP_NOTICE_COOLDOWN_MINS".to_string())
+            })?,
+        connect_max_attempts: std::env::var("CONNECT_MAX_ATTEMPTS")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| SlackCoderError::Config("Invalid CONNECT_MAX_ATTEMPTS".to_string()))?,
+        connect_backoff_ms: std::env::var("CONNECT_BACKOFF_MS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid CONNECT_BACKOFF_MS".to_string()))?,
+        max_system_prompt_chars: std::env::var("MAX_SYSTEM_PROMPT_CHARS")
+            .unwrap_or_else(|_| "100000".to_string())
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid MAX_SYSTEM_PROMPT_CHARS".to_string()))?,
+        truncate_system_prompt: std::env::var("TRUNCATE_SYSTEM_PROMPT")
+            .unwrap_or_else(|_| "false".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid TRUNCATE_SYSTEM_PROMPT".to_string()))?,
+    };
+
+    // Load display config
+    let display = DisplayConfig {
+        progress_finish_mode: std::env::var("PROGRESS_ON_FINISH")
+            .unwrap_or_else(|_| "summary".to_string())
+            .parse()?,
+        long_response_mode: std::env::var("LONG_RESPONSE_MODE")
+            .unwrap_or_else(|_| "chunk".to_string())
+            .parse()?,
+        max_message_bytes: std::env::var("MAX_MESSAGE_BYTES")
+            .unwrap_or_else(|_| MAX_MESSAGE_BYTES.to_string())
+            .parse()
+            .ok()
+            .filter(|bytes| (MIN_MESSAGE_BYTES..=MAX_MESSAGE_BYTES).contains(bytes))
+            .ok_or_else(|| {
+                SlackCoderError::Config(format!(
+                    "Invalid MAX_MESSAGE_BYTES. Expected {} to {}",
+                    MIN_MESSAGE_BYTES, MAX_MESSAGE_BYTES
+                ))
+            })?,
+        truncate_upload_full: std::env::var("TRUNCATE_UPLOAD_FULL")
+            .unwrap_or_else(|_| "true".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid TRUNCATE_UPLOAD_FULL".to_string()))?,
+        snippet_threshold_bytes: std::env::var("SNIPPET_THRESHOLD_BYTES")
+            .unwrap_or_else(|_| "39000".to_string())
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid SNIPPET_THRESHOLD_BYTES".to_string()))?,
+        snippet_code_block_bytes: std::env::var("SNIPPET_CODE_BLOCK_BYTES")
+            .unwrap_or_else(|_| "8000".to_string())
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid SNIPPET_CODE_BLOCK_BYTES".to_string()))?,
+        diff_snippet_bytes: std::env::var("DIFF_SNIPPET_BYTES")
+            .unwrap_or_else(|_| "4000".to_string())
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid DIFF_SNIPPET_BYTES".to_string()))?,
+        reply_in_thread: std::env::var("REPLY_IN_THREAD")
+            .unwrap_or_else(|_| "true".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid REPLY_IN_THREAD".to_string()))?,
+        busy_topic: std::env::var("BUSY_TOPIC")
+            .unwrap_or_else(|_| "false".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid BUSY_TOPIC".to_string()))?,
+        resolve_mentions: std::env::var("RESOLVE_MENTIONS")
+            .unwrap_or_else(|_| "false".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid RESOLVE_MENTIONS".to_string()))?,
+        placeholder_message: parse_placeholder_message(
+            std::env::var("PLACEHOLDER_MESSAGE").ok().as_deref(),
+        ),
+        show_actions: std::env::var("SHOW_ACTIONS")
+            .unwrap_or_else(|_| "true".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid SHOW_ACTIONS".to_string()))?,
+        quote_request: std::env::var("QUOTE_REQUEST")
+            .unwrap_or_else(|_| "false".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid QUOTE_REQUEST".to_string()))?,
+        rerun_on_edit: std::env::var("RERUN_ON_EDIT")
+            .unwrap_or_else(|_| "false".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid RERUN_ON_EDIT".to_string()))?,
+        emoji_shortcodes: std::env::var("EMOJI_SHORTCODES")
+            .unwrap_or_else(|_| "false".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid EMOJI_SHORTCODES".to_string()))?,
+        progress_emoji: {
+            let defaults = ProgressEmoji::default();
+            let emoji = |var: &str, default: &str| {
+                parse_emoji(var, std::env::var(var).ok().as_deref(), default)
+            };
+            ProgressEmoji {
+                completed: emoji("PROGRESS_EMOJI_COMPLETED", &defaults.completed)?,
+                in_progress: emoji("PROGRESS_EMOJI_IN_PROGRESS", &defaults.in_progress)?,
+                pending: emoji("PROGRESS_EMOJI_PENDING", &defaults.pending)?,
+            }
+        },
+    };
+
+    // Load server config
+    let server = ServerConfig {
+        health_port: std::env::var("HEALTH_PORT")
+            .unwrap_or_else(|_| "8080".to_string())
+            .parse()
+            .map_err(|_| SlackCoderError::Config("Invalid HEALTH_PORT".to_string()))?,
+    };
+
+    // Load filter config
+    let filters = FilterConfig {
+        input_filters: parse_input_filters(
+            &std::env::var("INPUT_FILTERS").unwrap_or_else(|_| "secrets".to_string()),
+        )?,
+        output_filter: std::env::var("OUTPUT_FILTER")
+            .unwrap_or_else(|_| "mask".to_string())
+            .parse()?,
+        secrets: SecretScanner::from_spec(
+            &std::env::var("SECRET_PATTERNS").unwrap_or_else(|_| "all".to_string()),
+            &std::env::var("EXTRA_SECRET_PATTERNS").unwrap_or_default(),
+        )?,
+    };
+
+    // Load channel access lists
+    let access = ChannelAccess {
+        notify_disallowed: std::env::var("CHANNEL_DISALLOWED_NOTICE")
+            .unwrap_or_else(|_| "true".to_string())
+            .trim()
+            .parse()
+            .map_err(|_| {
+                SlackCoderError::Config("Invalid CHANNEL_DISALLOWED_NOTICE".to_string())
+            })?,
+        ..ChannelAccess::from_lists(
+            &std::env::var("CHANNEL_ALLOWLIST").unwrap_or_default(),
+            &std::env::var("CHANNEL_DENYLIST").unwrap_or_default(),
+        )?
     };
 
+    // Load git hosts
+    let git = GitHosts::from_config(
+        &std::env::var("GIT_DEFAULT_HOST").unwrap_or_default(),
+        &std::env::var("GIT_ALLOWED_HOSTS").unwrap_or_default(),
+    )?;
+
     Ok(Settings {
         slack,
         claude,
         workspace,
         agent,
+        display,
+        server,
+        filters,
+        access,
+        git,
     })
 }
+
+/// Load settings again on reload. Edits to `.env` replace the values read from it at
+/// startup, but variables set by the real environment (e.g. by an orchestrator) still
+/// win, as they do at startup.
+pub fn reload_settings() -> Result<Settings> {
+    let process_env = process_env();
+    if let Ok(entries) = dotenvy::dotenv_iter() {
+        for (name, value) in entries.flatten() {
+            if !process_env.contains(&name) {
+                // SAFETY: the reload task is the only writer of the environment after
+                // startup, as it was with `dotenv_override`
+                unsafe { std::env::set_var(name, value) };
+            }
+        }
+    }
+    load_settings()
+}
+
+/// Settings with the `load_settings` defaults, for tests that don't read the environment
+#[cfg(test)]
+pub(crate) fn test_settings() -> Settings {
+    Settings {
+        slack: SlackConfig {
+            bot_token: "xoxb-test".to_string(),
+            app_token: "xapp-test".to_string(),
+            signing_secret: "test-secret".to_string(),
+            admin_user_ids: Vec::new(),
+            event_dedup_retention_secs: 3600,
+            event_dedup_max_entries: 10000,
+            command_prefix: "/".to_string(),
+            rate_limit_per_minute: 0,
+            audit_channel_id: None,
+            metadata_negative_ttl_secs: Some(60),
+        },
+        claude: ClaudeConfig {
+            model: "claude-sonnet-4".to_string(),
+            max_tokens: 65536,
+        },
+        workspace: WorkspaceConfig {
+            base_path: PathBuf::from("/tmp/slack-coder-test"),
+            max_repo_size_mb: 1024,
+            cleanup_interval_secs: 3600,
+        },
+        agent: AgentConfig {
+            main_agent_prompt_path: PathBuf::from("specs/0003-system-prompt.md"),
+            global_prompt_overlay: None,
+            agent_timeout_secs: 1800,
+            idle_warning_percent: 80,
+            setup_timeout_secs: 600,
+            query_timeout_secs: 900,
+            max_concurrent_requests: 10,
+            max_concurrent_restores: 8,
+            max_queued_messages: 5,
+            busy_session_policy: BusySessionPolicy::default(),
+            permission_mode: AgentPermissionMode::default(),
+            response_locale: None,
+            session_scope: SessionScope::default(),
+            user_attribution: UserAttribution::default(),
+            startup_notifications: true,
+            startup_notice_cooldown_mins: 30,
+            connect_max_attempts: 3,
+            connect_backoff_ms: 1000,
+            max_system_prompt_chars: 100_000,
+            truncate_system_prompt: false,
+        },
+        display: DisplayConfig::default(),
+        server: ServerConfig { health_port: 8080 },
+        filters: FilterConfig::default(),
+        access: ChannelAccess::from_lists("", "").expect("empty access lists"),
+        git: GitHosts::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_busy_session_policy_parse() {
+        assert_eq!(
+            "defer".parse::<BusySessionPolicy>().unwrap(),
+            BusySessionPolicy::Defer
+        );
+        assert_eq!(
+            " Reject ".parse::<BusySessionPolicy>().unwrap(),
+            BusySessionPolicy::Reject
+        );
+        assert!("cancel".parse::<BusySessionPolicy>().is_err());
+    }
+
+    fn claude_config() -> ClaudeConfig {
+        ClaudeConfig {
+            model: "claude-sonnet-4".to_string(),
+            max_tokens: 65536,
+        }
+    }
+
+    #[test]
+    fn test_claude_config_overrides() {
+        let global = claude_config();
+
+        let unchanged = global.with_overrides(&ChannelConfig::default());
+        assert_eq!(unchanged.model, "claude-sonnet-4");
+        assert_eq!(unchanged.max_tokens, 65536);
+
+        let channel = ChannelConfig {
+            model: Some("claude-opus-4".to_string()),
+            max_tokens: Some(8192),
+            ..ChannelConfig::default()
+        };
+        let overridden = global.with_overrides(&channel);
+        assert_eq!(overridden.model, "claude-opus-4");
+        assert_eq!(overridden.max_tokens, 8192);
+    }
+
+    #[test]
+    fn test_claude_config_cli_env() {
+        let env = claude_config().cli_env();
+        assert_eq!(
+            env.get(MAX_OUTPUT_TOKENS_ENV).map(String::as_str),
+            Some("65536")
+        );
+    }
+
+    #[test]
+    fn test_agent_permission_mode_parse() {
+        assert_eq!(
+            "accept-edits".parse::<AgentPermissionMode>().unwrap(),
+            AgentPermissionMode::AcceptEdits
+        );
+        assert_eq!(
+            "bypassPermissions".parse::<AgentPermissionMode>().unwrap(),
+            AgentPermissionMode::BypassPermissions
+        );
+        assert_eq!(
+            " Plan ".parse::<AgentPermissionMode>().unwrap(),
+            AgentPermissionMode::Plan
+        );
+        assert!("yolo".parse::<AgentPermissionMode>().is_err());
+
+        // Display and parse round-trip
+        for mode in [
+            AgentPermissionMode::Default,
+            AgentPermissionMode::AcceptEdits,
+            AgentPermissionMode::Plan,
+            AgentPermissionMode::BypassPermissions,
+        ] {
+            assert_eq!(
+                mode.to_string().parse::<AgentPermissionMode>().unwrap(),
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_negative_ttl() {
+        assert_eq!(parse_negative_ttl(None).unwrap(), Some(60));
+        assert_eq!(parse_negative_ttl(Some(" 300 ")).unwrap(), Some(300));
+        assert_eq!(parse_negative_ttl(Some("0")).unwrap(), Some(0));
+        assert_eq!(parse_negative_ttl(Some("never")).unwrap(), None);
+        assert_eq!(parse_negative_ttl(Some("-1")).unwrap(), None);
+        assert!(parse_negative_ttl(Some("-5")).is_err());
+    }
+
+    #[test]
+    fn test_parse_placeholder_message() {
+        assert_eq!(
+            parse_placeholder_message(None).as_deref(),
+            Some(DEFAULT_PLACEHOLDER_MESSAGE)
+        );
+        assert_eq!(
+            parse_placeholder_message(Some(" ⏳ On it ")).as_deref(),
+            Some("⏳ On it")
+        );
+        assert_eq!(parse_placeholder_message(Some("")), None);
+        assert_eq!(parse_placeholder_message(Some("NONE")), None);
+    }
+
+    #[test]
+    fn test_load_global_overlay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("overlay.md");
+        std::fs::write(&path, "\nNever force-push.\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        assert_eq!(
+            load_global_overlay(Some(path)).unwrap().as_deref(),
+            Some("Never force-push.")
+        );
+        assert_eq!(load_global_overlay(None).unwrap(), None);
+        assert_eq!(load_global_overlay(Some(" ")).unwrap(), None);
+        assert!(load_global_overlay(Some("/nonexistent/overlay.md")).is_err());
+    }
+
+    #[test]
+    fn test_parse_emoji() {
+        assert_eq!(
+            parse_emoji("PROGRESS_EMOJI_PENDING", None, ":white_medium_square:").unwrap(),
+            ":white_medium_square:"
+        );
+        assert_eq!(
+            parse_emoji(
+                "PROGRESS_EMOJI_PENDING",
+                Some(" :hourglass_flowing_sand: "),
+                ""
+            )
+            .unwrap(),
+            ":hourglass_flowing_sand:"
+        );
+        assert_eq!(
+            parse_emoji("PROGRESS_EMOJI_COMPLETED", Some(":+1:"), "").unwrap(),
+            ":+1:"
+        );
+        for invalid in [
+            "✅",
+            "white_check_mark",
+            ":white_check_mark",
+            "::",
+            ":two words:",
+        ] {
+            assert!(parse_emoji("PROGRESS_EMOJI_COMPLETED", Some(invalid), "").is_err());
+        }
+    }
+
+    #[test]
+    fn test_parse_input_filters() {
+        assert_eq!(
+            parse_input_filters("secrets").unwrap(),
+            vec![InputFilterKind::Secrets]
+        );
+        assert_eq!(
+            parse_input_filters(" Secrets, ").unwrap(),
+            vec![InputFilterKind::Secrets]
+        );
+        assert!(parse_input_filters("none").unwrap().is_empty());
+        assert!(parse_input_filters("").unwrap().is_empty());
+        assert!(parse_input_filters("secrets,profanity").is_err());
+    }
+
+    #[test]
+    fn test_output_filter_mode_parse() {
+        assert_eq!(
+            "Block".parse::<OutputFilterMode>().unwrap(),
+            OutputFilterMode::Block
+        );
+        assert_eq!(
+            "off".parse::<OutputFilterMode>().unwrap(),
+            OutputFilterMode::Off
+        );
+        assert!("redact".parse::<OutputFilterMode>().is_err());
+    }
+
+    #[test]
+    fn test_session_scope_parse() {
+        assert_eq!(
+            "thread".parse::<SessionScope>().unwrap(),
+            SessionScope::Thread
+        );
+        assert_eq!(
+            " Channel ".parse::<SessionScope>().unwrap(),
+            SessionScope::Channel
+        );
+        assert!("user".parse::<SessionScope>().is_err());
+    }
+
+    #[test]
+    fn test_user_attribution() {
+        assert_eq!(
+            " Hashed ".parse::<UserAttribution>().unwrap(),
+            UserAttribution::Hashed
+        );
+        assert!("name".parse::<UserAttribution>().is_err());
+
+        assert_eq!(UserAttribution::Off.attribute("U123"), None);
+        assert_eq!(
+            UserAttribution::Id.attribute("U123").as_deref(),
+            Some("U123")
+        );
+
+        let hashed = UserAttribution::Hashed.attribute("U123").unwrap();
+        assert!(hashed.starts_with("slack-") && !hashed.contains("U123"));
+        assert_eq!(UserAttribution::Hashed.attribute("U123").unwrap(), hashed);
+        assert_ne!(UserAttribution::Hashed.attribute("U124").unwrap(), hashed);
+    }
+
+    #[test]
+    fn test_progress_finish_mode_parse() {
+        assert_eq!(
+            "summary".parse::<ProgressFinishMode>().unwrap(),
+            ProgressFinishMode::Summary
+        );
+        assert_eq!(
+            "DELETE".parse::<ProgressFinishMode>().unwrap(),
+            ProgressFinishMode::Delete
+        );
+        assert!("archive".parse::<ProgressFinishMode>().is_err());
+    }
+
+    #[test]
+    fn test_long_response_mode_parse() {
+        assert_eq!(
+            "chunk".parse::<LongResponseMode>().unwrap(),
+            LongResponseMode::Chunk
+        );
+        assert_eq!(
+            "Snippet".parse::<LongResponseMode>().unwrap(),
+            LongResponseMode::Snippet
+        );
+        assert_eq!(
+            "truncate".parse::<LongResponseMode>().unwrap(),
+            LongResponseMode::Truncate
+        );
+        assert!("upload".parse::<LongResponseMode>().is_err());
+    }
+}
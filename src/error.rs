@@ -1,10 +1,37 @@
+use slack_morphism::errors::SlackClientError;
+use std::time::Duration;
 use thiserror::Error;
 
+/// Slack error codes that mean the token is missing, invalid, or lacks permission
+const SLACK_AUTH_ERROR_CODES: &[&str] = &[
+    "not_authed",
+    "invalid_auth",
+    "account_inactive",
+    "token_revoked",
+    "token_expired",
+    "no_permission",
+    "missing_scope",
+    "not_allowed_token_type",
+];
+
+/// Slack error codes meaning a message is gone (deleted by a user, or too old to
+/// edit), so edits to it can never succeed again
+const SLACK_GONE_MESSAGE_CODES: &[&str] = &["message_not_found", "cant_update_message"];
+
 #[derive(Debug, Error)]
 pub enum SlackCoderError {
     #[error("Slack API error: {0}")]
     SlackApi(String),
 
+    #[error("Slack API rate limited{}", format_retry_after(.retry_after))]
+    SlackRateLimited { retry_after: Option<Duration> },
+
+    #[error("Slack authentication error: {0}")]
+    SlackAuth(String),
+
+    #[error("Slack message is gone: {0}")]
+    SlackMessageGone(String),
+
     #[error("Claude agent error: {0}")]
     ClaudeAgent(String),
 
@@ -14,17 +41,120 @@ pub enum SlackCoderError {
     #[error("Configuration error: {0}")]
     Config(String),
 
+    #[error("Invalid ID: {0}")]
+    InvalidId(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
     #[error("Serialization error: {0}")]
     Serde(#[from] serde_json::Error),
 
+    #[error("Repository too large: {size_mb} MB exceeds the {limit_mb} MB limit")]
+    RepoTooLarge { size_mb: u64, limit_mb: u64 },
+
+    #[error(
+        "Repository setup timed out after {timeout_secs}s. The clone or analysis may be stuck - \
+         check that the repository is accessible and try again"
+    )]
+    SetupTimeout { timeout_secs: u64 },
+
     #[error("Channel not setup: {0}")]
     ChannelNotSetup(String),
 
+    #[error(
+        "Setup for channel {channel} is incomplete: {missing}. Mention me with the repository \
+         name (`owner/repo`) to run setup again"
+    )]
+    IncompleteSetup {
+        channel: String,
+        missing: &'static str,
+    },
+
+    #[error("Git error: {0}")]
+    Git(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
 pub type Result<T> = std::result::Result<T, SlackCoderError>;
+
+impl From<SlackClientError> for SlackCoderError {
+    fn from(err: SlackClientError) -> Self {
+        match &err {
+            SlackClientError::RateLimitError(e) => Self::SlackRateLimited {
+                retry_after: e.retry_after,
+            },
+            SlackClientError::ApiError(e) if SLACK_AUTH_ERROR_CODES.contains(&e.code.as_str()) => {
+                Self::SlackAuth(err.to_string())
+            }
+            SlackClientError::ApiError(e)
+                if SLACK_GONE_MESSAGE_CODES.contains(&e.code.as_str()) =>
+            {
+                Self::SlackMessageGone(err.to_string())
+            }
+            _ => Self::SlackApi(err.to_string()),
+        }
+    }
+}
+
+fn format_retry_after(retry_after: &Option<Duration>) -> String {
+    match retry_after {
+        Some(d) => format!(" (retry after {}s)", d.as_secs()),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slack_morphism::errors::{SlackClientApiError, SlackRateLimitError};
+
+    fn api_error(code: &str) -> SlackCoderError {
+        SlackClientError::ApiError(SlackClientApiError::new(code.to_string())).into()
+    }
+
+    #[test]
+    fn test_rate_limit_keeps_retry_after() {
+        let limited: SlackCoderError = SlackClientError::RateLimitError(
+            SlackRateLimitError::new().with_retry_after(Duration::from_secs(30)),
+        )
+        .into();
+        assert!(matches!(
+            limited,
+            SlackCoderError::SlackRateLimited {
+                retry_after: Some(d)
+            } if d == Duration::from_secs(30)
+        ));
+        assert_eq!(
+            limited.to_string(),
+            "Slack API rate limited (retry after 30s)"
+        );
+
+        let unknown: SlackCoderError =
+            SlackClientError::RateLimitError(SlackRateLimitError::new()).into();
+        assert_eq!(unknown.to_string(), "Slack API rate limited");
+    }
+
+    #[test]
+    fn test_api_errors_are_classified_by_code() {
+        assert!(matches!(
+            api_error("invalid_auth"),
+            SlackCoderError::SlackAuth(_)
+        ));
+        assert!(matches!(
+            api_error("missing_scope"),
+            SlackCoderError::SlackAuth(_)
+        ));
+        assert!(matches!(
+            api_error("message_not_found"),
+            SlackCoderError::SlackMessageGone(_)
+        ));
+        // Anything unrecognized stays a generic API error
+        assert!(matches!(
+            api_error("channel_not_found"),
+            SlackCoderError::SlackApi(_)
+        ));
+    }
+}
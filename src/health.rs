@@ -0,0 +1,122 @@
+//! HTTP liveness/readiness endpoints for running under an orchestrator
+//!
+//! - `GET /healthz` returns 200 while the Socket Mode listener is connected
+//! - `GET /readyz` additionally reports the number of live agents
+//! - `GET /metrics` exposes Prometheus metrics (see [`crate::telemetry`])
+
+use crate::agent::AgentManager;
+use crate::error::Result;
+use crate::metadata::MetadataCache;
+use crate::slack::ConnectionState;
+use crate::telemetry;
+use axum::extract::State;
+use axum::http::{StatusCode, header};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// Shared state for the health endpoints
+#[derive(Clone)]
+pub struct HealthState {
+    pub connection: ConnectionState,
+    pub agent_manager: Arc<AgentManager>,
+    pub metadata_cache: Arc<MetadataCache>,
+}
+
+/// Body of the `/readyz` response
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct ReadyReport {
+    pub connected: bool,
+    pub agents: usize,
+}
+
+/// Build the router serving the health endpoints
+pub fn router(state: HealthState) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .route("/metrics", get(prometheus_metrics))
+        .with_state(state)
+}
+
+/// Serve the health endpoints on `port` until `shutdown` resolves
+pub async fn serve(
+    port: u16,
+    state: HealthState,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await?;
+    tracing::info!(port = port, "Health check server listening");
+
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(shutdown)
+        .await?;
+
+    tracing::info!("Health check server stopped");
+    Ok(())
+}
+
+async fn healthz(State(state): State<HealthState>) -> (StatusCode, &'static str) {
+    liveness(state.connection.is_connected())
+}
+
+async fn readyz(State(state): State<HealthState>) -> (StatusCode, Json<ReadyReport>) {
+    readiness(
+        state.connection.is_connected(),
+        state.agent_manager.agent_count(),
+    )
+}
+
+async fn prometheus_metrics(
+    State(state): State<HealthState>,
+) -> ([(header::HeaderName, &'static str); 1], String) {
+    let cache_stats = state.metadata_cache.get_stats().await;
+    let body = telemetry::metrics().render(state.agent_manager.agent_count(), &cache_stats);
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+fn liveness(connected: bool) -> (StatusCode, &'static str) {
+    if connected {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "disconnected")
+    }
+}
+
+fn readiness(connected: bool, agents: usize) -> (StatusCode, Json<ReadyReport>) {
+    let status = if connected {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(ReadyReport { connected, agents }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_liveness_follows_connection() {
+        assert_eq!(liveness(true).0, StatusCode::OK);
+        assert_eq!(liveness(false).0, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn test_readiness_reports_agent_count() {
+        let (status, Json(report)) = readiness(true, 3);
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            report,
+            ReadyReport {
+                connected: true,
+                agents: 3
+            }
+        );
+
+        let (status, Json(report)) = readiness(false, 0);
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!report.connected);
+    }
+}
@@ -1,10 +1,13 @@
 pub mod agent;
 pub mod config;
 pub mod error;
+pub mod filter;
+pub mod health;
 pub mod logging;
 pub mod metadata;
 pub mod session;
 pub mod slack;
 pub mod storage;
+pub mod telemetry;
 
 pub use error::{Result, SlackCoderError};
@@ -1,6 +1,100 @@
 //! Logging utilities for structured tracing
 
+use crate::error::{Result, SlackCoderError};
+use std::str::FromStr;
 use std::time::Instant;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{EnvFilter, Layer, Registry, reload};
+
+/// Default filter when `RUST_LOG` isn't set
+const DEFAULT_LOG_FILTER: &str = "slack_coder=debug,slack_morphism=debug";
+
+/// Log output format, chosen with `LOG_FORMAT`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines, for local development
+    #[default]
+    Text,
+    /// One JSON object per line, with the current span and its parents' fields
+    /// (`channel_id`, `user`, `ts`, ...), for log aggregation
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = SlackCoderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "text" | "" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            other => Err(SlackCoderError::Config(format!(
+                "Invalid LOG_FORMAT: '{}'. Expected `text` or `json`",
+                other
+            ))),
+        }
+    }
+}
+
+impl LogFormat {
+    /// Read `LOG_FORMAT`, defaulting to text
+    pub fn from_env() -> Result<Self> {
+        std::env::var("LOG_FORMAT")
+            .map(|value| value.parse())
+            .unwrap_or(Ok(Self::Text))
+    }
+}
+
+/// `RUST_LOG`, or the default filter when it isn't set
+fn env_filter() -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER))
+}
+
+/// Swaps the log filter of the installed subscriber
+pub struct LogFilterHandle(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilterHandle {
+    /// Re-read `RUST_LOG` and apply it to the running subscriber
+    pub fn reload_from_env(&self) -> Result<()> {
+        self.0
+            .reload(env_filter())
+            .map_err(|e| SlackCoderError::Config(format!("Failed to reload log filter: {}", e)))
+    }
+}
+
+/// Install the global tracing subscriber. The returned handle changes its filter later.
+pub fn init_tracing(format: LogFormat) -> LogFilterHandle {
+    let (filter, handle) = reload::Layer::new(env_filter());
+    let registry = tracing_subscriber::registry().with(filter);
+
+    match format {
+        LogFormat::Text => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_target(true)
+                    .with_line_number(true),
+            )
+            .init(),
+        LogFormat::Json => registry.with(json_layer(std::io::stdout)).init(),
+    }
+    LogFilterHandle(handle)
+}
+
+fn json_layer<S, W>(writer: W) -> impl Layer<S>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+    W: for<'writer> MakeWriter<'writer> + Send + Sync + 'static,
+{
+    tracing_subscriber::fmt::layer()
+        .json()
+        .with_writer(writer)
+        .with_target(true)
+        .with_line_number(true)
+        .with_current_span(true)
+        .with_span_list(true)
+}
 
 /// Track operation timing and log on drop
 pub struct Timer {
@@ -42,9 +136,56 @@ pub fn log_error(operation: &str, error: &impl std::error::Error) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::{Arc, Mutex};
     use std::thread;
     use std::time::Duration;
 
+    #[test]
+    fn test_log_format_parse() {
+        assert_eq!("json".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert_eq!(" TEXT ".parse::<LogFormat>().unwrap(), LogFormat::Text);
+        assert!("pretty".parse::<LogFormat>().is_err());
+    }
+
+    /// Collects everything the subscriber writes
+    #[derive(Clone, Default)]
+    struct Captured(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_json_output_includes_span_fields() {
+        let captured = Captured::default();
+        let writer = captured.clone();
+        let subscriber = tracing_subscriber::registry()
+            .with(EnvFilter::new("info"))
+            .with(json_layer(move || writer.clone()));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("app_mention", channel = "C123", user = "alice");
+            let _guard = span.enter();
+            tracing::info!(session = "session-C123-1", "Handling message");
+        });
+
+        let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+        let line: serde_json::Value = serde_json::from_str(output.lines().next().unwrap()).unwrap();
+
+        assert_eq!(line["fields"]["message"], "Handling message");
+        assert_eq!(line["fields"]["session"], "session-C123-1");
+        assert_eq!(line["span"]["channel"], "C123");
+        assert_eq!(line["span"]["user"], "alice");
+        assert_eq!(line["spans"][0]["name"], "app_mention");
+    }
+
     #[test]
     fn test_timer_tracks_duration() {
         let _timer = Timer::new("test_operation");
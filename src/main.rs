@@ -1,33 +1,29 @@
 use slack_coder::agent::AgentManager;
-use slack_coder::config::load_settings;
-use slack_coder::error::Result;
+use slack_coder::config::{SharedSettings, load_dotenv, load_settings, reload_settings};
+use slack_coder::error::{Result, SlackCoderError};
+use slack_coder::health::{self, HealthState};
+use slack_coder::logging::{LogFilterHandle, LogFormat, init_tracing};
 use slack_coder::metadata::MetadataCache;
-use slack_coder::slack::{EventHandler, ProgressTracker, SlackClient};
+use slack_coder::slack::{ChannelId, EventHandler, ProgressTracker, SlackClient, SlackLimits};
 use slack_coder::storage::Workspace;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::signal;
-use tracing_subscriber::EnvFilter;
 
 #[tokio::main]
 async fn main() -> Result<()> {
     // Initialize rustls crypto provider
     let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
 
-    // Initialize tracing
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| EnvFilter::new("slack_coder=debug,slack_morphism=debug")),
-        )
-        .with_target(true)
-        .with_line_number(true)
-        .init();
+    // Initialize tracing (.env is loaded early so LOG_FORMAT can come from it too)
+    load_dotenv();
+    let log_filter = init_tracing(LogFormat::from_env()?);
 
     tracing::info!("🚀 Starting Slack Coder Bot");
 
     // Load configuration
-    let settings = Arc::new(load_settings()?);
+    let shared_settings = SharedSettings::new(load_settings()?);
+    let settings = shared_settings.load();
     tracing::info!("✅ Configuration loaded");
     tracing::debug!(
         "Config: model={}, workspace={:?}",
@@ -44,21 +40,54 @@ async fn main() -> Result<()> {
     );
 
     // Create Slack client
-    let slack_client = Arc::new(SlackClient::new(settings.slack.clone())?);
+    let slack_client = Arc::new(SlackClient::new(settings.slack.clone())?.with_limits(
+        SlackLimits::default().with_max_message_bytes(settings.display.max_message_bytes),
+    ));
     tracing::info!("Slack client created");
 
+    // Fail fast on a bad token rather than on the first event
+    let identity = slack_client.validate_token().await?;
+    tracing::info!(
+        bot_user_id = %identity.user_id,
+        bot_id = ?identity.bot_id,
+        team_id = %identity.team_id,
+        team = ?identity.team,
+        scopes = ?identity.scopes,
+        "✅ Slack token validated"
+    );
+    let missing_optional = identity.missing_optional_scopes();
+    if !missing_optional.is_empty() {
+        tracing::warn!(
+            missing = ?missing_optional,
+            "Bot token lacks optional scopes; snippets or feedback reactions won't work"
+        );
+    }
+
     // Create metadata cache for enriched logging
-    let metadata_cache = Arc::new(MetadataCache::new(slack_client.clone()));
+    let metadata_cache = Arc::new(
+        MetadataCache::new(slack_client.clone()).with_negative_ttl(
+            settings
+                .slack
+                .metadata_negative_ttl_secs
+                .map(Duration::from_secs),
+        ),
+    );
     tracing::info!("Metadata cache initialized");
 
     // Create progress tracker
-    let progress_tracker = Arc::new(ProgressTracker::new(slack_client.clone()));
+    let progress_tracker = Arc::new(
+        ProgressTracker::new(
+            slack_client.clone(),
+            settings.display.progress_emoji.clone(),
+        )
+        .with_finish_mode(settings.display.progress_finish_mode),
+    );
     tracing::debug!("Progress tracker initialized");
 
     // Create agent manager
     let agent_manager = Arc::new(
         AgentManager::new(
-            settings.clone(),
+            shared_settings.clone(),
             workspace.clone(),
             progress_tracker.clone(),
         )
@@ -73,6 +102,22 @@ async fn main() -> Result<()> {
         .await?;
     tracing::info!("Channels scanned and agents restored");
 
+    // Start background cleanup of inactive agents
+    // Check often enough that every idle agent gets its warning before being reaped
+    let mut cleanup_interval = Duration::from_secs(settings.workspace.cleanup_interval_secs);
+    if let Some(warn_after) = settings.agent.idle_warning_after() {
+        let warning_window =
+            Duration::from_secs(settings.agent.agent_timeout_secs).saturating_sub(warn_after);
+        cleanup_interval = cleanup_interval.min(warning_window / 2);
+    }
+    let (cleanup_shutdown_tx, cleanup_shutdown_rx) = tokio::sync::watch::channel(false);
+    let cleanup_handle =
+        spawn_cleanup_task(agent_manager.clone(), cleanup_interval, cleanup_shutdown_rx);
+    tracing::info!(
+        interval_secs = cleanup_interval.as_secs(),
+        "Inactive agent cleanup task started"
+    );
+
     // Start event handler
     tracing::info!("Starting event handler (Socket Mode)");
     let event_handler = EventHandler::new(
@@ -81,9 +126,25 @@ async fn main() -> Result<()> {
         metadata_cache.clone(),
     );
 
+    // Start health check server, reporting the listener's connection state
+    let (health_shutdown_tx, health_shutdown_rx) = tokio::sync::watch::channel(false);
+    let health_handle = spawn_health_server(
+        settings.server.health_port,
+        HealthState {
+            connection: event_handler.connection_state(),
+            agent_manager: agent_manager.clone(),
+            metadata_cache: metadata_cache.clone(),
+        },
+        health_shutdown_rx,
+    );
+
+    // Reload settings on SIGHUP without dropping agents
+    spawn_reload_handler(shared_settings, log_filter);
+
     // Clone references for shutdown handler
     let shutdown_agent_manager = agent_manager.clone();
     let shutdown_slack_client = slack_client.clone();
+    let shutdown_metadata_cache = metadata_cache.clone();
 
     // Setup shutdown signal handler in background
     let (shutdown_tx, mut shutdown_rx) = tokio::sync::mpsc::channel::<String>(1);
@@ -95,7 +156,13 @@ async fn main() -> Result<()> {
     // Run application with shutdown handling
     let shutdown_result = tokio::select! {
         result = event_handler.start() => {
-            tracing::info!("Event handler completed normally");
+            // An error (e.g. revoked tokens) is returned from main for a non-zero exit
+            match &result {
+                Ok(()) => tracing::info!("Event handler completed normally"),
+                Err(e) => tracing::error!(error = %e, "Event handler stopped"),
+            }
+            stop_cleanup_task(cleanup_shutdown_tx, cleanup_handle).await;
+            stop_health_server(health_shutdown_tx, health_handle).await;
             result
         }
         Some(signal_name) = shutdown_rx.recv() => {
@@ -104,8 +171,18 @@ async fn main() -> Result<()> {
                 "Received shutdown signal, initiating graceful shutdown"
             );
 
+            // Stop the cleanup task first so it doesn't race with agent disconnects
+            stop_cleanup_task(cleanup_shutdown_tx, cleanup_handle).await;
+
             // Send shutdown notifications and cleanup agents
-            shutdown_gracefully(&shutdown_agent_manager, &shutdown_slack_client).await;
+            shutdown_gracefully(
+                &shutdown_agent_manager,
+                &shutdown_slack_client,
+                &shutdown_metadata_cache,
+            )
+            .await;
+
+            stop_health_server(health_shutdown_tx, health_handle).await;
 
             tracing::info!("Graceful shutdown complete");
             Ok(())
@@ -117,6 +194,82 @@ async fn main() -> Result<()> {
     shutdown_result
 }
 
+/// Spawn a background task that periodically reaps inactive agents
+/// The task exits when `shutdown_rx` observes `true`
+fn spawn_cleanup_task(
+    agent_manager: Arc<AgentManager>,
+    interval: Duration,
+    mut shutdown_rx: tokio::sync::watch::Receiver<bool>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval.max(Duration::from_secs(1)));
+        // The first tick completes immediately - skip it so we don't reap right at startup
+        ticker.tick().await;
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    match agent_manager.cleanup_inactive_agents().await {
+                        Ok(0) => tracing::debug!("No inactive agents to reap"),
+                        Ok(reaped) => tracing::info!(reaped = reaped, "Reaped inactive agents"),
+                        Err(e) => tracing::warn!(error = %e, "Inactive agent cleanup failed"),
+                    }
+                }
+                _ = shutdown_rx.changed() => {
+                    tracing::debug!("Cleanup task received shutdown signal");
+                    break;
+                }
+            }
+        }
+    })
+}
+
+/// Signal the cleanup task to stop and wait for it to exit
+async fn stop_cleanup_task(
+    shutdown_tx: tokio::sync::watch::Sender<bool>,
+    handle: tokio::task::JoinHandle<()>,
+) {
+    let _ = shutdown_tx.send(true);
+    if let Err(e) = handle.await {
+        tracing::warn!(error = %e, "Cleanup task terminated abnormally");
+    }
+}
+
+/// Spawn the health check HTTP server, unless disabled with port 0
+/// The server stops when `shutdown_rx` observes `true`
+fn spawn_health_server(
+    port: u16,
+    state: HealthState,
+    mut shutdown_rx: tokio::sync::watch::Receiver<bool>,
+) -> Option<tokio::task::JoinHandle<()>> {
+    if port == 0 {
+        tracing::info!("Health check server disabled");
+        return None;
+    }
+
+    Some(tokio::spawn(async move {
+        let shutdown = async move {
+            let _ = shutdown_rx.changed().await;
+        };
+        if let Err(e) = health::serve(port, state, shutdown).await {
+            tracing::error!(error = %e, port = port, "Health check server failed");
+        }
+    }))
+}
+
+/// Signal the health check server to stop and wait for it to exit
+async fn stop_health_server(
+    shutdown_tx: tokio::sync::watch::Sender<bool>,
+    handle: Option<tokio::task::JoinHandle<()>>,
+) {
+    let _ = shutdown_tx.send(true);
+    if let Some(handle) = handle
+        && let Err(e) = handle.await
+    {
+        tracing::warn!(error = %e, "Health check server terminated abnormally");
+    }
+}
+
 /// Setup signal handlers for graceful shutdown
 /// Handles SIGINT (Ctrl+C), SIGTERM, and SIGQUIT on Unix systems
 async fn setup_shutdown_handler() -> String {
@@ -153,14 +306,80 @@ async fn setup_shutdown_handler() -> String {
     }
 }
 
+/// Reload configuration whenever SIGHUP arrives (Unix only)
+fn spawn_reload_handler(settings: SharedSettings, log_filter: LogFilterHandle) {
+    #[cfg(unix)]
+    {
+        use signal::unix::{SignalKind, signal};
+
+        let mut sighup = match signal(SignalKind::hangup()) {
+            Ok(sighup) => sighup,
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to setup SIGHUP handler; config reload disabled");
+                return;
+            }
+        };
+        tokio::spawn(async move {
+            while sighup.recv().await.is_some() {
+                tracing::info!("Caught SIGHUP signal, reloading configuration");
+                reload_config(&settings, &log_filter);
+            }
+        });
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (settings, log_filter);
+    }
+}
+
+/// Re-read `.env` and the environment and apply what can change at runtime: the log
+/// filter, timeouts, display options and the like. Settings only read at startup
+/// (tokens, ports, paths) keep their values and are reported as needing a restart.
+fn reload_config(settings: &SharedSettings, log_filter: &LogFilterHandle) {
+    let new = match reload_settings() {
+        Ok(new) => new,
+        Err(e) => {
+            tracing::error!(error = %e, "Config reload failed, keeping current settings");
+            return;
+        }
+    };
+    let report = settings.reload(new);
+
+    if let Err(e) = log_filter.reload_from_env() {
+        tracing::warn!(error = %e, "Failed to apply log filter");
+    }
+    if report.restart_required.is_empty() && report.new_agents_only.is_empty() {
+        tracing::info!("✅ Configuration reloaded");
+    }
+    if !report.restart_required.is_empty() {
+        tracing::warn!(
+            settings = ?report.restart_required,
+            "Configuration reloaded; changes to {} require restart",
+            report.restart_required.join(", ")
+        );
+    }
+    if !report.new_agents_only.is_empty() {
+        tracing::warn!(
+            settings = ?report.new_agents_only,
+            "Configuration reloaded; changes to {} apply to new agents (use /restart for running ones)",
+            report.new_agents_only.join(", ")
+        );
+    }
+}
+
 /// Gracefully shutdown the application
 /// 1. Send shutdown notifications to all channels
 /// 2. Disconnect all agents properly
-async fn shutdown_gracefully(agent_manager: &Arc<AgentManager>, slack_client: &Arc<SlackClient>) {
+async fn shutdown_gracefully(
+    agent_manager: &Arc<AgentManager>,
+    slack_client: &Arc<SlackClient>,
+    metadata_cache: &Arc<MetadataCache>,
+) {
     tracing::info!("Starting graceful shutdown sequence");
 
     // Step 1: Send shutdown notifications
-    send_shutdown_notifications(agent_manager, slack_client).await;
+    send_shutdown_notifications(agent_manager, slack_client, metadata_cache).await;
 
     // Step 2: Disconnect all agents to cleanup resources
     disconnect_all_agents(agent_manager).await;
@@ -168,11 +387,21 @@ async fn shutdown_gracefully(agent_manager: &Arc<AgentManager>, slack_client: &A
     tracing::info!("All cleanup tasks completed");
 }
 
+/// Time allowed for one channel's shutdown notice, including its retry
+const SHUTDOWN_NOTICE_BUDGET: Duration = Duration::from_secs(3);
+
+/// Pause before retrying a failed shutdown notice
+const SHUTDOWN_NOTICE_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+/// Cap on the whole notification step, so shutdown stays bounded
+const SHUTDOWN_NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+
 /// Send shutdown notifications to all active channels (in parallel)
-/// This function will send all notifications concurrently with 5s total timeout
+/// Each notice is retried once within its 3s budget; the whole step is capped at 10s
 async fn send_shutdown_notifications(
     agent_manager: &Arc<AgentManager>,
     slack_client: &Arc<SlackClient>,
+    metadata_cache: &Arc<MetadataCache>,
 ) {
     tracing::info!("Sending shutdown notifications to all channels");
 
@@ -185,19 +414,34 @@ async fn send_shutdown_notifications(
         return;
     }
 
+    // Every notice shares the overall deadline, so each one still reports its own outcome
+    let overall_deadline = tokio::time::Instant::now() + SHUTDOWN_NOTIFY_TIMEOUT;
+
     // Send all shutdown notices in parallel
     let notification_futures: Vec<_> = agents
         .into_iter()
         .map(|(channel_id, session_id)| {
             let client = slack_client.clone();
             async move {
-                let result = tokio::time::timeout(
-                    Duration::from_secs(3),
-                    client.send_shutdown_notice(&channel_id, &session_id),
-                )
-                .await;
+                let deadline =
+                    (tokio::time::Instant::now() + SHUTDOWN_NOTICE_BUDGET).min(overall_deadline);
+                let send = async {
+                    if let Err(e) = client.send_shutdown_notice(&channel_id, &session_id).await {
+                        tracing::debug!(
+                            channel_id = %channel_id,
+                            error = %e,
+                            "Shutdown notice failed, retrying once"
+                        );
+                        tokio::time::sleep(SHUTDOWN_NOTICE_RETRY_DELAY).await;
+                        client
+                            .send_shutdown_notice(&channel_id, &session_id)
+                            .await?;
+                    }
+                    Ok::<_, SlackCoderError>(())
+                };
+                let result = tokio::time::timeout_at(deadline, send).await;
 
-                (channel_id, session_id, result)
+                (channel_id, result)
             }
         })
         .collect();
@@ -205,68 +449,76 @@ async fn send_shutdown_notifications(
     let total = notification_futures.len();
     tracing::info!(total = total, "Sending shutdown notices in parallel");
 
-    // Execute all in parallel with overall 10s timeout (generous to ensure delivery)
-    let results = tokio::time::timeout(
-        Duration::from_secs(10),
-        futures::future::join_all(notification_futures),
-    )
-    .await;
+    let results = futures::future::join_all(notification_futures).await;
 
     // Count successes/failures
     let mut success_count = 0;
-    let mut failure_count = 0;
-
-    match results {
-        Ok(results) => {
-            for (channel_id, _session_id, result) in results {
-                match result {
-                    Ok(Ok(_)) => {
-                        success_count += 1;
-                        tracing::debug!(
-                            channel_id = %channel_id.as_str(),
-                            "Shutdown notice sent"
-                        );
-                    }
-                    Ok(Err(e)) => {
-                        failure_count += 1;
-                        tracing::warn!(
-                            channel_id = %channel_id.as_str(),
-                            error = %e,
-                            "Failed to send shutdown notice"
-                        );
-                    }
-                    Err(_) => {
-                        failure_count += 1;
-                        tracing::warn!(
-                            channel_id = %channel_id.as_str(),
-                            "Timeout sending shutdown notice"
-                        );
-                    }
-                }
+    let mut failed_channels = Vec::new();
+
+    for (channel_id, result) in results {
+        match result {
+            Ok(Ok(())) => {
+                success_count += 1;
+                tracing::debug!(
+                    channel_id = %channel_id,
+                    "Shutdown notice sent"
+                );
+            }
+            Ok(Err(e)) => {
+                tracing::warn!(
+                    channel_id = %channel_id,
+                    error = %e,
+                    "Failed to send shutdown notice after retry"
+                );
+                failed_channels.push(channel_id);
+            }
+            Err(_) => {
+                tracing::warn!(
+                    channel_id = %channel_id,
+                    "Timeout sending shutdown notice"
+                );
+                failed_channels.push(channel_id);
             }
-        }
-        Err(_) => {
-            tracing::warn!(
-                timeout_secs = 10,
-                "Overall shutdown notification timeout - messages may not have been delivered"
-            );
-            failure_count = total;
         }
     }
 
-    let success_rate = if total > 0 {
-        (success_count as f32 / total as f32 * 100.0) as u32
-    } else {
-        0
-    };
+    let success_rate = (success_count as f32 / total as f32 * 100.0) as u32;
 
     tracing::info!(
         succeeded = success_count,
-        failed = failure_count,
+        failed = failed_channels.len(),
         total = total,
         success_rate = success_rate,
         "Shutdown notification summary"
     );
+
+    if !failed_channels.is_empty() {
+        let names = channel_names(metadata_cache, &failed_channels).await;
+        tracing::warn!(
+            failed_channels = %names.join(", "),
+            "Channels not notified of shutdown: {}",
+            names.join(", ")
+        );
+    }
+}
+
+/// Display names for channels, from the metadata cache
+///
+/// Names not already cached get one quick lookup; the channel id is used if that fails
+/// or is slow, so a flaky network can't stall shutdown.
+async fn channel_names(metadata_cache: &MetadataCache, channels: &[ChannelId]) -> Vec<String> {
+    let lookups = channels.iter().map(|channel_id| async move {
+        tokio::time::timeout(
+            Duration::from_millis(500),
+            metadata_cache.get_channel_info(channel_id.as_str()),
+        )
+        .await
+        .ok()
+        .flatten()
+        .map(|info| format!("{} ({})", info.display_name(), channel_id))
+        .unwrap_or_else(|| channel_id.to_string())
+    });
+    futures::future::join_all(lookups).await
 }
 
 /// Disconnect all agents to cleanup resources properly
@@ -287,13 +539,13 @@ async fn disconnect_all_agents(agent_manager: &Arc<AgentManager>) {
 
     for channel in agent_channels {
         tracing::debug!(
-            channel_id = %channel.as_str(),
+            channel_id = %channel,
             "Disconnecting agent"
         );
 
         if let Err(e) = agent_manager.remove_agent(&channel).await {
             tracing::warn!(
-                channel_id = %channel.as_str(),
+                channel_id = %channel,
                 error = %e,
                 "Failed to disconnect agent"
             );
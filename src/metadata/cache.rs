@@ -2,12 +2,16 @@
 
 use crate::error::Result;
 use crate::metadata::types::{ChannelInfo, LogContext, UserInfo};
-use crate::slack::SlackClient;
+use crate::slack::{SlackClient, user_handles};
 use dashmap::DashMap;
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
 
+/// Default TTL for remembering failed lookups
+const DEFAULT_NEGATIVE_TTL: Duration = Duration::from_secs(60);
+
 /// Cache statistics for monitoring
 #[derive(Debug, Default, Clone)]
 pub struct CacheStats {
@@ -17,6 +21,50 @@ pub struct CacheStats {
     pub user_misses: u64,
     pub api_calls: u64,
     pub api_errors: u64,
+    pub negative_hits: u64,
+}
+
+impl CacheStats {
+    /// Channel cache hit rate as a percentage
+    pub fn channel_hit_rate(&self) -> u32 {
+        Self::hit_rate(self.channel_hits, self.channel_misses)
+    }
+
+    /// User cache hit rate as a percentage
+    pub fn user_hit_rate(&self) -> u32 {
+        Self::hit_rate(self.user_hits, self.user_misses)
+    }
+
+    fn hit_rate(hits: u64, misses: u64) -> u32 {
+        if hits + misses > 0 {
+            (hits as f32 / (hits + misses) as f32 * 100.0) as u32
+        } else {
+            0
+        }
+    }
+
+    /// Format statistics as a Slack message
+    pub fn format_slack_message(&self, channels_cached: usize, users_cached: usize) -> String {
+        format!(
+            "📈 *Metadata Cache Statistics*\n\
+             • Cached: {} channels, {} users\n\
+             • Channel hit rate: {}% ({} hits, {} misses)\n\
+             • User hit rate: {}% ({} hits, {} misses)\n\
+             • Negative hits: {}\n\
+             • API calls: {} ({} errors)",
+            channels_cached,
+            users_cached,
+            self.channel_hit_rate(),
+            self.channel_hits,
+            self.channel_misses,
+            self.user_hit_rate(),
+            self.user_hits,
+            self.user_misses,
+            self.negative_hits,
+            self.api_calls,
+            self.api_errors
+        )
+    }
 }
 
 /// Metadata cache with lazy-loading from Slack API
@@ -33,9 +81,18 @@ pub struct MetadataCache {
     /// User metadata cache (lazy-populated)
     users: Arc<DashMap<String, UserInfo>>,
 
+    /// Recently failed channel lookups (kept apart from successful entries)
+    negative_channels: Arc<DashMap<String, Instant>>,
+
+    /// Recently failed user lookups (kept apart from successful entries)
+    negative_users: Arc<DashMap<String, Instant>>,
+
     /// Cache TTL (how long before refresh)
     ttl: Duration,
 
+    /// Negative cache TTL (how long a failed lookup is remembered; None = forever)
+    negative_ttl: Option<Duration>,
+
     /// Cache statistics
     stats: Arc<RwLock<CacheStats>>,
 }
@@ -48,8 +105,19 @@ impl MetadataCache {
 
     /// Create a new metadata cache with custom TTL
     pub fn with_ttl(slack_client: Arc<SlackClient>, ttl: Duration) -> Self {
+        Self::with_ttls(slack_client, ttl, Some(DEFAULT_NEGATIVE_TTL))
+    }
+
+    /// Create a new metadata cache with custom TTLs for both successful and failed
+    /// lookups. A `negative_ttl` of None remembers failures until restart.
+    pub fn with_ttls(
+        slack_client: Arc<SlackClient>,
+        ttl: Duration,
+        negative_ttl: Option<Duration>,
+    ) -> Self {
         tracing::info!(
             ttl_secs = ttl.as_secs(),
+            negative_ttl_secs = ?negative_ttl.map(|ttl| ttl.as_secs()),
             "Creating metadata cache with lazy-loading"
         );
 
@@ -57,11 +125,20 @@ impl MetadataCache {
             slack_client,
             channels: Arc::new(DashMap::new()),
             users: Arc::new(DashMap::new()),
+            negative_channels: Arc::new(DashMap::new()),
+            negative_users: Arc::new(DashMap::new()),
             ttl,
+            negative_ttl,
             stats: Arc::new(RwLock::new(CacheStats::default())),
         }
     }
 
+    /// Set how long failed lookups are remembered (None = until restart)
+    pub fn with_negative_ttl(mut self, negative_ttl: Option<Duration>) -> Self {
+        self.negative_ttl = negative_ttl;
+        self
+    }
+
     /// Get channel info (fetch if not cached or stale)
     ///
     /// This is LAZY - only fetches when actually needed.
@@ -86,6 +163,13 @@ impl MetadataCache {
             }
         }
 
+        // Recently failed - skip the API call and let callers fall back to the ID
+        if self.is_negative(&self.negative_channels, channel_id) {
+            self.stats.write().await.negative_hits += 1;
+            tracing::trace!(channel_id = %channel_id, "Channel negative cache hit");
+            return None;
+        }
+
         // Cache miss or stale - fetch from API (only this specific channel)
         self.stats.write().await.channel_misses += 1;
         tracing::debug!(
@@ -130,6 +214,13 @@ impl MetadataCache {
             }
         }
 
+        // Recently failed - skip the API call and let callers fall back to the ID
+        if self.is_negative(&self.negative_users, user_id) {
+            self.stats.write().await.negative_hits += 1;
+            tracing::trace!(user_id = %user_id, "User negative cache hit");
+            return None;
+        }
+
         // Cache miss or stale - fetch from API (only this specific user)
         self.stats.write().await.user_misses += 1;
         tracing::debug!(
@@ -156,21 +247,57 @@ impl MetadataCache {
 
         match self.slack_client.get_channel_info(channel_id).await {
             Ok(info) => {
+                let info = if info.needs_member_name() {
+                    self.name_from_members(info).await
+                } else {
+                    info
+                };
                 tracing::info!(
                     channel_id = %channel_id,
                     channel = %info.name,
                     "Fetched and cached channel info"
                 );
                 self.channels.insert(channel_id.to_string(), info.clone());
+                self.negative_channels.remove(channel_id);
                 Ok(info)
             }
             Err(e) => {
                 self.stats.write().await.api_errors += 1;
+                self.negative_channels
+                    .insert(channel_id.to_string(), Instant::now());
                 Err(e)
             }
         }
     }
 
+    /// Name a DM or group DM after its members, keeping the fallback name if they
+    /// can't be listed (e.g. without the `im:read`/`mpim:read` scopes)
+    async fn name_from_members(&self, info: ChannelInfo) -> ChannelInfo {
+        let member_ids = match self.slack_client.get_channel_members(&info.id).await {
+            Ok(ids) => ids,
+            Err(e) => {
+                tracing::debug!(
+                    channel_id = %info.id,
+                    error = %e,
+                    "Failed to list members for channel name"
+                );
+                return info;
+            }
+        };
+
+        let bot_user_id = self.slack_client.cached_bot_user_id().cloned();
+        let mut members = Vec::with_capacity(member_ids.len());
+        for id in &member_ids {
+            if Some(id) == bot_user_id.as_ref() {
+                continue;
+            }
+            if let Some(user) = self.get_user_info(id.as_str()).await {
+                members.push(user);
+            }
+        }
+        info.with_member_name(&members, bot_user_id.as_ref().map(|id| id.as_str()))
+    }
+
     /// Fetch user info from Slack API
     async fn fetch_user_info(&self, user_id: &str) -> Result<UserInfo> {
         self.stats.write().await.api_calls += 1;
@@ -183,15 +310,31 @@ impl MetadataCache {
                     "Fetched and cached user info"
                 );
                 self.users.insert(user_id.to_string(), info.clone());
+                self.negative_users.remove(user_id);
                 Ok(info)
             }
             Err(e) => {
                 self.stats.write().await.api_errors += 1;
+                self.negative_users
+                    .insert(user_id.to_string(), Instant::now());
                 Err(e)
             }
         }
     }
 
+    /// Check whether a lookup failed recently (within the negative TTL)
+    fn is_negative(&self, entries: &DashMap<String, Instant>, id: &str) -> bool {
+        entries
+            .get(id)
+            .is_some_and(|failed_at| self.is_negative_fresh(*failed_at))
+    }
+
+    /// Whether a failure recorded at `failed_at` is still remembered
+    fn is_negative_fresh(&self, failed_at: Instant) -> bool {
+        self.negative_ttl
+            .is_none_or(|negative_ttl| failed_at.elapsed() < negative_ttl)
+    }
+
     /// Create a logging context with enriched metadata
     ///
     /// This fetches channel and user info if needed (lazy-loading).
@@ -213,6 +356,18 @@ impl MetadataCache {
         self.stats.read().await.clone()
     }
 
+    /// Handles of cached, fresh users mapped to their ids, for resolving `@name`
+    /// mentions in agent output (see [`crate::slack::linkify_mentions`])
+    pub fn user_handles(&self) -> HashMap<String, String> {
+        let users: Vec<UserInfo> = self
+            .users
+            .iter()
+            .filter(|entry| !entry.is_stale(self.ttl))
+            .map(|entry| entry.value().clone())
+            .collect();
+        user_handles(&users)
+    }
+
     /// Get current cache sizes
     pub fn cache_sizes(&self) -> (usize, usize) {
         (self.channels.len(), self.users.len())
@@ -230,6 +385,12 @@ impl MetadataCache {
         // Clean users
         self.users.retain(|_, info| !info.is_stale(ttl));
 
+        // Clean expired negative entries
+        self.negative_channels
+            .retain(|_, failed_at| self.is_negative_fresh(*failed_at));
+        self.negative_users
+            .retain(|_, failed_at| self.is_negative_fresh(*failed_at));
+
         let removed_channels = initial_channels - self.channels.len();
         let removed_users = initial_users - self.users.len();
 
@@ -249,26 +410,14 @@ impl MetadataCache {
         let stats = self.get_stats().await;
         let (channel_count, user_count) = self.cache_sizes();
 
-        let channel_hit_rate = if stats.channel_hits + stats.channel_misses > 0 {
-            (stats.channel_hits as f32 / (stats.channel_hits + stats.channel_misses) as f32 * 100.0)
-                as u32
-        } else {
-            0
-        };
-
-        let user_hit_rate = if stats.user_hits + stats.user_misses > 0 {
-            (stats.user_hits as f32 / (stats.user_hits + stats.user_misses) as f32 * 100.0) as u32
-        } else {
-            0
-        };
-
         tracing::info!(
             channels_cached = channel_count,
             users_cached = user_count,
-            channel_hit_rate = channel_hit_rate,
-            user_hit_rate = user_hit_rate,
+            channel_hit_rate = stats.channel_hit_rate(),
+            user_hit_rate = stats.user_hit_rate(),
             api_calls = stats.api_calls,
             api_errors = stats.api_errors,
+            negative_hits = stats.negative_hits,
             "Metadata cache statistics"
         );
     }
@@ -279,8 +428,7 @@ mod tests {
     use super::*;
     use crate::config::SlackConfig;
 
-    #[test]
-    fn test_cache_sizes() {
+    fn test_client() -> Arc<SlackClient> {
         // Initialize crypto provider for rustls
         let _ = rustls::crypto::aws_lc_rs::default_provider().install_default();
 
@@ -288,12 +436,97 @@ mod tests {
             bot_token: "xoxb-test".to_string(),
             app_token: "xapp-test".to_string(),
             signing_secret: "test-secret".to_string(),
+            admin_user_ids: vec![],
+            event_dedup_retention_secs: 3600,
+            event_dedup_max_entries: 100,
+            command_prefix: "/".to_string(),
+            rate_limit_per_minute: 0,
+            audit_channel_id: None,
+            metadata_negative_ttl_secs: Some(60),
         };
-        let slack_client = Arc::new(SlackClient::new(config).unwrap());
-        let cache = MetadataCache::new(slack_client);
+        Arc::new(SlackClient::new(config).unwrap())
+    }
+
+    #[test]
+    fn test_cache_sizes() {
+        let cache = MetadataCache::new(test_client());
 
         let (channels, users) = cache.cache_sizes();
         assert_eq!(channels, 0);
         assert_eq!(users, 0);
     }
+
+    #[test]
+    fn test_format_stats_message() {
+        let stats = CacheStats {
+            channel_hits: 3,
+            channel_misses: 1,
+            user_hits: 1,
+            user_misses: 1,
+            api_calls: 2,
+            api_errors: 1,
+            negative_hits: 4,
+        };
+
+        let message = stats.format_slack_message(5, 7);
+
+        assert!(message.contains("5 channels, 7 users"));
+        assert!(message.contains("Channel hit rate: 75%"));
+        assert!(message.contains("User hit rate: 50%"));
+        assert!(message.contains("Negative hits: 4"));
+        assert!(message.contains("API calls: 2 (1 errors)"));
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache_skips_api() {
+        let cache = MetadataCache::new(test_client());
+        cache
+            .negative_users
+            .insert("U_DEACTIVATED".to_string(), Instant::now());
+        cache
+            .negative_channels
+            .insert("C_GONE".to_string(), Instant::now());
+
+        assert!(cache.get_user_info("U_DEACTIVATED").await.is_none());
+        assert!(cache.get_channel_info("C_GONE").await.is_none());
+
+        let stats = cache.get_stats().await;
+        assert_eq!(stats.negative_hits, 2);
+        assert_eq!(stats.api_calls, 0);
+        assert_eq!(stats.user_misses, 0);
+        assert_eq!(stats.channel_misses, 0);
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache_expires() {
+        let cache = MetadataCache::with_ttls(
+            test_client(),
+            Duration::from_secs(3600),
+            Some(Duration::ZERO),
+        );
+        cache
+            .negative_users
+            .insert("U_EXPIRED".to_string(), Instant::now());
+
+        assert!(!cache.is_negative(&cache.negative_users, "U_EXPIRED"));
+
+        cache.cleanup_stale().await;
+        assert!(cache.negative_users.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache_never_expires() {
+        let cache = MetadataCache::with_ttls(test_client(), Duration::from_secs(3600), None);
+        let long_ago = Instant::now()
+            .checked_sub(Duration::from_secs(60 * 60))
+            .unwrap();
+        cache
+            .negative_users
+            .insert("U_DEACTIVATED".to_string(), long_ago);
+
+        assert!(cache.is_negative(&cache.negative_users, "U_DEACTIVATED"));
+
+        cache.cleanup_stale().await;
+        assert_eq!(cache.negative_users.len(), 1);
+    }
 }
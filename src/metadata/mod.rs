@@ -7,6 +7,7 @@
 //! - Lazy-loading: Only fetches metadata when needed
 //! - No bulk fetching: Never fetches all workspace users
 //! - TTL-based caching: 1-hour default (configurable)
+//! - Negative caching: Failed lookups are remembered briefly to avoid API hammering
 //! - Graceful degradation: Falls back to IDs if API fails
 
 mod cache;
@@ -26,8 +26,15 @@ pub struct ChannelInfo {
 
     /// Topic/description (optional)
     pub topic: Option<String>,
+
+    /// Slack returned no name (DMs, or missing scopes), so `name` is a fallback built
+    /// from the id rather than a real channel name
+    pub name_is_fallback: bool,
 }
 
+/// Members named in a group DM's derived name before the rest are counted
+const MAX_NAMED_MEMBERS: usize = 3;
+
 /// Channel type enumeration
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ChannelType {
@@ -52,6 +59,44 @@ impl ChannelInfo {
             _ => self.name.clone(),
         }
     }
+
+    /// Whether this is a DM or group DM still named after its id, whose name can be
+    /// derived from its members instead
+    pub fn needs_member_name(&self) -> bool {
+        self.name_is_fallback
+            && matches!(
+                self.channel_type,
+                ChannelType::DirectMessage | ChannelType::MultiPartyDirectMessage
+            )
+    }
+
+    /// Name a DM or group DM after its members ("DM with @john"), leaving out the bot.
+    /// The name is kept as-is when no other member is known.
+    pub fn with_member_name(mut self, members: &[UserInfo], bot_user_id: Option<&str>) -> Self {
+        let names: Vec<String> = members
+            .iter()
+            .filter(|user| Some(user.id.as_str()) != bot_user_id)
+            .map(|user| match user.best_name() {
+                "" => format!("@{}", user.name),
+                _ => user.display_name_with_at(),
+            })
+            .collect();
+        if names.is_empty() {
+            return self;
+        }
+
+        self.name = match self.channel_type {
+            ChannelType::DirectMessage => format!("DM with {}", names[0]),
+            _ if names.len() > MAX_NAMED_MEMBERS => format!(
+                "Group DM with {} +{} more",
+                names[..MAX_NAMED_MEMBERS].join(", "),
+                names.len() - MAX_NAMED_MEMBERS
+            ),
+            _ => format!("Group DM with {}", names.join(", ")),
+        };
+        self.name_is_fallback = false;
+        self
+    }
 }
 
 /// User metadata information
@@ -174,6 +219,7 @@ mod tests {
             member_count: Some(42),
             fetched_at: Instant::now(),
             topic: None,
+            name_is_fallback: false,
         };
 
         assert_eq!(channel.display_name(), "#engineering");
@@ -189,11 +235,82 @@ mod tests {
             member_count: Some(2),
             fetched_at: Instant::now(),
             topic: None,
+            name_is_fallback: false,
         };
 
         assert_eq!(channel.display_name(), "john.doe");
     }
 
+    fn member(id: &str, name: &str, display_name: Option<&str>) -> UserInfo {
+        UserInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            real_name: None,
+            display_name: display_name.map(str::to_string),
+            email: None,
+            is_bot: id.starts_with('B'),
+            fetched_at: Instant::now(),
+        }
+    }
+
+    fn unnamed(channel_type: ChannelType) -> ChannelInfo {
+        ChannelInfo {
+            id: "D123".to_string(),
+            name: "dm-D123".to_string(),
+            channel_type,
+            is_private: true,
+            member_count: None,
+            fetched_at: Instant::now(),
+            topic: None,
+            name_is_fallback: true,
+        }
+    }
+
+    #[test]
+    fn test_dm_named_after_member() {
+        let channel = unnamed(ChannelType::DirectMessage);
+        assert!(channel.needs_member_name());
+
+        let members = [
+            member("B01", "coder", None),
+            member("U01", "john", Some("")),
+        ];
+        let channel = channel.with_member_name(&members, Some("B01"));
+        assert_eq!(channel.name, "DM with @john");
+        assert_eq!(channel.display_name(), "DM with @john");
+        assert!(!channel.needs_member_name());
+    }
+
+    #[test]
+    fn test_group_dm_named_after_members() {
+        let members: Vec<UserInfo> = ["ann", "bob", "cat", "dan", "eve"]
+            .iter()
+            .enumerate()
+            .map(|(i, name)| member(&format!("U0{i}"), name, None))
+            .collect();
+
+        let channel = unnamed(ChannelType::MultiPartyDirectMessage);
+        assert_eq!(
+            channel.clone().with_member_name(&members[..2], None).name,
+            "Group DM with @ann, @bob"
+        );
+        assert_eq!(
+            channel.with_member_name(&members, None).name,
+            "Group DM with @ann, @bob, @cat +2 more"
+        );
+    }
+
+    #[test]
+    fn test_member_name_kept_without_members() {
+        let channel = unnamed(ChannelType::DirectMessage).with_member_name(&[], None);
+        assert_eq!(channel.name, "dm-D123");
+        assert!(channel.needs_member_name());
+
+        let mut named = unnamed(ChannelType::PrivateChannel);
+        named.name_is_fallback = true;
+        assert!(!named.needs_member_name());
+    }
+
     #[test]
     fn test_user_info_best_name() {
         let user = UserInfo {
@@ -246,6 +363,7 @@ mod tests {
             member_count: None,
             fetched_at: Instant::now(),
             topic: None,
+            name_is_fallback: false,
         };
 
         let user = UserInfo {
@@ -1,21 +1,195 @@
-use crate::slack::ChannelId;
+use crate::config::SessionScope;
+use crate::error::{Result, SlackCoderError};
+use crate::slack::{ChannelId, ThreadTs};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
 pub type SessionId = String;
 
+/// The parts of a session id: `session-{channel}-{timestamp}-{nonce}`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Session {
+    /// Channel the session was started for
+    pub channel_id: ChannelId,
+    /// When the session was started (whole seconds)
+    pub created_at: SystemTime,
+    /// Random suffix keeping ids started in the same second apart
+    pub nonce: String,
+}
+
+impl Session {
+    /// A new session for `channel_id`, started now
+    pub fn new(channel_id: &ChannelId) -> Self {
+        let secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        Self {
+            channel_id: channel_id.clone(),
+            created_at: UNIX_EPOCH + Duration::from_secs(secs),
+            nonce: Uuid::new_v4().to_string()[..6].to_string(),
+        }
+    }
+
+    /// Parse a session id produced by [`generate_session_id`]
+    pub fn parse(id: &str) -> Result<Self> {
+        let invalid = || SlackCoderError::InvalidId(format!("Invalid session id: '{}'", id));
+        let rest = id.strip_prefix("session-").ok_or_else(invalid)?;
+        // Channel ids never contain `-`, so the parts split cleanly
+        let [channel, timestamp, nonce] = rest
+            .split('-')
+            .collect::<Vec<_>>()
+            .try_into()
+            .map_err(|_| invalid())?;
+
+        let channel_id = ChannelId::parse(channel).map_err(|_| invalid())?;
+        let secs: u64 = timestamp.parse().map_err(|_| invalid())?;
+        if nonce.is_empty() || !nonce.chars().all(|c| c.is_ascii_alphanumeric()) {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            channel_id,
+            created_at: UNIX_EPOCH + Duration::from_secs(secs),
+            nonce: nonce.to_string(),
+        })
+    }
+
+    /// Whether the session was started for `channel_id`
+    pub fn belongs_to(&self, channel_id: &ChannelId) -> bool {
+        &self.channel_id == channel_id
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self
+            .created_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        write!(
+            f,
+            "session-{}-{}-{}",
+            self.channel_id.as_str(),
+            secs,
+            self.nonce
+        )
+    }
+}
+
+impl FromStr for Session {
+    type Err = SlackCoderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::parse(s)
+    }
+}
+
+/// Thread sessions kept per channel; the least recently used is forgotten beyond this
+const MAX_THREAD_SESSIONS: usize = 256;
+
+/// Tracks which Claude session each conversation in a channel uses.
+///
+/// With [`SessionScope::Channel`] every message shares the channel's session. With
+/// [`SessionScope::Thread`] each Slack thread gets its own session, created on first
+/// use; a top-level message starts the session for the thread its replies will form.
+/// Messages without a thread fall back to the channel session.
+#[derive(Debug)]
+pub struct SessionRegistry {
+    channel_id: ChannelId,
+    scope: SessionScope,
+    channel_session: SessionId,
+    /// Session and last use for each thread (thread scope only)
+    thread_sessions: HashMap<ThreadTs, (SessionId, Instant)>,
+}
+
+impl SessionRegistry {
+    pub fn new(channel_id: ChannelId, scope: SessionScope) -> Self {
+        let channel_session = generate_session_id(&channel_id);
+        Self {
+            channel_id,
+            scope,
+            channel_session,
+            thread_sessions: HashMap::new(),
+        }
+    }
+
+    pub fn scope(&self) -> SessionScope {
+        self.scope
+    }
+
+    /// The channel-wide session (the only one in channel scope)
+    pub fn channel_session(&self) -> &SessionId {
+        &self.channel_session
+    }
+
+    /// Session for a message in `thread`, creating one for a new thread in thread scope
+    pub fn session_for(&mut self, thread: Option<&ThreadTs>) -> SessionId {
+        let Some(thread) = thread.filter(|_| self.scope == SessionScope::Thread) else {
+            return self.channel_session.clone();
+        };
+
+        let now = Instant::now();
+        if let Some((session_id, last_used)) = self.thread_sessions.get_mut(thread) {
+            *last_used = now;
+            return session_id.clone();
+        }
+
+        if self.thread_sessions.len() >= MAX_THREAD_SESSIONS
+            && let Some(oldest) = self
+                .thread_sessions
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(thread, _)| thread.clone())
+        {
+            self.thread_sessions.remove(&oldest);
+        }
+
+        let session_id = generate_session_id(&self.channel_id);
+        tracing::info!(
+            session_id = %session_id,
+            thread_ts = %thread,
+            "Starting thread session {}",
+            self.channel_id.log_format()
+        );
+        self.thread_sessions
+            .insert(thread.clone(), (session_id.clone(), now));
+        session_id
+    }
+
+    /// Whether `session_id` is the channel session or a thread's current session
+    pub fn contains(&self, session_id: &str) -> bool {
+        self.channel_session == session_id
+            || self
+                .thread_sessions
+                .values()
+                .any(|(thread_session, _)| thread_session == session_id)
+    }
+
+    /// Number of threads with their own session
+    pub fn thread_count(&self) -> usize {
+        self.thread_sessions.len()
+    }
+
+    /// Start over: a new channel session, and every thread gets a fresh one on its next
+    /// message. Returns the new channel session id.
+    pub fn reset(&mut self) -> SessionId {
+        self.channel_session = generate_session_id(&self.channel_id);
+        self.thread_sessions.clear();
+        self.channel_session.clone()
+    }
+}
+
 /// Generate a unique session ID for a channel
 ///
 /// Format: session-{channel_id}-{timestamp}-{random}
 /// Example: session-C09NNKZ8SPP-1761520471-a3f9b2
 pub fn generate_session_id(channel_id: &ChannelId) -> SessionId {
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_secs();
-
-    let random = &Uuid::new_v4().to_string()[..6];
-
-    format!("session-{}-{}-{}", channel_id.as_str(), timestamp, random)
+    Session::new(channel_id).to_string()
 }
 
 #[cfg(test)]
@@ -43,6 +217,99 @@ mod tests {
         assert_eq!(parts[3].len(), 6);
     }
 
+    #[test]
+    fn test_thread_scope_keeps_threads_independent() {
+        let mut sessions = SessionRegistry::new(ChannelId::new("C1"), SessionScope::Thread);
+        let thread_a = ThreadTs::new("1700000000.000100");
+        let thread_b = ThreadTs::new("1700000000.000200");
+
+        let a = sessions.session_for(Some(&thread_a));
+        let b = sessions.session_for(Some(&thread_b));
+        assert_ne!(a, b);
+        assert_ne!(&a, sessions.channel_session());
+
+        // Each thread keeps its own session across messages
+        assert_eq!(sessions.session_for(Some(&thread_a)), a);
+        assert_eq!(sessions.session_for(Some(&thread_b)), b);
+        assert_eq!(sessions.thread_count(), 2);
+
+        // No thread: the channel session
+        assert_eq!(&sessions.session_for(None), sessions.channel_session());
+
+        assert!(sessions.contains(&a) && sessions.contains(&b));
+        let channel_session = sessions.reset();
+        assert_eq!(sessions.thread_count(), 0);
+        assert!(!sessions.contains(&a));
+        assert!(sessions.contains(&channel_session));
+        assert_ne!(sessions.session_for(Some(&thread_a)), a);
+        assert_eq!(sessions.channel_session(), &channel_session);
+    }
+
+    #[test]
+    fn test_channel_scope_shares_one_session() {
+        let mut sessions = SessionRegistry::new(ChannelId::new("C1"), SessionScope::Channel);
+        let a = sessions.session_for(Some(&ThreadTs::new("1.1")));
+        let b = sessions.session_for(Some(&ThreadTs::new("2.2")));
+        assert_eq!(a, b);
+        assert_eq!(&a, sessions.channel_session());
+        assert_eq!(sessions.thread_count(), 0);
+    }
+
+    #[test]
+    fn test_thread_sessions_are_bounded() {
+        let mut sessions = SessionRegistry::new(ChannelId::new("C1"), SessionScope::Thread);
+        let first = ThreadTs::new("0");
+        let first_session = sessions.session_for(Some(&first));
+        for i in 1..=MAX_THREAD_SESSIONS {
+            sessions.session_for(Some(&ThreadTs::new(i.to_string())));
+        }
+        assert_eq!(sessions.thread_count(), MAX_THREAD_SESSIONS);
+        // The least recently used thread was forgotten and starts over
+        assert_ne!(sessions.session_for(Some(&first)), first_session);
+    }
+
+    #[test]
+    fn test_session_parse_round_trip() {
+        let channel = ChannelId::new("C09NNKZ8SPP");
+        let session_id = generate_session_id(&channel);
+        let session = Session::parse(&session_id).unwrap();
+        assert!(session.belongs_to(&channel));
+        assert!(!session.belongs_to(&ChannelId::new("C1")));
+        assert_eq!(session.to_string(), session_id);
+
+        let session: Session = "session-C09NNKZ8SPP-1761520471-a3f9b2".parse().unwrap();
+        assert_eq!(session.channel_id, channel);
+        assert_eq!(
+            session.created_at,
+            UNIX_EPOCH + Duration::from_secs(1761520471)
+        );
+        assert_eq!(session.nonce, "a3f9b2");
+        assert_eq!(session.to_string(), "session-C09NNKZ8SPP-1761520471-a3f9b2");
+
+        // Sessions sort by when they started
+        let older = Session::parse("session-C1-1700000000-ffffff").unwrap();
+        let mut sessions = [session.clone(), older.clone()];
+        sessions.sort_by_key(|session| session.created_at);
+        assert_eq!(sessions, [older, session]);
+    }
+
+    #[test]
+    fn test_session_parse_rejects_malformed_ids() {
+        for id in [
+            "",
+            "session-",
+            "C09NNKZ8SPP-1761520471-a3f9b2",
+            "session-C09NNKZ8SPP-1761520471",
+            "session-C09NNKZ8SPP-soon-a3f9b2",
+            "session-c09nnkz8spp-1761520471-a3f9b2",
+            "session-C09NNKZ8SPP-1761520471-",
+            "session-C09NNKZ8SPP-1761520471-a3f9b2-extra",
+            "session-C09NNKZ8SPP--1761520471-a3f9b2",
+        ] {
+            assert!(Session::parse(id).is_err(), "{id:?}");
+        }
+    }
+
     #[test]
     fn test_session_id_uniqueness() {
         let channel = ChannelId::new("C09NNKZ8SPP");
@@ -0,0 +1,181 @@
+//! Argument parsing for message commands
+
+use crate::error::{Result, SlackCoderError};
+
+/// Arguments following a command name, e.g. `--keep-plan` in `/new-session --keep-plan`
+///
+/// Words are split on whitespace; single or double quotes group words into one argument
+/// (`"two words"`), and a backslash escapes the next character inside double quotes.
+/// Slack's smart quotes (“ ”) count as double quotes. Words starting with `--` are flags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandArgs {
+    raw: String,
+    positional: Vec<String>,
+    flags: Vec<String>,
+}
+
+impl CommandArgs {
+    pub fn parse(raw: &str) -> Result<Self> {
+        let mut positional = Vec::new();
+        let mut flags = Vec::new();
+
+        for token in tokenize(raw)? {
+            // Quoted words are never flags, so `"--literal"` can still be passed through
+            match token.word.strip_prefix("--") {
+                Some(flag) if !token.quoted && !flag.is_empty() => flags.push(flag.to_string()),
+                _ => positional.push(token.word),
+            }
+        }
+
+        Ok(Self {
+            raw: raw.trim().to_string(),
+            positional,
+            flags,
+        })
+    }
+
+    /// Everything after the command name, untouched (for payloads such as JSON)
+    pub fn raw(&self) -> &str {
+        &self.raw
+    }
+
+    /// Non-flag arguments, in order
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    /// Whether `--name` was given
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|flag| flag == name)
+    }
+
+    /// Flags not in `known`, for rejecting typos
+    pub fn unknown_flags<'a>(&'a self, known: &[&str]) -> Vec<&'a str> {
+        self.flags
+            .iter()
+            .map(String::as_str)
+            .filter(|flag| !known.contains(flag))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positional.is_empty() && self.flags.is_empty()
+    }
+}
+
+struct Token {
+    word: String,
+    quoted: bool,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut chars = input.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' | '“' | '”' => {
+                in_word = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"' | '“' | '”') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => word.push(escaped),
+                            None => return Err(unterminated('"')),
+                        },
+                        Some(inner) => word.push(inner),
+                        None => return Err(unterminated('"')),
+                    }
+                }
+            }
+            '\'' => {
+                in_word = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(inner) => word.push(inner),
+                        None => return Err(unterminated('\'')),
+                    }
+                }
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    tokens.push(Token {
+                        word: std::mem::take(&mut word),
+                        quoted,
+                    });
+                    in_word = false;
+                    quoted = false;
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        tokens.push(Token { word, quoted });
+    }
+    Ok(tokens)
+}
+
+fn unterminated(quote: char) -> SlackCoderError {
+    SlackCoderError::Config(format!("Unterminated {} quote in command arguments", quote))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_args_and_flags() {
+        let args = CommandArgs::parse("  check owner/repo --verbose ").unwrap();
+        assert_eq!(args.positional(), ["check", "owner/repo"]);
+        assert!(args.has_flag("verbose"));
+        assert!(!args.has_flag("keep-plan"));
+        assert_eq!(args.raw(), "check owner/repo --verbose");
+
+        assert!(CommandArgs::parse("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_quoted_strings() {
+        let args = CommandArgs::parse(r#"say "hello world" 'it''s' "a \"b\"""#).unwrap();
+        assert_eq!(args.positional(), ["say", "hello world", "its", r#"a "b""#]);
+
+        // Slack turns straight quotes into smart quotes
+        let args = CommandArgs::parse("“two words” three").unwrap();
+        assert_eq!(args.positional(), ["two words", "three"]);
+
+        // Empty quotes are still an argument
+        let args = CommandArgs::parse(r#""" x"#).unwrap();
+        assert_eq!(args.positional(), ["", "x"]);
+    }
+
+    #[test]
+    fn test_quoted_flag_is_positional() {
+        let args = CommandArgs::parse(r#""--keep-plan" --keep-plan"#).unwrap();
+        assert_eq!(args.positional(), ["--keep-plan"]);
+        assert!(args.has_flag("keep-plan"));
+    }
+
+    #[test]
+    fn test_unknown_flags() {
+        let args = CommandArgs::parse("--keep-plan --keep-plna").unwrap();
+        assert_eq!(args.unknown_flags(&["keep-plan"]), vec!["keep-plna"]);
+    }
+
+    #[test]
+    fn test_unterminated_quote_is_an_error() {
+        assert!(CommandArgs::parse(r#"say "hello"#).is_err());
+        assert!(CommandArgs::parse("say 'hello").is_err());
+        assert!(CommandArgs::parse(r#"say "trailing\"#).is_err());
+    }
+}
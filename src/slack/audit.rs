@@ -0,0 +1,114 @@
+//! Audit trail of agent queries, posted to `AUDIT_CHANNEL_ID`
+
+use crate::metadata::LogContext;
+use crate::slack::UsageMetrics;
+
+/// Longest request shown in an audit record, in characters
+const MAX_AUDIT_PROMPT_CHARS: usize = 200;
+
+/// One answered query: who asked what, where, and what it cost
+#[derive(Debug, Clone)]
+pub struct AuditRecord<'a> {
+    /// Names and ids of the user and channel
+    pub context: &'a LogContext,
+    /// The request as the user wrote it, already passed through the output filter
+    pub prompt: &'a str,
+    pub metrics: Option<&'a UsageMetrics>,
+    /// The agent session the query ran in
+    pub session_id: &'a str,
+}
+
+impl AuditRecord<'_> {
+    /// Format as a compact Slack message
+    pub fn format_slack_message(&self) -> String {
+        let mut prompt: String = self
+            .prompt
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .replace('`', "'");
+        if prompt.chars().count() > MAX_AUDIT_PROMPT_CHARS {
+            prompt = prompt.chars().take(MAX_AUDIT_PROMPT_CHARS).collect();
+            prompt.push('…');
+        }
+
+        let session = self
+            .metrics
+            .map_or(self.session_id, |metrics| metrics.session_id.as_str());
+        let mut details = vec![format!("session `{}`", session)];
+        if let Some(metrics) = self.metrics {
+            if let Some(cost) = metrics.cost_usd {
+                details.push(format!("${:.4}", cost));
+            }
+            details.push(format!("{} tokens", metrics.total_tokens));
+            details.push(format!("{:.1}s", metrics.duration_ms as f64 / 1000.0));
+        }
+
+        format!(
+            "🧾 {} (`{}`) in {} (`{}`)\n> {}\n{}",
+            self.context.user_display,
+            self.context.user_id,
+            self.context.channel_display,
+            self.context.channel_id,
+            prompt,
+            details.join(" · ")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context() -> LogContext {
+        LogContext {
+            channel_id: "C12345".to_string(),
+            channel_name: "payments".to_string(),
+            channel_display: "#payments".to_string(),
+            user_id: "U456".to_string(),
+            user_name: "alice".to_string(),
+            user_display: "@Alice".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_audit_record_fields() {
+        let context = context();
+        let metrics = UsageMetrics {
+            total_tokens: 1234,
+            cost_usd: Some(0.05),
+            duration_ms: 12_300,
+            session_id: "session-from-result".to_string(),
+            ..Default::default()
+        };
+        let record = AuditRecord {
+            context: &context,
+            prompt: "fix the `login`\nbug",
+            metrics: Some(&metrics),
+            session_id: "session-1",
+        };
+
+        assert_eq!(
+            record.format_slack_message(),
+            "🧾 @Alice (`U456`) in #payments (`C12345`)\n\
+             > fix the 'login' bug\n\
+             session `session-from-result` · $0.0500 · 1234 tokens · 12.3s"
+        );
+    }
+
+    #[test]
+    fn test_audit_record_truncates_prompt() {
+        let context = context();
+        let prompt = "x".repeat(MAX_AUDIT_PROMPT_CHARS + 50);
+        let record = AuditRecord {
+            context: &context,
+            prompt: &prompt,
+            metrics: None,
+            session_id: "session-1",
+        };
+
+        let message = record.format_slack_message();
+        assert!(message.contains(&format!("> {}…\n", "x".repeat(MAX_AUDIT_PROMPT_CHARS))));
+        assert!(message.ends_with("session `session-1`"));
+    }
+}
@@ -0,0 +1,162 @@
+//! Startup validation of the bot token via `auth.test`
+
+use crate::error::{Result, SlackCoderError};
+use crate::slack::UserId;
+use serde::Deserialize;
+
+/// Scopes the bot can't work without; startup fails if any is missing
+pub(crate) const REQUIRED_BOT_SCOPES: &[&str] = &[
+    "app_mentions:read",
+    "channels:history",
+    "channels:read",
+    "chat:write",
+];
+
+/// Scopes for optional features; a missing one is logged but startup continues
+pub(crate) const OPTIONAL_BOT_SCOPES: &[&str] = &["files:read", "files:write", "reactions:read"];
+
+/// Who the bot token belongs to, as reported by `auth.test`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotIdentity {
+    pub user_id: UserId,
+    pub bot_id: Option<String>,
+    pub team_id: String,
+    pub team: Option<String>,
+    /// Granted scopes, from the `x-oauth-scopes` response header (`None` if Slack omitted it)
+    pub scopes: Option<Vec<String>>,
+}
+
+/// The parts of the `auth.test` body we use
+#[derive(Debug, Deserialize)]
+pub(crate) struct AuthTestResponse {
+    ok: bool,
+    error: Option<String>,
+    user_id: Option<String>,
+    bot_id: Option<String>,
+    team_id: Option<String>,
+    team: Option<String>,
+}
+
+impl BotIdentity {
+    /// Build the identity from an `auth.test` reply, failing on an invalid token or
+    /// missing required scopes
+    pub(crate) fn from_auth_test(
+        response: AuthTestResponse,
+        scopes: Option<Vec<String>>,
+    ) -> Result<Self> {
+        if !response.ok {
+            return Err(SlackCoderError::SlackAuth(format!(
+                "auth.test rejected the bot token ({}). Check SLACK_BOT_TOKEN",
+                response.error.as_deref().unwrap_or("unknown error")
+            )));
+        }
+
+        let (Some(user_id), Some(team_id)) = (response.user_id, response.team_id) else {
+            return Err(SlackCoderError::SlackAuth(
+                "auth.test response is missing the user or team id".to_string(),
+            ));
+        };
+
+        if let Some(granted) = &scopes {
+            let missing = missing_scopes(granted, REQUIRED_BOT_SCOPES);
+            if !missing.is_empty() {
+                return Err(SlackCoderError::SlackAuth(format!(
+                    "Bot token is missing required scopes: {}. Add them under OAuth & Permissions and reinstall the app",
+                    missing.join(", ")
+                )));
+            }
+        }
+
+        Ok(Self {
+            user_id: UserId::new(user_id),
+            bot_id: response.bot_id,
+            team_id,
+            team: response.team,
+            scopes,
+        })
+    }
+
+    /// Optional scopes the token lacks (empty if scopes are unknown)
+    pub fn missing_optional_scopes(&self) -> Vec<&'static str> {
+        self.scopes
+            .as_deref()
+            .map(|granted| missing_scopes(granted, OPTIONAL_BOT_SCOPES))
+            .unwrap_or_default()
+    }
+}
+
+/// Split the comma-separated `x-oauth-scopes` header
+pub(crate) fn parse_scopes(header: &str) -> Vec<String> {
+    header
+        .split(',')
+        .map(str::trim)
+        .filter(|scope| !scope.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn missing_scopes(granted: &[String], wanted: &[&'static str]) -> Vec<&'static str> {
+    wanted
+        .iter()
+        .copied()
+        .filter(|scope| !granted.iter().any(|g| g == scope))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ok_response() -> AuthTestResponse {
+        serde_json::from_str(
+            r#"{"ok":true,"url":"https://acme.slack.com/","team":"Acme","user":"coder","team_id":"T1","user_id":"UBOT","bot_id":"B1"}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_scopes() {
+        assert_eq!(
+            parse_scopes("chat:write, channels:read,,im:read"),
+            vec!["chat:write", "channels:read", "im:read"]
+        );
+    }
+
+    #[test]
+    fn test_identity_from_valid_token() {
+        let scopes =
+            parse_scopes("app_mentions:read,channels:history,channels:read,chat:write,files:write");
+        let identity = BotIdentity::from_auth_test(ok_response(), Some(scopes)).unwrap();
+
+        assert_eq!(identity.user_id.as_str(), "UBOT");
+        assert_eq!(identity.team.as_deref(), Some("Acme"));
+        assert_eq!(
+            identity.missing_optional_scopes(),
+            vec!["files:read", "reactions:read"]
+        );
+    }
+
+    #[test]
+    fn test_identity_without_scope_header() {
+        let identity = BotIdentity::from_auth_test(ok_response(), None).unwrap();
+        assert!(identity.missing_optional_scopes().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_token_is_rejected() {
+        let response: AuthTestResponse =
+            serde_json::from_str(r#"{"ok":false,"error":"invalid_auth"}"#).unwrap();
+        let err = BotIdentity::from_auth_test(response, None).unwrap_err();
+        assert!(err.to_string().contains("invalid_auth"));
+    }
+
+    #[test]
+    fn test_missing_required_scopes_are_rejected() {
+        let scopes = parse_scopes("chat:write,channels:read");
+        let err = BotIdentity::from_auth_test(ok_response(), Some(scopes)).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("app_mentions:read"));
+        assert!(message.contains("channels:history"));
+        assert!(!message.contains("chat:write"));
+    }
+}
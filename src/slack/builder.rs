@@ -0,0 +1,144 @@
+//! Composition of agent replies: body, then a footer of actions, metrics and the
+//! completion marker
+
+use crate::slack::UsageMetrics;
+
+/// Separates the body from the footer
+const FOOTER_SEPARATOR: &str = "\n\n---\n";
+
+/// Closes a finished task's reply
+const COMPLETION_MARKER: &str = "✅ *Task Complete* - All operations finished!";
+
+/// Builds a reply so every site uses the same separators and markers.
+///
+/// The body is used as given (convert it with `markdown_to_slack` first). Without
+/// metrics or a completion marker the footer is empty and the reply is just the body.
+#[derive(Debug, Clone, Default)]
+pub struct SlackMessageBuilder {
+    body: String,
+    actions: Option<String>,
+    metrics: Option<UsageMetrics>,
+    complete: bool,
+}
+
+impl SlackMessageBuilder {
+    pub fn new(body: impl Into<String>) -> Self {
+        Self {
+            body: body.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Add an "Actions taken" section (from `AgentResponse::actions_summary`) to the
+    /// footer (`None` leaves it out)
+    pub fn with_actions(mut self, summary: Option<String>) -> Self {
+        self.actions = summary;
+        self
+    }
+
+    /// Append the query's metrics in a footer (`None` leaves them out)
+    pub fn with_metrics(mut self, metrics: Option<UsageMetrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// End with the task-complete marker
+    pub fn with_completion(mut self, complete: bool) -> Self {
+        self.complete = complete;
+        self
+    }
+
+    /// Everything after the body, including the leading separator (empty if there is
+    /// no footer). Useful when the body is posted separately, e.g. as a snippet.
+    pub fn footer(&self) -> String {
+        let mut sections = Vec::new();
+        if let Some(actions) = &self.actions {
+            sections.push(actions.clone());
+        }
+        if let Some(metrics) = &self.metrics {
+            sections.push(metrics.format_slack_message());
+        }
+        if self.complete {
+            sections.push(COMPLETION_MARKER.to_string());
+        }
+
+        if sections.is_empty() {
+            return String::new();
+        }
+        format!("{}{}", FOOTER_SEPARATOR, sections.join("\n\n"))
+    }
+
+    /// The full reply
+    pub fn build(&self) -> String {
+        format!("{}{}", self.body, self.footer())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metrics() -> UsageMetrics {
+        UsageMetrics {
+            input_tokens: 100,
+            output_tokens: 50,
+            total_tokens: 150,
+            cost_usd: Some(0.01),
+            duration_ms: 1500,
+            duration_api_ms: 1200,
+            num_turns: 2,
+            session_id: "sdk-session".to_string(),
+            ..UsageMetrics::default()
+        }
+    }
+
+    #[test]
+    fn test_body_only() {
+        let builder = SlackMessageBuilder::new("Done.");
+        assert_eq!(builder.footer(), "");
+        assert_eq!(builder.build(), "Done.");
+
+        let builder = SlackMessageBuilder::new("Done.").with_metrics(None);
+        assert_eq!(builder.build(), "Done.");
+    }
+
+    #[test]
+    fn test_body_with_metrics() {
+        let message = SlackMessageBuilder::new("Done.")
+            .with_metrics(Some(metrics()))
+            .with_completion(true)
+            .build();
+
+        assert_eq!(
+            message,
+            format!(
+                "Done.\n\n---\n{}\n\n✅ *Task Complete* - All operations finished!",
+                metrics().format_slack_message()
+            )
+        );
+        assert!(message.contains("• Tokens: 100 input + 50 output = *150 total*"));
+    }
+
+    #[test]
+    fn test_actions_come_first_in_footer() {
+        let message = SlackMessageBuilder::new("Done.")
+            .with_actions(Some("*Actions taken*\n• Ran 1 command: `ls`".to_string()))
+            .with_completion(true)
+            .build();
+        assert_eq!(
+            message,
+            "Done.\n\n---\n*Actions taken*\n• Ran 1 command: `ls`\n\n✅ *Task Complete* - All operations finished!"
+        );
+    }
+
+    #[test]
+    fn test_completion_without_metrics() {
+        let message = SlackMessageBuilder::new("Done.")
+            .with_completion(true)
+            .build();
+        assert_eq!(
+            message,
+            "Done.\n\n---\n✅ *Task Complete* - All operations finished!"
+        );
+    }
+}
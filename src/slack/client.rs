@@ -1,14 +1,71 @@
 use crate::config::SlackConfig;
 use crate::error::{Result, SlackCoderError};
 use crate::metadata::{ChannelInfo, ChannelType, UserInfo};
-use crate::slack::{ChannelId, MessageTs, ThreadTs, UsageMetrics};
+use crate::slack::auth::{AuthTestResponse, parse_scopes};
+use crate::slack::{
+    BotIdentity, ChannelId, MessageTs, SharedFile, SlackLimits, ThreadTs, UsageMetrics, UserId,
+};
+use crate::telemetry::{self, SlackErrorKind};
+use slack_morphism::errors::SlackClientError;
 use slack_morphism::prelude::*;
-use std::sync::Arc;
-use std::time::Instant;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Channels requested per `conversations.list` page
+const LIST_CHANNELS_PAGE_SIZE: u16 = 200;
+/// Pause between pages to stay under `conversations.list` rate limits
+const LIST_CHANNELS_PAGE_DELAY: Duration = Duration::from_millis(500);
+/// How many times a rate-limited page is retried before giving up
+const MAX_RATE_LIMIT_RETRIES: u32 = 3;
+/// Wait used when Slack rate-limits without a `Retry-After` hint
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(5);
+/// Members requested when naming a DM or group DM (group DMs hold at most 9 people)
+const MAX_MEMBERS_PAGE: u16 = 20;
+/// Called directly (not through slack-morphism) to read the `x-oauth-scopes` header
+const AUTH_TEST_URL: &str = "https://slack.com/api/auth.test";
+/// Largest uploaded file `download_text_file` accepts
+const MAX_DOWNLOAD_BYTES: usize = 1024 * 1024;
+
+/// Convert a failed Slack API call, counting it in the telemetry metrics.
+///
+/// Counted here, where the call failed, rather than in the `From` conversion, so an
+/// error converted more than once is still counted once.
+fn api_error(err: SlackClientError) -> SlackCoderError {
+    let error = SlackCoderError::from(err);
+    let kind = match &error {
+        SlackCoderError::SlackRateLimited { .. } => SlackErrorKind::RateLimited,
+        SlackCoderError::SlackAuth(_) => SlackErrorKind::Auth,
+        _ => SlackErrorKind::Api,
+    };
+    telemetry::metrics().record_slack_error(kind);
+    error
+}
+
+/// Per-message posting options
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SendOptions {
+    /// Let Slack render link and media previews. Off by default so agent output,
+    /// which is often full of links, stays compact.
+    pub unfurl: bool,
+}
+
+impl SendOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_unfurl(mut self, unfurl: bool) -> Self {
+        self.unfurl = unfurl;
+        self
+    }
+}
 
 pub struct SlackClient {
     client: Arc<SlackHyperClient>,
     token: SlackApiToken,
+    limits: SlackLimits,
+    /// The bot's own user id, looked up once via `auth.test`
+    bot_user_id: OnceLock<UserId>,
 }
 
 impl SlackClient {
@@ -19,7 +76,23 @@ impl SlackClient {
         let client = Arc::new(slack_morphism::SlackClient::new(connector));
         let token = SlackApiToken::new(config.bot_token.into());
 
-        Ok(Self { client, token })
+        Ok(Self {
+            client,
+            token,
+            limits: SlackLimits::default(),
+            bot_user_id: OnceLock::new(),
+        })
+    }
+
+    /// Override the Slack platform limits (e.g. after a Slack API change)
+    pub fn with_limits(mut self, limits: SlackLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Slack platform limits used for chunking and rate limiting
+    pub fn limits(&self) -> &SlackLimits {
+        &self.limits
     }
 
     pub fn get_client(&self) -> Arc<SlackHyperClient> {
@@ -39,32 +112,98 @@ impl SlackClient {
         &self.token
     }
 
-    /// Send a message to a channel with Slack markdown formatting
+    /// Send a message to a channel with Slack markdown formatting (links not unfurled)
     pub async fn send_message(
         &self,
         channel: &ChannelId,
         text: &str,
         thread_ts: Option<&ThreadTs>,
+    ) -> Result<MessageTs> {
+        self.send_message_with_options(channel, text, thread_ts, SendOptions::default())
+            .await
+    }
+
+    /// Send a message with explicit posting options
+    pub async fn send_message_with_options(
+        &self,
+        channel: &ChannelId,
+        text: &str,
+        thread_ts: Option<&ThreadTs>,
+        options: SendOptions,
     ) -> Result<MessageTs> {
         let session = self.client.open_session(&self.token);
 
-        let mut request = SlackApiChatPostMessageRequest::new(
-            channel.as_str().into(),
+        let request = post_message_request(
+            channel,
             SlackMessageContent::new().with_text(text.into()),
+            thread_ts,
+            options,
         );
 
-        if let Some(ts) = thread_ts {
-            request.thread_ts = Some(ts.as_str().into());
-        }
+        let response = session
+            .chat_post_message(&request)
+            .await
+            .map_err(api_error)?;
+
+        Ok(MessageTs::new(response.ts.to_string()))
+    }
+
+    /// Reply in the thread under a specific message, starting the thread if needed
+    pub async fn reply_to(
+        &self,
+        channel: &ChannelId,
+        parent_ts: &MessageTs,
+        text: &str,
+    ) -> Result<MessageTs> {
+        let thread_ts = ThreadTs::from_parent(parent_ts);
+        self.send_message(channel, text, Some(&thread_ts)).await
+    }
+
+    /// Send a message followed by a row of buttons
+    ///
+    /// Each button is an `(action_id, label)` pair; clicks arrive as interaction events
+    /// carrying the action id.
+    pub async fn send_message_with_buttons(
+        &self,
+        channel: &ChannelId,
+        text: &str,
+        buttons: &[(&str, &str)],
+        thread_ts: Option<&ThreadTs>,
+        options: SendOptions,
+    ) -> Result<MessageTs> {
+        let session = self.client.open_session(&self.token);
 
-        // Unfurl links to show previews
-        request.unfurl_links = Some(false);
-        request.unfurl_media = Some(false);
+        let elements = buttons
+            .iter()
+            .map(|(action_id, label)| {
+                SlackActionBlockElement::Button(SlackBlockButtonElement::new(
+                    SlackActionId::new(action_id.to_string()),
+                    SlackBlockPlainTextOnly::from(label.to_string()),
+                ))
+            })
+            .collect();
+
+        let blocks = vec![
+            SlackBlock::Section(SlackSectionBlock::new().with_text(SlackBlockText::MarkDown(
+                SlackBlockMarkDownText::new(text.to_string()),
+            ))),
+            SlackBlock::Actions(SlackActionsBlock::new(elements)),
+        ];
+
+        // Keep `text` as the notification/fallback content
+        let request = post_message_request(
+            channel,
+            SlackMessageContent::new()
+                .with_text(text.into())
+                .with_blocks(blocks),
+            thread_ts,
+            options,
+        );
 
         let response = session
             .chat_post_message(&request)
             .await
-            .map_err(|e| SlackCoderError::SlackApi(e.to_string()))?;
+            .map_err(api_error)?;
 
         Ok(MessageTs::new(response.ts.to_string()))
     }
@@ -84,14 +223,136 @@ impl SlackClient {
             ts.as_str().into(),
         );
 
+        session.chat_update(&request).await.map_err(api_error)?;
+
+        Ok(())
+    }
+
+    /// Set a channel's topic (needs `channels:write.topic`, or `groups:write.topic` for
+    /// private channels)
+    pub async fn set_topic(&self, channel: &ChannelId, topic: &str) -> Result<()> {
+        let session = self.client.open_session(&self.token);
+
+        let request =
+            SlackApiConversationsSetTopicRequest::new(channel.as_str().into(), topic.to_string());
+
         session
-            .chat_update(&request)
+            .conversations_set_topic(&request)
             .await
-            .map_err(|e| SlackCoderError::SlackApi(e.to_string()))?;
+            .map_err(api_error)?;
 
         Ok(())
     }
 
+    /// Delete a message posted by the bot
+    pub async fn delete_message(&self, channel: &ChannelId, ts: &MessageTs) -> Result<()> {
+        let session = self.client.open_session(&self.token);
+
+        let request = SlackApiChatDeleteRequest::new(channel.as_str().into(), ts.as_str().into());
+
+        session.chat_delete(&request).await.map_err(api_error)?;
+
+        Ok(())
+    }
+
+    /// Upload text as a file snippet, optionally into a thread
+    ///
+    /// Uses Slack's external upload flow: request an upload URL, POST the content
+    /// to it, then complete the upload to share the file in the channel.
+    pub async fn upload_snippet(
+        &self,
+        channel: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+        filename: &str,
+        content: &str,
+        filetype: &str,
+    ) -> Result<()> {
+        let session = self.client.open_session(&self.token);
+
+        // Step 1: reserve an upload URL
+        let url_request =
+            SlackApiFilesGetUploadUrlExternalRequest::new(filename.to_string(), content.len())
+                .with_snippet_type(filetype.to_string());
+        let url_response = session
+            .get_upload_url_external(&url_request)
+            .await
+            .map_err(api_error)?;
+
+        // Step 2: upload the content
+        let upload_request = SlackApiFilesUploadViaUrlRequest::new(
+            url_response.upload_url,
+            content.as_bytes().to_vec(),
+            "text/plain".to_string(),
+        );
+        session
+            .files_upload_via_url(&upload_request)
+            .await
+            .map_err(api_error)?;
+
+        // Step 3: complete the upload and share it
+        let mut complete_request = SlackApiFilesCompleteUploadExternalRequest::new(vec![
+            SlackApiFilesComplete::new(url_response.file_id).with_title(filename.to_string()),
+        ])
+        .with_channel_id(channel.as_str().into());
+        if let Some(ts) = thread_ts {
+            complete_request = complete_request.with_thread_ts(ts.as_str().into());
+        }
+        session
+            .files_complete_upload_external(&complete_request)
+            .await
+            .map_err(api_error)?;
+
+        tracing::debug!(
+            channel_id = %channel,
+            filename = %filename,
+            size = content.len(),
+            "Snippet uploaded"
+        );
+
+        Ok(())
+    }
+
+    /// Download a text file shared in Slack, such as a `/config import` attachment
+    ///
+    /// Private file URLs need the bot token (and the `files:read` scope). Files over
+    /// `MAX_DOWNLOAD_BYTES` are rejected.
+    pub async fn download_text_file(&self, file: &SharedFile) -> Result<String> {
+        let response = reqwest::Client::new()
+            .get(&file.url)
+            .bearer_auth(&self.token.token_value.0)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|e| {
+                SlackCoderError::SlackApi(format!("Downloading '{}' failed: {}", file.name, e))
+            })?;
+
+        if response
+            .content_length()
+            .is_some_and(|len| len > MAX_DOWNLOAD_BYTES as u64)
+        {
+            return Err(SlackCoderError::SlackApi(format!(
+                "'{}' is larger than {} KB",
+                file.name,
+                MAX_DOWNLOAD_BYTES / 1024
+            )));
+        }
+
+        let bytes = response.bytes().await.map_err(|e| {
+            SlackCoderError::SlackApi(format!("Downloading '{}' failed: {}", file.name, e))
+        })?;
+        if bytes.len() > MAX_DOWNLOAD_BYTES {
+            return Err(SlackCoderError::SlackApi(format!(
+                "'{}' is larger than {} KB",
+                file.name,
+                MAX_DOWNLOAD_BYTES / 1024
+            )));
+        }
+
+        String::from_utf8(bytes.to_vec())
+            .map_err(|_| SlackCoderError::SlackApi(format!("'{}' is not a text file", file.name)))
+    }
+
     /// Send a code block with syntax highlighting
     pub async fn send_code_block(
         &self,
@@ -105,35 +366,58 @@ impl SlackClient {
     }
 
     /// Get list of channels where bot is a member
+    ///
+    /// Follows `next_cursor` until the last page so large workspaces aren't truncated.
     pub async fn list_channels(&self) -> Result<Vec<ChannelId>> {
         tracing::debug!("📋 Fetching channel list from Slack API...");
         let session = self.client.open_session(&self.token);
+        let session = &session;
+
+        let channels = collect_channel_pages(LIST_CHANNELS_PAGE_DELAY, move |cursor| async move {
+            let mut request = SlackApiConversationsListRequest::new()
+                .with_types(vec![
+                    SlackConversationType::Public,
+                    SlackConversationType::Private,
+                    SlackConversationType::Im,
+                ])
+                .with_limit(LIST_CHANNELS_PAGE_SIZE);
+            if let Some(cursor) = cursor {
+                request = request.with_cursor(SlackCursorId(cursor));
+            }
 
-        let request = SlackApiConversationsListRequest::new().with_types(vec![
-            SlackConversationType::Public,
-            SlackConversationType::Private,
-        ]);
-
-        let response = session
-            .conversations_list(&request)
-            .await
-            .map_err(|e| SlackCoderError::SlackApi(e.to_string()))?;
-
-        tracing::debug!("Received {} total channels", response.channels.len());
-
-        let channels: Vec<ChannelId> = response
-            .channels
-            .iter()
-            .filter(|c| c.flags.is_member.unwrap_or(false))
-            .map(|c| {
-                tracing::debug!(
-                    "  Channel: {} (member: {})",
-                    c.id,
-                    c.flags.is_member.unwrap_or(false)
-                );
-                ChannelId::new(c.id.to_string())
+            let response = session
+                .conversations_list(&request)
+                .await
+                .map_err(api_error)?;
+
+            tracing::debug!("Received {} channels in page", response.channels.len());
+
+            let channels = response
+                .channels
+                .iter()
+                .filter(|c| is_bot_conversation(&c.flags))
+                .map(|c| {
+                    tracing::debug!(
+                        "  Channel: {} (member: {}, im: {})",
+                        c.id,
+                        c.flags.is_member.unwrap_or(false),
+                        c.flags.is_im.unwrap_or(false)
+                    );
+                    ChannelId::new(c.id.to_string())
+                })
+                .collect();
+
+            let next_cursor = response
+                .response_metadata
+                .and_then(|m| m.next_cursor)
+                .map(|c| c.to_string());
+
+            Ok(ChannelPage {
+                channels,
+                next_cursor,
             })
-            .collect();
+        })
+        .await?;
 
         tracing::info!("Found {} channels where bot is a member", channels.len());
         for ch in &channels {
@@ -174,6 +458,83 @@ impl SlackClient {
         self.send_message(channel, &text, None).await
     }
 
+    /// Warn that the agent will be disconnected soon unless the channel uses it
+    pub async fn send_idle_warning(
+        &self,
+        channel: &ChannelId,
+        remaining: Duration,
+    ) -> Result<MessageTs> {
+        let minutes = remaining.as_secs().div_ceil(60).max(1);
+        let text = format!(
+            "⏰ *Going idle soon*\n\nI'll disconnect in about {} minute{} and this conversation's context will be lost. Say something to keep me active.",
+            minutes,
+            if minutes == 1 { "" } else { "s" }
+        );
+        self.send_message(channel, &text, None).await
+    }
+
+    /// Send idle timeout notification (agent reaped for inactivity)
+    pub async fn send_idle_timeout_notice(
+        &self,
+        channel: &ChannelId,
+        session_id: &str,
+        idle_timeout: Duration,
+    ) -> Result<MessageTs> {
+        let text = format!(
+            "💤 *Agent Idle*\n\nSession ID: `{}` ended after {} minutes of inactivity. Mention me to start again.",
+            session_id,
+            idle_timeout.as_secs() / 60
+        );
+        self.send_message(channel, &text, None).await
+    }
+
+    /// Check the bot token with `auth.test` and return who it belongs to.
+    ///
+    /// Fails with `SlackAuth` if the token is rejected or lacks a required scope, so a
+    /// misconfigured bot stops at startup instead of deep in event handling. Also caches
+    /// the bot's user id for `bot_user_id`.
+    pub async fn validate_token(&self) -> Result<BotIdentity> {
+        let response = reqwest::Client::new()
+            .post(AUTH_TEST_URL)
+            .bearer_auth(&self.token.token_value.0)
+            .send()
+            .await
+            .map_err(|e| SlackCoderError::SlackApi(format!("auth.test request failed: {}", e)))?;
+
+        let scopes = response
+            .headers()
+            .get("x-oauth-scopes")
+            .and_then(|value| value.to_str().ok())
+            .map(parse_scopes);
+        let body: AuthTestResponse = response
+            .json()
+            .await
+            .map_err(|e| SlackCoderError::SlackApi(format!("Invalid auth.test response: {}", e)))?;
+
+        let identity = BotIdentity::from_auth_test(body, scopes)?;
+        let _ = self.bot_user_id.set(identity.user_id.clone());
+        Ok(identity)
+    }
+
+    /// The bot's own user ID, used to recognize its mentions and messages.
+    ///
+    /// Looked up with `auth.test` on first use and cached for the client's lifetime.
+    pub async fn bot_user_id(&self) -> Result<UserId> {
+        if let Some(id) = self.bot_user_id.get() {
+            return Ok(id.clone());
+        }
+
+        let session = self.client.open_session(&self.token);
+        let response = session.auth_test().await.map_err(api_error)?;
+        let id = UserId::new(response.user_id.to_string());
+        Ok(self.bot_user_id.get_or_init(|| id).clone())
+    }
+
+    /// The bot's user ID if it has already been looked up
+    pub fn cached_bot_user_id(&self) -> Option<&UserId> {
+        self.bot_user_id.get()
+    }
+
     /// Get channel information from Slack API
     pub async fn get_channel_info(&self, channel_id: &str) -> Result<ChannelInfo> {
         let session = self.client.open_session(&self.token);
@@ -183,7 +544,7 @@ impl SlackClient {
         let response = session
             .conversations_info(&request)
             .await
-            .map_err(|e| SlackCoderError::SlackApi(e.to_string()))?;
+            .map_err(api_error)?;
 
         let channel = response.channel;
 
@@ -202,27 +563,48 @@ impl SlackClient {
             ChannelType::PublicChannel // Default fallback
         };
 
+        let name_is_fallback = channel.name.is_none();
         Ok(ChannelInfo {
             id: channel.id.to_string(),
-            name: channel.name.unwrap_or_else(|| channel_id.to_string()),
+            name: channel
+                .name
+                .unwrap_or_else(|| fallback_channel_name(channel_id, &channel_type)),
             channel_type,
             is_private: channel.flags.is_private.unwrap_or(false),
             member_count: channel.num_members.map(|n| n as u32),
             fetched_at: Instant::now(),
             topic: channel.topic.map(|t| t.value),
+            name_is_fallback,
         })
     }
 
+    /// List a conversation's members (first page only, which covers DMs and group DMs)
+    pub async fn get_channel_members(&self, channel_id: &str) -> Result<Vec<UserId>> {
+        let session = self.client.open_session(&self.token);
+
+        let request = SlackApiConversationsMembersRequest::new()
+            .with_channel(SlackChannelId(channel_id.to_string()))
+            .with_limit(MAX_MEMBERS_PAGE);
+
+        let response = session
+            .conversations_members(&request)
+            .await
+            .map_err(api_error)?;
+
+        Ok(response
+            .members
+            .into_iter()
+            .map(|id| UserId::new(id.to_string()))
+            .collect())
+    }
+
     /// Get user information from Slack API
     pub async fn get_user_info(&self, user_id: &str) -> Result<UserInfo> {
         let session = self.client.open_session(&self.token);
 
         let request = SlackApiUsersInfoRequest::new(SlackUserId(user_id.to_string()));
 
-        let response = session
-            .users_info(&request)
-            .await
-            .map_err(|e| SlackCoderError::SlackApi(e.to_string()))?;
+        let response = session.users_info(&request).await.map_err(api_error)?;
 
         let user = response.user;
 
@@ -240,3 +622,217 @@ impl SlackClient {
         })
     }
 }
+
+/// Whether a listed conversation is one the bot is in. IM conversations don't report
+/// membership; the bot is always a party to them.
+fn is_bot_conversation(flags: &SlackChannelFlags) -> bool {
+    flags.is_member.unwrap_or(false) || flags.is_im.unwrap_or(false)
+}
+
+/// Name for a conversation Slack doesn't name - DMs get a readable label
+fn fallback_channel_name(channel_id: &str, channel_type: &ChannelType) -> String {
+    match channel_type {
+        ChannelType::DirectMessage => format!("dm-{}", channel_id),
+        _ => channel_id.to_string(),
+    }
+}
+
+/// One page of `conversations.list` results
+struct ChannelPage {
+    channels: Vec<ChannelId>,
+    next_cursor: Option<String>,
+}
+
+/// Fetch pages until the cursor runs out, pausing between pages and waiting
+/// out rate limits
+async fn collect_channel_pages<F, Fut>(
+    page_delay: Duration,
+    mut fetch_page: F,
+) -> Result<Vec<ChannelId>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<ChannelPage>>,
+{
+    let mut channels = Vec::new();
+    let mut cursor = None;
+    let mut retries = 0;
+
+    loop {
+        let page = match fetch_page(cursor.clone()).await {
+            Ok(page) => page,
+            Err(SlackCoderError::SlackRateLimited { retry_after })
+                if retries < MAX_RATE_LIMIT_RETRIES =>
+            {
+                retries += 1;
+                let wait = retry_after.unwrap_or(DEFAULT_RATE_LIMIT_WAIT);
+                tracing::warn!(
+                    wait_secs = wait.as_secs(),
+                    attempt = retries,
+                    "Rate limited while listing channels, retrying"
+                );
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        retries = 0;
+        channels.extend(page.channels);
+
+        match page.next_cursor.filter(|c| !c.is_empty()) {
+            Some(next) => {
+                cursor = Some(next);
+                tokio::time::sleep(page_delay).await;
+            }
+            None => break,
+        }
+    }
+
+    Ok(channels)
+}
+
+/// Build a `chat.postMessage` request, optionally in a thread
+fn post_message_request(
+    channel: &ChannelId,
+    content: SlackMessageContent,
+    thread_ts: Option<&ThreadTs>,
+    options: SendOptions,
+) -> SlackApiChatPostMessageRequest {
+    let mut request = SlackApiChatPostMessageRequest::new(channel.as_str().into(), content);
+
+    if let Some(ts) = thread_ts {
+        request.thread_ts = Some(ts.as_str().into());
+    }
+
+    request.unfurl_links = Some(options.unfurl);
+    request.unfurl_media = Some(options.unfurl);
+
+    request
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn page(ids: &[&str], next_cursor: Option<&str>) -> ChannelPage {
+        ChannelPage {
+            channels: ids.iter().map(|id| ChannelId::new(*id)).collect(),
+            next_cursor: next_cursor.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collect_channel_pages_follows_cursor() {
+        let cursors = Mutex::new(Vec::new());
+
+        let channels = collect_channel_pages(Duration::ZERO, |cursor| {
+            cursors.lock().unwrap().push(cursor.clone());
+            async move {
+                Ok(match cursor.as_deref() {
+                    None => page(&["C1", "C2"], Some("page2")),
+                    Some("page2") => page(&["C3"], Some("")),
+                    Some(other) => panic!("unexpected cursor {}", other),
+                })
+            }
+        })
+        .await
+        .unwrap();
+
+        let ids: Vec<&str> = channels.iter().map(|c| c.as_str()).collect();
+        assert_eq!(ids, vec!["C1", "C2", "C3"]);
+        assert_eq!(
+            *cursors.lock().unwrap(),
+            vec![None, Some("page2".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_channel_pages_retries_rate_limit() {
+        let calls = Mutex::new(0);
+
+        let channels = collect_channel_pages(Duration::ZERO, |_| {
+            let call = {
+                let mut calls = calls.lock().unwrap();
+                *calls += 1;
+                *calls
+            };
+            async move {
+                if call == 1 {
+                    Err(SlackCoderError::SlackRateLimited {
+                        retry_after: Some(Duration::ZERO),
+                    })
+                } else {
+                    Ok(page(&["C1"], None))
+                }
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(channels.len(), 1);
+        assert_eq!(*calls.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_dm_conversations_are_listed() {
+        let flags = |json| serde_json::from_value::<SlackChannelFlags>(json).unwrap();
+
+        assert!(is_bot_conversation(&flags(
+            serde_json::json!({ "is_member": true })
+        )));
+        assert!(is_bot_conversation(&flags(
+            serde_json::json!({ "is_im": true })
+        )));
+        assert!(!is_bot_conversation(&flags(
+            serde_json::json!({ "is_member": false })
+        )));
+        assert!(!is_bot_conversation(&flags(serde_json::json!({}))));
+    }
+
+    #[test]
+    fn test_fallback_channel_name() {
+        assert_eq!(
+            fallback_channel_name("D123", &ChannelType::DirectMessage),
+            "dm-D123"
+        );
+        assert_eq!(
+            fallback_channel_name("G123", &ChannelType::MultiPartyDirectMessage),
+            "G123"
+        );
+    }
+
+    #[test]
+    fn test_post_message_request_unfurl() {
+        let channel = ChannelId::new("C123");
+        let content = || SlackMessageContent::new().with_text("hi".into());
+
+        let request = post_message_request(&channel, content(), None, SendOptions::default());
+        assert_eq!(request.unfurl_links, Some(false));
+        assert_eq!(request.unfurl_media, Some(false));
+        assert!(request.thread_ts.is_none());
+
+        let options = SendOptions::new().with_unfurl(true);
+        let request = post_message_request(&channel, content(), None, options);
+        assert_eq!(request.unfurl_links, Some(true));
+        assert_eq!(request.unfurl_media, Some(true));
+    }
+
+    #[test]
+    fn test_reply_request_targets_parent_thread() {
+        let channel = ChannelId::new("C123");
+        let parent = MessageTs::new("1700000000.000100");
+        let thread_ts = ThreadTs::from_parent(&parent);
+
+        let request = post_message_request(
+            &channel,
+            SlackMessageContent::new().with_text("reply".into()),
+            Some(&thread_ts),
+            SendOptions::default(),
+        );
+
+        assert_eq!(
+            request.thread_ts.map(|ts| ts.to_string()),
+            Some("1700000000.000100".to_string())
+        );
+    }
+}
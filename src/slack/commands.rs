@@ -1,15 +1,333 @@
-use crate::agent::AgentManager;
-use crate::error::Result;
-use crate::slack::{ChannelId, SlackClient};
+use crate::agent::{
+    ActiveAgent, AgentManager, MAX_AGENT_TIMEOUT, MIN_AGENT_TIMEOUT, Plan, RecentErrors,
+    TEARDOWN_CONFIRM_WINDOW, format_plan_summary,
+};
+use crate::config::{
+    AgentPermissionMode, BusySessionPolicy, ChannelConfig, GitHosts, ResponseLocale,
+};
+use crate::error::{Result, SlackCoderError};
+use crate::metadata::{MetadataCache, UserInfo};
+use crate::slack::args::CommandArgs;
+use crate::slack::{ChannelId, FormHandler, SharedFile, SlackClient, UserId};
+use crate::storage::{
+    BranchSwitch, FeedbackSummary, GitStatus, GithubRepoInfo, SetupStatus, checkout_branch,
+    format_bytes,
+};
+use std::collections::HashSet;
 use std::sync::Arc;
 
+/// What `/summarize` should summarize
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SummaryTarget {
+    /// The repository the channel is configured for
+    Repo,
+    /// The recent conversation in the current thread/session
+    Thread,
+}
+
+impl SummaryTarget {
+    /// Parse the `/summarize` argument (defaults to `repo` when omitted)
+    pub fn parse(arg: &str) -> Result<Self> {
+        match arg.trim().to_lowercase().as_str() {
+            "" | "repo" | "repository" => Ok(Self::Repo),
+            "thread" | "conversation" => Ok(Self::Thread),
+            other => Err(SlackCoderError::Config(format!(
+                "Unknown summarize target: '{}'. Expected `repo` or `thread`",
+                other
+            ))),
+        }
+    }
+
+    /// Extract the target from a `/summarize [target]` command, if the text is one
+    pub fn from_command(text: &str) -> Option<Result<Self>> {
+        let rest = text.trim().strip_prefix("/summarize")?;
+        if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+            return None;
+        }
+        Some(Self::parse(rest))
+    }
+
+    /// Build the standardized prompt forwarded to the repository agent
+    pub fn build_prompt(&self) -> String {
+        match self {
+            Self::Repo => r#"Please give me an onboarding summary of this repository.
+
+Cover:
+1. What the project does and who it is for
+2. The high-level architecture and the most important modules/directories
+3. How to build, test, and run it locally
+4. Key conventions (code style, error handling, testing) a new contributor should follow
+
+Keep it concise and skimmable - use short sections and bullet points."#
+                .to_string(),
+            Self::Thread => r#"Please summarize our recent conversation in this thread.
+
+Cover:
+1. What was asked and what was decided
+2. What changes were made (files, commits, PRs), if any
+3. Open questions or follow-up work that remains
+
+Keep it concise and skimmable - use short sections and bullet points."#
+                .to_string(),
+        }
+    }
+}
+
+/// Normalize a message into the canonical `/name args` form if it starts with the
+/// configured command prefix, or `None` if it's an ordinary message.
+///
+/// Commands are registered and dispatched under `/`; this is the one place the
+/// configured prefix is recognized, so mentions and messages route identically.
+pub fn parse_command_text(text: &str, prefix: &str) -> Option<String> {
+    let rest = text.trim().strip_prefix(prefix)?;
+    if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    Some(format!("/{}", rest))
+}
+
+/// Render a canonical command (`/help`) with the configured prefix (`!help`)
+pub(crate) fn display_command(command: &str, prefix: &str) -> String {
+    format!("{}{}", prefix, command.strip_prefix('/').unwrap_or(command))
+}
+
+/// Most agents `/agents` lists; the rest are counted
+const MAX_AGENT_LISTINGS: usize = 40;
+
+/// One row of `/agents`
+struct AgentListing {
+    agent: ActiveAgent,
+    /// Display name from the metadata cache, if it could be looked up
+    name: Option<String>,
+}
+
+/// One row of `/list-setups`
+struct SetupListing {
+    channel: ChannelId,
+    /// Display name from the metadata cache, if it could be looked up
+    name: Option<String>,
+    status: SetupStatus,
+    has_agent: bool,
+    /// Whether the bot is still in the channel (`None` if membership is unknown)
+    is_member: Option<bool>,
+}
+
+/// A message command known to the bot
+struct CommandSpec {
+    /// Command name including the leading slash
+    name: &'static str,
+    /// Argument synopsis shown in help (empty if the command takes none)
+    args: &'static str,
+    description: &'static str,
+    admin_only: bool,
+    handler: CommandAction,
+}
+
+/// What to run for a registered command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CommandAction {
+    Help,
+    NewSession,
+    Clear,
+    Progress,
+    Plan,
+    Usage,
+    Errors,
+    Feedback,
+    Repo,
+    Branch,
+    Restart,
+    Permissions,
+    Timeout,
+    Verbose,
+    Lang,
+    Teardown,
+    SyncChannels,
+    ListSetups,
+    Agents,
+    /// Forwarded to the agent by the message processor, never dispatched here
+    Summarize,
+    WhoAmI,
+    CacheStats,
+    Config,
+}
+
+/// Registry of all message commands; dispatch and `/help` are both driven by it
+const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "/help",
+        args: "",
+        description: "Show this help message",
+        admin_only: false,
+        handler: CommandAction::Help,
+    },
+    CommandSpec {
+        name: "/new-session",
+        args: "[--keep-plan]",
+        description: "Start a fresh conversation (clears context)",
+        admin_only: false,
+        handler: CommandAction::NewSession,
+    },
+    CommandSpec {
+        name: "/clear",
+        args: "",
+        description: "Clear the task progress board (keeps context)",
+        admin_only: false,
+        handler: CommandAction::Clear,
+    },
+    CommandSpec {
+        name: "/progress",
+        args: "",
+        description: "Re-post the current task board at the bottom of the channel",
+        admin_only: false,
+        handler: CommandAction::Progress,
+    },
+    CommandSpec {
+        name: "/plan",
+        args: "",
+        description: "Show the agent's current todo list as a one-off text snapshot",
+        admin_only: false,
+        handler: CommandAction::Plan,
+    },
+    CommandSpec {
+        name: "/usage",
+        args: "",
+        description: "Show token and cost totals for the current session",
+        admin_only: false,
+        handler: CommandAction::Usage,
+    },
+    CommandSpec {
+        name: "/errors",
+        args: "",
+        description: "Show the last few errors from this channel's agent (secrets redacted)",
+        admin_only: false,
+        handler: CommandAction::Errors,
+    },
+    CommandSpec {
+        name: "/feedback",
+        args: "summary",
+        description: "Show 👍/👎 ratings on the bot's replies in this channel",
+        admin_only: false,
+        handler: CommandAction::Feedback,
+    },
+    CommandSpec {
+        name: "/repo",
+        args: "[check [host/]owner/repo|resync]",
+        description: "Show branch and uncommitted changes, check a repository is reachable before setup, or re-clone this channel's repository",
+        admin_only: false,
+        handler: CommandAction::Repo,
+    },
+    CommandSpec {
+        name: "/branch",
+        args: "[name]",
+        description: "Show or change the branch the agent works on, creating it if needed",
+        admin_only: false,
+        handler: CommandAction::Branch,
+    },
+    CommandSpec {
+        name: "/restart",
+        args: "",
+        description: "Cancel any running task and recreate this channel's agent",
+        admin_only: true,
+        handler: CommandAction::Restart,
+    },
+    CommandSpec {
+        name: "/permissions",
+        args: "[default|accept-edits|plan|bypass-permissions]",
+        description: "Show or change what the agent may do without asking",
+        admin_only: true,
+        handler: CommandAction::Permissions,
+    },
+    CommandSpec {
+        name: "/timeout",
+        args: "[seconds|default]",
+        description: "Show or change how long this channel's agent may sit idle before it's disconnected",
+        admin_only: true,
+        handler: CommandAction::Timeout,
+    },
+    CommandSpec {
+        name: "/teardown",
+        args: "[confirm]",
+        description: "Remove this channel's agent, repository clone and prompt to free disk",
+        admin_only: true,
+        handler: CommandAction::Teardown,
+    },
+    CommandSpec {
+        name: "/sync-channels",
+        args: "",
+        description: "Rescan Slack: start agents for newly set-up channels, drop ones the bot left",
+        admin_only: true,
+        handler: CommandAction::SyncChannels,
+    },
+    CommandSpec {
+        name: "/list-setups",
+        args: "",
+        description: "List every channel set up on disk, flagging ones the bot has left",
+        admin_only: true,
+        handler: CommandAction::ListSetups,
+    },
+    CommandSpec {
+        name: "/agents",
+        args: "",
+        description: "List every running agent with its session and idle time",
+        admin_only: true,
+        handler: CommandAction::Agents,
+    },
+    CommandSpec {
+        name: "/verbose",
+        args: "[on|off]",
+        description: "Show or change whether the agent's file edits and commands are posted while it works",
+        admin_only: false,
+        handler: CommandAction::Verbose,
+    },
+    CommandSpec {
+        name: "/lang",
+        args: "[code|default]",
+        description: "Show or change the language the agent answers in (e.g. `ja`, `pt-BR`)",
+        admin_only: false,
+        handler: CommandAction::Lang,
+    },
+    CommandSpec {
+        name: "/summarize",
+        args: "[repo|thread]",
+        description: "Summarize the repository (default) or the recent conversation",
+        admin_only: false,
+        handler: CommandAction::Summarize,
+    },
+    CommandSpec {
+        name: "/whoami",
+        args: "",
+        description: "Show how the bot sees you (user ID, names, admin status)",
+        admin_only: false,
+        handler: CommandAction::WhoAmI,
+    },
+    CommandSpec {
+        name: "/cache-stats",
+        args: "",
+        description: "Show metadata cache statistics",
+        admin_only: true,
+        handler: CommandAction::CacheStats,
+    },
+    CommandSpec {
+        name: "/config",
+        args: "export|import [json]",
+        description: "Back up or restore this channel's configuration",
+        admin_only: true,
+        handler: CommandAction::Config,
+    },
+];
+
 pub struct SlackCommandHandler {
     slack_client: Arc<SlackClient>,
+    metadata_cache: Arc<MetadataCache>,
 }
 
 impl SlackCommandHandler {
-    pub fn new(slack_client: Arc<SlackClient>) -> Self {
-        Self { slack_client }
+    pub fn new(slack_client: Arc<SlackClient>, metadata_cache: Arc<MetadataCache>) -> Self {
+        Self {
+            slack_client,
+            metadata_cache,
+        }
     }
 
     /// Handle a slash command
@@ -17,100 +335,1927 @@ impl SlackCommandHandler {
         &self,
         command: &str,
         channel: &ChannelId,
-        agent_manager: &AgentManager,
+        user: &UserId,
+        files: &[SharedFile],
+        agent_manager: &Arc<AgentManager>,
     ) -> Result<()> {
         tracing::info!("Handling command: {} in {}", command, channel.log_format());
+        let settings = agent_manager.settings();
+        let prefix = settings.slack.command_prefix.as_str();
 
-        match command.trim() {
-            "/help" => self.handle_help(channel).await,
-            "/new-session" => self.handle_new_session(channel, agent_manager).await,
-            _ => {
+        let command = command.trim();
+        let (name, args) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+
+        let Some(spec) = COMMANDS.iter().find(|spec| spec.name == name) else {
+            self.slack_client
+                .send_message(
+                    channel,
+                    &format!(
+                        "❓ Unknown command: `{}`\n\nType `{}` for available commands.",
+                        display_command(command, prefix),
+                        display_command("/help", prefix)
+                    ),
+                    None,
+                )
+                .await?;
+            return Ok(());
+        };
+
+        if spec.admin_only && !self.require_admin(channel, user, agent_manager).await? {
+            return Ok(());
+        }
+
+        let args = match CommandArgs::parse(args) {
+            Ok(args) => args,
+            Err(e) => {
+                self.slack_client
+                    .send_message(channel, &format!("❌ {}", e), None)
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        match spec.handler {
+            CommandAction::Help => self.handle_help(channel, prefix).await,
+            CommandAction::NewSession => {
+                self.handle_new_session(&args, channel, agent_manager).await
+            }
+            CommandAction::Clear => self.handle_clear(channel, agent_manager).await,
+            CommandAction::Progress => self.handle_progress(channel, agent_manager).await,
+            CommandAction::Plan => self.handle_plan(channel, agent_manager).await,
+            CommandAction::Usage => self.handle_usage(channel, agent_manager).await,
+            CommandAction::Errors => self.handle_errors(channel, agent_manager).await,
+            CommandAction::Feedback => self.handle_feedback(&args, channel, agent_manager).await,
+            CommandAction::Repo => self.handle_repo(&args, channel, agent_manager).await,
+            CommandAction::Branch => self.handle_branch(&args, channel, agent_manager).await,
+            CommandAction::Restart => self.handle_restart(channel, agent_manager).await,
+            CommandAction::Permissions => {
+                self.handle_permissions(&args, channel, agent_manager).await
+            }
+            CommandAction::Timeout => self.handle_timeout(&args, channel, agent_manager).await,
+            CommandAction::Verbose => self.handle_verbose(&args, channel, agent_manager).await,
+            CommandAction::Lang => self.handle_lang(&args, channel, agent_manager).await,
+            CommandAction::Teardown => self.handle_teardown(&args, channel, agent_manager).await,
+            CommandAction::SyncChannels => self.handle_sync_channels(channel, agent_manager).await,
+            CommandAction::ListSetups => self.handle_list_setups(channel, agent_manager).await,
+            CommandAction::Agents => self.handle_agents(channel, agent_manager).await,
+            CommandAction::Summarize => {
+                tracing::warn!("/summarize reached the command handler instead of the agent");
                 self.slack_client
                     .send_message(
                         channel,
                         &format!(
-                            "❓ Unknown command: `{}`\n\nType `/help` for available commands.",
-                            command
+                            "⚠️ `{}` needs a configured repository agent in this channel.",
+                            display_command("/summarize", prefix)
                         ),
                         None,
                     )
                     .await?;
                 Ok(())
             }
+            CommandAction::WhoAmI => self.handle_whoami(channel, user, agent_manager).await,
+            CommandAction::CacheStats => self.handle_cache_stats(channel).await,
+            CommandAction::Config => {
+                self.handle_config(&args, channel, files, agent_manager)
+                    .await
+            }
         }
     }
 
-    /// Handle /help command
-    async fn handle_help(&self, channel: &ChannelId) -> Result<()> {
-        let help_text = r#"📚 *Available Commands*
+    /// Render a command for a usage or hint message, using the configured prefix
+    fn cmd(agent_manager: &AgentManager, command: &str) -> String {
+        display_command(command, &agent_manager.settings().slack.command_prefix)
+    }
+
+    /// Render the help text from the command registry, using the configured prefix
+    fn help_text(prefix: &str) -> String {
+        let cmd = |name: &str| display_command(name, prefix);
+        let commands: Vec<String> = COMMANDS
+            .iter()
+            .map(|spec| {
+                let usage = if spec.args.is_empty() {
+                    cmd(spec.name)
+                } else {
+                    format!("{} {}", cmd(spec.name), spec.args)
+                };
+                let admin = if spec.admin_only { " (admin only)" } else { "" };
+                format!("`{}` - {}{}", usage, spec.description, admin)
+            })
+            .collect();
 
-`/help` - Show this help message
-`/new-session` - Start a fresh conversation (clears context)
+        format!(
+            r#"📚 *Available Commands*
+
+{}
 
 *Examples:*
-• Type `/new-session` to start over with a clean slate
-• Type `/help` anytime to see available commands
+• Type `{}` to start over with a clean slate
+• Type `{} thread` to recap what happened so far
+• Type `{}` anytime to see available commands
 
-*Note:* Commands must be sent as a message to the bot (mention me or DM), not as Slack's built-in slash commands."#;
+*Note:* Commands must be sent as a message to the bot (mention me or DM), not as Slack's built-in slash commands."#,
+            commands.join("\n"),
+            cmd("/new-session"),
+            cmd("/summarize"),
+            cmd("/help")
+        )
+    }
 
+    /// Handle /help command
+    async fn handle_help(&self, channel: &ChannelId, prefix: &str) -> Result<()> {
         tracing::info!("Sending help message to {}", channel.log_format());
         self.slack_client
-            .send_message(channel, help_text, None)
+            .send_message(channel, &Self::help_text(prefix), None)
             .await?;
         Ok(())
     }
 
-    /// Handle /new-session command
-    async fn handle_new_session(
+    /// Reply with a refusal if the user isn't an admin. Returns true if allowed.
+    async fn require_admin(
+        &self,
+        channel: &ChannelId,
+        user: &UserId,
+        agent_manager: &AgentManager,
+    ) -> Result<bool> {
+        if agent_manager.settings().slack.is_admin(user.as_str()) {
+            return Ok(true);
+        }
+
+        tracing::warn!(
+            user_id = %user,
+            "Non-admin user attempted admin command"
+        );
+        self.slack_client
+            .send_message(
+                channel,
+                "🔒 This command is restricted to bot administrators.",
+                None,
+            )
+            .await?;
+        Ok(false)
+    }
+
+    /// Handle /whoami command - report the caller's identity as the bot resolves it
+    async fn handle_whoami(
         &self,
         channel: &ChannelId,
+        user: &UserId,
         agent_manager: &AgentManager,
     ) -> Result<()> {
-        // Check if agent exists for this channel
-        if !agent_manager.has_agent(channel) {
+        let info = self.metadata_cache.get_user_info(user.as_str()).await;
+        if info.is_none() {
+            tracing::warn!(user_id = %user, "User lookup failed for /whoami");
+        }
+
+        let is_admin = agent_manager.settings().slack.is_admin(user.as_str());
+        let message = Self::format_whoami(user, info.as_ref(), is_admin);
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Format the /whoami reply, falling back to just the ID if the lookup failed
+    fn format_whoami(user: &UserId, info: Option<&UserInfo>, is_admin: bool) -> String {
+        let mut lines = vec![
+            "👤 *Who am I?*".to_string(),
+            String::new(),
+            format!("• User ID: `{}`", user.as_str()),
+        ];
+
+        match info {
+            Some(info) => {
+                lines.push(format!("• Username: `{}`", info.name));
+                lines.push(format!(
+                    "• Display name: {}",
+                    info.display_name
+                        .as_deref()
+                        .filter(|n| !n.is_empty())
+                        .unwrap_or("_not set_")
+                ));
+                if let Some(real_name) = info.real_name.as_deref().filter(|n| !n.is_empty()) {
+                    lines.push(format!("• Real name: {}", real_name));
+                }
+                lines.push(format!("• Bot: {}", if info.is_bot { "yes" } else { "no" }));
+            }
+            None => {
+                lines.push("• _Profile details unavailable (user lookup failed)_".to_string());
+            }
+        }
+
+        lines.push(format!("• Admin: {}", if is_admin { "yes" } else { "no" }));
+        lines.join("\n")
+    }
+
+    /// Handle /cache-stats command
+    async fn handle_cache_stats(&self, channel: &ChannelId) -> Result<()> {
+        let stats = self.metadata_cache.get_stats().await;
+        let (channels_cached, users_cached) = self.metadata_cache.cache_sizes();
+        let message = stats.format_slack_message(channels_cached, users_cached);
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Handle /config export|import command
+    async fn handle_config(
+        &self,
+        args: &CommandArgs,
+        channel: &ChannelId,
+        files: &[SharedFile],
+        agent_manager: &Arc<AgentManager>,
+    ) -> Result<()> {
+        let workspace = agent_manager.workspace();
+        // The import payload is JSON, so it's taken from the raw text rather than tokens
+        let raw = args.raw();
+        let (subcommand, payload) = raw.split_once(char::is_whitespace).unwrap_or((raw, ""));
+
+        match subcommand {
+            "export" => {
+                let config = workspace.load_channel_config(channel).await?;
+                let json = config.to_export_json()?;
+                tracing::info!("Exporting channel config {}", channel.log_format());
+                self.slack_client
+                    .upload_snippet(
+                        channel,
+                        None,
+                        &format!("channel-config-{}.json", channel),
+                        &json,
+                        "json",
+                    )
+                    .await?;
+            }
+            "import" => {
+                // An uploaded export file takes precedence over pasted JSON
+                let imported = match files.first() {
+                    Some(file) => self.slack_client.download_text_file(file).await,
+                    None => Ok(payload.to_string()),
+                }
+                .and_then(|json| ChannelConfig::from_import_json(&json));
+
+                let config = match imported {
+                    Ok(config) => config,
+                    Err(e) => {
+                        self.slack_client
+                            .send_message(channel, &format!("❌ Import rejected: {}", e), None)
+                            .await?;
+                        return Ok(());
+                    }
+                };
+
+                let previous = workspace.load_channel_config(channel).await?;
+                workspace.save_channel_config(channel, &config).await?;
+                tracing::info!("Imported channel config {}", channel.log_format());
+
+                if config.repo_name.is_some() && config.repo_name != previous.repo_name {
+                    // The clone on disk is of the old repository; set up the new one
+                    self.slack_client
+                        .send_message(
+                            channel,
+                            "✅ *Channel configuration imported.*\n\nThe repository changed, so it will be cloned again.",
+                            None,
+                        )
+                        .await?;
+                    let form_handler = FormHandler::new(
+                        self.slack_client.clone(),
+                        agent_manager.clone(),
+                        self.metadata_cache.clone(),
+                    );
+                    return form_handler.resync_repo(channel.clone()).await;
+                }
+
+                self.slack_client
+                    .send_message(
+                        channel,
+                        "✅ *Channel configuration imported.*\n\nChanges take effect the next time the agent is created.",
+                        None,
+                    )
+                    .await?;
+            }
+            _ => {
+                self.slack_client
+                    .send_message(
+                        channel,
+                        &format!(
+                            "Usage: `{}`, or `{}` with the exported file attached or its JSON pasted after the command",
+                            Self::cmd(agent_manager, "/config export"),
+                            Self::cmd(agent_manager, "/config import")
+                        ),
+                        None,
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Handle /clear command - wipe the progress board and plan, keep the conversation
+    async fn handle_clear(&self, channel: &ChannelId, agent_manager: &AgentManager) -> Result<()> {
+        if agent_manager.active_queries().is_active(channel) {
             self.slack_client
                 .send_message(
                     channel,
-                    "⚠️  *No agent configured for this channel.*\n\nPlease mention me with a repository name to set up first.",
+                    &format!(
+                        "⏳ *A task is currently running.*\n\nPlease run `{}` again once it finishes.",
+                        Self::cmd(agent_manager, "/clear")
+                    ),
                     None,
                 )
                 .await?;
             return Ok(());
         }
 
-        // Get agent and start new session
-        let agent_mutex = agent_manager.get_repo_agent(channel).await?;
-        let mut agent = agent_mutex.lock().await;
-
-        let new_session_id = agent.start_new_session().await?;
+        let had_board = agent_manager
+            .progress_tracker()
+            .clear_progress(channel)
+            .await?;
 
-        // Notify user
-        let message = format!(
-            r#"🔄 *New Session Started*
+        if agent_manager.has_agent(channel) {
+            let agent_mutex = agent_manager.get_repo_agent(channel).await?;
+            agent_mutex.lock().await.reset_plan();
+        }
 
-Session ID: `{}`
+        tracing::info!(
+            had_board = had_board,
+            "Cleared progress board {}",
+            channel.log_format()
+        );
 
-Your conversation context has been cleared. You can now start fresh!
+        self.slack_client
+            .send_message(
+                channel,
+                &format!(
+                    "🧹 *Task board cleared.*\n\nYour conversation context is unchanged - use `{}` to reset it too.",
+                    Self::cmd(agent_manager, "/new-session")
+                ),
+                None,
+            )
+            .await?;
+        Ok(())
+    }
 
-*What does this mean?*
-• Previous conversation history is no longer accessible
-• The bot won't remember earlier discussions in this channel
-• Great for switching to a completely different task
+    /// Handle /progress command - re-post the task board so it's back in view
+    async fn handle_progress(
+        &self,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let reposted = agent_manager
+            .progress_tracker()
+            .repost_progress(channel)
+            .await?;
 
-Type `/help` for more commands."#,
-            new_session_id
-        );
+        if !reposted {
+            self.slack_client
+                .send_message(
+                    channel,
+                    "💤 *The agent is idle.*\n\nThere's no task in progress in this channel.",
+                    None,
+                )
+                .await?;
+        }
 
         tracing::info!(
-            "New session created: {} for {}",
-            new_session_id,
+            reposted = reposted,
+            "Handled /progress {}",
             channel.log_format()
         );
+        Ok(())
+    }
+
+    /// Handle /plan command - post the todo list as text, unlike /progress which moves
+    /// the live board
+    async fn handle_plan(&self, channel: &ChannelId, agent_manager: &AgentManager) -> Result<()> {
+        // A running task's plan comes from its board, since the agent is locked while it
+        // works; otherwise the idle agent still holds the last plan
+        let plan = match agent_manager.progress_tracker().current_plan(channel) {
+            Some(plan) => Some(plan),
+            None => match agent_manager.get_repo_agent(channel).await {
+                Ok(agent) => agent.try_lock().ok().map(|agent| agent.get_plan()),
+                Err(_) => None,
+            },
+        };
+
+        let message = Self::format_plan_snapshot(plan.as_ref());
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    fn format_plan_snapshot(plan: Option<&Plan>) -> String {
+        match plan {
+            Some(plan) if !plan.todos.is_empty() => {
+                format!("📋 *Plan*\n```\n{}\n```", format_plan_summary(plan))
+            }
+            _ => "📋 *No plan yet.*\n\nThe agent hasn't written a todo list in this channel's current session.".to_string(),
+        }
+    }
+
+    /// Handle /usage command - report running totals for the current session
+    async fn handle_usage(&self, channel: &ChannelId, agent_manager: &AgentManager) -> Result<()> {
+        let message = match agent_manager.session_usage(channel).await? {
+            Some(usage) if usage.queries > 0 => usage.format_slack_message(),
+            _ => "📈 *Session Usage*\n\nNo queries have run in this session yet.".to_string(),
+        };
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Handle /errors command - list recent agent and query errors for the channel
+    async fn handle_errors(&self, channel: &ChannelId, agent_manager: &AgentManager) -> Result<()> {
+        let records = agent_manager.recent_errors().recent(channel);
+        let message = RecentErrors::format_slack_message(&records);
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Handle /feedback command - report reaction ratings collected in this channel
+    async fn handle_feedback(
+        &self,
+        args: &CommandArgs,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let wants_summary = match args.positional() {
+            [] => true,
+            [sub] => sub == "summary",
+            _ => false,
+        };
+        let message = if wants_summary {
+            let records = agent_manager.workspace().load_feedback().await?;
+            FeedbackSummary::for_channel(&records, channel.as_str()).format_slack_message()
+        } else {
+            format!("Usage: `{}`", Self::cmd(agent_manager, "/feedback summary"))
+        };
 
         self.slack_client
             .send_message(channel, &message, None)
             .await?;
+        Ok(())
+    }
+
+    /// Handle /repo command - summarize the repository's branch and working tree
+    async fn handle_repo(
+        &self,
+        args: &CommandArgs,
+        channel: &ChannelId,
+        agent_manager: &Arc<AgentManager>,
+    ) -> Result<()> {
+        if let [resync] = args.positional()
+            && resync == "resync"
+        {
+            // Same path as the "Resync Repo" button
+            let form_handler = FormHandler::new(
+                self.slack_client.clone(),
+                agent_manager.clone(),
+                self.metadata_cache.clone(),
+            );
+            return form_handler.resync_repo(channel.clone()).await;
+        }
+
+        if !args.is_empty() {
+            let message = match args.positional() {
+                [check, repo_name] if check == "check" => {
+                    Self::check_repo(&agent_manager.settings().git, repo_name).await
+                }
+                _ => format!(
+                    "Usage: `{}`, `{}` or `{}`",
+                    Self::cmd(agent_manager, "/repo"),
+                    Self::cmd(agent_manager, "/repo check owner/repo"),
+                    Self::cmd(agent_manager, "/repo resync")
+                ),
+            };
+            self.slack_client
+                .send_message(channel, &message, None)
+                .await?;
+            return Ok(());
+        }
 
+        let workspace = agent_manager.workspace();
+        let config = workspace.load_channel_config(channel).await?;
+        let status = GitStatus::read(&workspace.repo_path(channel)).await?;
+
+        let message = Self::format_repo_status(
+            config.repo_name.as_deref(),
+            config.branch.as_deref(),
+            status.as_ref(),
+        );
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
         Ok(())
     }
+
+    /// Handle /branch command - show or change the channel's working branch
+    async fn handle_branch(
+        &self,
+        args: &CommandArgs,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let workspace = agent_manager.workspace();
+        let mut config = workspace.load_channel_config(channel).await?;
+        let repo_path = workspace.repo_path(channel);
+
+        let branch = match args.positional() {
+            [] => {
+                let status = GitStatus::read(&repo_path).await?;
+                let message = format!(
+                    "{}\n\nUsage: `{}`",
+                    Self::format_repo_status(
+                        config.repo_name.as_deref(),
+                        config.branch.as_deref(),
+                        status.as_ref(),
+                    ),
+                    Self::cmd(agent_manager, "/branch <name>")
+                );
+                self.slack_client
+                    .send_message(channel, &message, None)
+                    .await?;
+                return Ok(());
+            }
+            [branch] => branch.clone(),
+            _ => {
+                self.slack_client
+                    .send_message(
+                        channel,
+                        &format!("Usage: `{}`", Self::cmd(agent_manager, "/branch <name>")),
+                        None,
+                    )
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        if config.repo_name.is_none() {
+            self.slack_client
+                .send_message(
+                    channel,
+                    "⚠️  *No repository configured for this channel.*\n\nPlease mention me with a repository name to set up first.",
+                    None,
+                )
+                .await?;
+            return Ok(());
+        }
+
+        // Switching under a running task would change files the agent is working on
+        if agent_manager.active_queries().is_active(channel) {
+            self.slack_client
+                .send_message(
+                    channel,
+                    "⏳ *A task is currently running.*\n\nPlease switch branches once it finishes.",
+                    None,
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let message = match checkout_branch(&repo_path, &branch).await {
+            Ok(switch) => {
+                config.branch = Some(branch.clone());
+                workspace.save_channel_config(channel, &config).await?;
+                tracing::info!(
+                    branch = %branch,
+                    switch = ?switch,
+                    "Updated working branch {}",
+                    channel.log_format()
+                );
+                let dirty_files = GitStatus::read(&repo_path)
+                    .await?
+                    .map_or(0, |status| status.dirty_files);
+                Self::format_branch_switch(&branch, switch, dirty_files)
+            }
+            Err(SlackCoderError::Git(reason)) => Self::format_branch_conflict(&branch, &reason),
+            Err(e) => return Err(e),
+        };
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    fn format_branch_switch(branch: &str, switch: BranchSwitch, dirty_files: usize) -> String {
+        let action = match switch {
+            BranchSwitch::Created => format!("Created branch `{}` and switched to it", branch),
+            BranchSwitch::Switched => format!("Switched to branch `{}`", branch),
+            BranchSwitch::Unchanged => format!("Already on branch `{}`", branch),
+        };
+        let mut message = format!(
+            "🌿 {}.\n\nThe agent works on it from now on, and it's checked out again after a restart or resync.",
+            action
+        );
+        if dirty_files > 0 && switch != BranchSwitch::Unchanged {
+            message.push_str(&format!(
+                "\n\n⚠️ {} with uncommitted changes came along from the previous branch.",
+                if dirty_files == 1 {
+                    "1 file".to_string()
+                } else {
+                    format!("{} files", dirty_files)
+                }
+            ));
+        }
+        message
+    }
+
+    fn format_branch_conflict(branch: &str, reason: &str) -> String {
+        format!(
+            "⚠️ *Couldn't switch to `{}`*\n\n```\n{}\n```\n\nIf uncommitted changes are in the way, commit or stash them (or ask me to), then try again.",
+            branch, reason
+        )
+    }
+
+    /// Check that a repository is reachable via `gh` without cloning it. Only
+    /// github.com repositories are looked up; `gh` can't query other hosts.
+    async fn check_repo(git_hosts: &GitHosts, repo_name: &str) -> String {
+        let repo = match git_hosts.resolve(repo_name) {
+            Ok(repo) => repo,
+            Err(e) => return format!("❌ {}", e),
+        };
+        let repo_name = repo.to_string();
+        if !repo.is_github_com() {
+            return format!(
+                "ℹ️ *Can't check `{}` ahead of setup*\n\nRepository checks only cover github.com. Reply with `{}` to set it up; setup validates access on {}.",
+                repo_name, repo_name, repo.host
+            );
+        }
+
+        match GithubRepoInfo::fetch(&repo_name).await {
+            Ok(info) => Self::format_repo_check(&info, &repo_name),
+            Err(e) => {
+                tracing::info!(repo = %repo_name, error = %e, "Repository check failed");
+                format!(
+                    "❌ *Repository check failed*\n\n{}\n\nCheck the spelling and that the bot's GitHub account has access.",
+                    e
+                )
+            }
+        }
+    }
+
+    /// `repo_name` is the spec to reply with (it carries the host for non-github.com repos)
+    fn format_repo_check(info: &GithubRepoInfo, repo_name: &str) -> String {
+        let size = info
+            .size_bytes()
+            .map(format_bytes)
+            .unwrap_or_else(|| "unknown".to_string());
+        format!(
+            "✅ *`{}` is accessible*\n\n• Visibility: {}\n• Default branch: `{}`\n• Approximate size: {}\n\nReply with `{}` to set it up.",
+            info.name_with_owner,
+            info.visibility.to_lowercase(),
+            info.default_branch().unwrap_or("(empty repository)"),
+            size,
+            repo_name
+        )
+    }
+
+    fn format_repo_status(
+        repo_name: Option<&str>,
+        working_branch: Option<&str>,
+        status: Option<&GitStatus>,
+    ) -> String {
+        let repo = repo_name.unwrap_or("(unknown)");
+        match status {
+            Some(status) => {
+                let changes = match status.dirty_files {
+                    0 => "✅ Working tree clean".to_string(),
+                    1 => "✏️ 1 file with uncommitted changes".to_string(),
+                    n => format!("✏️ {} files with uncommitted changes", n),
+                };
+                let working = match working_branch {
+                    Some(branch) if branch == status.branch => " (working branch)".to_string(),
+                    Some(branch) => format!(" - working branch `{}` is not checked out", branch),
+                    None => String::new(),
+                };
+                format!(
+                    "📦 *Repository:* `{}`\n🌿 *Branch:* `{}`{}\n{}",
+                    repo, status.branch, working, changes
+                )
+            }
+            None if repo_name.is_some() => format!(
+                "📦 *Repository:* `{}`\n⚠️ The working copy is missing or isn't a git repository.",
+                repo
+            ),
+            None => "⚠️  *No repository configured for this channel.*\n\nPlease mention me with a repository name to set up first.".to_string(),
+        }
+    }
+
+    /// Message shown when /new-session is requested while a task is running
+    fn busy_session_notice(policy: BusySessionPolicy, prefix: &str) -> String {
+        match policy {
+            BusySessionPolicy::Defer => {
+                "⏳ *A task is currently running.*\n\nYour session will be reset as soon as it finishes.".to_string()
+            }
+            BusySessionPolicy::Reject => format!(
+                "⏳ *A task is currently running.*\n\nPlease run `{}` again once it finishes.",
+                display_command("/new-session", prefix)
+            ),
+        }
+    }
+
+    /// Handle /new-session command
+    async fn handle_new_session(
+        &self,
+        args: &CommandArgs,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let unknown = args.unknown_flags(&["keep-plan"]);
+        if !unknown.is_empty() || !args.positional().is_empty() {
+            self.slack_client
+                .send_message(
+                    channel,
+                    &format!(
+                        "Usage: `{}`",
+                        Self::cmd(agent_manager, "/new-session [--keep-plan]")
+                    ),
+                    None,
+                )
+                .await?;
+            return Ok(());
+        }
+        let keep_plan = args.has_flag("keep-plan");
+
+        // Check if agent exists for this channel
+        if !agent_manager.has_agent(channel) {
+            self.slack_client
+                .send_message(
+                    channel,
+                    "⚠️  *No agent configured for this channel.*\n\nPlease mention me with a repository name to set up first.",
+                    None,
+                )
+                .await?;
+            return Ok(());
+        }
+
+        // A query in flight holds the agent lock until it finishes
+        if agent_manager.active_queries().is_active(channel) {
+            let settings = agent_manager.settings();
+            let policy = settings.agent.busy_session_policy;
+            tracing::info!(
+                policy = ?policy,
+                "New session requested while a query is in flight {}",
+                channel.log_format()
+            );
+            self.slack_client
+                .send_message(
+                    channel,
+                    &Self::busy_session_notice(policy, &settings.slack.command_prefix),
+                    None,
+                )
+                .await?;
+            if policy == BusySessionPolicy::Reject {
+                return Ok(());
+            }
+        }
+
+        // Queued messages belong to the old conversation
+        let dropped = agent_manager.query_queues().clear(channel);
+        if dropped > 0 {
+            tracing::info!(
+                dropped = dropped,
+                "Dropped queued messages for new session {}",
+                channel.log_format()
+            );
+        }
+
+        // Get agent and start new session (waits for any in-flight query)
+        let agent_mutex = agent_manager.get_repo_agent(channel).await?;
+        let mut agent = agent_mutex.lock().await;
+
+        let new_session_id = agent.start_new_session(keep_plan).await?;
+        drop(agent);
+        agent_manager
+            .reset_session_usage(channel, &new_session_id)
+            .await?;
+
+        // Notify user
+        let mut message = format!(
+            r#"🔄 *New Session Started*
+
+Session ID: `{}`
+
+Your conversation context has been cleared. You can now start fresh!
+
+*What does this mean?*
+• Previous conversation history is no longer accessible
+• The bot won't remember earlier discussions in this channel
+• Great for switching to a completely different task
+
+Type `{}` for more commands."#,
+            new_session_id,
+            Self::cmd(agent_manager, "/help")
+        );
+        if keep_plan {
+            message.push_str("\n\n📋 The task board was kept.");
+        }
+        if dropped > 0 {
+            message.push_str(&format!(
+                "\n\n🗑️ Dropped {} queued message(s) from the previous session.",
+                dropped
+            ));
+        }
+
+        tracing::info!(
+            "New session created: {} for {}",
+            new_session_id,
+            channel.log_format()
+        );
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Handle /restart command - recreate a wedged agent without restarting the bot
+    async fn handle_restart(
+        &self,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let was_busy = agent_manager.active_queries().is_active(channel);
+        self.slack_client
+            .send_message(
+                channel,
+                if was_busy {
+                    "♻️ *Restarting agent...* Cancelling the running task first."
+                } else {
+                    "♻️ *Restarting agent...*"
+                },
+                None,
+            )
+            .await?;
+
+        let message = match agent_manager.restart_agent(channel).await {
+            Ok(session_id) => format!(
+                "✅ *Agent restarted*\n\nSession ID: `{}`\n\nConversation context was reset; the repository is unchanged.",
+                session_id
+            ),
+            Err(SlackCoderError::ChannelNotSetup(_)) => {
+                "⚠️  *No agent configured for this channel.*\n\nPlease mention me with a repository name to set up first.".to_string()
+            }
+            Err(SlackCoderError::IncompleteSetup { missing, .. }) => format!(
+                "⚠️  *This channel's setup is incomplete:* {}.\n\nPlease mention me with the repository name to run setup again.",
+                missing
+            ),
+            Err(e) => {
+                tracing::error!(error = %e, "Agent restart failed {}", channel.log_format());
+                format!("❌ *Agent restart failed*\n\n{}", e)
+            }
+        };
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Handle /teardown command - two-phase: `/teardown` explains, `/teardown confirm` deletes
+    async fn handle_teardown(
+        &self,
+        args: &CommandArgs,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let workspace = agent_manager.workspace();
+        let prefix = agent_manager.settings().slack.command_prefix.clone();
+
+        let message = match args.positional() {
+            [] => {
+                let size = workspace.channel_size_bytes(channel).await?;
+                if size > 0 {
+                    agent_manager.request_teardown(channel);
+                }
+                Self::format_teardown_prompt(size, &prefix)
+            }
+            [confirm] if confirm == "confirm" => {
+                if agent_manager.active_queries().is_active(channel) {
+                    format!(
+                        "⏳ *A task is currently running.*\n\nPlease run `{}` again once it finishes.",
+                        display_command("/teardown confirm", &prefix)
+                    )
+                } else if !agent_manager.take_teardown_request(channel) {
+                    format!(
+                        "⚠️ *No teardown is pending.*\n\nRun `{}` first to see what will be deleted.",
+                        display_command("/teardown", &prefix)
+                    )
+                } else {
+                    match agent_manager.teardown_channel(channel).await {
+                        Ok(reclaimed) => format!(
+                            "🗑️ *Channel torn down.*\n\nRemoved the agent, repository clone and system prompt, reclaiming {}.\n\nMention me with `owner/repo` to set this channel up again.",
+                            format_bytes(reclaimed)
+                        ),
+                        Err(e) => {
+                            tracing::error!(error = %e, "Teardown failed {}", channel.log_format());
+                            format!("❌ *Teardown failed*\n\n{}", e)
+                        }
+                    }
+                }
+            }
+            _ => format!(
+                "Usage: `{}` or `{}`",
+                display_command("/teardown", &prefix),
+                display_command("/teardown confirm", &prefix)
+            ),
+        };
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Handle /sync-channels command - reconcile agents with the channels the bot is in
+    async fn handle_sync_channels(
+        &self,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        self.slack_client
+            .send_message(channel, "🔄 Scanning channels...", None)
+            .await?;
+
+        let message = match agent_manager.sync_channels(&self.slack_client).await {
+            Ok(report) => report.format_slack_message(),
+            Err(e) => {
+                tracing::error!(error = %e, "Channel sync failed");
+                format!("❌ *Channel sync failed*\n\n{}", e)
+            }
+        };
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Handle /list-setups command - every channel with a setup on disk and its state
+    async fn handle_list_setups(
+        &self,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let workspace = agent_manager.workspace();
+        let channels = workspace.list_setup_channels().await?;
+
+        // Membership is best-effort: without it, orphaned setups just aren't flagged
+        let members: Option<HashSet<ChannelId>> = match self.slack_client.list_channels().await {
+            Ok(members) => Some(members.into_iter().collect()),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to list channels, not flagging orphans");
+                None
+            }
+        };
+
+        let mut listings = Vec::with_capacity(channels.len());
+        for channel_id in channels {
+            let name = self
+                .metadata_cache
+                .get_channel_info(channel_id.as_str())
+                .await
+                .map(|info| info.display_name());
+            listings.push(SetupListing {
+                status: workspace.is_channel_setup(&channel_id).await,
+                has_agent: agent_manager.has_agent(&channel_id),
+                is_member: members.as_ref().map(|m| m.contains(&channel_id)),
+                name,
+                channel: channel_id,
+            });
+        }
+
+        self.slack_client
+            .send_message(channel, &Self::format_setup_list(&listings), None)
+            .await?;
+        Ok(())
+    }
+
+    /// Handle /agents command - every running agent, busiest first
+    async fn handle_agents(&self, channel: &ChannelId, agent_manager: &AgentManager) -> Result<()> {
+        let agents = agent_manager.active_agents().await;
+        let total = agents.len();
+        // Names are only looked up for the rows shown, all at once
+        let listings =
+            futures::future::join_all(Self::shown_agents(agents).into_iter().map(|agent| async {
+                let name = self
+                    .metadata_cache
+                    .get_channel_info(agent.channel_id.as_str())
+                    .await
+                    .map(|info| info.display_name());
+                AgentListing { agent, name }
+            }))
+            .await;
+
+        self.slack_client
+            .send_message(channel, &Self::format_agent_list(&listings, total), None)
+            .await?;
+        Ok(())
+    }
+
+    /// The agents `/agents` lists: busy ones first, then the most recently active
+    fn shown_agents(mut agents: Vec<ActiveAgent>) -> Vec<ActiveAgent> {
+        agents.sort_by_key(|agent| agent.idle);
+        agents.truncate(MAX_AGENT_LISTINGS);
+        agents
+    }
+
+    /// List the shown agents, out of `total` running
+    fn format_agent_list(listings: &[AgentListing], total: usize) -> String {
+        if total == 0 {
+            return "🤖 *Agents*\n\nNo agents are running.".to_string();
+        }
+
+        let lines: Vec<String> = listings
+            .iter()
+            .map(|listing| {
+                let label = match &listing.name {
+                    Some(name) => format!("{} (`{}`)", name, listing.agent.channel_id),
+                    None => format!("`{}`", listing.agent.channel_id),
+                };
+                let state = match (&listing.agent.session_id, listing.agent.idle) {
+                    (Some(session), Some(idle)) => {
+                        format!("session `{}`, idle {}", session, format_idle(idle))
+                    }
+                    _ => "🔄 busy with a query".to_string(),
+                };
+                format!("• {} - {}", label, state)
+            })
+            .collect();
+
+        let mut message = format!("🤖 *Agents* ({})\n\n{}", total, lines.join("\n"));
+        if total > listings.len() {
+            message.push_str(&format!("\n_…and {} more_", total - listings.len()));
+        }
+        message
+    }
+
+    fn format_setup_list(listings: &[SetupListing]) -> String {
+        if listings.is_empty() {
+            return "📂 *Setups*\n\nNo channels are set up on disk.".to_string();
+        }
+
+        let lines: Vec<String> = listings
+            .iter()
+            .map(|listing| {
+                let label = match &listing.name {
+                    Some(name) => format!("{} (`{}`)", name, listing.channel),
+                    None => format!("`{}`", listing.channel),
+                };
+                let state = if listing.is_member == Some(false) {
+                    "👻 orphaned - the bot is no longer in this channel".to_string()
+                } else if let Some(missing) = listing.status.missing() {
+                    format!("⚠️ incomplete: {}", missing)
+                } else if listing.has_agent {
+                    "🟢 agent running".to_string()
+                } else {
+                    "⚪ idle (agent starts on the next message)".to_string()
+                };
+                format!("• {} - {}", label, state)
+            })
+            .collect();
+
+        let orphaned = listings
+            .iter()
+            .filter(|l| l.is_member == Some(false))
+            .count();
+        let mut message = format!("📂 *Setups* ({})\n\n{}", listings.len(), lines.join("\n"));
+        if orphaned > 0 {
+            message.push_str(&format!(
+                "\n\n{} orphaned setup(s) still use disk space. Invite the bot back to reuse them.",
+                orphaned
+            ));
+        }
+        message
+    }
+
+    fn format_teardown_prompt(size: u64, prefix: &str) -> String {
+        if size == 0 {
+            return "ℹ️ Nothing is stored for this channel.".to_string();
+        }
+        format!(
+            "⚠️ *This will permanently delete this channel's setup:*\n• The agent and its conversation\n• The repository clone and system prompt ({})\n• Channel settings and usage totals\n\nUnpushed changes in the clone will be lost. Run `{}` within {} minutes to proceed.",
+            format_bytes(size),
+            display_command("/teardown confirm", prefix),
+            TEARDOWN_CONFIRM_WINDOW.as_secs() / 60
+        )
+    }
+
+    /// Handle /permissions command - show or override the channel's permission mode
+    async fn handle_permissions(
+        &self,
+        args: &CommandArgs,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let workspace = agent_manager.workspace();
+        let mut config = workspace.load_channel_config(channel).await?;
+        let global = agent_manager.settings().agent.permission_mode;
+
+        if args.is_empty() {
+            let message = Self::format_permissions(
+                config.permission_mode,
+                global,
+                &agent_manager.settings().slack.command_prefix,
+            );
+            self.slack_client
+                .send_message(channel, &message, None)
+                .await?;
+            return Ok(());
+        }
+
+        let mode = match args.raw().parse::<AgentPermissionMode>() {
+            Ok(mode) => mode,
+            Err(e) => {
+                self.slack_client
+                    .send_message(channel, &format!("❌ {}", e), None)
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        if agent_manager.active_queries().is_active(channel) {
+            self.slack_client
+                .send_message(
+                    channel,
+                    "⏳ *A task is currently running.*\n\nPlease change permissions once it finishes.",
+                    None,
+                )
+                .await?;
+            return Ok(());
+        }
+
+        config.permission_mode = Some(mode);
+        workspace.save_channel_config(channel, &config).await?;
+        tracing::info!(
+            permission_mode = %mode,
+            "Updated permission mode {}",
+            channel.log_format()
+        );
+
+        // Options are fixed when the Claude client is built, so a live agent is recreated
+        let message = if agent_manager.has_agent(channel) {
+            match agent_manager.restart_agent(channel).await {
+                Ok(session_id) => format!(
+                    "🔐 Permission mode set to `{}`.\n\nThe agent was recreated to apply it (new session `{}`).",
+                    mode, session_id
+                ),
+                Err(e) => {
+                    tracing::error!(error = %e, "Agent restart failed {}", channel.log_format());
+                    format!(
+                        "🔐 Permission mode set to `{}`, but recreating the agent failed: {}\n\nRun `{}` to apply it.",
+                        mode,
+                        e,
+                        Self::cmd(agent_manager, "/restart")
+                    )
+                }
+            }
+        } else {
+            format!(
+                "🔐 Permission mode set to `{}`.\n\nIt applies the next time the agent starts.",
+                mode
+            )
+        };
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    fn format_permissions(
+        channel_mode: Option<AgentPermissionMode>,
+        global: AgentPermissionMode,
+        prefix: &str,
+    ) -> String {
+        let (mode, source) = match channel_mode {
+            Some(mode) => (mode, "channel override"),
+            None => (global, "global default"),
+        };
+        let mut message = format!("🔐 *Permission mode:* `{}` ({})", mode, source);
+        if mode == AgentPermissionMode::BypassPermissions {
+            message.push_str(
+                "\n\n⚠️ The agent runs every tool without asking, including shell commands and pushes.",
+            );
+        }
+        message.push_str(&format!(
+            "\n\nUsage: `{}`",
+            display_command(
+                "/permissions default|accept-edits|plan|bypass-permissions",
+                prefix
+            )
+        ));
+        message
+    }
+
+    /// Handle /timeout command - show or override the channel's idle timeout
+    async fn handle_timeout(
+        &self,
+        args: &CommandArgs,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let message = if args.is_empty() {
+            Self::format_timeout(
+                agent_manager.agent_timeout(channel),
+                agent_manager.agent_timeout_override(channel).is_some(),
+                &agent_manager.settings().slack.command_prefix,
+            )
+        } else {
+            let result = Self::parse_timeout(args.raw()).and_then(|timeout| {
+                agent_manager.set_agent_timeout(channel, timeout)?;
+                Ok(timeout)
+            });
+            match result {
+                Ok(timeout) => {
+                    tracing::info!(
+                        timeout_secs = ?timeout.map(|t| t.as_secs()),
+                        "Updated idle timeout {}",
+                        channel.log_format()
+                    );
+                    let effective = format_timeout_duration(agent_manager.agent_timeout(channel));
+                    match timeout {
+                        Some(_) => format!(
+                            "⏲️ Idle timeout set to {} for this channel, until the bot restarts.",
+                            effective
+                        ),
+                        None => format!("⏲️ Idle timeout reset to the default ({}).", effective),
+                    }
+                }
+                Err(e) => format!("❌ {}", e),
+            }
+        };
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Handle /verbose command - show or toggle the live feed of tool calls
+    async fn handle_verbose(
+        &self,
+        args: &CommandArgs,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let workspace = agent_manager.workspace();
+        let mut config = workspace.load_channel_config(channel).await?;
+
+        let message = if args.is_empty() {
+            let state = if config.verbose.unwrap_or(false) {
+                "on"
+            } else {
+                "off"
+            };
+            format!(
+                "🔧 *Verbose mode:* {}\n\nUsage: `{}`",
+                state,
+                Self::cmd(agent_manager, "/verbose on|off")
+            )
+        } else {
+            match Self::parse_on_off(args.raw()) {
+                Some(verbose) => {
+                    config.verbose = Some(verbose);
+                    workspace.save_channel_config(channel, &config).await?;
+                    tracing::info!(verbose, "Updated verbose mode {}", channel.log_format());
+                    if verbose {
+                        "🔧 Verbose mode on: files the agent edits and commands it runs are posted in the thread as it works.".to_string()
+                    } else {
+                        "🔧 Verbose mode off: only the final answer is posted.".to_string()
+                    }
+                }
+                None => format!(
+                    "❌ Invalid argument: '{}'. Expected `on` or `off`",
+                    args.raw().trim()
+                ),
+            }
+        };
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Handle /lang command - show or change the language the agent answers in
+    async fn handle_lang(
+        &self,
+        args: &CommandArgs,
+        channel: &ChannelId,
+        agent_manager: &AgentManager,
+    ) -> Result<()> {
+        let workspace = agent_manager.workspace();
+        let mut config = workspace.load_channel_config(channel).await?;
+        let settings = agent_manager.settings();
+        let global = settings.agent.response_locale.as_ref();
+
+        if args.is_empty() {
+            let message = Self::format_lang(
+                config.response_locale.as_ref(),
+                global,
+                &settings.slack.command_prefix,
+            );
+            self.slack_client
+                .send_message(channel, &message, None)
+                .await?;
+            return Ok(());
+        }
+
+        let locale = match Self::parse_lang(args.raw()) {
+            Ok(locale) => locale,
+            Err(e) => {
+                self.slack_client
+                    .send_message(channel, &format!("❌ {}", e), None)
+                    .await?;
+                return Ok(());
+            }
+        };
+
+        if agent_manager.active_queries().is_active(channel) {
+            self.slack_client
+                .send_message(
+                    channel,
+                    "⏳ *A task is currently running.*\n\nPlease change the language once it finishes.",
+                    None,
+                )
+                .await?;
+            return Ok(());
+        }
+
+        config.response_locale = locale;
+        workspace.save_channel_config(channel, &config).await?;
+        tracing::info!(
+            locale = ?config.response_locale,
+            "Updated response language {}",
+            channel.log_format()
+        );
+
+        let language = match config.response_locale.as_ref().or(global) {
+            Some(locale) => Self::describe_locale(locale),
+            None => "the language of each request".to_string(),
+        };
+        // The instruction is part of the system prompt, so a live agent is recreated
+        let message = if agent_manager.has_agent(channel) {
+            match agent_manager.restart_agent(channel).await {
+                Ok(session_id) => format!(
+                    "🌐 The agent now answers in {}.\n\nIt was recreated to apply this (new session `{}`).",
+                    language, session_id
+                ),
+                Err(e) => {
+                    tracing::error!(error = %e, "Agent restart failed {}", channel.log_format());
+                    format!(
+                        "🌐 Language set to {}, but recreating the agent failed: {}\n\nRun `{}` to apply it.",
+                        language,
+                        e,
+                        Self::cmd(agent_manager, "/restart")
+                    )
+                }
+            }
+        } else {
+            format!(
+                "🌐 Language set to {}.\n\nIt applies the next time the agent starts.",
+                language
+            )
+        };
+
+        self.slack_client
+            .send_message(channel, &message, None)
+            .await?;
+        Ok(())
+    }
+
+    /// Parse the `/lang` argument: a locale code, or `default` to drop the override
+    fn parse_lang(arg: &str) -> Result<Option<ResponseLocale>> {
+        match arg.trim().to_lowercase().as_str() {
+            "default" | "reset" => Ok(None),
+            _ => arg.parse().map(Some),
+        }
+    }
+
+    fn describe_locale(locale: &ResponseLocale) -> String {
+        match locale.language_name() {
+            Some(name) => format!("{} (`{}`)", name, locale),
+            None => format!("`{}`", locale),
+        }
+    }
+
+    fn format_lang(
+        channel: Option<&ResponseLocale>,
+        global: Option<&ResponseLocale>,
+        prefix: &str,
+    ) -> String {
+        let current = match (channel, global) {
+            (Some(locale), _) => format!("{} (channel override)", Self::describe_locale(locale)),
+            (None, Some(locale)) => format!("{} (global default)", Self::describe_locale(locale)),
+            (None, None) => "the language of each request (global default)".to_string(),
+        };
+        format!(
+            "🌐 *Response language:* {}\n\nUsage: `{}` (e.g. `ja`, `pt-BR`) or `{}`",
+            current,
+            display_command("/lang <code>", prefix),
+            display_command("/lang default", prefix)
+        )
+    }
+
+    fn parse_on_off(arg: &str) -> Option<bool> {
+        match arg.trim().to_lowercase().as_str() {
+            "on" | "true" | "yes" => Some(true),
+            "off" | "false" | "no" => Some(false),
+            _ => None,
+        }
+    }
+
+    /// Parse the `/timeout` argument: seconds, or `default` to drop the override
+    fn parse_timeout(arg: &str) -> Result<Option<std::time::Duration>> {
+        match arg.trim().to_lowercase().as_str() {
+            "default" | "reset" => Ok(None),
+            secs => secs
+                .parse()
+                .map(|secs| Some(std::time::Duration::from_secs(secs)))
+                .map_err(|_| {
+                    SlackCoderError::Config(format!(
+                        "Invalid timeout: '{}'. Expected a number of seconds or `default`",
+                        arg.trim()
+                    ))
+                }),
+        }
+    }
+
+    fn format_timeout(timeout: std::time::Duration, overridden: bool, prefix: &str) -> String {
+        let source = if overridden {
+            "channel override, until restart"
+        } else {
+            "global default"
+        };
+        format!(
+            "⏲️ *Idle timeout:* {} ({})\n\nUsage: `{}` ({} to {}) or `{}`",
+            format_timeout_duration(timeout),
+            source,
+            display_command("/timeout <seconds>", prefix),
+            MIN_AGENT_TIMEOUT.as_secs(),
+            MAX_AGENT_TIMEOUT.as_secs(),
+            display_command("/timeout default", prefix)
+        )
+    }
+}
+
+/// `5400s (1h 30m)`
+fn format_timeout_duration(timeout: std::time::Duration) -> String {
+    let secs = timeout.as_secs();
+    let (hours, mins) = (secs / 3600, secs % 3600 / 60);
+    let human = match (hours, mins) {
+        (0, 0) => return format!("{}s", secs),
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    };
+    format!("{}s ({})", secs, human)
+}
+
+/// `45s`, `12m`, `3h 5m`
+fn format_idle(idle: std::time::Duration) -> String {
+    let secs = idle.as_secs();
+    match (secs / 3600, secs % 3600 / 60) {
+        (0, 0) => format!("{}s", secs),
+        (0, m) => format!("{}m", m),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{Task, TaskStatus};
+
+    #[test]
+    fn test_summary_target_parse() {
+        assert_eq!(SummaryTarget::parse("").unwrap(), SummaryTarget::Repo);
+        assert_eq!(SummaryTarget::parse("repo").unwrap(), SummaryTarget::Repo);
+        assert_eq!(
+            SummaryTarget::parse(" Thread ").unwrap(),
+            SummaryTarget::Thread
+        );
+        assert!(SummaryTarget::parse("everything").is_err());
+    }
+
+    #[test]
+    fn test_summary_target_from_command() {
+        assert_eq!(
+            SummaryTarget::from_command("/summarize").unwrap().unwrap(),
+            SummaryTarget::Repo
+        );
+        assert_eq!(
+            SummaryTarget::from_command("/summarize thread")
+                .unwrap()
+                .unwrap(),
+            SummaryTarget::Thread
+        );
+        assert!(
+            SummaryTarget::from_command("/summarize nope")
+                .unwrap()
+                .is_err()
+        );
+        assert!(SummaryTarget::from_command("/summarizer").is_none());
+        assert!(SummaryTarget::from_command("/help").is_none());
+    }
+
+    #[test]
+    fn test_busy_session_notice() {
+        let defer = SlackCommandHandler::busy_session_notice(BusySessionPolicy::Defer, "/");
+        assert!(defer.contains("will be reset as soon as it finishes"));
+
+        let reject = SlackCommandHandler::busy_session_notice(BusySessionPolicy::Reject, "/");
+        assert!(reject.contains("run `/new-session` again"));
+
+        let custom = SlackCommandHandler::busy_session_notice(BusySessionPolicy::Reject, "!");
+        assert!(custom.contains("run `!new-session` again"));
+    }
+
+    #[test]
+    fn test_repo_summary_prompt() {
+        let prompt = SummaryTarget::Repo.build_prompt();
+        assert!(prompt.contains("onboarding summary of this repository"));
+        assert!(prompt.contains("architecture"));
+        assert!(!prompt.contains("conversation"));
+    }
+
+    #[test]
+    fn test_thread_summary_prompt() {
+        let prompt = SummaryTarget::Thread.build_prompt();
+        assert!(prompt.contains("summarize our recent conversation"));
+        assert!(prompt.contains("follow-up work"));
+        assert!(!prompt.contains("repository"));
+    }
+
+    #[test]
+    fn test_help_lists_every_registered_command() {
+        let help = SlackCommandHandler::help_text("/");
+        for spec in COMMANDS {
+            assert!(
+                help.contains(&format!("`{}", spec.name)),
+                "help is missing {}",
+                spec.name
+            );
+            assert!(help.contains(spec.description));
+        }
+        assert!(help.contains("not as Slack's built-in slash commands"));
+    }
+
+    #[test]
+    fn test_help_uses_command_prefix() {
+        let help = SlackCommandHandler::help_text("!");
+        assert!(help.contains("`!new-session [--keep-plan]`"));
+        assert!(help.contains("Type `!help` anytime"));
+        assert!(!help.contains("`/help`"));
+    }
+
+    #[test]
+    fn test_parse_command_text() {
+        // Default prefix
+        assert_eq!(parse_command_text(" /help ", "/").as_deref(), Some("/help"));
+        assert_eq!(parse_command_text("fix the bug", "/"), None);
+
+        // A custom prefix routes to the command handler in canonical form...
+        assert_eq!(
+            parse_command_text("!teardown confirm", "!").as_deref(),
+            Some("/teardown confirm")
+        );
+        // ...while default-prefix text is an ordinary message
+        assert_eq!(parse_command_text("/help", "!"), None);
+        assert_eq!(parse_command_text("! not a command", "!"), None);
+        assert_eq!(parse_command_text("!", "!"), None);
+    }
+
+    #[test]
+    fn test_command_names_are_unique() {
+        for (i, spec) in COMMANDS.iter().enumerate() {
+            assert!(spec.name.starts_with('/'));
+            assert!(
+                COMMANDS[i + 1..]
+                    .iter()
+                    .all(|other| other.name != spec.name)
+            );
+        }
+    }
+
+    fn user_info() -> UserInfo {
+        UserInfo {
+            id: "U123".to_string(),
+            name: "john.doe".to_string(),
+            real_name: Some("John Doe".to_string()),
+            display_name: Some("Johnny".to_string()),
+            email: None,
+            is_bot: false,
+            fetched_at: std::time::Instant::now(),
+        }
+    }
+
+    #[test]
+    fn test_format_whoami() {
+        let user = UserId::new("U123");
+        let message = SlackCommandHandler::format_whoami(&user, Some(&user_info()), true);
+
+        assert!(message.contains("User ID: `U123`"));
+        assert!(message.contains("Username: `john.doe`"));
+        assert!(message.contains("Display name: Johnny"));
+        assert!(message.contains("Real name: John Doe"));
+        assert!(message.contains("Bot: no"));
+        assert!(message.contains("Admin: yes"));
+    }
+
+    #[test]
+    fn test_format_whoami_lookup_failed() {
+        let user = UserId::new("U123");
+        let message = SlackCommandHandler::format_whoami(&user, None, false);
+
+        assert!(message.contains("User ID: `U123`"));
+        assert!(message.contains("lookup failed"));
+        assert!(!message.contains("Username"));
+        assert!(message.contains("Admin: no"));
+    }
+
+    #[test]
+    fn test_format_repo_status() {
+        let clean = GitStatus {
+            branch: "main".to_string(),
+            dirty_files: 0,
+        };
+        let message =
+            SlackCommandHandler::format_repo_status(Some("owner/repo"), None, Some(&clean));
+        assert!(message.contains("`owner/repo`"));
+        assert!(message.contains("`main`"));
+        assert!(message.contains("clean"));
+        assert!(!message.contains("working branch"));
+
+        let dirty = GitStatus {
+            branch: "feature".to_string(),
+            dirty_files: 3,
+        };
+        let message =
+            SlackCommandHandler::format_repo_status(Some("owner/repo"), None, Some(&dirty));
+        assert!(message.contains("3 files with uncommitted changes"));
+
+        let message = SlackCommandHandler::format_repo_status(
+            Some("owner/repo"),
+            Some("feature"),
+            Some(&dirty),
+        );
+        assert!(message.contains("`feature` (working branch)"));
+        let message =
+            SlackCommandHandler::format_repo_status(Some("owner/repo"), Some("fix"), Some(&dirty));
+        assert!(message.contains("working branch `fix` is not checked out"));
+
+        let message = SlackCommandHandler::format_repo_status(Some("owner/repo"), None, None);
+        assert!(message.contains("isn't a git repository"));
+    }
+
+    #[test]
+    fn test_format_branch_switch() {
+        let created =
+            SlackCommandHandler::format_branch_switch("feature", BranchSwitch::Created, 0);
+        assert!(created.starts_with("🌿 Created branch `feature`"));
+        assert!(!created.contains("uncommitted"));
+
+        let switched = SlackCommandHandler::format_branch_switch("main", BranchSwitch::Switched, 2);
+        assert!(switched.contains("2 files with uncommitted changes came along"));
+
+        let conflict = SlackCommandHandler::format_branch_conflict(
+            "main",
+            "error: Your local changes would be overwritten by checkout",
+        );
+        assert!(conflict.contains("Couldn't switch to `main`"));
+        assert!(conflict.contains("commit or stash"));
+    }
+
+    #[test]
+    fn test_format_repo_check() {
+        let info = GithubRepoInfo {
+            name_with_owner: "owner/repo".to_string(),
+            visibility: "PRIVATE".to_string(),
+            default_branch_ref: Some(crate::storage::BranchRef {
+                name: "main".to_string(),
+            }),
+            disk_usage: Some(2048),
+        };
+        let message = SlackCommandHandler::format_repo_check(&info, "owner/repo");
+
+        assert!(message.contains("`owner/repo` is accessible"));
+        assert!(message.contains("Reply with `owner/repo`"));
+        assert!(message.contains("Visibility: private"));
+        assert!(message.contains("Default branch: `main`"));
+        assert!(message.contains("2.0 MB"));
+    }
+
+    #[test]
+    fn test_format_permissions() {
+        let global = SlackCommandHandler::format_permissions(
+            None,
+            AgentPermissionMode::BypassPermissions,
+            "/",
+        );
+        assert!(global.contains("`bypass-permissions` (global default)"));
+        assert!(global.contains("without asking"));
+
+        let channel = SlackCommandHandler::format_permissions(
+            Some(AgentPermissionMode::Plan),
+            AgentPermissionMode::BypassPermissions,
+            "!",
+        );
+        assert!(channel.contains("`plan` (channel override)"));
+        assert!(!channel.contains("without asking"));
+        assert!(channel.contains("Usage: `!permissions "));
+    }
+
+    #[test]
+    fn test_timeout_command() {
+        use std::time::Duration;
+
+        assert_eq!(
+            SlackCommandHandler::parse_timeout(" 7200 ").unwrap(),
+            Some(Duration::from_secs(7200))
+        );
+        assert_eq!(SlackCommandHandler::parse_timeout("Default").unwrap(), None);
+        assert!(SlackCommandHandler::parse_timeout("2h").is_err());
+        assert!(SlackCommandHandler::parse_timeout("-5").is_err());
+
+        let message = SlackCommandHandler::format_timeout(Duration::from_secs(5400), true, "!");
+        assert!(message.contains("5400s (1h 30m) (channel override, until restart)"));
+        assert!(message.contains("`!timeout <seconds>`"));
+        assert!(!message.contains("`/timeout"));
+        assert_eq!(
+            format_timeout_duration(Duration::from_secs(1800)),
+            "1800s (30m)"
+        );
+        assert_eq!(format_timeout_duration(Duration::from_secs(45)), "45s");
+    }
+
+    #[test]
+    fn test_parse_on_off() {
+        assert_eq!(SlackCommandHandler::parse_on_off(" ON "), Some(true));
+        assert_eq!(SlackCommandHandler::parse_on_off("off"), Some(false));
+        assert_eq!(SlackCommandHandler::parse_on_off("loud"), None);
+    }
+
+    #[test]
+    fn test_format_plan_snapshot() {
+        assert!(SlackCommandHandler::format_plan_snapshot(None).contains("No plan yet"));
+        assert!(
+            SlackCommandHandler::format_plan_snapshot(Some(&Plan::new())).contains("No plan yet")
+        );
+
+        let task = |content: &str, status: TaskStatus| Task {
+            content: content.to_string(),
+            active_form: format!("{}ing", content),
+            status,
+            start_time: None,
+            completion_time: None,
+        };
+        let plan = Plan {
+            todos: vec![
+                task("Read", TaskStatus::Completed),
+                task("Fix", TaskStatus::InProgress),
+                task("Test", TaskStatus::Pending),
+            ],
+        };
+        let snapshot = SlackCommandHandler::format_plan_snapshot(Some(&plan));
+        assert_eq!(
+            snapshot,
+            "📋 *Plan*\n```\nProgress: 1/3\nCurrent: Fixing\n✅ Read\n⏳ Fixing\n⬜ Test\n```"
+        );
+    }
+
+    #[test]
+    fn test_lang_command() {
+        assert_eq!(
+            SlackCommandHandler::parse_lang(" pt_br ")
+                .unwrap()
+                .unwrap()
+                .as_str(),
+            "pt-BR"
+        );
+        assert_eq!(SlackCommandHandler::parse_lang("Default").unwrap(), None);
+        assert!(SlackCommandHandler::parse_lang("klingon").is_err());
+
+        let ja: ResponseLocale = "ja".parse().unwrap();
+        let de: ResponseLocale = "de".parse().unwrap();
+        assert!(
+            SlackCommandHandler::format_lang(Some(&ja), Some(&de), "/")
+                .contains("Japanese (`ja`) (channel override)")
+        );
+        assert!(
+            SlackCommandHandler::format_lang(None, Some(&de), "/")
+                .contains("German (`de`) (global default)")
+        );
+        assert!(
+            SlackCommandHandler::format_lang(None, None, "/")
+                .contains("the language of each request")
+        );
+        assert!(SlackCommandHandler::format_lang(None, None, "!").contains("`!lang default`"));
+    }
+
+    #[test]
+    fn test_format_setup_list() {
+        let listing = |id: &str, status, has_agent, is_member| SetupListing {
+            channel: ChannelId::new(id),
+            name: Some(format!("#{}", id.to_lowercase())),
+            status,
+            has_agent,
+            is_member,
+        };
+        let message = SlackCommandHandler::format_setup_list(&[
+            listing("C1", SetupStatus::FullySetup, true, Some(true)),
+            listing("C2", SetupStatus::FullySetup, false, Some(false)),
+            listing("C3", SetupStatus::PromptOnly, false, None),
+        ]);
+
+        assert!(message.contains("*Setups* (3)"));
+        assert!(message.contains("#c1 (`C1`) - 🟢 agent running"));
+        assert!(message.contains("#c2 (`C2`) - 👻 orphaned"));
+        assert!(message.contains("#c3 (`C3`) - ⚠️ incomplete"));
+        assert!(message.contains("1 orphaned setup(s)"));
+
+        assert!(SlackCommandHandler::format_setup_list(&[]).contains("No channels are set up"));
+    }
+
+    #[test]
+    fn test_format_agent_list() {
+        let agent = |id: &str, idle_secs: Option<u64>| ActiveAgent {
+            channel_id: ChannelId::new(id),
+            session_id: idle_secs.map(|_| format!("session-{}", id)),
+            idle: idle_secs.map(std::time::Duration::from_secs),
+        };
+        let listings = |agents: Vec<ActiveAgent>| -> Vec<AgentListing> {
+            SlackCommandHandler::shown_agents(agents)
+                .into_iter()
+                .map(|agent| AgentListing {
+                    name: Some(format!("#{}", agent.channel_id.as_str().to_lowercase())),
+                    agent,
+                })
+                .collect()
+        };
+        let shown = listings(vec![
+            agent("C1", Some(3900)),
+            agent("C2", None),
+            agent("C3", Some(45)),
+        ]);
+        let message = SlackCommandHandler::format_agent_list(&shown, 3);
+
+        assert!(message.contains("*Agents* (3)"));
+        assert!(message.contains("• #c1 (`C1`) - session `session-C1`, idle 1h 5m"));
+        assert!(message.contains("• #c2 (`C2`) - 🔄 busy with a query"));
+        assert!(message.contains("• #c3 (`C3`) - session `session-C3`, idle 45s"));
+        // Busy first, then by idle time
+        let position = |id: &str| message.find(id).unwrap();
+        assert!(position("`C2`") < position("`C3`") && position("`C3`") < position("`C1`"));
+
+        let many = (0..MAX_AGENT_LISTINGS + 2)
+            .map(|i| agent(&format!("C{}", i), Some(60)))
+            .collect();
+        let shown = listings(many);
+        assert_eq!(shown.len(), MAX_AGENT_LISTINGS);
+        let message = SlackCommandHandler::format_agent_list(&shown, MAX_AGENT_LISTINGS + 2);
+        assert_eq!(message.matches("\n• ").count(), MAX_AGENT_LISTINGS);
+        assert!(message.ends_with("_…and 2 more_"));
+
+        assert!(SlackCommandHandler::format_agent_list(&[], 0).contains("No agents"));
+    }
+
+    #[tokio::test]
+    async fn test_check_repo_skips_non_github_hosts() {
+        let git_hosts = GitHosts::from_config("", "gitlab.com").unwrap();
+
+        // Answered without running `gh`, which can't look up GitLab repositories
+        let message = SlackCommandHandler::check_repo(&git_hosts, "gitlab.com/acme/widgets").await;
+        assert!(message.contains("Can't check `gitlab.com/acme/widgets`"));
+        assert!(message.contains("setup validates access on gitlab.com"));
+
+        let rejected =
+            SlackCommandHandler::check_repo(&git_hosts, "evil.example/acme/widgets").await;
+        assert!(rejected.starts_with("❌"));
+        assert!(rejected.contains("not allowed"));
+    }
+
+    #[test]
+    fn test_format_teardown_prompt() {
+        let prompt = SlackCommandHandler::format_teardown_prompt(2048, "!");
+        assert!(prompt.contains("(2.0 KB)"));
+        assert!(prompt.contains("`!teardown confirm` within 2 minutes"));
+
+        let empty = SlackCommandHandler::format_teardown_prompt(0, "/");
+        assert!(empty.contains("Nothing is stored"));
+    }
 }
@@ -0,0 +1,134 @@
+//! Bounded cache of recently processed event keys
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}h", secs / 3600)
+    }
+}
+
+/// Remembers event keys for `retention`, holding at most `capacity` of them
+///
+/// Slack retries deliveries it thinks failed and may send one message as several event
+/// types, so every key is checked here before processing. Keys are evicted oldest-first
+/// once they age out or the cache is full, keeping memory flat in busy workspaces.
+pub struct EventDedup {
+    retention: Duration,
+    capacity: usize,
+    inner: Mutex<DedupInner>,
+}
+
+#[derive(Default)]
+struct DedupInner {
+    seen: HashMap<String, Instant>,
+    /// Keys in insertion (and therefore age) order
+    order: VecDeque<(String, Instant)>,
+}
+
+impl EventDedup {
+    pub fn new(retention: Duration, capacity: usize) -> Self {
+        Self {
+            retention,
+            capacity: capacity.max(1),
+            inner: Mutex::new(DedupInner::default()),
+        }
+    }
+
+    /// Record `key`. Returns true if it was already seen within the retention window.
+    pub fn check(&self, key: &str) -> bool {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        inner.evict(now, self.retention, self.capacity);
+
+        if let Some(last_seen) = inner.seen.get(key) {
+            tracing::debug!(
+                event_key = %key,
+                last_seen_ago = format_duration(now.duration_since(*last_seen)),
+                "Duplicate event detected, skipping"
+            );
+            return true;
+        }
+
+        // Make room before inserting so the cache never exceeds capacity
+        inner.evict(now, self.retention, self.capacity - 1);
+        inner.seen.insert(key.to_string(), now);
+        inner.order.push_back((key.to_string(), now));
+        tracing::debug!(event_key = %key, "Processing new event");
+        false
+    }
+
+    /// Number of keys currently remembered
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl DedupInner {
+    /// Drop expired keys, then the oldest keys until at most `max_len` remain
+    fn evict(&mut self, now: Instant, retention: Duration, max_len: usize) {
+        while let Some((key, seen_at)) = self.order.front() {
+            let expired = now.duration_since(*seen_at) >= retention;
+            if !expired && self.order.len() <= max_len {
+                break;
+            }
+            self.seen.remove(key);
+            self.order.pop_front();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_duplicates() {
+        let dedup = EventDedup::new(Duration::from_secs(60), 10);
+        assert!(!dedup.check("event:Ev1"));
+        assert!(dedup.check("event:Ev1"));
+        assert!(!dedup.check("event:Ev2"));
+        assert_eq!(dedup.len(), 2);
+    }
+
+    #[test]
+    fn test_evicts_oldest_at_capacity() {
+        let dedup = EventDedup::new(Duration::from_secs(3600), 3);
+        let start = Instant::now();
+
+        for (i, key) in ["a", "b", "c", "d"].iter().enumerate() {
+            assert!(!dedup.check_at(key, start + Duration::from_millis(i as u64)));
+        }
+        assert_eq!(dedup.len(), 3);
+
+        // "a" was evicted to make room for "d", so it's new again
+        let later = start + Duration::from_millis(10);
+        assert!(!dedup.check_at("a", later));
+        assert!(dedup.check_at("d", later));
+        assert_eq!(dedup.len(), 3);
+    }
+
+    #[test]
+    fn test_evicts_after_retention() {
+        let dedup = EventDedup::new(Duration::from_secs(60), 100);
+        let start = Instant::now();
+
+        assert!(!dedup.check_at("a", start));
+        assert!(dedup.check_at("a", start + Duration::from_secs(59)));
+        assert!(!dedup.check_at("a", start + Duration::from_secs(61)));
+    }
+}
@@ -0,0 +1,152 @@
+//! Large ```` ```diff ```` blocks in agent replies, moved out to snippet uploads
+//!
+//! Slack doesn't color diffs inline, and a long one buries the rest of the reply. Diffs
+//! over the threshold are replaced by a short note and uploaded as `.diff` files, which
+//! Slack renders with syntax highlighting.
+
+const FENCE: &str = "```";
+
+/// A diff taken out of a reply to be uploaded as a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DiffAttachment {
+    pub filename: String,
+    /// The diff without its fence lines
+    pub content: String,
+}
+
+/// Replace every `diff` (or `patch`) fenced block whose content exceeds `max_bytes`
+/// with a note naming its attachment. Returns the rewritten text and the diffs to
+/// upload, in order. Smaller diffs and other code blocks are left untouched, as is
+/// everything when `max_bytes` is 0.
+pub(crate) fn extract_large_diffs(text: &str, max_bytes: usize) -> (String, Vec<DiffAttachment>) {
+    if max_bytes == 0 {
+        return (text.to_string(), Vec::new());
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut diffs = Vec::new();
+    // Lines of the diff block being read, starting with its opening fence
+    let mut diff_block: Option<Vec<&str>> = None;
+    let mut in_other_code = false;
+
+    for line in text.split_inclusive('\n') {
+        // Only a bare fence closes a block, so ```diff inside another block is just text
+        let is_closing_fence = line.trim() == FENCE;
+
+        if let Some(block) = diff_block.as_mut() {
+            block.push(line);
+            if is_closing_fence {
+                let block = diff_block.take().unwrap_or_default();
+                let content = block[1..block.len() - 1].concat();
+                if content.len() > max_bytes {
+                    let attachment = DiffAttachment {
+                        filename: format!("changes-{}.diff", diffs.len() + 1),
+                        content,
+                    };
+                    result.push_str(&diff_note(&attachment));
+                    // Keep the line break the closing fence had
+                    if line.ends_with('\n') {
+                        result.push('\n');
+                    }
+                    diffs.push(attachment);
+                } else {
+                    result.push_str(&block.concat());
+                }
+            }
+            continue;
+        }
+
+        if in_other_code {
+            in_other_code = !is_closing_fence;
+        } else if line.trim_start().starts_with(FENCE) && line.matches(FENCE).count() % 2 == 1 {
+            if is_diff_fence(line) {
+                diff_block = Some(vec![line]);
+                continue;
+            }
+            in_other_code = true;
+        }
+        result.push_str(line);
+    }
+
+    // An unterminated diff is left inline, as written
+    if let Some(block) = diff_block {
+        result.push_str(&block.concat());
+    }
+
+    (result, diffs)
+}
+
+/// Whether an opening fence starts a diff (```` ```diff ```` or ```` ```patch ````)
+fn is_diff_fence(line: &str) -> bool {
+    let lang = line.trim().trim_start_matches(FENCE).trim();
+    lang.eq_ignore_ascii_case("diff") || lang.eq_ignore_ascii_case("patch")
+}
+
+/// What's shown in place of an extracted diff
+fn diff_note(attachment: &DiffAttachment) -> String {
+    format!(
+        "📎 _Diff ({} lines) attached as `{}`_",
+        attachment.content.lines().count(),
+        attachment.filename
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL_DIFF: &str = "```diff\n-let a = 1;\n+let a = 2;\n```\n";
+
+    fn large_diff() -> String {
+        format!("```diff\n{}```\n", "+let a = 1;\n".repeat(50))
+    }
+
+    #[test]
+    fn test_small_diff_stays_inline() {
+        let text = format!("Here's the change:\n{}Done.", SMALL_DIFF);
+        let (rewritten, diffs) = extract_large_diffs(&text, 100);
+        assert_eq!(rewritten, text);
+        assert!(diffs.is_empty());
+    }
+
+    #[test]
+    fn test_large_diff_is_extracted() {
+        let text = format!("Here's the change:\n{}{}Done.", large_diff(), SMALL_DIFF);
+        let (rewritten, diffs) = extract_large_diffs(&text, 100);
+
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].filename, "changes-1.diff");
+        assert_eq!(diffs[0].content, "+let a = 1;\n".repeat(50));
+        assert_eq!(
+            rewritten,
+            format!(
+                "Here's the change:\n📎 _Diff (50 lines) attached as `changes-1.diff`_\n{}Done.",
+                SMALL_DIFF
+            )
+        );
+    }
+
+    #[test]
+    fn test_other_code_blocks_are_left_alone() {
+        // A diff fence inside another code block is just text
+        let text = format!("```markdown\n{}```\n", large_diff());
+        let (rewritten, diffs) = extract_large_diffs(&text, 100);
+        assert!(diffs.is_empty());
+        assert_eq!(rewritten, text);
+
+        let text = format!("```rust\n{}```\n", "let a = 1;\n".repeat(50));
+        assert_eq!(extract_large_diffs(&text, 100).0, text);
+    }
+
+    #[test]
+    fn test_disabled_or_unterminated() {
+        let text = large_diff();
+        assert_eq!(extract_large_diffs(&text, 0), (text.clone(), Vec::new()));
+
+        let unterminated = "```patch\n+a\n+b\n";
+        assert_eq!(
+            extract_large_diffs(unterminated, 1),
+            (unterminated.to_string(), Vec::new())
+        );
+    }
+}
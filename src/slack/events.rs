@@ -1,39 +1,273 @@
 use crate::agent::AgentManager;
-use crate::error::Result;
+use crate::error::{Result, SlackCoderError};
 use crate::metadata::MetadataCache;
+use crate::slack::dedup::EventDedup;
 use crate::slack::{
-    ChannelId, FormHandler, MessageProcessor, MessageTs, SlackClient, SlackMessage, ThreadTs,
-    UserId,
+    ChannelId, FormHandler, MessageProcessor, MessageTs, SharedFile, SlackClient, SlackMessage,
+    ThreadTs, UserId, parse_command_text,
 };
-use dashmap::DashMap;
+use crate::storage::{FeedbackRecord, Rating};
+use crate::telemetry;
 use slack_morphism::prelude::*;
 use std::sync::Arc;
-use std::time::{Duration, Instant};
-
-fn format_duration(d: Duration) -> String {
-    let secs = d.as_secs();
-    if secs < 60 {
-        format!("{}s", secs)
-    } else if secs < 3600 {
-        format!("{}m", secs / 60)
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+
+/// Slack error codes meaning the bot's tokens no longer work at all (revoked, expired or
+/// the app uninstalled), so reconnecting can never succeed
+const REVOKED_TOKEN_CODES: &[&str] = &[
+    "not_authed",
+    "invalid_auth",
+    "account_inactive",
+    "token_revoked",
+    "token_expired",
+];
+
+/// What the listener does after a Slack error
+#[derive(Debug, PartialEq, Eq)]
+enum ListenerErrorAction {
+    /// A one-off failure; keep serving
+    Continue,
+    /// The credentials are gone; stop, with the reason
+    Shutdown(String),
+}
+
+/// Decide whether a listener error is fatal. Only errors naming a dead token are;
+/// missing scopes and the like affect single calls and are survivable.
+fn listener_error_action(err: &(dyn std::error::Error + Send + Sync)) -> ListenerErrorAction {
+    let text = err.to_string();
+    match REVOKED_TOKEN_CODES.iter().find(|code| text.contains(*code)) {
+        Some(code) => {
+            ListenerErrorAction::Shutdown(format!("Slack rejected the bot's token ({code})"))
+        }
+        None => ListenerErrorAction::Continue,
+    }
+}
+
+/// Slack sends `hello` on every new Socket Mode connection. The listener opens
+/// `initial` connections at startup, so any beyond those are reconnects.
+fn is_reconnect(opened: u64, initial: u32) -> bool {
+    opened > u64::from(initial)
+}
+
+/// Whether the Socket Mode listener is connected, shared with the health endpoint
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionState(Arc<AtomicBool>);
+
+impl ConnectionState {
+    pub fn is_connected(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn set_connected(&self, connected: bool) {
+        self.0.store(connected, Ordering::Relaxed);
+    }
+}
+
+/// The downloadable files attached to a message
+fn shared_files(content: &SlackMessageContent) -> Vec<SharedFile> {
+    content
+        .files
+        .iter()
+        .flatten()
+        .filter_map(|file| {
+            Some(SharedFile {
+                name: file.name.clone().unwrap_or_default(),
+                url: file.url_private.as_ref()?.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Remove mentions of the bot from message text, keeping mentions of anyone else
+/// (e.g. "assign this to <@U123>").
+///
+/// Slack encodes mentions as `<@U123>` or `<@U123|name>`. If the bot's id couldn't be
+/// looked up, only mentions at the start of the text - where the bot is addressed - are
+/// removed.
+fn strip_bot_mention(text: &str, bot_user_id: Option<&UserId>) -> String {
+    let is_mention = |word: &str| word.starts_with("<@") && word.ends_with('>');
+
+    let Some(bot_user_id) = bot_user_id else {
+        let mut rest = text.trim_start();
+        while let Some(word) = rest.split_whitespace().next()
+            && is_mention(word)
+        {
+            rest = rest[word.len()..].trim_start();
+        }
+        return rest.trim().to_string();
+    };
+
+    let mut cleaned = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<@") {
+        let Some(len) = rest[start..].find('>').map(|end| end + 1) else {
+            break;
+        };
+        let mention = &rest[start + 2..start + len - 1];
+        let mentioned = mention.split('|').next().unwrap_or(mention);
+
+        cleaned.push_str(&rest[..start]);
+        if mentioned != bot_user_id.as_str() {
+            cleaned.push_str(&rest[start..start + len]);
+        }
+        rest = &rest[start + len..];
+    }
+    cleaned.push_str(rest);
+
+    cleaned.trim().to_string()
+}
+
+/// The channel an event says the bot has lost, if any: the bot was removed from it, or
+/// it was archived or deleted.
+///
+/// Other members leaving is ignored. If the bot's own id is unknown, leave events can't
+/// be attributed and only archive/delete count.
+fn departed_channel<'a>(
+    event: &'a SlackEventCallbackBody,
+    bot_user_id: Option<&UserId>,
+) -> Option<&'a str> {
+    match event {
+        SlackEventCallbackBody::MemberLeftChannel(left) => {
+            let bot_user_id = bot_user_id?;
+            (left.user.0 == bot_user_id.as_str()).then_some(left.channel.as_ref())
+        }
+        SlackEventCallbackBody::ChannelArchive(archived) => Some(archived.channel.as_ref()),
+        SlackEventCallbackBody::ChannelDeleted(deleted) => Some(deleted.channel.as_ref()),
+        _ => None,
+    }
+}
+
+/// Where a request goes once the bot's mention is stripped
+#[derive(Debug, PartialEq, Eq)]
+enum TextRoute {
+    Command,
+    /// A single `owner/repo`-like word
+    RepoSetup,
+    Message,
+}
+
+/// Route text starting with the configured command prefix to command handling and a
+/// lone `owner/repo` word to repository setup. Anything else - including `/help` when
+/// the prefix isn't `/` - is an ordinary message for the agent.
+fn text_route(text: &str, prefix: &str) -> TextRoute {
+    if parse_command_text(text, prefix).is_some() {
+        TextRoute::Command
+    } else if text.contains('/') && !text.starts_with('/') && text.split_whitespace().count() == 1 {
+        TextRoute::RepoSetup
     } else {
-        format!("{}h", secs / 3600)
+        TextRoute::Message
     }
 }
 
+/// A message sent to the bot in a direct message, which needs no mention
+#[derive(Debug, PartialEq, Eq)]
+struct DirectMessageInput<'a> {
+    user: &'a str,
+    text: &'a str,
+}
+
+/// The user's input if `message` is a plain message (or a file upload, with any
+/// comment as the text) in a DM; other subtypes such as joins and edits aren't input.
+fn direct_message_input<'a>(
+    message: &'a SlackMessageEvent,
+    channel_id: &ChannelId,
+) -> Option<DirectMessageInput<'a>> {
+    if !channel_id.is_direct_message()
+        || !matches!(
+            message.subtype,
+            None | Some(SlackMessageEventType::FileShare)
+        )
+    {
+        return None;
+    }
+    let user = message.sender.user.as_ref()?;
+    let text = message.content.as_ref()?.text.as_deref()?;
+    Some(DirectMessageInput {
+        user: user.as_ref(),
+        text,
+    })
+}
+
+/// A user's change to the text of one of their messages
+#[derive(Debug, PartialEq, Eq)]
+struct MessageEdit<'a> {
+    /// The edited message
+    ts: &'a str,
+    user: &'a str,
+    text: &'a str,
+}
+
+/// The edit a `message_changed` event describes, if a user changed what a message says.
+///
+/// Edits by bots - including this bot updating its own replies - are ignored so a reply
+/// can never trigger a re-run, as are changes that keep the text (such as Slack
+/// attaching link previews).
+fn user_edit<'a>(
+    message: &'a SlackMessageEvent,
+    bot_user_id: Option<&UserId>,
+) -> Option<MessageEdit<'a>> {
+    if message.subtype != Some(SlackMessageEventType::MessageChanged) {
+        return None;
+    }
+    let edited = message.message.as_ref()?;
+    if edited.sender.bot_id.is_some() {
+        return None;
+    }
+    let user = edited.sender.user.as_ref()?;
+    if bot_user_id.is_some_and(|bot| bot.as_str() == user.as_ref()) {
+        return None;
+    }
+
+    let text = edited.content.as_ref()?.text.as_deref()?;
+    let previous = message
+        .previous_message
+        .as_ref()
+        .and_then(|previous| previous.content.as_ref())
+        .and_then(|content| content.text.as_deref());
+    if previous == Some(text) {
+        return None;
+    }
+
+    Some(MessageEdit {
+        ts: edited.ts.as_ref(),
+        user: user.as_ref(),
+        text,
+    })
+}
+
 #[derive(Clone)]
 struct BotState {
     message_processor: Arc<MessageProcessor>,
     form_handler: Arc<FormHandler>,
     slack_client: Arc<SlackClient>,
     metadata_cache: Arc<MetadataCache>,
-    processed_events: Arc<DashMap<String, Instant>>,
+    processed_events: Arc<EventDedup>,
+    /// The bot's own user id, for stripping its mentions and spotting reactions on its
+    /// messages (`None` if the lookup failed: feedback capture is disabled)
+    bot_user_id: Option<UserId>,
+    /// Set once the bot's credentials stop working, which stops the listener
+    auth_failure: Arc<watch::Sender<Option<String>>>,
+    /// Socket Mode connections opened so far, to tell reconnects from the first ones
+    connections_opened: Arc<AtomicU64>,
+    initial_connections: u32,
+}
+
+impl BotState {
+    /// Stop the bot: its tokens are revoked or the app was uninstalled, so every Slack
+    /// call (and reconnect) would fail from now on
+    fn fail_auth(&self, reason: String) {
+        tracing::error!(reason = %reason, "Slack credentials no longer valid, shutting down");
+        self.auth_failure.send_replace(Some(reason));
+    }
 }
 
 pub struct EventHandler {
     slack_client: Arc<SlackClient>,
     agent_manager: Arc<AgentManager>,
     metadata_cache: Arc<MetadataCache>,
+    connection: ConnectionState,
 }
 
 impl EventHandler {
@@ -46,15 +280,26 @@ impl EventHandler {
             slack_client,
             agent_manager,
             metadata_cache,
+            connection: ConnectionState::default(),
         }
     }
 
+    /// Handle to the listener's connection state, for health checks
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connection.clone()
+    }
+
     /// Start listening for Slack events using Socket Mode
     pub async fn start(self) -> Result<()> {
         tracing::info!("Initializing event handler components");
 
         // Create SHARED processed_events cache (same instance across all event callbacks)
-        let processed_events = Arc::new(DashMap::new());
+        let settings = self.agent_manager.settings();
+        let slack_config = &settings.slack;
+        let processed_events = Arc::new(EventDedup::new(
+            Duration::from_secs(slack_config.event_dedup_retention_secs),
+            slack_config.event_dedup_max_entries,
+        ));
         tracing::debug!("Created event deduplication cache");
 
         // Create state with our components
@@ -66,14 +311,29 @@ impl EventHandler {
         let form_handler = Arc::new(FormHandler::new(
             self.slack_client.clone(),
             self.agent_manager.clone(),
+            self.metadata_cache.clone(),
         ));
 
+        let bot_user_id = match self.slack_client.bot_user_id().await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to look up bot user; feedback reactions disabled, only leading mentions stripped");
+                None
+            }
+        };
+
+        let socket_mode_config = SlackClientSocketModeConfig::new();
+        let (auth_failure, mut auth_failed) = watch::channel(None);
         let bot_state = BotState {
             message_processor,
             form_handler,
             slack_client: self.slack_client.clone(),
             metadata_cache: self.metadata_cache.clone(),
             processed_events,
+            bot_user_id,
+            auth_failure: Arc::new(auth_failure),
+            connections_opened: Arc::new(AtomicU64::new(0)),
+            initial_connections: socket_mode_config.max_connections_count,
         };
 
         tracing::debug!("Creating listener environment");
@@ -84,12 +344,14 @@ impl EventHandler {
         );
 
         tracing::debug!("Configuring Socket Mode callbacks");
-        let callbacks =
-            SlackSocketModeListenerCallbacks::new().with_push_events(Self::handle_push_event);
+        let callbacks = SlackSocketModeListenerCallbacks::new()
+            .with_hello_events(Self::handle_hello_event)
+            .with_push_events(Self::handle_push_event)
+            .with_interaction_events(Self::handle_interaction_event);
 
         tracing::debug!("Creating Socket Mode listener");
         let socket_mode_listener = SlackClientSocketModeListener::new(
-            &SlackClientSocketModeConfig::new(),
+            &socket_mode_config,
             listener_environment,
             callbacks,
         );
@@ -98,17 +360,48 @@ impl EventHandler {
         let app_token = self.slack_client.get_app_token();
         tracing::info!("Connecting to Slack via Socket Mode");
 
-        socket_mode_listener
-            .listen_for(&app_token)
-            .await
-            .map_err(|e| crate::error::SlackCoderError::SlackApi(e.to_string()))?;
+        socket_mode_listener.listen_for(&app_token).await?;
+        self.connection.set_connected(true);
 
         tracing::info!("Connected to Slack Socket Mode");
         tracing::info!("Bot is ready to receive messages");
 
-        socket_mode_listener.serve().await;
+        // The listener reconnects on its own; serve() only returns once it has stopped.
+        // Reconnecting with a revoked token would spin forever, so that stops it instead.
+        let auth_failure = async {
+            match auth_failed.wait_for(Option::is_some).await {
+                Ok(reason) => reason.clone().unwrap_or_default(),
+                Err(_) => std::future::pending().await,
+            }
+        };
+        let result = tokio::select! {
+            _ = socket_mode_listener.serve() => Ok(()),
+            reason = auth_failure => {
+                socket_mode_listener.shutdown().await;
+                Err(SlackCoderError::SlackAuth(reason))
+            }
+        };
+        self.connection.set_connected(false);
 
-        Ok(())
+        result
+    }
+
+    /// Count reconnects: the listener reconnects on its own, so `hello` is the only sign
+    async fn handle_hello_event(
+        _event: SlackSocketModeHelloEvent,
+        _client: Arc<SlackHyperClient>,
+        user_state: SlackClientEventsUserState,
+    ) {
+        let storage = user_state.read().await;
+        let Some(state) = storage.get_user_state::<BotState>() else {
+            return;
+        };
+
+        let opened = state.connections_opened.fetch_add(1, Ordering::Relaxed) + 1;
+        if is_reconnect(opened, state.initial_connections) {
+            tracing::info!(connections = opened, "Reconnected to Slack Socket Mode");
+            telemetry::metrics().record_reconnect();
+        }
     }
 
     async fn handle_push_event(
@@ -120,6 +413,10 @@ impl EventHandler {
         let event_type = match &event.event {
             SlackEventCallbackBody::AppMention(_) => "app_mention",
             SlackEventCallbackBody::Message(_) => "message",
+            SlackEventCallbackBody::ReactionAdded(_) => "reaction_added",
+            SlackEventCallbackBody::MemberLeftChannel(_) => "member_left_channel",
+            SlackEventCallbackBody::ChannelArchive(_) => "channel_archive",
+            SlackEventCallbackBody::ChannelDeleted(_) => "channel_deleted",
             _ => "other",
         };
         tracing::debug!(event_type = event_type, "Received push event");
@@ -133,8 +430,10 @@ impl EventHandler {
                 .clone()
         };
 
-        // Cleanup old events (older than 1 hour) to prevent memory growth
-        Self::cleanup_old_events(&state.processed_events);
+        // Slack retries deliveries it didn't see acknowledged; the event_id is stable across them
+        if Self::is_duplicate_key(&state, format!("event:{}", event.event_id)) {
+            return Ok(());
+        }
 
         // Spawn processing as background task and return immediately
         // This ensures we acknowledge within 3 seconds (Slack's timeout)
@@ -148,30 +447,124 @@ impl EventHandler {
         Ok(())
     }
 
+    async fn handle_interaction_event(
+        event: SlackInteractionEvent,
+        _client: Arc<SlackHyperClient>,
+        user_state: SlackClientEventsUserState,
+    ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let state: BotState = {
+            let storage = user_state.read().await;
+            storage
+                .get_user_state::<BotState>()
+                .expect("BotState should be set")
+                .clone()
+        };
+
+        // Same as push events: acknowledge right away, do the work in the background
+        tokio::spawn(async move {
+            if let Err(e) = Self::process_interaction(event, state).await {
+                tracing::error!(error = %e, "Interaction processing failed");
+            }
+        });
+
+        Ok(())
+    }
+
+    async fn process_interaction(event: SlackInteractionEvent, state: BotState) -> Result<()> {
+        match event {
+            SlackInteractionEvent::BlockActions(block_actions) => {
+                // Slack retries unacknowledged interactions with the same trigger id
+                let event_key = format!("interaction:{}", block_actions.trigger_id);
+                if Self::is_duplicate_key(&state, event_key) {
+                    return Ok(());
+                }
+
+                let Some(channel_id) = block_actions
+                    .channel
+                    .as_ref()
+                    .and_then(|c| Self::parse_channel_id(c.id.as_ref()))
+                else {
+                    tracing::debug!("Ignoring block action without a channel");
+                    return Ok(());
+                };
+                let Some(user) = block_actions.user.as_ref() else {
+                    tracing::debug!("Ignoring block action without a user");
+                    return Ok(());
+                };
+                let user_id = UserId::new(user.id.to_string());
+
+                for action in block_actions.actions.iter().flatten() {
+                    state
+                        .form_handler
+                        .handle_block_action(
+                            channel_id.clone(),
+                            user_id.clone(),
+                            action.action_id.as_ref(),
+                        )
+                        .await?;
+                }
+            }
+            SlackInteractionEvent::ViewSubmission(submission) => {
+                if let Some(trigger_id) = submission.trigger_id.as_ref()
+                    && Self::is_duplicate_key(&state, format!("interaction:{}", trigger_id))
+                {
+                    return Ok(());
+                }
+
+                let callback_id = match &submission.view.view {
+                    SlackView::Modal(modal) => modal.callback_id.as_ref().map(|c| c.to_string()),
+                    _ => None,
+                };
+
+                state
+                    .form_handler
+                    .handle_view_submission(
+                        UserId::new(submission.user.id.to_string()),
+                        callback_id.as_deref(),
+                    )
+                    .await?;
+            }
+            _ => {
+                tracing::debug!("Unhandled interaction type");
+            }
+        }
+
+        Ok(())
+    }
+
     async fn process_event(
         event: SlackPushEventCallback,
         state: BotState,
     ) -> std::result::Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        // The bot can no longer use channels it left (or that went away), so stop their agents
+        if let Some(channel) = departed_channel(&event.event, state.bot_user_id.as_ref()) {
+            if let Some(channel_id) = Self::parse_channel_id(channel) {
+                state
+                    .form_handler
+                    .agent_manager
+                    .release_channel(&channel_id)
+                    .await;
+            }
+            return Ok(());
+        }
+
         match event.event {
+            SlackEventCallbackBody::AppUninstalled(_) => {
+                state.fail_auth("The app was uninstalled from the workspace".to_string());
+            }
+            SlackEventCallbackBody::TokensRevoked(_) => {
+                state.fail_auth("The bot's tokens were revoked".to_string());
+            }
             SlackEventCallbackBody::AppMention(mention) => {
+                let Some(channel_id) = Self::parse_channel_id(mention.channel.as_ref()) else {
+                    return Ok(());
+                };
+
                 // Deduplicate events using timestamp
                 // Use message ts as unique key - same message should never be processed twice
-                let event_key = format!("mention:{}:{}", mention.channel, mention.origin.ts);
-                if let Some(last_seen) = state.processed_events.get(&event_key) {
-                    // Event was already processed - skip regardless of how long ago
-                    tracing::debug!(
-                        event_key = %event_key,
-                        last_seen_ago = format_duration(last_seen.elapsed()),
-                        "Duplicate event detected, skipping"
-                    );
+                if Self::is_duplicate(&state, channel_id.as_str(), mention.origin.ts.as_ref()) {
                     return Ok(());
                 }
-                state
-                    .processed_events
-                    .insert(event_key.clone(), Instant::now());
-                tracing::debug!(event_key = %event_key, "Processing new event");
-
-                let channel_id = ChannelId::new(mention.channel.to_string());
 
                 let text = mention.content.text.clone().unwrap_or_default();
                 let user_id = UserId::new(mention.user.to_string());
@@ -192,23 +585,16 @@ impl EventHandler {
                 );
                 let _guard = span.enter();
 
-                // Show first 150 chars of message for context
-                let message_preview = if text.len() > 150 {
-                    format!("{}...", text.chars().take(150).collect::<String>())
-                } else {
-                    text.clone()
-                };
-
+                // The text isn't logged: it hasn't been through the input filters yet,
+                // and a blocked secret must not reach the logs
                 tracing::info!(
                     channel_id = %ctx.channel_id,
                     channel = %ctx.channel_display,
                     user_id = %ctx.user_id,
                     user = %ctx.user_display,
-                    message = %message_preview,
-                    "App mentioned in {} by {}: \"{}\"",
+                    "App mentioned in {} by {}",
                     ctx.channel_display,
-                    ctx.user_display,
-                    message_preview
+                    ctx.user_display
                 );
 
                 // Log selective fields instead of full debug dump
@@ -224,70 +610,9 @@ impl EventHandler {
                     .thread_ts
                     .map(|t| ThreadTs::new(t.to_string()));
 
-                // Strip bot mention from text
-                let clean_text = text
-                    .split_whitespace()
-                    .filter(|w| !w.starts_with("<@"))
-                    .collect::<Vec<_>>()
-                    .join(" ")
-                    .trim()
-                    .to_string();
-
-                tracing::debug!(
-                    original_len = text.len(),
-                    cleaned_len = clean_text.len(),
-                    "Cleaned mention text"
-                );
-
-                // Check if this is a command (starts with /)
-                if clean_text.starts_with('/') {
-                    tracing::info!(command = %clean_text, "Processing command");
-                    // Forward to message processor for command handling
-                    let slack_message = SlackMessage {
-                        channel: channel_id.clone(),
-                        user: user_id.clone(),
-                        text: clean_text.clone(),
-                        thread_ts: thread_ts.clone(),
-                        ts: ts.clone(),
-                    };
-
-                    if let Err(e) = state.message_processor.process_message(slack_message).await {
-                        tracing::error!(error = %e, "Command processing failed");
-                    }
-                }
-                // Check if this looks like a repository name (owner/repo pattern)
-                else if clean_text.contains('/') && clean_text.split_whitespace().count() == 1 {
-                    tracing::info!(repo = %clean_text, "Processing setup request");
-                    if let Err(e) = state
-                        .form_handler
-                        .handle_repo_setup(channel_id.clone(), clean_text.clone())
-                        .await
-                    {
-                        tracing::error!(error = %e, repo = %clean_text, "Setup failed");
-                        let _ = state
-                            .slack_client
-                            .send_message(
-                                &channel_id,
-                                &format!("Setup failed: {}", e),
-                                thread_ts.as_ref(),
-                            )
-                            .await;
-                    }
-                } else {
-                    tracing::info!("Processing regular message");
-                    // Regular message - process it
-                    let slack_message = SlackMessage {
-                        channel: channel_id,
-                        user: user_id,
-                        text: clean_text,
-                        thread_ts,
-                        ts,
-                    };
+                let files = shared_files(&mention.content);
 
-                    if let Err(e) = state.message_processor.process_message(slack_message).await {
-                        tracing::error!(error = %e, "Message processing failed");
-                    }
-                }
+                Self::route_text(&state, channel_id, user_id, &text, files, thread_ts, ts).await;
             }
             SlackEventCallbackBody::Message(message) => {
                 let channel = message.origin.channel.as_ref().map(|c| c.to_string());
@@ -308,27 +633,89 @@ impl EventHandler {
                     return Ok(());
                 }
 
-                // Ignore message updates/edits
+                // Edits only matter when they change a request the agent answered
                 if message.subtype == Some(SlackMessageEventType::MessageChanged) {
-                    tracing::debug!("Ignoring message edit");
+                    Self::rerun_edited(&state, &message).await;
                     return Ok(());
                 }
 
+                let Some(channel_id) = message
+                    .origin
+                    .channel
+                    .as_ref()
+                    .and_then(|c| Self::parse_channel_id(c.as_ref()))
+                else {
+                    tracing::debug!("Ignoring message without a valid channel");
+                    return Ok(());
+                };
+
+                // Direct messages don't need a mention - treat the whole text as input
+                if let Some(DirectMessageInput { user, text }) =
+                    direct_message_input(&message, &channel_id)
+                {
+                    if Self::is_duplicate(&state, channel_id.as_str(), message.origin.ts.as_ref()) {
+                        return Ok(());
+                    }
+
+                    let ctx = state
+                        .metadata_cache
+                        .log_context(channel_id.as_str(), user)
+                        .await;
+
+                    let span = tracing::info_span!(
+                        "direct_message",
+                        channel_id = %ctx.channel_id,
+                        user_id = %ctx.user_id,
+                        user = %ctx.user_name,
+                        ts = %message.origin.ts
+                    );
+                    let _guard = span.enter();
+
+                    tracing::info!(
+                        channel_id = %ctx.channel_id,
+                        user_id = %ctx.user_id,
+                        user = %ctx.user_display,
+                        text_len = text.len(),
+                        "Direct message from {}",
+                        ctx.user_display
+                    );
+
+                    let ts = MessageTs::new(message.origin.ts.to_string());
+                    let thread_ts = message
+                        .origin
+                        .thread_ts
+                        .as_ref()
+                        .map(|t| ThreadTs::new(t.to_string()));
+                    let files = message
+                        .content
+                        .as_ref()
+                        .map(shared_files)
+                        .unwrap_or_default();
+
+                    Self::route_text(
+                        &state,
+                        channel_id,
+                        UserId::new(user),
+                        text,
+                        files,
+                        thread_ts,
+                        ts,
+                    )
+                    .await;
+                }
                 // Check if this is a channel_join event (bot was invited)
-                if message.subtype == Some(SlackMessageEventType::ChannelJoin) {
-                    if let Some(channel_id) = message.origin.channel {
-                        let channel = ChannelId::new(channel_id.to_string());
-                        tracing::info!(channel = %channel.as_str(), "Bot joined channel");
-
-                        // Check if already setup
-                        if state.form_handler.agent_manager.has_agent(&channel) {
-                            tracing::info!("Channel already configured");
-                        } else {
-                            tracing::info!("Showing setup instructions");
-                            if let Err(e) = state.form_handler.show_repo_setup_form(&channel).await
-                            {
-                                tracing::error!(error = %e, "Failed to show setup form");
-                            }
+                else if message.subtype == Some(SlackMessageEventType::ChannelJoin) {
+                    tracing::info!(channel = %channel_id, "Bot joined channel");
+
+                    // Check if already setup
+                    if state.form_handler.agent_manager.has_agent(&channel_id) {
+                        tracing::info!("Channel already configured");
+                    } else if !Self::channel_allowed(&state, &channel_id).await {
+                        Self::notify_disallowed(&state, &channel_id, None).await;
+                    } else {
+                        tracing::info!("Showing setup instructions");
+                        if let Err(e) = state.form_handler.show_repo_setup_form(&channel_id).await {
+                            tracing::error!(error = %e, "Failed to show setup form");
                         }
                     }
                 } else {
@@ -339,6 +726,9 @@ impl EventHandler {
                     );
                 }
             }
+            SlackEventCallbackBody::ReactionAdded(reaction) => {
+                Self::record_feedback(reaction, &state).await?;
+            }
             _ => {
                 tracing::debug!("Unhandled event type");
             }
@@ -347,34 +737,551 @@ impl EventHandler {
         Ok(())
     }
 
+    /// Re-run an answered request after its author edits it (with `RERUN_ON_EDIT`)
+    async fn rerun_edited(state: &BotState, message: &SlackMessageEvent) {
+        if !state
+            .form_handler
+            .agent_manager
+            .settings()
+            .display
+            .rerun_on_edit
+        {
+            tracing::debug!("Ignoring message edit");
+            return;
+        }
+
+        let Some(channel_id) = message
+            .origin
+            .channel
+            .as_ref()
+            .and_then(|c| Self::parse_channel_id(c.as_ref()))
+        else {
+            return;
+        };
+        let Some(edit) = user_edit(message, state.bot_user_id.as_ref()) else {
+            tracing::debug!("Ignoring edit that doesn't change a user's text");
+            return;
+        };
+
+        let answers = state.form_handler.agent_manager.answered_requests();
+        if answers.is_response(&channel_id, edit.ts) {
+            tracing::debug!("Ignoring edit of the bot's own reply");
+            return;
+        }
+        let Some(answer) = answers.find(&channel_id, edit.ts) else {
+            tracing::debug!("Ignoring edit of a message that wasn't answered");
+            return;
+        };
+        if !Self::channel_allowed(state, &channel_id).await {
+            return;
+        }
+
+        // Every edit gets its own event ts, so only a redelivered edit is skipped
+        if Self::is_duplicate_key(
+            state,
+            format!("edit:{}:{}", channel_id.as_str(), message.origin.ts),
+        ) {
+            return;
+        }
+
+        tracing::info!(
+            channel_id = %channel_id,
+            ts = edit.ts,
+            "Re-running edited request"
+        );
+        let slack_message = SlackMessage {
+            channel: channel_id,
+            user: UserId::new(edit.user),
+            text: strip_bot_mention(edit.text, state.bot_user_id.as_ref()),
+            thread_ts: answer.thread_ts,
+            ts: answer.request_ts,
+            files: Vec::new(),
+        };
+        if let Err(e) = state
+            .message_processor
+            .process_edit(slack_message, answer.response_ts)
+            .await
+        {
+            tracing::error!(error = %e, "Edited request processing failed");
+        }
+    }
+
+    /// Whether the access lists allow setting up and answering in a channel
+    async fn channel_allowed(state: &BotState, channel_id: &ChannelId) -> bool {
+        let channel_name = async {
+            state
+                .metadata_cache
+                .get_channel_info(channel_id.as_str())
+                .await
+                .map(|info| info.name)
+        };
+        state
+            .form_handler
+            .agent_manager
+            .settings()
+            .access
+            .allows(channel_id.as_str(), channel_name)
+            .await
+    }
+
+    /// Tell a disallowed channel the bot won't work there (unless notices are disabled)
+    async fn notify_disallowed(
+        state: &BotState,
+        channel_id: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+    ) {
+        tracing::info!(
+            "Channel not allowed by access lists {}",
+            channel_id.log_format()
+        );
+        if !state
+            .form_handler
+            .agent_manager
+            .settings()
+            .access
+            .notify_disallowed
+        {
+            return;
+        }
+        if let Err(e) = state
+            .slack_client
+            .send_message(
+                channel_id,
+                "🚫 I'm not enabled in this channel, so I won't set it up or answer here. Ask an admin if you need me.",
+                thread_ts,
+            )
+            .await
+        {
+            tracing::warn!(error = %e, "Failed to send not-enabled notice");
+        }
+    }
+
+    /// Record a 👍/👎 reaction on one of the bot's messages as response feedback
+    async fn record_feedback(reaction: SlackReactionAddedEvent, state: &BotState) -> Result<()> {
+        let Some(rating) = Rating::from_reaction(&reaction.reaction.0) else {
+            return Ok(());
+        };
+
+        // Only the bot's own messages are responses worth rating
+        let Some(bot_user_id) = &state.bot_user_id else {
+            return Ok(());
+        };
+        if reaction.item_user.as_ref().map(|u| u.0.as_str()) != Some(bot_user_id.as_str()) {
+            return Ok(());
+        }
+
+        let SlackReactionsItem::Message(message) = reaction.item else {
+            return Ok(());
+        };
+        let Some(channel_id) = message
+            .origin
+            .channel
+            .as_ref()
+            .and_then(|c| Self::parse_channel_id(c.as_ref()))
+        else {
+            return Ok(());
+        };
+        let message_ts = message.origin.ts.to_string();
+
+        if Self::is_duplicate_key(
+            state,
+            format!(
+                "reaction:{}:{}:{}:{}",
+                channel_id, message_ts, reaction.user, reaction.reaction.0
+            ),
+        ) {
+            return Ok(());
+        }
+
+        // The session the rated reply ran in, not whichever one the channel is on now
+        let agent_manager = &state.form_handler.agent_manager;
+        let session_id = agent_manager
+            .answered_requests()
+            .find_response(&channel_id, &message_ts)
+            .and_then(|answer| answer.session_id);
+
+        let record = FeedbackRecord {
+            channel: channel_id.to_string(),
+            session_id,
+            message_ts,
+            user: reaction.user.to_string(),
+            rating,
+            recorded_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+        };
+        agent_manager.workspace().append_feedback(&record).await?;
+
+        tracing::info!(
+            rating = ?rating,
+            message_ts = %record.message_ts,
+            user_id = %record.user,
+            "Recorded response feedback {}",
+            channel_id.log_format()
+        );
+        Ok(())
+    }
+
+    /// Validate an inbound channel id, logging and dropping malformed ones
+    fn parse_channel_id(raw: &str) -> Option<ChannelId> {
+        match ChannelId::parse(raw) {
+            Ok(channel_id) => Some(channel_id),
+            Err(e) => {
+                tracing::warn!(error = %e, "Ignoring event with malformed channel id");
+                None
+            }
+        }
+    }
+
+    /// Record an event by channel and message ts. Returns true if it was already seen.
+    ///
+    /// Retries are caught earlier by `event_id`, but a mention in a DM arrives as two
+    /// separate events (`app_mention` and `message.im`) with different ids. The key is
+    /// shared by both so the same message is never processed twice.
+    fn is_duplicate(state: &BotState, channel: &str, ts: &str) -> bool {
+        Self::is_duplicate_key(state, format!("message:{}:{}", channel, ts))
+    }
+
+    /// Record an event by key. Returns true if it was already seen.
+    fn is_duplicate_key(state: &BotState, event_key: String) -> bool {
+        state.processed_events.check(&event_key)
+    }
+
+    /// Route user text to command handling, repository setup, or the agent
+    async fn route_text(
+        state: &BotState,
+        channel_id: ChannelId,
+        user_id: UserId,
+        text: &str,
+        files: Vec<SharedFile>,
+        thread_ts: Option<ThreadTs>,
+        ts: MessageTs,
+    ) {
+        if !Self::channel_allowed(state, &channel_id).await {
+            Self::notify_disallowed(state, &channel_id, thread_ts.as_ref()).await;
+            return;
+        }
+
+        // Strip the bot's own mention; other mentions are part of the request
+        let clean_text = strip_bot_mention(text, state.bot_user_id.as_ref());
+
+        tracing::debug!(
+            original_len = text.len(),
+            cleaned_len = clean_text.len(),
+            "Cleaned mention text"
+        );
+
+        let settings = state.form_handler.agent_manager.settings();
+        let route = text_route(&clean_text, &settings.slack.command_prefix);
+        if route == TextRoute::Command {
+            tracing::info!(command = %clean_text, "Processing command");
+            // Forward to message processor for command handling
+            let slack_message = SlackMessage {
+                channel: channel_id,
+                user: user_id,
+                text: clean_text,
+                thread_ts,
+                ts,
+                files,
+            };
+
+            if let Err(e) = state.message_processor.process_message(slack_message).await {
+                tracing::error!(error = %e, "Command processing failed");
+            }
+        } else if route == TextRoute::RepoSetup {
+            tracing::info!(repo = %clean_text, "Processing setup request");
+            if let Err(e) = state
+                .form_handler
+                .handle_repo_setup(channel_id.clone(), clean_text.clone())
+                .await
+            {
+                tracing::error!(error = %e, repo = %clean_text, "Setup failed");
+                let _ = state
+                    .slack_client
+                    .send_message(
+                        &channel_id,
+                        &format!("Setup failed: {}", e),
+                        thread_ts.as_ref(),
+                    )
+                    .await;
+            }
+        } else {
+            tracing::info!("Processing regular message");
+            // Regular message - process it
+            let slack_message = SlackMessage {
+                channel: channel_id,
+                user: user_id,
+                text: clean_text,
+                thread_ts,
+                ts,
+                files,
+            };
+
+            if let Err(e) = state.message_processor.process_message(slack_message).await {
+                tracing::error!(error = %e, "Message processing failed");
+            }
+        }
+    }
+
     fn error_handler(
         err: Box<dyn std::error::Error + Send + Sync>,
         _client: Arc<SlackHyperClient>,
-        _states: SlackClientEventsUserState,
+        states: SlackClientEventsUserState,
     ) -> HttpStatusCode {
         tracing::error!(
             error = %err,
             error_kind = std::any::type_name_of_val(&*err),
             "Slack event error"
         );
+
+        if let ListenerErrorAction::Shutdown(reason) = listener_error_action(&*err) {
+            // The handler is synchronous; the state is only written once, at startup
+            match states.try_read() {
+                Ok(storage) => match storage.get_user_state::<BotState>() {
+                    Some(state) => state.fail_auth(reason),
+                    None => {
+                        tracing::error!(reason = %reason, "Bot state missing, can't stop listener")
+                    }
+                },
+                Err(_) => tracing::error!(reason = %reason, "Bot state busy, can't stop listener"),
+            }
+            return HttpStatusCode::UNAUTHORIZED;
+        }
         HttpStatusCode::OK
     }
+}
 
-    /// Cleanup events older than 1 hour to prevent memory growth
-    fn cleanup_old_events(events: &Arc<DashMap<String, Instant>>) {
-        let cutoff = Duration::from_secs(3600); // 1 hour
-        let mut removed = 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        events.retain(|_key, instant| {
-            let keep = instant.elapsed() < cutoff;
-            if !keep {
-                removed += 1;
-            }
-            keep
+    fn event(json: serde_json::Value) -> SlackEventCallbackBody {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_revoked_token_shuts_down() {
+        let revoked: Box<dyn std::error::Error + Send + Sync> =
+            "Slack API error: token_revoked".into();
+        assert_eq!(
+            listener_error_action(&*revoked),
+            ListenerErrorAction::Shutdown("Slack rejected the bot's token (token_revoked)".into())
+        );
+
+        let auth = SlackCoderError::SlackAuth("invalid_auth".to_string());
+        assert!(matches!(
+            listener_error_action(&auth),
+            ListenerErrorAction::Shutdown(_)
+        ));
+
+        // Transient and per-call failures keep the listener running
+        for text in ["connection reset by peer", "Slack API error: missing_scope"] {
+            let err: Box<dyn std::error::Error + Send + Sync> = text.into();
+            assert_eq!(listener_error_action(&*err), ListenerErrorAction::Continue);
+        }
+    }
+
+    #[test]
+    fn test_is_reconnect() {
+        // Startup opens two connections; a third hello means one dropped and came back
+        assert!(!is_reconnect(1, 2));
+        assert!(!is_reconnect(2, 2));
+        assert!(is_reconnect(3, 2));
+    }
+
+    #[test]
+    fn test_departed_channel() {
+        let bot = UserId::new("UBOT");
+        let bot_left = event(serde_json::json!({
+            "type": "member_left_channel",
+            "user": "UBOT",
+            "channel": "C123",
+            "channel_type": "C",
+            "team": "T1"
+        }));
+        let other_left = event(serde_json::json!({
+            "type": "member_left_channel",
+            "user": "U456",
+            "channel": "C123",
+            "channel_type": "C",
+            "team": "T1"
+        }));
+        let archived = event(serde_json::json!({
+            "type": "channel_archive",
+            "channel": "C789",
+            "user": "U456"
+        }));
+        let deleted = event(serde_json::json!({
+            "type": "channel_deleted",
+            "channel": "C999"
+        }));
+
+        assert_eq!(departed_channel(&bot_left, Some(&bot)), Some("C123"));
+        assert_eq!(departed_channel(&other_left, Some(&bot)), None);
+        // Without the bot's id a leave can't be attributed to it
+        assert_eq!(departed_channel(&bot_left, None), None);
+        assert_eq!(departed_channel(&archived, None), Some("C789"));
+        assert_eq!(departed_channel(&deleted, Some(&bot)), Some("C999"));
+    }
+
+    #[test]
+    fn test_text_route() {
+        assert_eq!(text_route("!help", "!"), TextRoute::Command);
+        // With a custom prefix, `/help` is a message, not a repository named "/help"
+        assert_eq!(text_route("/help", "!"), TextRoute::Message);
+        assert_eq!(text_route("/help", "/"), TextRoute::Command);
+        assert_eq!(text_route("!help", "/"), TextRoute::Message);
+
+        assert_eq!(text_route("tyrchen/slack-coder", "!"), TextRoute::RepoSetup);
+        assert_eq!(
+            text_route("https://github.com/tyrchen/slack-coder", "/"),
+            TextRoute::RepoSetup
+        );
+        assert_eq!(
+            text_route("look at src/main.rs please", "/"),
+            TextRoute::Message
+        );
+    }
+
+    fn message(channel: &str, subtype: Option<&str>, text: &str) -> SlackMessageEvent {
+        let mut json = serde_json::json!({
+            "type": "message",
+            "channel": channel,
+            "user": "U456",
+            "text": text,
+            "ts": "1700000000.000100"
         });
+        if let Some(subtype) = subtype {
+            json["subtype"] = subtype.into();
+        }
+        let SlackEventCallbackBody::Message(message) = event(json) else {
+            panic!("expected a message event");
+        };
+        message
+    }
 
-        if removed > 0 {
-            tracing::debug!(removed_count = removed, "Cleaned up old events from cache");
+    #[test]
+    fn test_direct_message_input() {
+        let dm = ChannelId::new("D123");
+        let plain = message("D123", None, "fix the login bug");
+        assert_eq!(
+            direct_message_input(&plain, &dm),
+            Some(DirectMessageInput {
+                user: "U456",
+                text: "fix the login bug",
+            })
+        );
+
+        // An upload's comment is the input, so `/config import` works with a file in a DM
+        let upload = message("D123", Some("file_share"), "/config import");
+        assert_eq!(
+            direct_message_input(&upload, &dm).map(|input| input.text),
+            Some("/config import")
+        );
+
+        let join = message("D123", Some("channel_join"), "joined");
+        assert_eq!(direct_message_input(&join, &dm), None);
+
+        // Channel messages still need a mention
+        let channel = ChannelId::new("C123");
+        let in_channel = message("C123", None, "fix the login bug");
+        assert_eq!(direct_message_input(&in_channel, &channel), None);
+    }
+
+    fn message_changed(
+        user: &str,
+        bot_id: Option<&str>,
+        old: &str,
+        new: &str,
+    ) -> SlackMessageEvent {
+        let mut edited = serde_json::json!({
+            "type": "message",
+            "user": user,
+            "text": new,
+            "ts": "1700000000.000100",
+            "edited": { "user": user, "ts": "1700000050.000000" }
+        });
+        if let Some(bot_id) = bot_id {
+            edited["bot_id"] = bot_id.into();
         }
+        let SlackEventCallbackBody::Message(message) = event(serde_json::json!({
+            "type": "message",
+            "subtype": "message_changed",
+            "channel": "C123",
+            "ts": "1700000050.000200",
+            "hidden": true,
+            "message": edited,
+            "previous_message": {
+                "type": "message",
+                "user": user,
+                "text": old,
+                "ts": "1700000000.000100"
+            }
+        })) else {
+            panic!("expected a message event");
+        };
+        message
+    }
+
+    #[test]
+    fn test_user_edit() {
+        let bot = UserId::new("UBOT");
+        let edit = message_changed("U456", None, "fix the bug", "fix the login bug");
+        assert_eq!(
+            user_edit(&edit, Some(&bot)),
+            Some(MessageEdit {
+                ts: "1700000000.000100",
+                user: "U456",
+                text: "fix the login bug",
+            })
+        );
+
+        // The bot updating its own replies must never loop back into a re-run
+        let own_reply = message_changed("UBOT", None, "🤔 Working on it…", "Done");
+        assert_eq!(user_edit(&own_reply, Some(&bot)), None);
+        let bot_message = message_changed("U789", Some("B123"), "a", "b");
+        assert_eq!(user_edit(&bot_message, Some(&bot)), None);
+
+        // Link previews being attached arrive as edits with the same text
+        let unfurl = message_changed("U456", None, "see https://x.io", "see https://x.io");
+        assert_eq!(user_edit(&unfurl, Some(&bot)), None);
+    }
+
+    #[test]
+    fn test_strip_bot_mention_keeps_other_mentions() {
+        let bot = UserId::new("UBOT");
+        assert_eq!(
+            strip_bot_mention("<@UBOT> assign this to <@U123>", Some(&bot)),
+            "assign this to <@U123>"
+        );
+        assert_eq!(
+            strip_bot_mention("ping <@UBOT|coder> and <@U456|alice>", Some(&bot)),
+            "ping  and <@U456|alice>"
+        );
+        assert_eq!(
+            strip_bot_mention("<@UBOT> tyrchen/slack-coder", Some(&bot)),
+            "tyrchen/slack-coder"
+        );
+    }
+
+    #[test]
+    fn test_strip_bot_mention_preserves_formatting() {
+        let bot = UserId::new("UBOT");
+        assert_eq!(
+            strip_bot_mention("<@UBOT> fix this:\n```\nfn main() {}\n```", Some(&bot)),
+            "fix this:\n```\nfn main() {}\n```"
+        );
+        // An unclosed `<@` is left alone
+        assert_eq!(strip_bot_mention("a <@UBOT", Some(&bot)), "a <@UBOT");
+    }
+
+    #[test]
+    fn test_strip_bot_mention_without_bot_id_strips_leading_only() {
+        assert_eq!(
+            strip_bot_mention("<@UBOT> assign this to <@U123>", None),
+            "assign this to <@U123>"
+        );
+        assert_eq!(strip_bot_mention("/help", None), "/help");
     }
 }
@@ -1,18 +1,58 @@
-use crate::agent::AgentManager;
-use crate::error::{Result, SlackCoderError};
-use crate::slack::{ChannelId, SlackClient};
+use crate::agent::{AgentManager, SetupReport};
+use crate::config::RepoSpec;
+use crate::error::Result;
+use crate::metadata::MetadataCache;
+use crate::slack::{
+    ChannelId, SendOptions, SlackClient, SlackCommandHandler, UserId, display_command,
+    markdown_to_slack,
+};
+use crate::storage::format_bytes;
 use std::sync::Arc;
 
+/// Action id of the "Start New Session" button
+pub const ACTION_NEW_SESSION: &str = "new_session";
+/// Action id of the "Resync Repo" button
+pub const ACTION_RESYNC_REPO: &str = "resync_repo";
+
+/// Buttons attached to the setup completion message, as `(action_id, label)`
+const SESSION_BUTTONS: &[(&str, &str)] = &[
+    (ACTION_NEW_SESSION, "🔄 Start New Session"),
+    (ACTION_RESYNC_REPO, "📥 Resync Repo"),
+];
+
+/// A button click the bot knows how to handle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonAction {
+    NewSession,
+    ResyncRepo,
+}
+
+impl ButtonAction {
+    pub fn from_action_id(action_id: &str) -> Option<Self> {
+        match action_id {
+            ACTION_NEW_SESSION => Some(Self::NewSession),
+            ACTION_RESYNC_REPO => Some(Self::ResyncRepo),
+            _ => None,
+        }
+    }
+}
+
 pub struct FormHandler {
     slack_client: Arc<SlackClient>,
     pub agent_manager: Arc<AgentManager>,
+    metadata_cache: Arc<MetadataCache>,
 }
 
 impl FormHandler {
-    pub fn new(slack_client: Arc<SlackClient>, agent_manager: Arc<AgentManager>) -> Self {
+    pub fn new(
+        slack_client: Arc<SlackClient>,
+        agent_manager: Arc<AgentManager>,
+        metadata_cache: Arc<MetadataCache>,
+    ) -> Self {
         Self {
             slack_client,
             agent_manager,
+            metadata_cache,
         }
     }
 
@@ -41,9 +81,15 @@ Reply with your repository name to begin setup."#;
             repo_name
         );
 
-        // Validate repo name format
-        let (owner, repo) = Self::validate_repo_name_format(&repo_name)?;
-        tracing::debug!("✅ Validated format: owner={}, repo={}", owner, repo);
+        // Validate repo name format and host
+        let repo = self.agent_manager.settings().git.resolve(&repo_name)?;
+        tracing::debug!(
+            "✅ Validated format: host={}, owner={}, repo={}",
+            repo.host,
+            repo.owner,
+            repo.repo
+        );
+        let repo_name = repo.to_string();
 
         // Send acknowledgment
         tracing::debug!("Sending acknowledgment to Slack...");
@@ -58,53 +104,208 @@ Reply with your repository name to begin setup."#;
 
         // Trigger setup via agent manager
         tracing::info!("🚀 Invoking agent manager to setup channel...");
-        self.agent_manager
-            .setup_channel(channel.clone(), repo_name.clone())
+        let report = self
+            .agent_manager
+            .setup_channel(channel.clone(), repo.clone())
             .await?;
         tracing::info!("✅ Agent setup completed");
 
         // Send completion message with proper formatting
         tracing::debug!("Sending completion message...");
-        let completion_msg = format!(
-            ":white_check_mark: *Repository `{}` is now ready!*\n\n\
+        let completion_msg = Self::completion_message(
+            &repo,
+            &report,
+            &self.agent_manager.settings().slack.command_prefix,
+        );
+
+        self.slack_client
+            .send_message_with_buttons(
+                &channel,
+                &completion_msg,
+                SESSION_BUTTONS,
+                None,
+                // Preview the repository link
+                SendOptions::new().with_unfurl(true),
+            )
+            .await?;
+        tracing::info!("🎉 Setup workflow completed successfully");
+
+        Ok(())
+    }
+
+    /// The message posted once setup succeeds
+    fn completion_message(repo: &RepoSpec, report: &SetupReport, prefix: &str) -> String {
+        let summary = report
+            .summary
+            .as_deref()
+            .map(|summary| format!("> {}\n\n", markdown_to_slack(summary).replace('\n', " ")))
+            .unwrap_or_default();
+        let resumed = if report.skipped.is_empty() {
+            String::new()
+        } else {
+            format!(
+                "⏩ Resumed an earlier setup, skipped: {}\n",
+                report.skipped.join(", ")
+            )
+        };
+        format!(
+            ":white_check_mark: *Repository `{}` is now ready!*\n\
+            {}\n\n\
+            {}\
+            📦 Repository size: {}\n{}\n\
             You can now ask me to:\n\
             • Generate code\n\
             • Write documentation\n\
             • Refactor existing code\n\
             • Review and commit changes\n\
             • Create pull requests\n\n\
-            Try: `@slack-coder /help` for more information",
-            repo_name
+            Try: `@slack-coder {}` for more information",
+            repo,
+            repo.web_url(),
+            summary,
+            format_bytes(report.repo_size_bytes),
+            resumed,
+            display_command("/help", prefix)
+        )
+    }
+
+    /// Handle a button click from a Block Kit message
+    pub async fn handle_block_action(
+        &self,
+        channel: ChannelId,
+        user: UserId,
+        action_id: &str,
+    ) -> Result<()> {
+        let Some(action) = ButtonAction::from_action_id(action_id) else {
+            tracing::debug!(action_id = %action_id, "Ignoring unknown button action");
+            return Ok(());
+        };
+
+        tracing::info!(
+            action = ?action,
+            channel_id = %channel,
+            user_id = %user,
+            "Handling button click"
         );
 
-        self.slack_client
-            .send_message(&channel, &completion_msg, None)
-            .await?;
-        tracing::info!("🎉 Setup workflow completed successfully");
+        match action {
+            // Same path as typing `/new-session`
+            ButtonAction::NewSession => {
+                let command_handler = SlackCommandHandler::new(
+                    self.slack_client.clone(),
+                    self.metadata_cache.clone(),
+                );
+                command_handler
+                    .handle_command("/new-session", &channel, &user, &[], &self.agent_manager)
+                    .await
+            }
+            ButtonAction::ResyncRepo => self.resync_repo(channel).await,
+        }
+    }
 
+    /// Handle a modal view submission
+    ///
+    /// No modals are opened by the bot yet, so submissions are only logged.
+    pub async fn handle_view_submission(
+        &self,
+        user: UserId,
+        callback_id: Option<&str>,
+    ) -> Result<()> {
+        tracing::info!(
+            user_id = %user,
+            callback_id = ?callback_id,
+            "Ignoring view submission with no registered handler"
+        );
         Ok(())
     }
 
-    /// Validate repository name format (owner/repo)
-    fn validate_repo_name_format(name: &str) -> Result<(String, String)> {
-        let parts: Vec<&str> = name.split('/').collect();
+    /// Re-run setup for the repository the channel is already configured with
+    pub(crate) async fn resync_repo(&self, channel: ChannelId) -> Result<()> {
+        let config = self
+            .agent_manager
+            .workspace()
+            .load_channel_config(&channel)
+            .await?;
+
+        let Some(repo_name) = config.repo_name else {
+            self.slack_client
+                .send_message(
+                    &channel,
+                    "⚠️ No repository is configured for this channel yet. Reply with `owner/repo` to set one up.",
+                    None,
+                )
+                .await?;
+            return Ok(());
+        };
 
-        if parts.len() != 2 {
-            return Err(SlackCoderError::Config(format!(
-                "Invalid repository format: '{}'. Expected format: owner/repo-name",
-                name
-            )));
+        if self.agent_manager.active_queries().is_active(&channel) {
+            self.slack_client
+                .send_message(
+                    &channel,
+                    "⏳ The agent is busy with a task. Try resyncing once it finishes.",
+                    None,
+                )
+                .await?;
+            return Ok(());
         }
 
-        let owner = parts[0].trim();
-        let repo = parts[1].trim();
+        if let Err(e) = self.handle_repo_setup(channel.clone(), repo_name).await {
+            self.slack_client
+                .send_message(&channel, &format!("Resync failed: {}", e), None)
+                .await?;
+            return Err(e);
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if owner.is_empty() || repo.is_empty() {
-            return Err(SlackCoderError::Config(
-                "Owner and repository name cannot be empty".to_string(),
-            ));
+    #[test]
+    fn test_button_action_from_action_id() {
+        assert_eq!(
+            ButtonAction::from_action_id(ACTION_NEW_SESSION),
+            Some(ButtonAction::NewSession)
+        );
+        assert_eq!(
+            ButtonAction::from_action_id(ACTION_RESYNC_REPO),
+            Some(ButtonAction::ResyncRepo)
+        );
+        assert_eq!(ButtonAction::from_action_id("unknown"), None);
+
+        // Every button we post must map to an action
+        for (action_id, _) in SESSION_BUTTONS {
+            assert!(ButtonAction::from_action_id(action_id).is_some());
         }
+    }
+
+    #[test]
+    fn test_completion_message_includes_summary() {
+        let repo = RepoSpec::parse("tyrchen/slack-coder", crate::config::DEFAULT_GIT_HOST).unwrap();
+        let report = SetupReport {
+            repo_size_bytes: 2048,
+            skipped: Vec::new(),
+            summary: Some("A **Slack bot** that runs\nClaude agents per channel.".to_string()),
+        };
+        let message = FormHandler::completion_message(&repo, &report, "!");
+        assert!(message.contains("*Repository `tyrchen/slack-coder` is now ready!*"));
+        assert!(message.contains(
+            "https://github.com/tyrchen/slack-coder\n\n> A *Slack bot* that runs Claude agents per channel.\n\n📦 Repository size:"
+        ));
+        assert!(message.contains("`@slack-coder !help`"));
 
-        Ok((owner.to_string(), repo.to_string()))
+        let without = FormHandler::completion_message(
+            &repo,
+            &SetupReport {
+                summary: None,
+                ..report
+            },
+            "/",
+        );
+        assert!(without.contains("slack-coder\n\n📦 Repository size:"));
+        assert!(!without.contains('>'));
     }
 }
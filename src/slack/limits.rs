@@ -0,0 +1,183 @@
+//! Slack platform limits, centralized so chunking, truncation and rate limiting
+//! agree with each other
+
+use crate::slack::split_slack_message;
+use std::time::Duration;
+
+/// Slack's documented hard limit for `chat.postMessage` text (40KB)
+const SLACK_MAX_MESSAGE_BYTES: usize = 40_000;
+
+/// Room left in each chunk for a "(continued n/total)" marker
+pub const CONTINUATION_RESERVE: usize = 32;
+
+/// Room left in a truncated message for the note saying how much was cut
+pub const TRUNCATION_RESERVE: usize = 128;
+
+/// Constraints imposed by the Slack API
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlackLimits {
+    /// Maximum bytes of text we put in a single message (kept under Slack's 40KB)
+    pub max_message_bytes: usize,
+
+    /// Minimum interval between edits of the same message (chat.update)
+    pub min_update_interval: Duration,
+
+    /// Spacing between bulk posts to many channels (chat.postMessage is ~50/min)
+    pub min_post_interval: Duration,
+}
+
+impl Default for SlackLimits {
+    fn default() -> Self {
+        Self {
+            // Leave some margin for metadata Slack adds around the text
+            max_message_bytes: SLACK_MAX_MESSAGE_BYTES - 1000,
+            min_update_interval: Duration::from_millis(750),
+            min_post_interval: Duration::from_millis(1200),
+        }
+    }
+}
+
+impl SlackLimits {
+    /// Limits with a smaller message size, e.g. from `MAX_MESSAGE_BYTES` (never above
+    /// Slack's own limit)
+    pub fn with_max_message_bytes(mut self, max_message_bytes: usize) -> Self {
+        self.max_message_bytes = max_message_bytes.min(SLACK_MAX_MESSAGE_BYTES - 1000);
+        self
+    }
+
+    /// Check whether text fits in a single message
+    pub fn fits_message(&self, text: &str) -> bool {
+        text.len() <= self.max_message_bytes
+    }
+
+    /// Split text into chunks that each fit in a single message, leaving room
+    /// for a continuation marker (see [`split_slack_message`])
+    pub fn chunk_message(&self, text: &str) -> Vec<String> {
+        if self.fits_message(text) {
+            return vec![text.to_string()];
+        }
+        split_slack_message(
+            text,
+            self.max_message_bytes.saturating_sub(CONTINUATION_RESERVE),
+        )
+    }
+
+    /// Cut text down to what fits in one message alongside `reserve` bytes of footer,
+    /// leaving room for a truncation note. Returns the kept text and the number of
+    /// characters dropped, or `None` if nothing needs to be cut.
+    ///
+    /// The kept text is the first chunk [`chunk_message`](Self::chunk_message) would
+    /// post, so it ends on a paragraph or line boundary and code fences stay closed.
+    pub fn truncate_message(&self, text: &str, reserve: usize) -> Option<(String, usize)> {
+        if text.len() + reserve <= self.max_message_bytes {
+            return None;
+        }
+
+        let budget = self
+            .max_message_bytes
+            .saturating_sub(reserve + TRUNCATION_RESERVE);
+        let kept = split_slack_message(text, budget)
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        // The kept text may end with a fence we added, so count the shared prefix
+        let shown = text
+            .chars()
+            .zip(kept.chars())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Some((kept, text.chars().count() - shown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn small_limits() -> SlackLimits {
+        SlackLimits {
+            max_message_bytes: 10 + CONTINUATION_RESERVE,
+            min_update_interval: Duration::from_millis(100),
+            min_post_interval: Duration::from_millis(100),
+        }
+    }
+
+    #[test]
+    fn test_default_limits_under_slack_max() {
+        let limits = SlackLimits::default();
+        assert!(limits.max_message_bytes < SLACK_MAX_MESSAGE_BYTES);
+    }
+
+    #[test]
+    fn test_chunk_message_honors_limit() {
+        let limits = small_limits();
+        let text = "a".repeat(50);
+        let chunks = limits.chunk_message(&text);
+
+        assert_eq!(chunks.len(), 5);
+        assert!(chunks.iter().all(|c| limits.fits_message(c)));
+        assert!(chunks.iter().all(|c| c.len() <= 10));
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn test_chunk_message_multibyte() {
+        let limits = small_limits();
+        let text = "日本語のテキスト".repeat(2);
+        // Each char is 3 bytes, so only 3 fit in a 10-byte chunk (after the reserve)
+        let chunks = limits.chunk_message(&text);
+
+        assert!(chunks.iter().all(|c| limits.fits_message(c)));
+        assert_eq!(chunks[0], "日本語");
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn test_chunk_message_short_text() {
+        let limits = small_limits();
+        assert_eq!(limits.chunk_message("short"), vec!["short".to_string()]);
+    }
+
+    #[test]
+    fn test_truncate_message() {
+        let limits = SlackLimits::default().with_max_message_bytes(TRUNCATION_RESERVE + 20);
+        assert_eq!(limits.max_message_bytes, TRUNCATION_RESERVE + 20);
+        assert_eq!(limits.truncate_message("short", 0), None);
+
+        let text = format!("first line\n{}", "x".repeat(200));
+        let (kept, omitted) = limits.truncate_message(&text, 0).unwrap();
+        assert_eq!(kept, "first line\n");
+        assert_eq!(omitted, 200);
+
+        // The footer's bytes come out of the budget
+        let (kept, _) = limits.truncate_message(&text, 10).unwrap();
+        assert!(kept.len() <= 10);
+    }
+
+    #[test]
+    fn test_truncate_message_closes_code_fence() {
+        let limits = SlackLimits::default().with_max_message_bytes(TRUNCATION_RESERVE + 24);
+        let text = format!("```\n{}```\n", "let a = 1;\n".repeat(20));
+        let (kept, omitted) = limits.truncate_message(&text, 0).unwrap();
+
+        assert_eq!(kept, "```\nlet a = 1;\n```");
+        assert_eq!(omitted, text.len() - "```\nlet a = 1;\n".len());
+    }
+
+    #[test]
+    fn test_chunk_message_at_configured_limit() {
+        let limits = SlackLimits::default().with_max_message_bytes(CONTINUATION_RESERVE + 20);
+        let text = "0123456789\n".repeat(6);
+        let chunks = limits.chunk_message(&text);
+
+        assert_eq!(chunks.len(), 4);
+        assert!(chunks.iter().all(|c| c.len() <= 20));
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn test_with_max_message_bytes_stays_under_slack_max() {
+        let limits = SlackLimits::default().with_max_message_bytes(100_000);
+        assert_eq!(limits, SlackLimits::default());
+    }
+}
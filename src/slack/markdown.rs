@@ -1,4 +1,15 @@
 use regex::Regex;
+use std::sync::LazyLock;
+use unicode_width::UnicodeWidthStr;
+
+/// What a markdown horizontal rule becomes; Slack has no rule of its own
+pub const SLACK_DIVIDER: &str = "──────────────────────";
+
+/// A line of three or more `-`, `*` or `_` (optionally spaced), indented at most 3
+/// spaces. Table separators contain `|`, so they never match.
+static HORIZONTAL_RULE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^ {0,3}(?:(?:-[ \t]*){3,}|(?:\*[ \t]*){3,}|(?:_[ \t]*){3,})$").unwrap()
+});
 
 /// Convert markdown text to Slack mrkdwn format
 ///
@@ -7,18 +18,23 @@ use regex::Regex;
 /// - _italic_ (Slack) vs *italic* or _italic_ (Markdown)
 /// - Headers (##) -> Bold text with spacing
 /// - Tables -> Formatted with proper alignment
+/// - Horizontal rules (`---`, `***`, `___`) -> A line of `─`
 /// - URLs -> Wrapped in <URL> for auto-linking
+/// - `&`, `<` and `>` in prose -> `&amp;`, `&lt;`, `&gt;`, as Slack requires
 /// - Lists, code blocks work similarly
 ///
 /// This function converts standard markdown to Slack-compatible format.
 pub fn markdown_to_slack(text: &str) -> String {
     let mut result = text.to_string();
 
-    // Convert tables to formatted text
-    result = convert_tables(&result);
+    // Convert tables to formatted text (code blocks such as diffs may contain `|` rows)
+    result = outside_code_blocks(&result, convert_tables);
+
+    // Replace horizontal rules before `***` can be read as bold markers
+    result = outside_code_blocks(&result, convert_horizontal_rules);
 
-    // Convert headers to bold (## Header -> *Header*)
-    result = convert_headers(&result);
+    // Convert headers to bold (## Header -> *Header*), leaving `# comments` in code alone
+    result = outside_code_blocks(&result, convert_headers);
 
     // Convert **bold** to *bold* (avoid code blocks and URLs)
     result = convert_bold(&result);
@@ -26,12 +42,49 @@ pub fn markdown_to_slack(text: &str) -> String {
     // Format URLs for Slack (must be done after bold to avoid conflicts)
     result = format_urls(&result);
 
+    // Escape Slack's control characters last, once every `<link>` has been formed
+    result = escape_special_chars(&result);
+
     // Clean up extra newlines
     result = clean_newlines(&result);
 
     result
 }
 
+/// Apply `convert` to the text between fenced code blocks, leaving the blocks untouched
+fn outside_code_blocks(text: &str, convert: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut prose = String::new();
+    let mut in_code = false;
+
+    // Converters work on whole lines and may drop trailing newlines, so those are kept aside
+    let flush = |result: &mut String, prose: &mut String| {
+        let body = prose.trim_end_matches('\n');
+        result.push_str(&convert(body));
+        result.push_str(&prose[body.len()..]);
+        prose.clear();
+    };
+
+    for line in text.split_inclusive('\n') {
+        let fences = line.matches("```").count();
+        if !in_code && fences == 0 {
+            prose.push_str(line);
+            continue;
+        }
+
+        if !in_code {
+            flush(&mut result, &mut prose);
+        }
+        result.push_str(line);
+        if fences % 2 == 1 {
+            in_code = !in_code;
+        }
+    }
+    flush(&mut result, &mut prose);
+
+    result
+}
+
 fn convert_tables(text: &str) -> String {
     let lines: Vec<&str> = text.lines().collect();
     let mut result = Vec::new();
@@ -75,28 +128,28 @@ fn format_table(lines: &[&str]) -> String {
         return lines.join("\n");
     }
 
-    // Parse table rows
+    // Parse table rows. Only the outer pipes are stripped, so empty cells keep their column
     let parse_row = |line: &str| -> Vec<String> {
-        line.split('|')
-            .map(|s| s.trim().to_string())
-            .filter(|s| !s.is_empty())
-            .collect()
+        let line = line.trim();
+        let line = line.strip_prefix('|').unwrap_or(line);
+        let line = line.strip_suffix('|').unwrap_or(line);
+        line.split('|').map(|s| s.trim().to_string()).collect()
     };
 
     let header = parse_row(lines[0]);
+    let columns = header.len();
     let rows: Vec<Vec<String>> = lines
         .iter()
         .skip(2) // Skip header and separator
-        .map(|line| parse_row(line))
+        .map(|line| fit_row(parse_row(line), columns))
         .collect();
 
-    // Calculate column widths
-    let mut widths = header.iter().map(|h| h.len()).collect::<Vec<_>>();
+    // Calculate column widths by display width, so emoji and wide characters
+    // don't throw off alignment inside the monospace block
+    let mut widths = header.iter().map(|h| h.width()).collect::<Vec<_>>();
     for row in &rows {
         for (i, cell) in row.iter().enumerate() {
-            if i < widths.len() {
-                widths[i] = widths[i].max(cell.len());
-            }
+            widths[i] = widths[i].max(cell.width());
         }
     }
 
@@ -105,7 +158,7 @@ fn format_table(lines: &[&str]) -> String {
     let header_line = header
         .iter()
         .enumerate()
-        .map(|(i, h)| format!("{:width$}", h, width = widths.get(i).unwrap_or(&h.len())))
+        .map(|(i, h)| pad_to_width(h, widths[i]))
         .collect::<Vec<_>>()
         .join(" │ ");
     formatted.push(format!("```\n{}", header_line));
@@ -123,13 +176,7 @@ fn format_table(lines: &[&str]) -> String {
         let row_line = row
             .iter()
             .enumerate()
-            .map(|(i, cell)| {
-                format!(
-                    "{:width$}",
-                    cell,
-                    width = widths.get(i).unwrap_or(&cell.len())
-                )
-            })
+            .map(|(i, cell)| pad_to_width(cell, widths[i]))
             .collect::<Vec<_>>()
             .join(" │ ");
         formatted.push(row_line);
@@ -139,6 +186,103 @@ fn format_table(lines: &[&str]) -> String {
     formatted.join("\n")
 }
 
+/// Make a row have exactly `columns` cells: short rows are padded with empty
+/// cells, and extra cells are folded into the last column so nothing is lost
+fn fit_row(mut row: Vec<String>, columns: usize) -> Vec<String> {
+    if row.len() > columns && columns > 0 {
+        let extra = row.split_off(columns);
+        let last = &mut row[columns - 1];
+        for cell in extra.iter().filter(|c| !c.is_empty()) {
+            if !last.is_empty() {
+                last.push_str(" | ");
+            }
+            last.push_str(cell);
+        }
+    }
+    row.resize(columns, String::new());
+    row
+}
+
+/// Common Slack emoji codes and their unicode equivalents
+const EMOJI_CODES: &[(&str, &str)] = &[
+    ("white_check_mark", "✅"),
+    ("heavy_check_mark", "✔️"),
+    ("ballot_box_with_check", "☑️"),
+    ("x", "❌"),
+    ("warning", "⚠️"),
+    ("no_entry", "⛔"),
+    ("no_entry_sign", "🚫"),
+    ("question", "❓"),
+    ("exclamation", "❗"),
+    ("wrench", "🔧"),
+    ("hammer", "🔨"),
+    ("construction", "🚧"),
+    ("rocket", "🚀"),
+    ("tada", "🎉"),
+    ("sparkles", "✨"),
+    ("fire", "🔥"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("memo", "📝"),
+    ("package", "📦"),
+    ("lock", "🔒"),
+    ("hourglass", "⌛"),
+    ("hourglass_flowing_sand", "⏳"),
+    ("star", "⭐"),
+    ("red_circle", "🔴"),
+    ("large_green_circle", "🟢"),
+    ("large_yellow_circle", "🟡"),
+    ("large_blue_circle", "🔵"),
+    ("heavy_minus_sign", "➖"),
+    ("+1", "👍"),
+    ("thumbsup", "👍"),
+    ("-1", "👎"),
+    ("thumbsdown", "👎"),
+];
+
+/// Any emoji in [`EMOJI_CODES`], with or without a trailing variation selector
+static EMOJI_RE: LazyLock<Regex> = LazyLock::new(|| {
+    let mut emoji: Vec<&str> = EMOJI_CODES
+        .iter()
+        .map(|(_, emoji)| emoji.trim_end_matches('\u{FE0F}'))
+        .collect();
+    // Longest first, so an emoji is never cut short by one it starts with
+    emoji.sort_by_key(|emoji| std::cmp::Reverse(emoji.len()));
+    emoji.dedup();
+    let alternatives: Vec<String> = emoji.into_iter().map(regex::escape).collect();
+    Regex::new(&format!("(?:{})\\x{{FE0F}}?", alternatives.join("|"))).unwrap()
+});
+
+/// Replace common unicode emoji with their Slack `:emoji_name:` codes, so replies
+/// show the same emoji whichever form the agent wrote. Code blocks (tables included,
+/// once converted) are left alone: Slack shows codes there literally.
+pub fn emoji_to_shortcodes(text: &str) -> String {
+    outside_code_blocks(text, |prose| {
+        EMOJI_RE
+            .replace_all(prose, |caps: &regex::Captures| {
+                let emoji = caps[0].trim_end_matches('\u{FE0F}');
+                EMOJI_CODES
+                    .iter()
+                    .find(|(_, known)| known.trim_end_matches('\u{FE0F}') == emoji)
+                    .map(|(name, _)| format!(":{}:", name))
+                    .unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned()
+    })
+}
+
+/// Pad text with spaces to the given display width (`format!` pads by char count)
+fn pad_to_width(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(text.width());
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+fn convert_horizontal_rules(text: &str) -> String {
+    HORIZONTAL_RULE_RE
+        .replace_all(text, SLACK_DIVIDER)
+        .to_string()
+}
+
 fn convert_headers(text: &str) -> String {
     // Use regex to convert headers, preserving content
     // Process from most specific (h6) to least specific (h1) to avoid incorrect matches
@@ -173,9 +317,10 @@ fn convert_headers(text: &str) -> String {
 }
 
 fn convert_bold(text: &str) -> String {
-    // Convert **text** to *text* but not inside code blocks
+    // Convert **text** to *text* but not inside code blocks or inline code
     // Also handle URLs specially to avoid breaking them
     let code_block_re = Regex::new(r"```[\s\S]*?```").unwrap();
+    let inline_code_re = Regex::new(r"`[^`]+`").unwrap();
 
     // Extract code blocks
     let mut code_blocks = Vec::new();
@@ -189,6 +334,21 @@ fn convert_bold(text: &str) -> String {
         );
     }
 
+    // Extract inline code
+    let mut inline_codes = Vec::new();
+    let inline_code_matches: Vec<String> = inline_code_re
+        .find_iter(&text_without_code)
+        .map(|cap| cap.as_str().to_string())
+        .collect();
+
+    for code in inline_code_matches {
+        inline_codes.push(code.clone());
+        text_without_code = text_without_code.replace(
+            &code,
+            &format!("__INLINE_CODE_{}__", inline_codes.len() - 1),
+        );
+    }
+
     // Convert **text** to *text* but handle URLs specially
     // First, handle **URL** pattern - just remove the ** without adding *
     let bold_url_re = Regex::new(r"\*\*(https?://[^\s\*]+)\*\*").unwrap();
@@ -213,6 +373,11 @@ fn convert_bold(text: &str) -> String {
         })
         .to_string();
 
+    // Restore inline code
+    for (i, code) in inline_codes.iter().enumerate() {
+        text_without_code = text_without_code.replace(&format!("__INLINE_CODE_{}__", i), code);
+    }
+
     // Restore code blocks
     for (i, block) in code_blocks.iter().enumerate() {
         text_without_code = text_without_code.replace(&format!("__CODE_BLOCK_{}__", i), block);
@@ -288,9 +453,10 @@ fn format_urls(text: &str) -> String {
     }
 
     // Now wrap remaining standalone URLs
-    // Exclude markdown formatting characters (*_~) and whitespace from URL capture
-    // This prevents trailing asterisks from broken markdown being included in URLs
-    let standalone_url_re = Regex::new(r"(https?://[^\s<>\*_~]+)").unwrap();
+    // Exclude markdown formatting characters (*~) and whitespace from URL capture, and
+    // don't end on `_`. This keeps trailing markdown out of URLs without cutting
+    // underscores in paths (github.com/owner/my_repo/pull/1).
+    let standalone_url_re = Regex::new(r"(https?://[^\s<>\*~]*[^\s<>\*_~])").unwrap();
     result = standalone_url_re.replace_all(&result, "<$1>").to_string();
 
     // Restore Slack links
@@ -311,6 +477,60 @@ fn format_urls(text: &str) -> String {
     result
 }
 
+/// Escape `&`, `<` and `>` outside code, Slack links and mentions (`<url|text>`,
+/// `<@U123>`, `<!here>`). Entities that are already escaped and blockquote markers at
+/// the start of a line are left alone.
+fn escape_special_chars(text: &str) -> String {
+    if !text.contains(['&', '<', '>']) {
+        return text.to_string();
+    }
+
+    let code_block_re = Regex::new(r"```[\s\S]*?```").unwrap();
+    let inline_code_re = Regex::new(r"`[^`]+`").unwrap();
+    let slack_link_re =
+        Regex::new(r"<(?:[@#!][^<>\s|]+|(?:https?://|mailto:)[^<>\s|]+)(?:\|[^<>\n]*)?>").unwrap();
+
+    // Extract code blocks, inline code and links, in that order
+    let mut protected = Vec::new();
+    let mut result = text.to_string();
+    for re in [&code_block_re, &inline_code_re, &slack_link_re] {
+        let matches: Vec<String> = re
+            .find_iter(&result)
+            .map(|cap| cap.as_str().to_string())
+            .collect();
+        for span in matches {
+            protected.push(span.clone());
+            result = result.replace(&span, &format!("__PROTECTED_{}__", protected.len() - 1));
+        }
+    }
+
+    let mut escaped = String::with_capacity(result.len());
+    let mut line_start = true;
+    for (i, c) in result.char_indices() {
+        match c {
+            '&' if ["&amp;", "&lt;", "&gt;"]
+                .iter()
+                .any(|entity| result[i..].starts_with(entity)) =>
+            {
+                escaped.push('&')
+            }
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            // `> quote` at the start of a line is a blockquote
+            '>' if line_start => escaped.push('>'),
+            '>' => escaped.push_str("&gt;"),
+            _ => escaped.push(c),
+        }
+        line_start = c == '\n' || (line_start && (c == ' ' || c == '>'));
+    }
+
+    // Restore in reverse, since later spans may hold earlier placeholders
+    for (i, span) in protected.iter().enumerate().rev() {
+        escaped = escaped.replace(&format!("__PROTECTED_{}__", i), span);
+    }
+    escaped
+}
+
 fn clean_newlines(text: &str) -> String {
     // Remove excessive newlines (more than 2 consecutive)
     let multi_newline_re = Regex::new(r"\n{3,}").unwrap();
@@ -330,6 +550,14 @@ mod tests {
         assert!(result.contains("*bold*"));
     }
 
+    #[test]
+    fn test_bold_not_converted_inside_inline_code() {
+        assert_eq!(convert_bold("`a**b**c`"), "`a**b**c`");
+
+        let result = markdown_to_slack("**bold** and `a**b**c` then **more**");
+        assert_eq!(result, "*bold* and `a**b**c` then *more*");
+    }
+
     #[test]
     fn test_convert_headers() {
         let input = "## Header\nSome text";
@@ -388,6 +616,39 @@ mod tests {
         assert!(output.contains("Cell 4"));
     }
 
+    #[test]
+    fn test_horizontal_rule_between_paragraphs() {
+        let output = markdown_to_slack("First part\n\n---\n\nSecond part");
+        assert_eq!(
+            output,
+            format!("First part\n\n{}\n\nSecond part", SLACK_DIVIDER)
+        );
+
+        for rule in ["***", "___", "- - -", "  -----"] {
+            let output = markdown_to_slack(&format!("a\n\n{}\n\nb", rule));
+            assert!(output.contains(SLACK_DIVIDER), "{:?} -> {:?}", rule, output);
+        }
+        // Not rules: too short, mixed or with other text
+        for text in ["--", "-*-", "--- not a rule", "    ---"] {
+            assert!(
+                !markdown_to_slack(text).contains(SLACK_DIVIDER),
+                "{:?}",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn test_horizontal_rule_leaves_tables_and_code_alone() {
+        let input = "| A | B |\n|---|---|\n| 1 | 2 |\n\n---\n\n```yaml\n---\nkey: value\n```";
+        let output = markdown_to_slack(input);
+        assert_eq!(output.matches(SLACK_DIVIDER).count(), 1);
+        // The table separator still becomes the table's own border
+        assert!(output.contains("──┼──") || output.contains("─┼─"));
+        assert!(!output.contains("|---|"));
+        assert!(output.contains("```yaml\n---\nkey: value\n```"));
+    }
+
     #[test]
     fn test_table_with_emojis() {
         let input = "| Feature | Status |\n|---------|--------|\n| Auth | :white_check_mark: |\n| Cache | :x: |";
@@ -398,6 +659,98 @@ mod tests {
         assert!(output.contains(":x:"));
     }
 
+    #[test]
+    fn test_emoji_to_shortcodes() {
+        assert_eq!(emoji_to_shortcodes("🚀 Deploy"), ":rocket: Deploy");
+        assert_eq!(
+            emoji_to_shortcodes("✅ done, 👍"),
+            ":white_check_mark: done, :+1:"
+        );
+        // With or without the variation selector
+        assert_eq!(emoji_to_shortcodes("⚠️ and ⚠"), ":warning: and :warning:");
+        // Unknown emoji, existing codes and code blocks are left alone
+        assert_eq!(emoji_to_shortcodes("🦀 :x:"), "🦀 :x:");
+        assert_eq!(
+            emoji_to_shortcodes("```\n✅ ok\n```\n✅"),
+            "```\n✅ ok\n```\n:white_check_mark:"
+        );
+    }
+
+    /// Display columns at which each `│` separator appears in a table line
+    fn separator_columns(line: &str) -> Vec<usize> {
+        line.match_indices('│')
+            .map(|(i, _)| line[..i].width())
+            .collect()
+    }
+
+    #[test]
+    fn test_table_with_unicode_emoji_stays_aligned() {
+        let input = "| Feature | Status |\n|---------|--------|\n| Auth | ✅ done |\n| Cache 🔧 | ❌ |\n| Search | 🚧 wip |";
+        let output = markdown_to_slack(input);
+
+        let table_lines: Vec<&str> = output
+            .lines()
+            .map(|l| l.trim_start_matches("```"))
+            .filter(|l| l.contains('│'))
+            .collect();
+        assert_eq!(table_lines.len(), 4);
+
+        let expected = separator_columns(table_lines[0]);
+        for line in &table_lines[1..] {
+            assert_eq!(separator_columns(line), expected, "misaligned: {}", line);
+        }
+    }
+
+    #[test]
+    fn test_table_with_japanese_text_stays_aligned() {
+        let input = "| 名前 | Role |\n|---|---|\n| 山田太郎 | 開発者 |\n| Bob | QA |";
+        let output = markdown_to_slack(input);
+
+        let table_lines: Vec<&str> = output
+            .lines()
+            .map(|l| l.trim_start_matches("```"))
+            .filter(|l| l.contains('│'))
+            .collect();
+        assert_eq!(table_lines.len(), 3);
+        for line in &table_lines {
+            assert_eq!(separator_columns(line), vec![9], "misaligned: {}", line);
+        }
+    }
+
+    #[test]
+    fn test_table_short_and_long_rows() {
+        let input = "| A | B | C |\n|---|---|---|\n| 1 |\n| x | | z |\n| p | q | r | s |";
+        let output = markdown_to_slack(input);
+
+        let table_lines: Vec<&str> = output
+            .lines()
+            .map(|l| l.trim_start_matches("```"))
+            .filter(|l| l.contains('│'))
+            .collect();
+        assert_eq!(table_lines.len(), 4);
+
+        let expected = separator_columns(table_lines[0]);
+        for line in &table_lines[1..] {
+            assert_eq!(separator_columns(line), expected, "misaligned: {}", line);
+        }
+
+        // Empty middle cell keeps `z` in the third column; extra cells are folded in
+        assert!(table_lines[2].trim_end().ends_with("│ z"));
+        assert!(table_lines[3].contains("r | s"));
+    }
+
+    #[test]
+    fn test_fit_row() {
+        let row = |cells: &[&str]| cells.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(fit_row(row(&["a"]), 3), row(&["a", "", ""]));
+        assert_eq!(fit_row(row(&["a", "b", "c"]), 3), row(&["a", "b", "c"]));
+        assert_eq!(
+            fit_row(row(&["a", "b", "c", "d"]), 2),
+            row(&["a", "b | c | d"])
+        );
+    }
+
     #[test]
     fn test_url_wrapped_in_angles() {
         let input = "https://github.com/user/repo/pull/1";
@@ -475,8 +828,80 @@ mod tests {
         assert!(output.contains("<https://github.com/user/repo/pull/4?tab=files>"));
     }
 
+    #[test]
+    fn test_diff_block_untouched() {
+        let input = "Updated the table:\n\n```diff\n--- a/README.md\n+++ b/README.md\n@@ -1,4 +1,4 @@\n # Title\n | key | value |\n |-----|-------|\n-| a | **1** |\n+| a | **2** |\n```\n\nSee https://example.com/docs";
+        let output = markdown_to_slack(input);
+
+        let diff_start = input.find("```diff").unwrap();
+        let diff_end = input.rfind("```").unwrap() + 3;
+        assert!(output.contains(&input[diff_start..diff_end]));
+        assert!(output.ends_with("See <https://example.com/docs>"));
+    }
+
+    #[test]
+    fn test_headers_and_tables_outside_code_still_converted() {
+        let input =
+            "## Plan\n\n```bash\n# install\ncargo build\n```\n\n| a | b |\n|---|---|\n| 1 | 2 |";
+        let output = markdown_to_slack(input);
+        assert!(output.contains("*Plan*"));
+        assert!(output.contains("```bash\n# install\ncargo build\n```"));
+        assert!(!output.contains("| a | b |"));
+    }
+
+    #[test]
+    fn test_url_with_underscores() {
+        let input = "Reviewed https://github.com/acme/my_repo/pull/4 and _https://example.com/a_b_";
+        let output = markdown_to_slack(input);
+        assert!(output.contains("<https://github.com/acme/my_repo/pull/4>"));
+        assert!(output.contains("_<https://example.com/a_b>_"));
+
+        // Links already in Slack format are left alone
+        let input = "See <https://github.com/acme/my_repo/pull/4|acme/my_repo#4>";
+        assert_eq!(markdown_to_slack(input), input);
+    }
+
     // NOTE: URLs with parentheses in markdown links are an edge case that would require
     // balanced parentheses matching. For now, use standalone URLs without parentheses,
     // or URL-encode the parentheses as %28 and %29.
     // Example that works: https://example.com/page%28test%29
+
+    #[test]
+    fn test_special_chars_escaped_in_prose() {
+        assert_eq!(
+            markdown_to_slack("if a < b && c > d"),
+            "if a &lt; b &amp;&amp; c &gt; d"
+        );
+        assert_eq!(markdown_to_slack("Vec<String>"), "Vec&lt;String&gt;");
+        // Already-escaped entities aren't escaped twice
+        assert_eq!(markdown_to_slack("a &lt; b &amp; c"), "a &lt; b &amp; c");
+    }
+
+    #[test]
+    fn test_special_chars_preserved_in_code_and_links() {
+        assert_eq!(
+            markdown_to_slack("Use `a < b && c > d` here"),
+            "Use `a < b && c > d` here"
+        );
+
+        let block = "```rust\nfn f() -> Option<&str> { None }\n```";
+        assert_eq!(markdown_to_slack(block), block);
+
+        assert_eq!(
+            markdown_to_slack("See [docs & notes](https://example.com/?a=1&b=2) for x > 1"),
+            "See <https://example.com/?a=1&b=2|docs & notes> for x &gt; 1"
+        );
+        assert_eq!(
+            markdown_to_slack("Ping <@U123> & <!here>: https://example.com/a?b=1&c=2"),
+            "Ping <@U123> &amp; <!here>: <https://example.com/a?b=1&c=2>"
+        );
+    }
+
+    #[test]
+    fn test_blockquote_marker_not_escaped() {
+        assert_eq!(
+            markdown_to_slack("> quoted a > b\n> > nested"),
+            "> quoted a &gt; b\n> > nested"
+        );
+    }
 }
@@ -0,0 +1,159 @@
+//! Turn `@username` references in agent output into Slack mentions
+//!
+//! Only users already in the metadata cache are resolved; anything else is left as
+//! plain text. Code, emails and URLs are never touched.
+
+use crate::metadata::UserInfo;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// `@handle` where the handle starts and ends with a letter or digit, so trailing
+/// punctuation (`@john.`) stays outside the match
+static MENTION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"@([A-Za-z0-9](?:[A-Za-z0-9._-]*[A-Za-z0-9])?)").unwrap());
+
+/// Lowercased handle -> user id for every cached human user, from both the username
+/// and a single-word display name. Handles shared by several users are dropped, and
+/// bots are skipped so the bot never mentions itself (or another bot) into a loop.
+pub fn user_handles<'a>(users: impl IntoIterator<Item = &'a UserInfo>) -> HashMap<String, String> {
+    let mut handles: HashMap<String, Option<String>> = HashMap::new();
+    for user in users.into_iter().filter(|user| !user.is_bot) {
+        let display = user
+            .display_name
+            .as_deref()
+            .filter(|name| !name.is_empty() && !name.contains(char::is_whitespace));
+        for handle in std::iter::once(user.name.as_str()).chain(display) {
+            handles
+                .entry(handle.to_lowercase())
+                .and_modify(|id| {
+                    if id.as_deref() != Some(user.id.as_str()) {
+                        *id = None;
+                    }
+                })
+                .or_insert_with(|| Some(user.id.clone()));
+        }
+    }
+    handles
+        .into_iter()
+        .filter_map(|(handle, id)| Some((handle, id?)))
+        .collect()
+}
+
+/// Rewrite known `@handle` references to `<@USERID>`, outside fenced and inline code
+pub fn linkify_mentions(text: &str, handles: &HashMap<String, String>) -> String {
+    if handles.is_empty() || !text.contains('@') {
+        return text.to_string();
+    }
+
+    let mut result = String::with_capacity(text.len());
+    let mut in_fence = false;
+    for line in text.split_inclusive('\n') {
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            result.push_str(line);
+            continue;
+        }
+        if in_fence {
+            result.push_str(line);
+            continue;
+        }
+        // Odd segments between backticks are inline code
+        for (i, segment) in line.split('`').enumerate() {
+            if i > 0 {
+                result.push('`');
+            }
+            if i % 2 == 0 {
+                result.push_str(&linkify_prose(segment, handles));
+            } else {
+                result.push_str(segment);
+            }
+        }
+    }
+    result
+}
+
+fn linkify_prose(text: &str, handles: &HashMap<String, String>) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for caps in MENTION_RE.captures_iter(text) {
+        let whole = caps.get(0).expect("match");
+        let before = text[..whole.start()].chars().next_back();
+        let after = text[whole.end()..].chars().next();
+
+        // Emails (`john@example.com`), URLs (`/@john`), existing `<@U…>` mentions and
+        // `@john@host` addresses are left alone
+        let embedded = before.is_some_and(|c| c.is_alphanumeric() || "@<./_-+:".contains(c))
+            || after.is_some_and(|c| c.is_alphanumeric() || c == '@' || c == '_');
+        let Some(user_id) = handles.get(&caps[1].to_lowercase()).filter(|_| !embedded) else {
+            continue;
+        };
+
+        result.push_str(&text[cursor..whole.start()]);
+        result.push_str(&format!("<@{user_id}>"));
+        cursor = whole.end();
+    }
+    result.push_str(&text[cursor..]);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn user(id: &str, name: &str, display: Option<&str>, is_bot: bool) -> UserInfo {
+        UserInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            real_name: None,
+            display_name: display.map(str::to_string),
+            email: None,
+            is_bot,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    fn handles() -> HashMap<String, String> {
+        let users = [
+            user("U01JOHN", "john", Some("Johnny"), false),
+            user("U02JANE", "jane.doe", Some("Jane Doe"), false),
+            user("B01BOT", "coder", None, true),
+        ];
+        user_handles(&users)
+    }
+
+    #[test]
+    fn test_known_username_is_linked() {
+        let text = "Thanks @john, and @Jane.Doe. Ping @Johnny (or @ghost).";
+        assert_eq!(
+            linkify_mentions(text, &handles()),
+            "Thanks <@U01JOHN>, and <@U02JANE>. Ping <@U01JOHN> (or @ghost)."
+        );
+    }
+
+    #[test]
+    fn test_email_is_not_rewritten() {
+        let text = "Mail john@example.com or @john@example.com about https://x.com/@john";
+        assert_eq!(linkify_mentions(text, &handles()), text);
+    }
+
+    #[test]
+    fn test_code_and_existing_mentions_untouched() {
+        let text = "Use `@john` here\n```\nnotify(@john)\n```\n<@U01JOHN> and @john_bot";
+        assert_eq!(linkify_mentions(text, &handles()), text);
+    }
+
+    #[test]
+    fn test_user_handles_skip_bots_and_ambiguous() {
+        let users = [
+            user("U01", "alex", Some("sam"), false),
+            user("U02", "sam", None, false),
+            user("B01", "coder", None, true),
+        ];
+        let handles = user_handles(&users);
+        assert_eq!(handles.get("alex").map(String::as_str), Some("U01"));
+        assert!(!handles.contains_key("sam"));
+        assert!(!handles.contains_key("coder"));
+    }
+}
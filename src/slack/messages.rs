@@ -1,21 +1,58 @@
-use crate::agent::AgentManager;
+use crate::agent::{AgentManager, AgentResponse, Answer, Enqueued, QueuedQuery, ToolAction};
+use crate::config::{DisplayConfig, LongResponseMode};
 use crate::error::{Result, SlackCoderError};
+use crate::filter::{FilterOutcome, FilterResult, InputFilterChain, SecretOutputFilter};
 use crate::logging::Timer;
 use crate::metadata::MetadataCache;
+use crate::session::SessionId;
+use crate::slack::audit::AuditRecord;
+use crate::slack::commands::display_command;
+use crate::slack::diff::{DiffAttachment, extract_large_diffs};
+use crate::slack::topic;
 use crate::slack::{
-    ChannelId, MessageTs, SlackClient, SlackCommandHandler, SlackMessage, ThreadTs, UsageMetrics,
-    markdown_to_slack,
+    ChannelId, MessageTs, RateLimiter, ReviewRequest, SlackClient, SlackCommandHandler,
+    SlackMessage, SlackMessageBuilder, SummaryTarget, ThreadTs, UsageMetrics, VerboseFeed,
+    emoji_to_shortcodes, linkify_mentions, markdown_to_slack, parse_command_text,
 };
-use claude_agent_sdk_rs::Message as ClaudeMessage;
-use futures::StreamExt;
+use crate::storage::{Workspace, format_bytes};
+use crate::telemetry;
+use claude_agent_sdk_rs::{ClaudeError, Message as ClaudeMessage};
+use futures::{Stream, StreamExt};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::time::timeout;
+use tokio::sync::mpsc;
 
+/// How long a timed-out query gets to wind down after it's interrupted
+const INTERRUPT_GRACE: Duration = Duration::from_secs(10);
+
+/// Longest request quoted at the top of a reply, in characters and lines
+const MAX_QUOTE_CHARS: usize = 200;
+const MAX_QUOTE_LINES: usize = 3;
+
+/// Longest error shown under an interrupted response, in characters
+const MAX_ERROR_SUMMARY_CHARS: usize = 200;
+
+/// Shown in an earlier answer while its edited request runs again
+const RERUN_MESSAGE: &str = "🔄 _Request edited, working on it again…_";
+
+/// The message a reply goes into: the placeholder to edit while one is showing, and
+/// the first message the reply was actually posted in
+#[derive(Debug, Default)]
+struct ReplySlot {
+    placeholder: Option<MessageTs>,
+    first: Option<MessageTs>,
+    /// The session the query ran in, once the agent has resolved it
+    session_id: Option<SessionId>,
+}
+
+#[derive(Clone)]
 pub struct MessageProcessor {
     slack_client: Arc<SlackClient>,
     agent_manager: Arc<AgentManager>,
     metadata_cache: Arc<MetadataCache>,
+    input_filters: InputFilterChain,
+    output_filter: SecretOutputFilter,
+    rate_limiter: RateLimiter,
 }
 
 impl MessageProcessor {
@@ -24,15 +61,38 @@ impl MessageProcessor {
         agent_manager: Arc<AgentManager>,
         metadata_cache: Arc<MetadataCache>,
     ) -> Self {
+        let settings = agent_manager.settings();
+        let filters = &settings.filters;
+        let input_filters = InputFilterChain::from_config(filters);
+        let output_filter = SecretOutputFilter::from_config(filters);
+        let rate_limiter = RateLimiter::new(settings.slack.rate_limit_per_minute);
         Self {
             slack_client,
             agent_manager,
             metadata_cache,
+            input_filters,
+            output_filter,
+            rate_limiter,
         }
     }
 
+    /// Replace the configured input filters (e.g. to add custom ones)
+    pub fn with_input_filters(mut self, input_filters: InputFilterChain) -> Self {
+        self.input_filters = input_filters;
+        self
+    }
+
     /// Process user message - forward to appropriate agent
     pub async fn process_message(&self, message: SlackMessage) -> Result<()> {
+        self.process(message, None).await
+    }
+
+    /// Run an edited request again, editing its earlier answer in place
+    pub async fn process_edit(&self, message: SlackMessage, previous: MessageTs) -> Result<()> {
+        self.process(message, Some(previous)).await
+    }
+
+    async fn process(&self, mut message: SlackMessage, replaces: Option<MessageTs>) -> Result<()> {
         let _timer = Timer::new("process_message");
 
         // Get enriched context
@@ -51,6 +111,22 @@ impl MessageProcessor {
         );
         let _guard = span.enter();
 
+        // Filter before anything else so a blocked secret isn't even logged
+        match self.input_filters.apply(&message.text) {
+            FilterOutcome::Accepted(text) => message.text = text,
+            FilterOutcome::Blocked { filter, reason } => {
+                tracing::warn!(filter = filter, "Message blocked by input filter");
+                self.slack_client
+                    .send_message(
+                        &message.channel,
+                        &format!("🛑 {}", reason),
+                        message.thread_ts.as_ref(),
+                    )
+                    .await?;
+                return Ok(());
+            }
+        }
+
         // Show message preview for context
         let message_preview = if message.text.len() > 150 {
             format!("{}...", message.text.chars().take(150).collect::<String>())
@@ -70,20 +146,107 @@ impl MessageProcessor {
             message_preview
         );
 
+        // Commands use the configured prefix; from here on they're in `/name` form
+        let command = parse_command_text(
+            &message.text,
+            &self.agent_manager.settings().slack.command_prefix,
+        );
+
+        // /summarize is forwarded to the agent as a standardized prompt
+        let mut query_text = message.text.clone();
+        if let Some(target) = command.as_deref().and_then(SummaryTarget::from_command) {
+            match target {
+                Ok(target) => {
+                    tracing::info!(target = ?target, "Processing summarize command");
+                    query_text = target.build_prompt();
+                }
+                Err(e) => {
+                    self.slack_client
+                        .send_message(
+                            &message.channel,
+                            &format!("❌ {}", e),
+                            message.thread_ts.as_ref(),
+                        )
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
         // Check if message is a command
-        if message.text.starts_with('/') {
-            tracing::info!(command = %message.text, "Processing command");
-            let command_handler = SlackCommandHandler::new(self.slack_client.clone());
+        else if let Some(command) = &command {
+            tracing::info!(command = %command, "Processing command");
+            let command_handler =
+                SlackCommandHandler::new(self.slack_client.clone(), self.metadata_cache.clone());
             return command_handler
-                .handle_command(&message.text, &message.channel, &self.agent_manager)
+                .handle_command(
+                    command,
+                    &message.channel,
+                    &message.user,
+                    &message.files,
+                    &self.agent_manager,
+                )
                 .await;
         }
+        // A pasted PR or commit link asking for a review gets a framed review task
+        else if let Some(review) = ReviewRequest::detect(&message.text) {
+            tracing::info!(review = %review, "Processing review request");
+            query_text = review.build_prompt();
+        }
+
+        // Commands are cheap; only queries for the agent count against the rate limit
+        if !self
+            .agent_manager
+            .settings()
+            .slack
+            .is_admin(message.user.as_str())
+            && let Err(retry_after) = self.rate_limiter.check(&message.user)
+        {
+            tracing::warn!(
+                retry_after_secs = retry_after.as_secs(),
+                "User rate limited, message dropped"
+            );
+            self.slack_client
+                .send_message(
+                    &message.channel,
+                    &self.rate_limiter.throttled_message(retry_after),
+                    message.thread_ts.as_ref(),
+                )
+                .await?;
+            return Ok(());
+        }
 
-        // Check if channel has configured agent
-        let has_agent = self.agent_manager.has_agent(&message.channel);
-        tracing::debug!(has_agent = has_agent, "Agent availability check");
+        // Check if channel has configured agent (recreating it if it was reaped while idle)
+        let status = match self.agent_manager.restore_agent(&message.channel).await {
+            Ok(status) => status,
+            Err(e) => {
+                self.agent_manager
+                    .recent_errors()
+                    .record(&message.channel, "agent restore", &e);
+                return Err(e);
+            }
+        };
+        tracing::debug!(status = ?status, "Agent availability check");
+
+        if status.is_partial() {
+            tracing::warn!(
+                status = ?status,
+                "Incomplete setup {}",
+                message.channel.log_format()
+            );
+            self.slack_client
+                .send_message(
+                    &message.channel,
+                    &format!(
+                        "⚠️ *This channel's setup is incomplete:* {}.\n\nPlease mention me with the repository name (`owner/repo-name`) to run setup again.",
+                        status.missing().unwrap_or_default()
+                    ),
+                    message.thread_ts.as_ref(),
+                )
+                .await?;
+            return Ok(());
+        }
 
-        if !has_agent {
+        if !status.is_ready() {
             tracing::info!("No agent configured, prompting for setup");
             self.slack_client
                 .send_message(
@@ -95,199 +258,1437 @@ impl MessageProcessor {
             return Ok(());
         }
 
-        // Forward to agent
-        tracing::debug!("Forwarding to repository agent");
-        // Use existing thread_ts if in thread, otherwise use message ts to create thread
-        let reply_thread_ts = message
-            .thread_ts
-            .as_ref()
-            .map(|t| t.clone())
-            .unwrap_or_else(|| ThreadTs::new(message.ts.as_str()));
-
-        self.forward_to_agent(
-            &message.text,
-            &message.channel,
-            &reply_thread_ts,
+        // Queue for the agent; a per-channel worker sends queries one at a time
+        let query = QueuedQuery {
+            text: query_text,
+            request: message.text.clone(),
+            user: message.user.clone(),
+            thread_ts: message.thread_ts.clone(),
+            ts: message.ts.clone(),
+            replaces,
+        };
+        let reply_thread_ts = Self::reply_thread_ts(
+            message.thread_ts.as_ref(),
             &message.ts,
+            self.agent_manager.settings().display.reply_in_thread,
+        );
+
+        match self
+            .agent_manager
+            .query_queues()
+            .push(&message.channel, query)
+        {
+            Enqueued::Started => {
+                tracing::debug!("Forwarding to repository agent");
+                self.spawn_queue_worker(message.channel.clone());
+            }
+            Enqueued::Queued { position } => {
+                tracing::info!(position = position, "Agent busy, message queued");
+                self.slack_client
+                    .send_message(
+                        &message.channel,
+                        &format!(
+                            "⏳ *Queued* - the agent is busy with a previous task. \
+                             Your message is #{} in line and will run automatically.",
+                            position
+                        ),
+                        reply_thread_ts.as_ref(),
+                    )
+                    .await?;
+            }
+            Enqueued::Full => {
+                tracing::warn!("Agent busy and queue full, message dropped");
+                self.slack_client
+                    .send_message(
+                        &message.channel,
+                        "🚫 *Queue full* - the agent is busy and too many messages are already waiting. \
+                         Please try again once the current tasks finish.",
+                        reply_thread_ts.as_ref(),
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Drain the channel's queue in the background, one query at a time
+    fn spawn_queue_worker(&self, channel: ChannelId) {
+        let processor = self.clone();
+        tokio::spawn(async move {
+            let busy_topic = if processor.agent_manager.settings().display.busy_topic {
+                topic::mark_busy(&processor.slack_client, &channel).await
+            } else {
+                None
+            };
+
+            let queues = processor.agent_manager.query_queues().clone();
+            while let Some(query) = queues.next(&channel) {
+                if let Err(e) = processor.forward_to_agent(&query, &channel).await {
+                    tracing::error!(error = %e, channel_id = %channel, "Queued query failed");
+                    processor
+                        .agent_manager
+                        .recent_errors()
+                        .record(&channel, "query", &e);
+                }
+            }
+            tracing::debug!(channel_id = %channel, "Query queue drained");
+
+            // Failed queries are logged above, so this runs however the tasks ended
+            if let Some(busy_topic) = busy_topic {
+                topic::restore(&processor.slack_client, &channel, busy_topic).await;
+            }
+        });
+    }
+
+    /// Pick the thread a reply goes to.
+    ///
+    /// Messages already in a thread are answered there. Otherwise `reply_in_thread`
+    /// starts a thread under the user's message, or replies at channel level when
+    /// disabled. Either way the agent keeps the same per-channel session, so
+    /// top-level replies lose no conversation context.
+    fn reply_thread_ts(
+        thread_ts: Option<&ThreadTs>,
+        message_ts: &MessageTs,
+        reply_in_thread: bool,
+    ) -> Option<ThreadTs> {
+        match thread_ts {
+            Some(ts) => Some(ts.clone()),
+            None if reply_in_thread => Some(ThreadTs::from_parent(message_ts)),
+            None => None,
+        }
+    }
+
+    /// Forward message to repository agent and stream response
+    async fn forward_to_agent(&self, query: &QueuedQuery, channel: &ChannelId) -> Result<()> {
+        let reply_thread_ts = Self::reply_thread_ts(
+            query.thread_ts.as_ref(),
+            &query.ts,
+            self.agent_manager.settings().display.reply_in_thread,
+        );
+        let thread_ts = reply_thread_ts.as_ref();
+
+        // A repository deleted from under the agent would leave Claude with a broken cwd
+        if let Some(guidance) = Self::missing_repo_guidance(
+            self.agent_manager.workspace(),
+            channel,
+            &self.agent_manager.settings().slack.command_prefix,
         )
         .await
+        {
+            tracing::warn!("Repository directory missing {}", channel.log_format());
+            self.slack_client
+                .send_message(channel, &guidance, thread_ts)
+                .await?;
+            return Ok(());
+        }
+
+        // Acknowledge right away; the placeholder is edited into the first reply message.
+        // A re-run of an edited request uses its earlier answer as the placeholder.
+        let mut reply = ReplySlot {
+            placeholder: match &query.replaces {
+                Some(previous) => self.mark_rerun(channel, previous).await,
+                None => self.post_placeholder(channel, thread_ts).await,
+            },
+            ..ReplySlot::default()
+        };
+        let quote = self
+            .agent_manager
+            .settings()
+            .display
+            .quote_request
+            .then(|| Self::quote_request(&query.request))
+            .filter(|quote| !quote.is_empty());
+        let result = self
+            .run_query(query, quote.as_deref(), channel, thread_ts, &mut reply)
+            .await;
+
+        if let Some(response_ts) = reply.first {
+            self.agent_manager.answered_requests().record(
+                channel,
+                Answer {
+                    request_ts: query.ts.clone(),
+                    thread_ts: query.thread_ts.clone(),
+                    response_ts,
+                    session_id: reply.session_id.clone(),
+                },
+            );
+        }
+
+        // A failed query never reached the paths that finish the board
+        if result.is_err()
+            && let Err(e) = self
+                .agent_manager
+                .progress_tracker()
+                .finish_progress(channel)
+                .await
+        {
+            tracing::warn!(error = %e, "Failed to finish progress board");
+        }
+
+        // Still showing after a failure, cancellation or empty response: take it down
+        if let Some(ts) = reply.placeholder
+            && let Err(e) = self.slack_client.delete_message(channel, &ts).await
+        {
+            tracing::warn!(error = %e, "Failed to remove placeholder message");
+        }
+        result
     }
 
-    /// Forward message to repository agent and stream response
-    async fn forward_to_agent(
+    /// Post the configured "working on it" message, if any
+    async fn post_placeholder(
         &self,
-        text: &str,
         channel: &ChannelId,
-        thread_ts: &ThreadTs,
-        _message_ts: &MessageTs,
+        thread_ts: Option<&ThreadTs>,
+    ) -> Option<MessageTs> {
+        let settings = self.agent_manager.settings();
+        let text = settings.display.placeholder_message.as_deref()?;
+        match self
+            .slack_client
+            .send_message(channel, text, thread_ts)
+            .await
+        {
+            Ok(ts) => Some(ts),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to post placeholder message");
+                None
+            }
+        }
+    }
+
+    /// Show that an edited request is running again in its earlier answer, which then
+    /// stands in for the placeholder. `None` if the answer can't be edited (e.g. it was
+    /// deleted), in which case the new answer is posted as a fresh message.
+    async fn mark_rerun(&self, channel: &ChannelId, previous: &MessageTs) -> Option<MessageTs> {
+        match self
+            .slack_client
+            .update_message(channel, previous, RERUN_MESSAGE)
+            .await
+        {
+            Ok(()) => Some(previous.clone()),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to edit previous answer for re-run");
+                None
+            }
+        }
+    }
+
+    /// Send a query to the channel's agent and post its response, editing the
+    /// placeholder (taken from `reply`) into the first message
+    async fn run_query(
+        &self,
+        query: &QueuedQuery,
+        quote: Option<&str>,
+        channel: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+        reply: &mut ReplySlot,
     ) -> Result<()> {
         tracing::debug!("Acquiring agent lock");
         // Get agent from manager (returns Arc<Mutex<RepoAgent>>)
         let agent_mutex = self.agent_manager.get_repo_agent(channel).await?;
 
-        // Try to acquire lock with timeout to avoid blocking forever
-        let agent_lock = timeout(Duration::from_secs(3), agent_mutex.lock()).await;
+        // The queue worker is the only sender, so this only waits on short-lived
+        // holders such as /new-session or /restart
+        let mut agent = agent_mutex.lock().await;
+        tracing::info!("Agent lock acquired, sending query to Claude");
+
+        // Mark the query as in flight until this function returns
+        let active_query = self.agent_manager.active_queries().start(channel);
+
+        // Send query to agent
+        // Replies go to the thread the conversation continues in, so it names the session;
+        // unthreaded channel messages share the channel session. Usage is recorded against
+        // the session the query actually ran in.
+        let attributed_user = self
+            .agent_manager
+            .settings()
+            .agent
+            .user_attribution
+            .attribute(query.user.as_str());
+        let session_id = agent
+            .query(&query.text, thread_ts, attributed_user.as_deref())
+            .await?;
+        reply.session_id = Some(session_id.clone());
+        tracing::debug!("Query sent, streaming response");
+
+        // Channels with `/verbose on` watch the agent's tool calls as they happen
+        let feed = self.verbose_feed(channel, thread_ts).await;
+
+        // Stream response - lock is held during entire streaming
+        let query_timeout = self.agent_manager.settings().agent.query_timeout();
+        let outcome = collect_response(
+            agent.receive_response(),
+            query_timeout,
+            active_query.cancelled(),
+            feed.as_ref().map(VerboseFeed::sender),
+        )
+        .await?;
+        if let Some(feed) = feed {
+            feed.finish().await;
+        }
+
+        let response = match outcome {
+            StreamOutcome::Completed(response) => response,
+            StreamOutcome::Cancelled => return Ok(()),
+            StreamOutcome::TimedOut { partial } => {
+                let recovered = agent.interrupt(INTERRUPT_GRACE).await;
+                // Release the lock before a restart needs it
+                drop(agent);
+                return self
+                    .report_timeout(
+                        channel,
+                        thread_ts,
+                        query_timeout,
+                        &partial,
+                        recovered,
+                        reply,
+                    )
+                    .await;
+            }
+            StreamOutcome::Failed { partial, error } => {
+                drop(agent);
+                return self
+                    .report_interrupted(channel, thread_ts, &partial, error, reply)
+                    .await;
+            }
+        };
+        let mut final_result = response.text();
+        // Quoted before filtering, so a secret in the request isn't echoed back either
+        if let Some(quote) = quote
+            && !final_result.is_empty()
+        {
+            final_result = format!("{}\n\n{}", quote, final_result);
+        }
 
-        let mut agent = match agent_lock {
-            Ok(agent) => {
-                tracing::info!("Agent lock acquired, sending query to Claude");
-                agent
+        // Add this query to the session's running totals
+        let metrics = response
+            .result
+            .as_ref()
+            .map(UsageMetrics::from_result_message);
+        if let Some(metrics) = &metrics {
+            telemetry::metrics().record_query(metrics);
+            if let Err(e) = self
+                .agent_manager
+                .record_usage(channel, &session_id, metrics)
+                .await
+            {
+                tracing::warn!(error = %e, "Failed to record session usage");
             }
-            Err(_) => {
-                tracing::warn!(timeout_secs = 3, "Agent lock timeout - agent busy");
+        }
+        self.post_audit(channel, query, metrics.clone(), &session_id);
 
-                // Send user-friendly message as reply in the same thread
-                self.slack_client
-                    .send_message(
+        // Mask (or withhold) any secrets the agent echoed before anything is posted
+        match self.output_filter.apply(&final_result) {
+            FilterResult::Pass => {}
+            FilterResult::Rewrite(masked) => final_result = masked,
+            FilterResult::Block(notice) => final_result = notice,
+        }
+
+        // Files and commands can carry secrets too, so the summary is filtered as well
+        let actions = if self.agent_manager.settings().display.show_actions {
+            let repo_root = self.agent_manager.workspace().repo_path(channel);
+            response
+                .actions_summary(Some(&repo_root))
+                .and_then(|summary| match self.output_filter.apply(&summary) {
+                    FilterResult::Pass => Some(summary),
+                    FilterResult::Rewrite(masked) => Some(masked),
+                    FilterResult::Block(_) => None,
+                })
+        } else {
+            None
+        };
+
+        if self.agent_manager.settings().display.resolve_mentions {
+            final_result = linkify_mentions(&final_result, &self.metadata_cache.user_handles());
+        }
+
+        // Send response to Slack
+        if !final_result.is_empty() {
+            if let Some(metrics) = &metrics {
+                tracing::debug!(
+                    tokens = metrics.total_tokens,
+                    cost_usd = metrics.cost_usd.unwrap_or(0.0),
+                    duration_ms = metrics.duration_ms,
+                    "Appending detailed metrics to result"
+                );
+            }
+            // Metrics and the completion marker go in the same message as the result
+            let has_metrics = metrics.is_some();
+            let settings = self.agent_manager.settings();
+            let display = &settings.display;
+            let whole_snippet = display.long_response_mode == LongResponseMode::Snippet
+                && Self::needs_snippet(&final_result, display);
+
+            // Large diffs are attached as files unless the whole response already is
+            let diffs = if whole_snippet {
+                Vec::new()
+            } else {
+                let (rewritten, diffs) =
+                    extract_large_diffs(&final_result, display.diff_snippet_bytes);
+                final_result = rewritten;
+                diffs
+            };
+
+            let message = SlackMessageBuilder::new(self.render_markdown(&final_result))
+                .with_actions(actions)
+                .with_metrics(metrics)
+                .with_completion(has_metrics);
+            let metrics_footer = message.footer();
+
+            let message_len = match display.long_response_mode {
+                LongResponseMode::Snippet if whole_snippet => {
+                    self.send_as_snippet(channel, thread_ts, &final_result, &metrics_footer, reply)
+                        .await?
+                }
+                LongResponseMode::Truncate => {
+                    self.send_truncated(
                         channel,
-                        "⏳ *Agent is currently processing another request*\n\n\
-                         Your message has been received, but the agent is busy with a previous task. \
-                         Please wait for the current task to complete and try again in a moment.\n\n\
-                         *Tip*: Long-running tasks (like comprehensive code analysis or documentation) \
-                         can take several minutes. You can check the latest progress update above.",
-                        Some(thread_ts), // This ensures it's a reply in the thread
+                        thread_ts,
+                        &final_result,
+                        &metrics_footer,
+                        display.truncate_upload_full,
+                        reply,
                     )
-                    .await?;
+                    .await?
+                }
+                _ => {
+                    self.send_chunked(channel, thread_ts, &message.build(), reply)
+                        .await?
+                }
+            };
 
-                return Ok(());
+            tracing::info!(
+                message_len = message_len,
+                has_metrics = response.result.is_some(),
+                diff_count = diffs.len(),
+                "Response sent with metrics"
+            );
+            self.upload_diffs(channel, thread_ts, diffs).await?;
+
+            // Collapse or remove the progress board so channels stay tidy
+            if let Err(e) = self
+                .agent_manager
+                .progress_tracker()
+                .finish_progress(channel)
+                .await
+            {
+                tracing::warn!(error = %e, "Failed to finish progress board");
+            }
+        } else {
+            tracing::warn!("No response received from agent");
+        }
+
+        Ok(())
+    }
+
+    /// Post the query's record to `AUDIT_CHANNEL_ID`, if one is set. It runs in the
+    /// background, so a failed audit post never delays or fails the reply.
+    fn post_audit(
+        &self,
+        channel: &ChannelId,
+        query: &QueuedQuery,
+        metrics: Option<UsageMetrics>,
+        session_id: &str,
+    ) {
+        let Some(audit_channel_id) = self.agent_manager.settings().slack.audit_channel_id.clone()
+        else {
+            return;
+        };
+        let audit_channel = match ChannelId::parse(&audit_channel_id) {
+            Ok(audit_channel) => audit_channel,
+            Err(e) => {
+                tracing::warn!(error = %e, "Invalid AUDIT_CHANNEL_ID, not posting audit record");
+                return;
             }
         };
 
-        // Send query to agent
-        agent.query(text).await?;
-        tracing::debug!("Query sent, streaming response");
+        // The audit channel may be read more widely than the channel the secret came from
+        let prompt = match self.output_filter.apply(&query.request) {
+            FilterResult::Pass => query.request.clone(),
+            FilterResult::Rewrite(masked) => masked,
+            FilterResult::Block(_) => "_(withheld by the output filter)_".to_string(),
+        };
+        let slack_client = Arc::clone(&self.slack_client);
+        let metadata_cache = Arc::clone(&self.metadata_cache);
+        let channel = channel.clone();
+        let user = query.user.clone();
+        let session_id = session_id.to_string();
+        tokio::spawn(async move {
+            let context = metadata_cache
+                .log_context(channel.as_str(), user.as_str())
+                .await;
+            let record = AuditRecord {
+                context: &context,
+                prompt: &prompt,
+                metrics: metrics.as_ref(),
+                session_id: &session_id,
+            };
+            if let Err(e) = slack_client
+                .send_message(&audit_channel, &record.format_slack_message(), None)
+                .await
+            {
+                tracing::warn!(error = %e, "Failed to post audit record {}", channel.log_format());
+            }
+        });
+    }
 
-        // Stream response - lock is held during entire streaming
-        let mut stream = agent.receive_response();
-        let mut final_result = String::new();
-        let mut result_message = None;
-        let mut message_count = 0;
+    /// Start a verbose feed if the channel turned it on
+    async fn verbose_feed(
+        &self,
+        channel: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+    ) -> Option<VerboseFeed> {
+        let workspace = self.agent_manager.workspace();
+        let verbose = match workspace.load_channel_config(channel).await {
+            Ok(config) => config.verbose.unwrap_or(false),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to load channel config for verbose mode");
+                false
+            }
+        };
+        verbose.then(|| {
+            VerboseFeed::spawn(
+                Arc::clone(&self.slack_client),
+                channel.clone(),
+                thread_ts.cloned(),
+                workspace.repo_path(channel),
+                self.output_filter.clone(),
+            )
+        })
+    }
 
-        while let Some(message) = stream.next().await {
-            message_count += 1;
-            tracing::debug!(message_num = message_count, "Received message from Claude");
+    /// Tell the channel a query hit `QUERY_TIMEOUT_SECS`, with whatever the agent had
+    /// written so far. An agent that didn't settle after the interrupt is restarted.
+    async fn report_timeout(
+        &self,
+        channel: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+        query_timeout: Duration,
+        partial: &str,
+        recovered: bool,
+        reply: &mut ReplySlot,
+    ) -> Result<()> {
+        tracing::warn!(
+            timeout_secs = query_timeout.as_secs(),
+            partial_len = partial.len(),
+            recovered = recovered,
+            "Query timed out {}",
+            channel.log_format()
+        );
+
+        if !recovered && let Err(e) = self.agent_manager.restart_agent(channel).await {
+            tracing::warn!(error = %e, "Failed to restart agent after timeout");
+        }
+
+        if let Err(e) = self
+            .agent_manager
+            .progress_tracker()
+            .finish_progress(channel)
+            .await
+        {
+            tracing::warn!(error = %e, "Failed to finish progress board");
+        }
 
-            let message = message.map_err(|e| SlackCoderError::ClaudeAgent(e.to_string()))?;
+        let partial = match self.output_filter.apply(partial) {
+            FilterResult::Pass => partial.to_string(),
+            FilterResult::Rewrite(masked) => masked,
+            FilterResult::Block(notice) => notice,
+        };
+        let message = Self::timeout_message(query_timeout, &partial, recovered);
+        self.send_chunked(channel, thread_ts, &message, reply)
+            .await?;
+        Ok(())
+    }
+
+    /// Post what the agent had written before its response stream failed, then return
+    /// the error so it's still logged and counted. With nothing written, only the error
+    /// is returned, as before.
+    async fn report_interrupted(
+        &self,
+        channel: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+        partial: &str,
+        error: ClaudeError,
+        reply: &mut ReplySlot,
+    ) -> Result<()> {
+        let error = SlackCoderError::ClaudeAgent(error.to_string());
+        if partial.trim().is_empty() {
+            return Err(error);
+        }
+        tracing::warn!(
+            error = %error,
+            partial_len = partial.len(),
+            "Response stream failed, posting partial output {}",
+            channel.log_format()
+        );
+
+        if let Err(e) = self
+            .agent_manager
+            .progress_tracker()
+            .finish_progress(channel)
+            .await
+        {
+            tracing::warn!(error = %e, "Failed to finish progress board");
+        }
+
+        let partial = match self.output_filter.apply(partial) {
+            FilterResult::Pass => partial.to_string(),
+            FilterResult::Rewrite(masked) => masked,
+            FilterResult::Block(notice) => notice,
+        };
+        // Stream errors can echo tool output, so the summary is filtered like the answer
+        let error_text = error.to_string();
+        let summary = match self.output_filter.apply(&error_text) {
+            FilterResult::Pass => error_text,
+            FilterResult::Rewrite(masked) => masked,
+            FilterResult::Block(_) => "withheld by the output filter".to_string(),
+        };
+        let message = Self::interrupted_message(&partial, &summary);
+        self.send_chunked(channel, thread_ts, &message, reply)
+            .await?;
+        Err(error)
+    }
+
+    /// Partial output of a response whose stream failed, with a one-line error summary
+    fn interrupted_message(partial: &str, error: &str) -> String {
+        let mut summary: String = error
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .take(MAX_ERROR_SUMMARY_CHARS)
+            .collect();
+        if summary.len() < error.trim_end().len() {
+            // Cut short, or more lines followed
+            summary.push('…');
+        }
+        format!(
+            "{}\n\n⚠️ *The response was interrupted:* `{}`",
+            markdown_to_slack(partial),
+            summary.replace('`', "'")
+        )
+    }
 
-            if let ClaudeMessage::Result(res) = message {
-                final_result = res.result.clone().unwrap_or_default();
-                result_message = Some(res);
-                tracing::info!(result_len = final_result.len(), "Received final result");
+    /// The request as a Slack blockquote, cut to a few lines. Code fences are dropped
+    /// and code lines shown inline, since a fence can't open inside a quote.
+    fn quote_request(request: &str) -> String {
+        let mut lines = Vec::new();
+        let mut in_code = false;
+        let mut chars = 0;
+        let mut truncated = false;
+        for line in request.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with("```") {
+                in_code = !in_code;
+                continue;
+            }
+            // Nested `>` would open a second quote level
+            let text = trimmed.trim_start_matches('>').trim_start();
+            if text.is_empty() {
+                continue;
+            }
+            if lines.len() == MAX_QUOTE_LINES || chars == MAX_QUOTE_CHARS {
+                truncated = true;
                 break;
             }
+            let available = MAX_QUOTE_CHARS - chars;
+            truncated = text.chars().count() > available;
+            let mut text: String = text.chars().take(available).collect();
+            chars += text.chars().count();
+            if in_code {
+                text = format!("`{}`", text.replace('`', "'"));
+            }
+            lines.push(format!("> {}", text));
+            if truncated {
+                break;
+            }
+        }
+        if truncated && let Some(last) = lines.last_mut() {
+            last.push_str(" …");
+        }
+        lines.join("\n")
+    }
+
+    /// Notice for a query stopped by the per-query timeout
+    fn timeout_message(query_timeout: Duration, partial: &str, recovered: bool) -> String {
+        let mut message = format!(
+            "⏱️ *This took too long and was stopped* (limit: {} minutes). Try splitting the task into smaller steps.",
+            query_timeout.as_secs().div_ceil(60)
+        );
+        if !recovered {
+            message
+                .push_str("\n\nThe agent was restarted, so the next message starts a new session.");
+        }
+        if !partial.trim().is_empty() {
+            message.push_str("\n\n*Partial output:*\n");
+            message.push_str(&markdown_to_slack(partial));
         }
+        message
+    }
 
-        // Send response to Slack
-        if !final_result.is_empty() {
-            // Convert markdown to Slack format
-            let slack_formatted = markdown_to_slack(&final_result);
+    /// User-facing guidance if the channel's repository directory has disappeared
+    /// (manual cleanup, disk issues), or `None` if it's still there
+    async fn missing_repo_guidance(
+        workspace: &Workspace,
+        channel: &ChannelId,
+        prefix: &str,
+    ) -> Option<String> {
+        if tokio::fs::metadata(workspace.repo_path(channel))
+            .await
+            .is_ok_and(|m| m.is_dir())
+        {
+            return None;
+        }
+
+        let repo_name = workspace
+            .load_channel_config(channel)
+            .await
+            .ok()
+            .and_then(|config| config.repo_name);
+        let setup_hint = match &repo_name {
+            Some(repo_name) => format!("mention me with `{}`", repo_name),
+            None => "mention me with `owner/repo-name`".to_string(),
+        };
+
+        Some(format!(
+            "📂 *This channel's repository workspace is missing.*\n\nThe local clone was removed, so I can't work on it. Run `{}` to clone it again, or {} to redo setup.",
+            display_command("/repo resync", prefix),
+            setup_hint
+        ))
+    }
+
+    /// Post a message, splitting it into numbered chunks if it exceeds Slack's size limit.
+    /// Returns the total length posted.
+    async fn send_chunked(
+        &self,
+        channel: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+        final_message: &str,
+        reply: &mut ReplySlot,
+    ) -> Result<usize> {
+        tracing::debug!(
+            final_len = final_message.len(),
+            "Prepared message with metrics"
+        );
 
-            // Append detailed metrics footer if available (consolidated - single message!)
-            let final_message = if let Some(result_msg) = &result_message {
-                let metrics = UsageMetrics::from_result_message(result_msg);
+        // Split into chunks if response is too large (Slack has 40KB limit)
+        let limits = self.slack_client.limits();
 
-                let cost_str = if let Some(cost) = metrics.cost_usd {
-                    format!("${:.4} USD", cost)
+        if !limits.fits_message(final_message) {
+            let chunks = limits.chunk_message(final_message);
+            tracing::warn!(
+                message_len = final_message.len(),
+                chunk_count = chunks.len(),
+                "Message exceeds size limit, splitting into chunks"
+            );
+
+            let total = chunks.len();
+            for (i, chunk_text) in chunks.iter().enumerate() {
+                let prefix = if i == 0 {
+                    String::new()
                 } else {
-                    "N/A".to_string()
+                    format!("*(continued {}/{})*\n\n", i + 1, total)
                 };
 
-                let duration_sec = metrics.duration_ms as f64 / 1000.0;
-                let api_duration_sec = metrics.duration_api_ms as f64 / 1000.0;
-
-                // Build detailed metrics section
-                let mut metrics_footer = format!(
-                    "\n\n---\n📊 *Query Metrics*\n\
-                     • Tokens: {} input + {} output = *{} total*\n\
-                     • Cost: {}\n\
-                     • Duration: {:.2}s (API: {:.2}s)\n\
-                     • Turns: {}\n\
-                     • Session: `{}`",
-                    metrics.input_tokens,
-                    metrics.output_tokens,
-                    metrics.total_tokens,
-                    cost_str,
-                    duration_sec,
-                    api_duration_sec,
-                    metrics.num_turns,
-                    metrics.session_id
-                );
+                self.post_reply(
+                    channel,
+                    thread_ts,
+                    &format!("{}{}", prefix, chunk_text),
+                    reply,
+                )
+                .await?;
+            }
+        } else {
+            self.post_reply(channel, thread_ts, final_message, reply)
+                .await?;
+        }
 
-                // Add cache info if present
-                if metrics.cache_creation_input_tokens > 0 || metrics.cache_read_input_tokens > 0 {
-                    metrics_footer.push_str(&format!(
-                        "\n• Cache: {} created, {} read",
-                        metrics.cache_creation_input_tokens, metrics.cache_read_input_tokens
-                    ));
-                }
+        Ok(final_message.len())
+    }
 
-                // Add task complete indicator
-                metrics_footer.push_str("\n\n✅ *Task Complete* - All operations finished!");
+    /// Post as much of the response as fits in one message, with a note on how much was
+    /// cut, and optionally the full text as a snippet. Returns the length posted.
+    async fn send_truncated(
+        &self,
+        channel: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+        final_result: &str,
+        metrics_footer: &str,
+        upload_full: bool,
+        reply: &mut ReplySlot,
+    ) -> Result<usize> {
+        let body = self.render_markdown(final_result);
+        let Some((kept, omitted)) = self
+            .slack_client
+            .limits()
+            .truncate_message(&body, metrics_footer.len())
+        else {
+            let final_message = format!("{}{}", body, metrics_footer);
+            self.post_reply(channel, thread_ts, &final_message, reply)
+                .await?;
+            return Ok(final_message.len());
+        };
 
-                tracing::debug!(
-                    tokens = metrics.total_tokens,
-                    cost_usd = metrics.cost_usd.unwrap_or(0.0),
-                    duration_ms = metrics.duration_ms,
-                    "Appending detailed metrics to result"
-                );
+        tracing::warn!(
+            message_len = body.len(),
+            omitted_chars = omitted,
+            "Message exceeds size limit, truncating"
+        );
 
-                format!("{}{}", slack_formatted, metrics_footer)
-            } else {
-                slack_formatted
+        // Losing the full text is acceptable; losing the reply is not
+        let uploaded = upload_full
+            && match self
+                .slack_client
+                .upload_snippet(channel, thread_ts, "response.md", final_result, "markdown")
+                .await
+            {
+                Ok(_) => true,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Failed to upload full response");
+                    false
+                }
             };
 
-            tracing::debug!(
-                original_len = final_result.len(),
-                final_len = final_message.len(),
-                "Prepared message with metrics"
-            );
+        let final_message = format!(
+            "{}{}{}",
+            kept,
+            Self::truncation_notice(omitted, uploaded),
+            metrics_footer
+        );
+        self.post_reply(channel, thread_ts, &final_message, reply)
+            .await?;
+        Ok(final_message.len())
+    }
 
-            // Split into chunks if response is too large (Slack has 40KB limit)
-            const MAX_SLACK_MESSAGE_SIZE: usize = 39000; // Leave some margin
+    /// Footer for a truncated response (fits in [`TRUNCATION_RESERVE`](crate::slack::limits::TRUNCATION_RESERVE))
+    fn truncation_notice(omitted: usize, uploaded: bool) -> String {
+        let attached = if uploaded {
+            " - full text attached as `response.md`"
+        } else {
+            ""
+        };
+        format!(
+            "\n\n_…(response truncated, {} chars omitted){}_",
+            omitted, attached
+        )
+    }
 
-            if final_message.len() > MAX_SLACK_MESSAGE_SIZE {
-                let chunk_count = final_message.len().div_ceil(MAX_SLACK_MESSAGE_SIZE);
-                tracing::warn!(
-                    message_len = final_message.len(),
-                    chunk_count = chunk_count,
-                    "Message exceeds size limit, splitting into chunks"
-                );
+    /// Upload diffs taken out of a reply, posting any that fail to upload inline instead
+    async fn upload_diffs(
+        &self,
+        channel: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+        diffs: Vec<DiffAttachment>,
+    ) -> Result<()> {
+        for diff in diffs {
+            if let Err(e) = self
+                .slack_client
+                .upload_snippet(channel, thread_ts, &diff.filename, &diff.content, "diff")
+                .await
+            {
+                tracing::warn!(error = %e, filename = %diff.filename, "Failed to upload diff");
+                let inline = format!("`{}`\n```diff\n{}```", diff.filename, diff.content);
+                self.send_chunked(channel, thread_ts, &inline, &mut ReplySlot::default())
+                    .await?;
+            }
+        }
+        Ok(())
+    }
 
-                for (i, chunk) in final_message
-                    .as_bytes()
-                    .chunks(MAX_SLACK_MESSAGE_SIZE)
-                    .enumerate()
-                {
-                    let chunk_text = String::from_utf8_lossy(chunk).to_string();
-                    let prefix = if i == 0 {
-                        String::new()
-                    } else {
-                        format!("*(continued {}/...)*\n\n", i + 1)
-                    };
+    /// Convert a response to Slack mrkdwn, with emoji as `:codes:` if so configured
+    fn render_markdown(&self, markdown: &str) -> String {
+        let text = markdown_to_slack(markdown);
+        if self.agent_manager.settings().display.emoji_shortcodes {
+            emoji_to_shortcodes(&text)
+        } else {
+            text
+        }
+    }
 
-                    self.slack_client
-                        .send_message(
-                            channel,
-                            &format!("{}{}", prefix, chunk_text),
-                            Some(thread_ts),
-                        )
-                        .await?;
-                }
-            } else {
-                self.slack_client
-                    .send_message(channel, &final_message, Some(thread_ts))
-                    .await?;
+    /// Upload the full response as a markdown snippet in the thread, with a short
+    /// preview message. Returns the length of the preview message.
+    async fn send_as_snippet(
+        &self,
+        channel: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+        final_result: &str,
+        metrics_footer: &str,
+        reply: &mut ReplySlot,
+    ) -> Result<usize> {
+        tracing::info!(
+            result_len = final_result.len(),
+            "Posting long response as a snippet"
+        );
+
+        self.slack_client
+            .upload_snippet(channel, thread_ts, "response.md", final_result, "markdown")
+            .await?;
+
+        let summary = format!(
+            "{}\n\n📎 _Full response ({}) attached as `response.md`_{}",
+            self.render_markdown(&Self::snippet_preview(final_result)),
+            format_bytes(final_result.len() as u64),
+            metrics_footer
+        );
+        self.send_chunked(channel, thread_ts, &summary, reply).await
+    }
+
+    /// Post a reply message, editing it into the placeholder while one is showing
+    async fn post_reply(
+        &self,
+        channel: &ChannelId,
+        thread_ts: Option<&ThreadTs>,
+        text: &str,
+        reply: &mut ReplySlot,
+    ) -> Result<()> {
+        place_reply(
+            reply,
+            |ts| async move { self.slack_client.update_message(channel, &ts, text).await },
+            |ts| async move { self.slack_client.delete_message(channel, &ts).await },
+            || self.slack_client.send_message(channel, text, thread_ts),
+        )
+        .await
+    }
+
+    /// Check whether a response should be posted as a snippet: it's over the
+    /// size threshold, or it contains a huge code block
+    fn needs_snippet(text: &str, display: &DisplayConfig) -> bool {
+        text.len() > display.snippet_threshold_bytes
+            || Self::largest_code_block_len(text) > display.snippet_code_block_bytes
+    }
+
+    /// Length in bytes of the largest fenced code block in the text
+    fn largest_code_block_len(text: &str) -> usize {
+        text.split("```")
+            .skip(1)
+            .step_by(2)
+            .map(str::len)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Short preview of a long response: the prose before the first code block, truncated
+    fn snippet_preview(text: &str) -> String {
+        const PREVIEW_CHARS: usize = 500;
+
+        let prose = text.split("```").next().unwrap_or_default().trim();
+        if prose.chars().count() > PREVIEW_CHARS {
+            format!("{}…", prose.chars().take(PREVIEW_CHARS).collect::<String>())
+        } else {
+            prose.to_string()
+        }
+    }
+}
+
+/// How a response stream ended
+#[derive(Debug)]
+enum StreamOutcome {
+    /// The stream finished (with no `result` if it ended without a result message)
+    Completed(AgentResponse),
+    /// The query was cancelled (`/cancel`, `/restart`, channel departure)
+    Cancelled,
+    /// The query ran past its timeout; carries the text the agent had written so far
+    TimedOut { partial: String },
+    /// The stream failed part way; carries the text the agent had written before it did
+    Failed { partial: String, error: ClaudeError },
+}
+
+/// Read a response stream until the result message arrives, the query is cancelled or
+/// `timeout` passes. The stream is dropped on return, so the agent lock can be released.
+///
+/// Tool actions are also sent to `actions` as they're seen, for the verbose feed.
+async fn collect_response<S>(
+    stream: S,
+    timeout: Duration,
+    cancelled: impl Future<Output = ()>,
+    actions: Option<&mpsc::UnboundedSender<ToolAction>>,
+) -> Result<StreamOutcome>
+where
+    S: Stream<Item = std::result::Result<ClaudeMessage, ClaudeError>>,
+{
+    let mut stream = std::pin::pin!(stream);
+    let mut cancelled = std::pin::pin!(cancelled);
+    let deadline = tokio::time::sleep(timeout);
+    let mut deadline = std::pin::pin!(deadline);
+    let mut response = AgentResponse::default();
+    let mut message_count = 0;
+
+    loop {
+        let message = tokio::select! {
+            message = stream.next() => message,
+            _ = &mut cancelled => {
+                tracing::warn!("Query cancelled, dropping response stream");
+                return Ok(StreamOutcome::Cancelled);
+            }
+            _ = &mut deadline => {
+                return Ok(StreamOutcome::TimedOut { partial: response.partial_text() });
             }
+        };
+        let Some(message) = message else {
+            return Ok(StreamOutcome::Completed(response));
+        };
+        message_count += 1;
+        tracing::debug!(message_num = message_count, "Received message from Claude");
 
+        let message = match message {
+            Ok(message) => message,
+            Err(error) => {
+                return Ok(StreamOutcome::Failed {
+                    partial: response.partial_text(),
+                    error,
+                });
+            }
+        };
+        let seen = response.actions.len();
+        let finished = response.record(message);
+        if let Some(actions) = actions {
+            for action in &response.actions[seen..] {
+                let _ = actions.send(action.clone());
+            }
+        }
+        if finished {
             tracing::info!(
-                message_len = final_message.len(),
-                has_metrics = result_message.is_some(),
-                "Response sent with metrics"
+                result_len = response.text().len(),
+                action_count = response.actions.len(),
+                "Received final result"
             );
-        } else {
-            tracing::warn!("No response received from agent");
+            return Ok(StreamOutcome::Completed(response));
         }
+    }
+}
 
-        Ok(())
+/// Place one reply message: `edit` it into the placeholder while one is showing,
+/// otherwise `post` it. If the edit fails, the placeholder is `remove`d and the
+/// message posted instead. The first message placed is recorded in `reply`.
+async fn place_reply<Edit, EditFut, Remove, RemoveFut, Post, PostFut>(
+    reply: &mut ReplySlot,
+    edit: Edit,
+    remove: Remove,
+    post: Post,
+) -> Result<()>
+where
+    Edit: FnOnce(MessageTs) -> EditFut,
+    EditFut: Future<Output = Result<()>>,
+    Remove: FnOnce(MessageTs) -> RemoveFut,
+    RemoveFut: Future<Output = Result<()>>,
+    Post: FnOnce() -> PostFut,
+    PostFut: Future<Output = Result<MessageTs>>,
+{
+    if let Some(ts) = reply.placeholder.take() {
+        match edit(ts.clone()).await {
+            Ok(()) => {
+                reply.first.get_or_insert(ts);
+                return Ok(());
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to edit placeholder, posting reply");
+                if let Err(e) = remove(ts).await {
+                    tracing::warn!(error = %e, "Failed to remove placeholder message");
+                }
+            }
+        }
+    }
+    let ts = post().await?;
+    reply.first.get_or_insert(ts);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ChannelConfig;
+    use crate::slack::limits::TRUNCATION_RESERVE;
+    use crate::storage::test_workspace;
+
+    #[tokio::test]
+    async fn test_missing_repo_guidance() {
+        let (_dir, workspace) = test_workspace(&[], "").await;
+        let channel = ChannelId::new("C123");
+
+        // Missing, with no config: generic re-setup hint
+        let guidance = MessageProcessor::missing_repo_guidance(&workspace, &channel, "/")
+            .await
+            .unwrap();
+        assert!(guidance.contains("workspace is missing"));
+        assert!(guidance.contains("`/repo resync`"));
+        assert!(guidance.contains("`owner/repo-name`"));
+
+        // Missing, with a configured repo: names it and uses the command prefix
+        let config = ChannelConfig {
+            repo_name: Some("tyrchen/slack-coder".to_string()),
+            ..ChannelConfig::default()
+        };
+        workspace
+            .save_channel_config(&channel, &config)
+            .await
+            .unwrap();
+        let guidance = MessageProcessor::missing_repo_guidance(&workspace, &channel, "!")
+            .await
+            .unwrap();
+        assert!(guidance.contains("`!repo resync`"));
+        assert!(guidance.contains("`tyrchen/slack-coder`"));
+
+        // Present: no guidance
+        tokio::fs::create_dir_all(workspace.repo_path(&channel))
+            .await
+            .unwrap();
+        assert!(
+            MessageProcessor::missing_repo_guidance(&workspace, &channel, "/")
+                .await
+                .is_none()
+        );
+    }
+
+    fn display(threshold: usize, code_block: usize) -> DisplayConfig {
+        DisplayConfig {
+            snippet_threshold_bytes: threshold,
+            snippet_code_block_bytes: code_block,
+            ..DisplayConfig::default()
+        }
+    }
+
+    /// Place a reply, logging which Slack calls were made; posts get ts `new-N`
+    async fn place(reply: &mut ReplySlot, edit_ok: bool, calls: &std::sync::Mutex<Vec<String>>) {
+        let log = |call: String| calls.lock().unwrap().push(call);
+        place_reply(
+            reply,
+            move |ts| async move {
+                log(format!("edit {}", ts));
+                if edit_ok {
+                    Ok(())
+                } else {
+                    Err(SlackCoderError::SlackMessageGone(
+                        "message_not_found".to_string(),
+                    ))
+                }
+            },
+            move |ts| async move {
+                log(format!("remove {}", ts));
+                Ok(())
+            },
+            move || async move {
+                let ts = format!("new-{}", calls.lock().unwrap().len());
+                log(format!("post {}", ts));
+                Ok(MessageTs::new(ts))
+            },
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_place_reply_edits_placeholder_first() {
+        let calls = std::sync::Mutex::new(Vec::new());
+        let mut reply = ReplySlot {
+            placeholder: Some(MessageTs::new("placeholder")),
+            ..ReplySlot::default()
+        };
+
+        // The first chunk replaces the placeholder; the rest are new messages
+        place(&mut reply, true, &calls).await;
+        place(&mut reply, true, &calls).await;
+        assert_eq!(*calls.lock().unwrap(), ["edit placeholder", "post new-1"]);
+        assert!(reply.placeholder.is_none());
+        assert_eq!(
+            reply.first.as_ref().map(MessageTs::as_str),
+            Some("placeholder")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_place_reply_replaces_uneditable_placeholder() {
+        let calls = std::sync::Mutex::new(Vec::new());
+        let mut reply = ReplySlot {
+            placeholder: Some(MessageTs::new("placeholder")),
+            ..ReplySlot::default()
+        };
+
+        place(&mut reply, false, &calls).await;
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["edit placeholder", "remove placeholder", "post new-2"]
+        );
+        assert!(reply.placeholder.is_none());
+        assert_eq!(reply.first.as_ref().map(MessageTs::as_str), Some("new-2"));
+    }
+
+    #[tokio::test]
+    async fn test_place_reply_without_placeholder() {
+        // `PLACEHOLDER_MESSAGE=none`: nothing was posted, so replies are plain posts
+        let calls = std::sync::Mutex::new(Vec::new());
+        let mut reply = ReplySlot::default();
+
+        place(&mut reply, true, &calls).await;
+        assert_eq!(*calls.lock().unwrap(), ["post new-0"]);
+        assert_eq!(reply.first.as_ref().map(MessageTs::as_str), Some("new-0"));
+    }
+
+    #[test]
+    fn test_reply_thread_ts_modes() {
+        let ts = MessageTs::new("1700000000.000100");
+        let thread = ThreadTs::new("1699999999.000001");
+
+        // Threaded mode starts a thread under the message
+        assert_eq!(
+            MessageProcessor::reply_thread_ts(None, &ts, true),
+            Some(ThreadTs::new("1700000000.000100"))
+        );
+        // Top-level mode replies in the channel
+        assert_eq!(MessageProcessor::reply_thread_ts(None, &ts, false), None);
+
+        // Messages already in a thread are answered there in both modes
+        for reply_in_thread in [true, false] {
+            assert_eq!(
+                MessageProcessor::reply_thread_ts(Some(&thread), &ts, reply_in_thread),
+                Some(thread.clone())
+            );
+        }
+    }
+
+    #[test]
+    fn test_largest_code_block_len() {
+        let text = "intro\n```rust\nfn a() {}\n```\nmiddle\n```\nxy\n```";
+        assert_eq!(
+            MessageProcessor::largest_code_block_len(text),
+            "rust\nfn a() {}\n".len()
+        );
+        assert_eq!(MessageProcessor::largest_code_block_len("no code"), 0);
+    }
+
+    #[tokio::test]
+    async fn test_collect_response_times_out() {
+        // A stub stream that never produces a message
+        let stream = futures::stream::pending::<std::result::Result<ClaudeMessage, ClaudeError>>();
+        let outcome = collect_response(
+            stream,
+            Duration::from_millis(20),
+            std::future::pending::<()>(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let StreamOutcome::TimedOut { partial } = outcome else {
+            panic!("expected a timeout, got {:?}", outcome);
+        };
+        assert!(partial.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_collect_response_cancelled() {
+        let stream = futures::stream::pending::<std::result::Result<ClaudeMessage, ClaudeError>>();
+        let outcome =
+            collect_response(stream, Duration::from_secs(5), std::future::ready(()), None)
+                .await
+                .unwrap();
+        assert!(matches!(outcome, StreamOutcome::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn test_collect_response_stream_ends() {
+        let stream = futures::stream::empty::<std::result::Result<ClaudeMessage, ClaudeError>>();
+        let outcome = collect_response(
+            stream,
+            Duration::from_secs(5),
+            std::future::pending::<()>(),
+            None,
+        )
+        .await
+        .unwrap();
+        let StreamOutcome::Completed(response) = outcome else {
+            panic!("expected completion, got {:?}", outcome);
+        };
+        assert!(response.result.is_none());
+        assert!(response.actions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_collect_response_keeps_partial_text_on_error() {
+        let assistant = serde_json::json!({
+            "type": "assistant",
+            "message": {"model": "claude", "content": [
+                {"type": "text", "text": "The bug is in `parse_args`."},
+            ]}
+        });
+        let stream = futures::stream::iter([
+            Ok(serde_json::from_value(assistant).unwrap()),
+            Err(ClaudeError::from(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "agent process exited",
+            ))),
+        ]);
+        let outcome = collect_response(
+            stream,
+            Duration::from_secs(5),
+            std::future::pending::<()>(),
+            None,
+        )
+        .await
+        .unwrap();
+
+        let StreamOutcome::Failed { partial, error } = outcome else {
+            panic!("expected a failure, got {:?}", outcome);
+        };
+        assert_eq!(partial, "The bug is in `parse_args`.");
+        assert!(error.to_string().contains("agent process exited"));
+    }
+
+    #[test]
+    fn test_interrupted_message() {
+        let message = MessageProcessor::interrupted_message(
+            "Found **two** bugs",
+            "stream `closed`\nwhile reading",
+        );
+        assert_eq!(
+            message,
+            "Found *two* bugs\n\n⚠️ *The response was interrupted:* `stream 'closed'…`"
+        );
+
+        let long_error = "x".repeat(MAX_ERROR_SUMMARY_CHARS + 10);
+        let message = MessageProcessor::interrupted_message("partial", &long_error);
+        assert!(message.ends_with(&format!("`{}…`", "x".repeat(MAX_ERROR_SUMMARY_CHARS))));
+    }
+
+    #[tokio::test]
+    async fn test_collect_response_gathers_tool_actions() {
+        let messages = [
+            serde_json::json!({
+                "type": "assistant",
+                "message": {"model": "claude", "content": [
+                    {"type": "text", "text": "Fixing it."},
+                    {"type": "tool_use", "id": "t1", "name": "Edit",
+                     "input": {"file_path": "/repos/C1/src/main.rs", "old_string": "a", "new_string": "b"}},
+                    {"type": "tool_use", "id": "t2", "name": "Bash",
+                     "input": {"command": "cargo build"}},
+                ]}
+            }),
+            serde_json::json!({
+                "type": "result", "subtype": "success", "duration_ms": 10,
+                "duration_api_ms": 8, "is_error": false, "num_turns": 2,
+                "session_id": "s1", "result": "Done."
+            }),
+        ];
+        let stream =
+            futures::stream::iter(messages.map(|value| {
+                Ok::<ClaudeMessage, ClaudeError>(serde_json::from_value(value).unwrap())
+            }));
+        let outcome = collect_response(
+            stream,
+            Duration::from_secs(5),
+            std::future::pending::<()>(),
+            None,
+        )
+        .await
+        .unwrap();
+        let StreamOutcome::Completed(response) = outcome else {
+            panic!("expected completion, got {:?}", outcome);
+        };
+        assert_eq!(response.text(), "Done.");
+        assert_eq!(response.commands_run(), ["cargo build"]);
+        assert_eq!(
+            response
+                .actions_summary(Some(std::path::Path::new("/repos/C1")))
+                .unwrap(),
+            "*Actions taken*\n• Changed 1 file: `src/main.rs`\n• Ran 1 command: `cargo build`"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_response_reports_actions_to_verbose_feed() {
+        let tool_use = serde_json::json!({
+            "type": "assistant",
+            "message": {"model": "claude", "content": [
+                {"type": "tool_use", "id": "t1", "name": "Read",
+                 "input": {"file_path": "/repos/C1/README.md"}},
+                {"type": "tool_use", "id": "t2", "name": "Bash",
+                 "input": {"command": "cargo test"}},
+            ]}
+        });
+        let stream = futures::stream::iter([Ok::<ClaudeMessage, ClaudeError>(
+            serde_json::from_value(tool_use).unwrap(),
+        )]);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        collect_response(
+            stream,
+            Duration::from_secs(5),
+            std::future::pending::<()>(),
+            Some(&tx),
+        )
+        .await
+        .unwrap();
+
+        // Reads aren't worth an update; the command is
+        let action = rx.try_recv().unwrap();
+        assert_eq!(action, ToolAction::CommandRun("cargo test".to_string()));
+        assert_eq!(action.describe(None), "💻 `cargo test`");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_quote_request() {
+        assert_eq!(
+            MessageProcessor::quote_request("Why does `cargo test` fail?"),
+            "> Why does `cargo test` fail?"
+        );
+
+        // Blank lines, fences and nested quotes are dropped; code lines go inline
+        let request = "Fix this:\n\n```rust\nlet x = `y`;\n```\n> quoted\nthanks\nmore";
+        assert_eq!(
+            MessageProcessor::quote_request(request),
+            "> Fix this:\n> `let x = 'y';`\n> quoted …"
+        );
+
+        let long = "a".repeat(MAX_QUOTE_CHARS + 50);
+        let quote = MessageProcessor::quote_request(&long);
+        assert_eq!(quote, format!("> {} …", "a".repeat(MAX_QUOTE_CHARS)));
+        assert_eq!(MessageProcessor::quote_request("  \n```\n```"), "");
+    }
+
+    #[test]
+    fn test_timeout_message() {
+        let message =
+            MessageProcessor::timeout_message(Duration::from_secs(900), "Found **two** bugs", true);
+        assert!(message.starts_with("⏱️ *This took too long and was stopped* (limit: 15 minutes)"));
+        assert!(message.ends_with("*Partial output:*\nFound *two* bugs"));
+        assert!(!message.contains("restarted"));
+
+        let message = MessageProcessor::timeout_message(Duration::from_secs(90), "  ", false);
+        assert!(message.contains("(limit: 2 minutes)"));
+        assert!(message.contains("The agent was restarted"));
+        assert!(!message.contains("Partial output"));
+    }
+
+    #[test]
+    fn test_truncation_notice() {
+        let notice = MessageProcessor::truncation_notice(1234, false);
+        assert_eq!(notice, "\n\n_…(response truncated, 1234 chars omitted)_");
+        assert!(notice.len() <= TRUNCATION_RESERVE);
+
+        let notice = MessageProcessor::truncation_notice(usize::MAX, true);
+        assert!(notice.contains("`response.md`"));
+        assert!(notice.len() <= TRUNCATION_RESERVE);
+    }
+
+    #[test]
+    fn test_needs_snippet() {
+        let config = display(100, 50);
+
+        assert!(!MessageProcessor::needs_snippet("short answer", &config));
+        assert!(MessageProcessor::needs_snippet(&"a".repeat(101), &config));
+
+        let big_block = format!("see:\n```\n{}\n```", "x".repeat(60));
+        assert!(MessageProcessor::needs_snippet(&big_block, &config));
+    }
+
+    #[test]
+    fn test_snippet_preview_stops_at_code() {
+        let text = "Here is the summary.\n\n```rust\nfn main() {}\n```";
+        assert_eq!(
+            MessageProcessor::snippet_preview(text),
+            "Here is the summary."
+        );
+
+        let long = "word ".repeat(200);
+        assert!(MessageProcessor::snippet_preview(&long).ends_with('…'));
     }
 }
@@ -4,7 +4,7 @@ use claude_agent_sdk_rs::ResultMessage;
 use serde::{Deserialize, Serialize};
 
 /// Usage statistics extracted from ResultMessage
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct UsageMetrics {
     pub input_tokens: u64,
     pub output_tokens: u64,
@@ -109,6 +109,51 @@ impl UsageMetrics {
     }
 }
 
+/// Running usage totals for one agent session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionUsage {
+    pub session_id: String,
+    pub queries: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl SessionUsage {
+    /// Start empty totals for a session
+    pub fn new(session_id: impl Into<String>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Add one query's metrics to the totals
+    pub fn record(&mut self, metrics: &UsageMetrics) {
+        self.queries += 1;
+        self.input_tokens += metrics.input_tokens;
+        self.output_tokens += metrics.output_tokens;
+        self.cost_usd += metrics.cost_usd.unwrap_or(0.0);
+    }
+
+    /// Format the totals as a Slack message
+    pub fn format_slack_message(&self) -> String {
+        format!(
+            "📈 *Session Usage*\n\
+             • Queries: {}\n\
+             • Tokens: {} input + {} output = *{} total*\n\
+             • Cost: ${:.4} USD\n\
+             • Session: `{}`",
+            self.queries,
+            self.input_tokens,
+            self.output_tokens,
+            self.input_tokens + self.output_tokens,
+            self.cost_usd,
+            self.session_id
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -217,4 +262,37 @@ mod tests {
         assert!(message.contains("N/A"));
         assert!(!message.contains("Cache:"));
     }
+
+    fn metrics(input: u64, output: u64, cost: Option<f64>) -> UsageMetrics {
+        UsageMetrics {
+            input_tokens: input,
+            output_tokens: output,
+            total_tokens: input + output,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
+            cost_usd: cost,
+            duration_ms: 1000,
+            duration_api_ms: 800,
+            num_turns: 1,
+            session_id: "sdk-session".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_session_usage_accumulates() {
+        let mut usage = SessionUsage::new("session-C123-1-abc");
+        usage.record(&metrics(100, 20, Some(0.01)));
+        usage.record(&metrics(50, 5, None));
+
+        assert_eq!(usage.queries, 2);
+        assert_eq!(usage.input_tokens, 150);
+        assert_eq!(usage.output_tokens, 25);
+        assert!((usage.cost_usd - 0.01).abs() < f64::EPSILON);
+
+        let message = usage.format_slack_message();
+        assert!(message.contains("Queries: 2"));
+        assert!(message.contains("175 total"));
+        assert!(message.contains("$0.0100 USD"));
+        assert!(message.contains("session-C123-1-abc"));
+    }
 }
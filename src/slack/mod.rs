@@ -1,19 +1,42 @@
+mod args;
+mod audit;
+mod auth;
+mod builder;
 mod client;
 mod commands;
+mod dedup;
+mod diff;
 mod events;
 mod forms;
+mod limits;
 mod markdown;
+mod mentions;
 mod messages;
 mod metrics;
 mod progress;
+mod rate_limit;
+mod review;
+mod split;
+mod topic;
 mod types;
+mod verbose;
 
-pub use client::SlackClient;
-pub use commands::SlackCommandHandler;
-pub use events::EventHandler;
+pub use audit::AuditRecord;
+pub use auth::BotIdentity;
+pub use builder::SlackMessageBuilder;
+pub use client::{SendOptions, SlackClient};
+pub(crate) use commands::display_command;
+pub use commands::{SlackCommandHandler, SummaryTarget, parse_command_text};
+pub use events::{ConnectionState, EventHandler};
 pub use forms::FormHandler;
-pub use markdown::markdown_to_slack;
+pub use limits::SlackLimits;
+pub use markdown::{emoji_to_shortcodes, markdown_to_slack};
+pub use mentions::{linkify_mentions, user_handles};
 pub use messages::MessageProcessor;
-pub use metrics::UsageMetrics;
+pub use metrics::{SessionUsage, UsageMetrics};
 pub use progress::ProgressTracker;
-pub use types::{ChannelId, MessageTs, SlackMessage, ThreadTs, UserId};
+pub use rate_limit::RateLimiter;
+pub use review::{ReviewRequest, ReviewSubject};
+pub use split::split_slack_message;
+pub use types::{ChannelId, MessageTs, SharedFile, SlackMessage, ThreadTs, UserId};
+pub use verbose::VerboseFeed;
@@ -1,22 +1,122 @@
 use crate::agent::{Plan, TaskStatus};
-use crate::error::Result;
+use crate::config::{ProgressEmoji, ProgressFinishMode};
+use crate::error::{Result, SlackCoderError};
 use crate::slack::{ChannelId, MessageTs, SlackClient};
 use dashmap::DashMap;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// A progress board message being kept up to date
+#[derive(Debug, Clone)]
+struct ProgressMessage {
+    ts: MessageTs,
+    started_at: Instant,
+    /// Last plan rendered on the board
+    plan: Plan,
+}
+
+impl ProgressMessage {
+    fn new(ts: MessageTs, plan: Plan) -> Self {
+        Self {
+            ts,
+            started_at: Instant::now(),
+            plan,
+        }
+    }
+}
+
+/// Per-channel debounce state for board edits
+#[derive(Debug, Default)]
+struct DebounceState {
+    last_sent: Option<Instant>,
+    /// Latest plan waiting for the debounce window to close
+    pending: Option<Plan>,
+    flush_scheduled: bool,
+    /// The scheduled flush task, so finishing the board can stop it
+    flush: Option<JoinHandle<()>>,
+}
+
+/// What to do with a progress update
+#[derive(Debug, PartialEq, Eq)]
+enum DebounceAction {
+    /// Edit the board now
+    SendNow,
+    /// Hold the update; schedule a flush after the delay unless one is already pending
+    Defer { flush_in: Option<Duration> },
+}
+
+impl DebounceState {
+    /// Decide whether an update can go out now or must wait for the window to close.
+    /// Final updates (plan complete) always go out immediately.
+    fn on_update(&mut self, plan: &Plan, now: Instant, interval: Duration) -> DebounceAction {
+        if let Some(last_sent) = self.last_sent
+            && !plan.is_complete()
+        {
+            let since = now.saturating_duration_since(last_sent);
+            if since < interval {
+                self.pending = Some(plan.clone());
+                if self.flush_scheduled {
+                    return DebounceAction::Defer { flush_in: None };
+                }
+                self.flush_scheduled = true;
+                return DebounceAction::Defer {
+                    flush_in: Some(interval - since),
+                };
+            }
+        }
+
+        self.last_sent = Some(now);
+        self.pending = None;
+        DebounceAction::SendNow
+    }
+
+    /// Take the pending plan once the scheduled flush fires
+    fn take_pending(&mut self, now: Instant) -> Option<Plan> {
+        self.flush_scheduled = false;
+        let pending = self.pending.take();
+        if pending.is_some() {
+            self.last_sent = Some(now);
+        }
+        pending
+    }
+
+    /// Stop the scheduled flush. One already editing the board is waited out, so its
+    /// edit can't land after the caller's own.
+    async fn cancel_flush(self) {
+        if let Some(flush) = self.flush {
+            flush.abort();
+            let _ = flush.await;
+        }
+    }
+}
 
 pub struct ProgressTracker {
     slack_client: Arc<SlackClient>,
-    active_progress: Arc<DashMap<ChannelId, MessageTs>>,
+    active_progress: Arc<DashMap<ChannelId, ProgressMessage>>,
+    debounce: Arc<DashMap<ChannelId, DebounceState>>,
+    finish_mode: ProgressFinishMode,
+    emoji: ProgressEmoji,
 }
 
 impl ProgressTracker {
-    pub fn new(slack_client: Arc<SlackClient>) -> Self {
+    pub fn new(slack_client: Arc<SlackClient>, emoji: ProgressEmoji) -> Self {
         Self {
             slack_client,
             active_progress: Arc::new(DashMap::new()),
+            debounce: Arc::new(DashMap::new()),
+            finish_mode: ProgressFinishMode::default(),
+            emoji,
         }
     }
 
+    /// Set what happens to the progress board when a task completes
+    pub fn with_finish_mode(mut self, finish_mode: ProgressFinishMode) -> Self {
+        self.finish_mode = finish_mode;
+        self
+    }
+
     /// Get reference to SlackClient for sending custom messages
     pub fn slack_client_ref(&self) -> Arc<SlackClient> {
         Arc::clone(&self.slack_client)
@@ -24,39 +124,225 @@ impl ProgressTracker {
 
     /// Display initial progress message
     pub async fn start_progress(&self, channel: &ChannelId, initial_plan: &Plan) -> Result<()> {
-        let formatted = Self::format_plan(initial_plan);
+        let formatted = Self::format_plan(initial_plan, &self.emoji);
         let ts = self
             .slack_client
             .send_message(channel, &formatted, None)
             .await?;
 
-        self.active_progress.insert(channel.clone(), ts);
+        self.active_progress.insert(
+            channel.clone(),
+            ProgressMessage::new(ts, initial_plan.clone()),
+        );
         Ok(())
     }
 
     /// Update progress message with new plan state
+    ///
+    /// Edits are debounced per channel to at most one per `min_update_interval`;
+    /// intermediate states are coalesced and the latest one is flushed when the
+    /// window closes, so the final state is never lost.
     pub async fn update_progress(&self, channel: &ChannelId, plan: &Plan) -> Result<()> {
-        let formatted = Self::format_plan(plan);
-
-        if let Some(ts) = self.active_progress.get(channel) {
-            self.slack_client
-                .update_message(channel, &ts, &formatted)
-                .await?;
+        let existing = self.active_progress.get(channel).map(|p| p.ts.clone());
+
+        if let Some(ts) = existing {
+            if let Some(mut progress) = self.active_progress.get_mut(channel) {
+                progress.plan = plan.clone();
+            }
+
+            let interval = self.slack_client.limits().min_update_interval;
+            let action = self.debounce.entry(channel.clone()).or_default().on_update(
+                plan,
+                Instant::now(),
+                interval,
+            );
+
+            match action {
+                DebounceAction::SendNow => {
+                    let formatted = Self::format_plan(plan, &self.emoji);
+                    let edited = self
+                        .slack_client
+                        .update_message(channel, &ts, &formatted)
+                        .await;
+                    replace_gone_board(
+                        &self.active_progress,
+                        channel,
+                        &ts,
+                        edited,
+                        self.slack_client.send_message(channel, &formatted, None),
+                    )
+                    .await?;
+                }
+                DebounceAction::Defer { flush_in } => {
+                    tracing::debug!(channel = %channel, "Progress update debounced");
+                    if let Some(delay) = flush_in {
+                        self.schedule_flush(channel.clone(), delay);
+                    }
+                }
+            }
         } else {
+            let formatted = Self::format_plan(plan, &self.emoji);
             // If no active progress message, create one
             let ts = self
                 .slack_client
                 .send_message(channel, &formatted, None)
                 .await?;
-            self.active_progress.insert(channel.clone(), ts);
+            self.active_progress
+                .insert(channel.clone(), ProgressMessage::new(ts, plan.clone()));
+        }
+
+        Ok(())
+    }
+
+    /// Send the latest pending plan once the debounce window closes
+    fn schedule_flush(&self, channel: ChannelId, delay: Duration) {
+        let slack_client = Arc::clone(&self.slack_client);
+        let active_progress = Arc::clone(&self.active_progress);
+        let debounce = Arc::clone(&self.debounce);
+        let emoji = self.emoji.clone();
+
+        let key = channel.clone();
+        let flush = tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+
+            let Some(plan) = debounce
+                .get_mut(&channel)
+                .and_then(|mut state| state.take_pending(Instant::now()))
+            else {
+                return;
+            };
+            // The board may have been finished or cleared while we waited
+            let Some(ts) = active_progress.get(&channel).map(|p| p.ts.clone()) else {
+                return;
+            };
+
+            let formatted = Self::format_plan(&plan, &emoji);
+            let edited = slack_client.update_message(&channel, &ts, &formatted).await;
+            if let Err(e) = replace_gone_board(
+                &active_progress,
+                &channel,
+                &ts,
+                edited,
+                slack_client.send_message(&channel, &formatted, None),
+            )
+            .await
+            {
+                tracing::error!(error = %e, "Failed to flush debounced progress update");
+            }
+        });
+        if let Some(mut state) = self.debounce.get_mut(&key) {
+            state.flush = Some(flush);
+        }
+    }
+
+    /// Drop the channel's debounce state, stopping any scheduled flush
+    async fn cancel_debounce(&self, channel: &ChannelId) {
+        if let Some((_, state)) = self.debounce.remove(channel) {
+            state.cancel_flush().await;
+        }
+    }
+
+    /// The plan the channel's board last rendered, while a task is in progress. Like
+    /// `repost_progress`, this doesn't lock the agent.
+    pub fn current_plan(&self, channel: &ChannelId) -> Option<Plan> {
+        self.active_progress.get(channel).map(|p| p.plan.clone())
+    }
+
+    /// Post a fresh copy of the board at the bottom of the channel and point
+    /// future updates at it. Returns false if no task is in progress.
+    ///
+    /// Uses the last plan the board rendered rather than locking the agent,
+    /// which is held for the whole duration of a running query.
+    pub async fn repost_progress(&self, channel: &ChannelId) -> Result<bool> {
+        let Some((old_ts, plan)) = self
+            .active_progress
+            .get(channel)
+            .map(|p| (p.ts.clone(), p.plan.clone()))
+        else {
+            return Ok(false);
+        };
+
+        if plan.todos.is_empty() || plan.is_complete() {
+            return Ok(false);
+        }
+
+        let ts = self
+            .slack_client
+            .send_message(channel, &Self::format_plan(&plan, &self.emoji), None)
+            .await?;
+        if let Some(mut progress) = self.active_progress.get_mut(channel) {
+            progress.ts = ts;
+        }
+
+        // Point the stale copy at the new board
+        if let Err(e) = self
+            .slack_client
+            .update_message(channel, &old_ts, "⬇️ _Task board moved below_")
+            .await
+        {
+            tracing::warn!(error = %e, "Failed to collapse old progress board");
+        }
+
+        Ok(true)
+    }
+
+    /// Clear progress tracking for channel, collapsing the old progress message
+    /// so a half-finished checklist doesn't linger. Returns true if there was one.
+    pub async fn clear_progress(&self, channel: &ChannelId) -> Result<bool> {
+        self.cancel_debounce(channel).await;
+        let Some((_, progress)) = self.active_progress.remove(channel) else {
+            return Ok(false);
+        };
+
+        self.slack_client
+            .update_message(channel, &progress.ts, "🧹 _Task board cleared_")
+            .await?;
+        Ok(true)
+    }
+
+    /// Drop the channel's board state without touching Slack, for channels the bot can
+    /// no longer post to. Returns whether there was a board.
+    pub fn forget_progress(&self, channel: &ChannelId) -> bool {
+        if let Some((_, state)) = self.debounce.remove(channel)
+            && let Some(flush) = state.flush
+        {
+            flush.abort();
+        }
+        self.active_progress.remove(channel).is_some()
+    }
+
+    /// Finish progress tracking once a task completes, deleting the board or
+    /// collapsing it into a compact summary depending on the configured mode
+    pub async fn finish_progress(&self, channel: &ChannelId) -> Result<()> {
+        // A deferred flush landing after the final edit would overwrite it
+        self.cancel_debounce(channel).await;
+        let Some((_, progress)) = self.active_progress.remove(channel) else {
+            return Ok(());
+        };
+
+        match self.finish_mode {
+            ProgressFinishMode::Delete => {
+                self.slack_client
+                    .delete_message(channel, &progress.ts)
+                    .await?;
+            }
+            ProgressFinishMode::Summary => {
+                let summary = Self::format_finished(progress.started_at.elapsed());
+                self.slack_client
+                    .update_message(channel, &progress.ts, &summary)
+                    .await?;
+            }
         }
 
         Ok(())
     }
 
-    /// Clear progress tracking for channel
-    pub async fn clear_progress(&self, channel: &ChannelId) {
-        self.active_progress.remove(channel);
+    /// Compact summary that replaces a finished progress board
+    fn format_finished(elapsed: Duration) -> String {
+        format!(
+            "✅ *Done* in {}",
+            Self::format_duration(elapsed.as_secs_f64())
+        )
     }
 
     /// Format duration in a human-readable way
@@ -97,21 +383,29 @@ impl ProgressTracker {
         )
     }
 
+    /// Format the estimated time remaining, or `None` once every task is done
+    fn format_eta(plan: &Plan) -> Option<String> {
+        if plan.get_completed_count() >= plan.get_total_count() {
+            return None;
+        }
+
+        Some(match plan.estimated_remaining_time() {
+            Some(seconds) => format!("⏱️ ETA: ~{} remaining", Self::format_duration(seconds)),
+            None => "⏱️ ETA: estimating…".to_string(),
+        })
+    }
+
     /// Format plan as Slack message with emojis and timing information
-    fn format_plan(plan: &Plan) -> String {
+    pub fn format_plan(plan: &Plan, emoji: &ProgressEmoji) -> String {
         let completed = plan.get_completed_count();
         let total = plan.get_total_count();
 
         let mut lines = vec![Self::format_progress_bar(completed, total)];
+        if let Some(eta) = Self::format_eta(plan) {
+            lines.push(eta);
+        }
 
         for task in &plan.todos {
-            // Use checkbox-style emojis for better visual clarity
-            let emoji = match task.status {
-                TaskStatus::Completed => ":ballot_box_with_check:",
-                TaskStatus::InProgress => ":arrows_counterclockwise:", // More dynamic animated emoji
-                TaskStatus::Pending => ":white_medium_square:",
-            };
-
             let text = if task.status == TaskStatus::InProgress {
                 &task.active_form
             } else {
@@ -138,18 +432,112 @@ impl ProgressTracker {
                 TaskStatus::Pending => String::new(),
             };
 
-            lines.push(format!("{} {}{}", emoji, text, timing));
+            lines.push(format!(
+                "{} {}{}",
+                status_emoji(emoji, &task.status),
+                text,
+                timing
+            ));
         }
 
         lines.join("\n")
     }
 }
 
+/// The configured emoji for a task in the given status
+fn status_emoji<'a>(emoji: &'a ProgressEmoji, status: &TaskStatus) -> &'a str {
+    match status {
+        TaskStatus::Completed => &emoji.completed,
+        TaskStatus::InProgress => &emoji.in_progress,
+        TaskStatus::Pending => &emoji.pending,
+    }
+}
+
+/// Handle the result of editing a board. If the message is gone, `repost` posts a
+/// fresh board and future updates are pointed at it, rather than failing every edit
+/// for the rest of the task. Nothing is posted if the board finished or moved on
+/// meanwhile, so a finished task doesn't get a stray board.
+async fn replace_gone_board(
+    active_progress: &DashMap<ChannelId, ProgressMessage>,
+    channel: &ChannelId,
+    stale_ts: &MessageTs,
+    edited: Result<()>,
+    repost: impl Future<Output = Result<MessageTs>>,
+) -> Result<()> {
+    let Err(error) = edited else {
+        return Ok(());
+    };
+    if !matches!(error, SlackCoderError::SlackMessageGone(_)) {
+        return Err(error);
+    }
+    let still_current = active_progress
+        .get(channel)
+        .is_some_and(|progress| progress.ts.as_str() == stale_ts.as_str());
+    if !still_current {
+        return Ok(());
+    }
+    tracing::warn!(
+        error = %error,
+        "Progress board is gone, posting a new one {}",
+        channel.log_format()
+    );
+
+    let ts = repost.await?;
+    // Only retarget the board we failed to edit; it may have finished meanwhile
+    if let Some(mut progress) = active_progress.get_mut(channel)
+        && progress.ts.as_str() == stale_ts.as_str()
+    {
+        progress.ts = ts;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::agent::Task;
 
+    #[tokio::test]
+    async fn test_gone_board_is_reposted() {
+        let channel = ChannelId::new("C12345");
+        let stale = MessageTs::new("1.0001");
+        let active_progress = DashMap::new();
+        active_progress.insert(
+            channel.clone(),
+            ProgressMessage::new(stale.clone(), Plan::new()),
+        );
+
+        let gone = Err(SlackCoderError::SlackMessageGone(
+            "message_not_found".to_string(),
+        ));
+        let repost = std::future::ready(Ok(MessageTs::new("2.0002")));
+        replace_gone_board(&active_progress, &channel, &stale, gone, repost)
+            .await
+            .unwrap();
+        let current = active_progress.get(&channel).unwrap().ts.clone();
+        assert_eq!(current.as_str(), "2.0002");
+
+        // Other failures are returned, and the board isn't replaced
+        let failed = Err(SlackCoderError::SlackApi("channel_not_found".to_string()));
+        let repost = std::future::ready(Ok(MessageTs::new("3.0003")));
+        let result = replace_gone_board(&active_progress, &channel, &current, failed, repost).await;
+        assert!(result.is_err());
+        assert_eq!(active_progress.get(&channel).unwrap().ts.as_str(), "2.0002");
+
+        // A board that finished meanwhile isn't reposted
+        active_progress.remove(&channel);
+        let gone = Err(SlackCoderError::SlackMessageGone(
+            "message_not_found".to_string(),
+        ));
+        let repost = std::future::ready(Err(SlackCoderError::Internal(
+            "finished board was reposted".to_string(),
+        )));
+        replace_gone_board(&active_progress, &channel, &current, gone, repost)
+            .await
+            .unwrap();
+        assert!(active_progress.get(&channel).is_none());
+    }
+
     #[test]
     fn test_format_duration() {
         assert_eq!(ProgressTracker::format_duration(0.5), "0.5s");
@@ -161,6 +549,14 @@ mod tests {
         assert_eq!(ProgressTracker::format_duration(7384.0), "2h 3m");
     }
 
+    #[test]
+    fn test_format_finished() {
+        assert_eq!(
+            ProgressTracker::format_finished(Duration::from_secs(65)),
+            "✅ *Done* in 1m 5s"
+        );
+    }
+
     #[test]
     fn test_format_progress_bar() {
         assert_eq!(
@@ -208,7 +604,7 @@ mod tests {
             },
         ];
 
-        let formatted = ProgressTracker::format_plan(&plan);
+        let formatted = ProgressTracker::format_plan(&plan, &ProgressEmoji::default());
 
         // Verify structure
         assert!(formatted.contains("*Task Progress*"));
@@ -247,7 +643,7 @@ mod tests {
             },
         ];
 
-        let formatted = ProgressTracker::format_plan(&plan);
+        let formatted = ProgressTracker::format_plan(&plan, &ProgressEmoji::default());
 
         // Check for checkbox-style emojis
         assert!(formatted.contains(":ballot_box_with_check:"));
@@ -262,4 +658,148 @@ mod tests {
         assert!(formatted.contains("Completed task"));
         assert!(formatted.contains("Pending task"));
     }
+
+    #[test]
+    fn test_format_plan_custom_emoji() {
+        let mut plan = Plan::new();
+        plan.todos = vec![
+            task("Task 1", TaskStatus::Completed, Some(2.0)),
+            task("Task 2", TaskStatus::InProgress, None),
+            task("Task 3", TaskStatus::Pending, None),
+        ];
+        let emoji = ProgressEmoji {
+            completed: ":done:".to_string(),
+            in_progress: ":loading:".to_string(),
+            pending: ":todo:".to_string(),
+        };
+
+        let formatted = ProgressTracker::format_plan(&plan, &emoji);
+        assert!(formatted.contains(":done: Task 1"));
+        assert!(formatted.contains(":loading: Task 2"));
+        assert!(formatted.contains(":todo: Task 3"));
+        assert!(!formatted.contains(":ballot_box_with_check:"));
+    }
+
+    fn task(content: &str, status: TaskStatus, completion_time: Option<f64>) -> Task {
+        Task {
+            content: content.to_string(),
+            active_form: content.to_string(),
+            status,
+            start_time: None,
+            completion_time,
+        }
+    }
+
+    #[test]
+    fn test_format_plan_eta() {
+        let mut plan = Plan::new();
+        plan.todos = vec![
+            task("Task 1", TaskStatus::Completed, Some(10.0)),
+            task("Task 2", TaskStatus::Completed, Some(20.0)),
+            task("Task 3", TaskStatus::Pending, None),
+            task("Task 4", TaskStatus::Pending, None),
+            task("Task 5", TaskStatus::Pending, None),
+        ];
+
+        // Average 15s × 3 remaining tasks
+        let formatted = ProgressTracker::format_plan(&plan, &ProgressEmoji::default());
+        assert!(formatted.contains("⏱️ ETA: ~45.0s remaining"));
+    }
+
+    #[test]
+    fn test_format_eta_estimating_and_done() {
+        let mut plan = Plan::new();
+        plan.todos = vec![task("Task 1", TaskStatus::Pending, None)];
+        assert_eq!(
+            ProgressTracker::format_eta(&plan).as_deref(),
+            Some("⏱️ ETA: estimating…")
+        );
+
+        plan.todos = vec![task("Task 1", TaskStatus::Completed, Some(3.0))];
+        assert_eq!(ProgressTracker::format_eta(&plan), None);
+    }
+
+    #[test]
+    fn test_debounce_coalesces_rapid_updates() {
+        let interval = Duration::from_millis(750);
+        let start = Instant::now();
+        let mut state = DebounceState::default();
+        let mut plan = Plan::new();
+        plan.todos = vec![
+            task("Task 1", TaskStatus::InProgress, None),
+            task("Task 2", TaskStatus::Pending, None),
+        ];
+
+        // First update goes straight out
+        assert_eq!(
+            state.on_update(&plan, start, interval),
+            DebounceAction::SendNow
+        );
+
+        // Rapid follow-ups inside the window: one flush scheduled, no more sends
+        let mut sends = 0;
+        let mut flushes = 0;
+        for ms in [50, 100, 200, 400] {
+            match state.on_update(&plan, start + Duration::from_millis(ms), interval) {
+                DebounceAction::SendNow => sends += 1,
+                DebounceAction::Defer { flush_in: Some(_) } => flushes += 1,
+                DebounceAction::Defer { flush_in: None } => {}
+            }
+        }
+        assert_eq!(sends, 0);
+        assert_eq!(flushes, 1);
+
+        // The flush delivers the latest pending plan exactly once
+        let flush_at = start + interval;
+        assert!(state.take_pending(flush_at).is_some());
+        assert!(state.take_pending(flush_at).is_none());
+
+        // After the window, updates go out immediately again
+        assert_eq!(
+            state.on_update(&plan, flush_at + interval, interval),
+            DebounceAction::SendNow
+        );
+    }
+
+    #[test]
+    fn test_debounce_never_holds_final_update() {
+        let interval = Duration::from_millis(750);
+        let start = Instant::now();
+        let mut state = DebounceState::default();
+        let mut plan = Plan::new();
+        plan.todos = vec![task("Task 1", TaskStatus::InProgress, None)];
+
+        assert_eq!(
+            state.on_update(&plan, start, interval),
+            DebounceAction::SendNow
+        );
+
+        plan.todos = vec![task("Task 1", TaskStatus::Completed, Some(1.0))];
+        assert_eq!(
+            state.on_update(&plan, start + Duration::from_millis(10), interval),
+            DebounceAction::SendNow
+        );
+        assert!(state.pending.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_flush_stops_scheduled_flush() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        let flushed = Arc::new(AtomicBool::new(false));
+        let state = DebounceState {
+            flush: Some(tokio::spawn({
+                let flushed = Arc::clone(&flushed);
+                async move {
+                    tokio::time::sleep(Duration::from_millis(50)).await;
+                    flushed.store(true, Ordering::SeqCst);
+                }
+            })),
+            ..DebounceState::default()
+        };
+
+        state.cancel_flush().await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!flushed.load(Ordering::SeqCst));
+    }
 }
@@ -0,0 +1,193 @@
+//! Per-user rate limiting of agent queries (`RATE_LIMIT_PER_MINUTE`)
+
+use crate::slack::UserId;
+use dashmap::DashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often buckets that have refilled completely are dropped
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(300);
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Token bucket per user: `per_minute` messages at once, refilling evenly over a minute.
+///
+/// A full bucket holds no information, so buckets idle for a whole window are
+/// dropped every `CLEANUP_INTERVAL` to keep memory flat.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    per_minute: u32,
+    buckets: Arc<DashMap<UserId, Bucket>>,
+    last_cleanup: Arc<Mutex<Instant>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// A limiter allowing `per_minute` messages per user (0 disables it)
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            per_minute,
+            buckets: Arc::new(DashMap::new()),
+            last_cleanup: Arc::new(Mutex::new(Instant::now())),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.per_minute > 0
+    }
+
+    /// Take a token for the user's message. `Err` carries how long until the next one.
+    pub fn check(&self, user: &UserId) -> Result<(), Duration> {
+        self.check_at(user, Instant::now())
+    }
+
+    fn check_at(&self, user: &UserId, now: Instant) -> Result<(), Duration> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+        self.cleanup_if_due(now);
+
+        let capacity = f64::from(self.per_minute);
+        let window = WINDOW.as_secs_f64();
+        let mut bucket = self.buckets.entry(user.clone()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * capacity / window).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            // Rounded up to whole seconds, so retrying then is sure to work
+            Err(Duration::from_secs(
+                ((1.0 - bucket.tokens) * window / capacity).ceil() as u64,
+            ))
+        }
+    }
+
+    fn cleanup_if_due(&self, now: Instant) {
+        {
+            let mut last_cleanup = self.last_cleanup.lock().unwrap();
+            if now.saturating_duration_since(*last_cleanup) < CLEANUP_INTERVAL {
+                return;
+            }
+            *last_cleanup = now;
+        }
+        // Counted as they go; other users' buckets may be added while this runs
+        let mut removed = 0;
+        self.buckets.retain(|_, bucket| {
+            let keep = now.saturating_duration_since(bucket.updated) < WINDOW;
+            if !keep {
+                removed += 1;
+            }
+            keep
+        });
+        tracing::debug!(removed = removed, "Cleaned up idle rate limit buckets");
+    }
+
+    /// Number of users currently tracked
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty()
+    }
+
+    /// The reply for a throttled user
+    pub fn throttled_message(&self, retry_after: Duration) -> String {
+        format!(
+            "🐢 *Slow down* - you can send up to {} messages a minute. Please try again in {}s.",
+            self.per_minute,
+            retry_after.as_secs().max(1)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> UserId {
+        UserId::new("U12345")
+    }
+
+    #[test]
+    fn test_rejects_message_over_limit() {
+        let limiter = RateLimiter::new(3);
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.check_at(&user(), now).is_ok());
+        }
+        let retry_after = limiter.check_at(&user(), now).unwrap_err();
+        // One token comes back every 20s at 3 per minute
+        assert_eq!(retry_after.as_secs(), 20);
+        assert!(
+            limiter
+                .throttled_message(retry_after)
+                .contains("try again in 20s")
+        );
+
+        // Other users have their own bucket
+        assert!(limiter.check_at(&UserId::new("U99999"), now).is_ok());
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let limiter = RateLimiter::new(2);
+        let start = Instant::now();
+        assert!(limiter.check_at(&user(), start).is_ok());
+        assert!(limiter.check_at(&user(), start).is_ok());
+        assert!(limiter.check_at(&user(), start).is_err());
+
+        // Half a token after 15s, one after 30s
+        assert!(
+            limiter
+                .check_at(&user(), start + Duration::from_secs(15))
+                .is_err()
+        );
+        assert!(
+            limiter
+                .check_at(&user(), start + Duration::from_secs(30))
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .check_at(&user(), start + Duration::from_secs(30))
+                .is_err()
+        );
+
+        // Never more than a full bucket, however long the user was away
+        let later = start + Duration::from_secs(3600);
+        assert!(limiter.check_at(&user(), later).is_ok());
+        assert!(limiter.check_at(&user(), later).is_ok());
+        assert!(limiter.check_at(&user(), later).is_err());
+    }
+
+    #[test]
+    fn test_disabled_and_cleanup() {
+        let disabled = RateLimiter::new(0);
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert!(disabled.check_at(&user(), now).is_ok());
+        }
+        assert!(disabled.is_empty());
+
+        let limiter = RateLimiter::new(5);
+        assert!(limiter.check_at(&user(), now).is_ok());
+        assert_eq!(limiter.len(), 1);
+        // The idle bucket is dropped on the first check after the cleanup interval
+        let later = now + CLEANUP_INTERVAL + Duration::from_secs(1);
+        assert!(limiter.check_at(&UserId::new("U99999"), later).is_ok());
+        assert_eq!(limiter.len(), 1);
+    }
+}
@@ -0,0 +1,197 @@
+//! Framing pasted GitHub PR and commit links as review requests
+
+use regex::Regex;
+use std::fmt;
+use std::sync::LazyLock;
+
+/// GitHub pull request or commit URL. Slack delivers links wrapped in `<…>` or
+/// `<…|label>`, which the pattern doesn't need to see.
+static GITHUB_URL_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"https?://(?:www\.)?github\.com/(?P<owner>[A-Za-z0-9-]+)/(?P<repo>[A-Za-z0-9._-]+)/(?:pull/(?P<pr>\d+)|commit/(?P<sha>[0-9a-fA-F]{7,40}))\b",
+    )
+    .expect("GitHub URL pattern is valid")
+});
+
+/// What a review request points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReviewSubject {
+    PullRequest(u64),
+    Commit(String),
+}
+
+/// A message asking the agent to review a GitHub pull request or commit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewRequest {
+    pub owner: String,
+    pub repo: String,
+    pub subject: ReviewSubject,
+    /// The link as pasted, without Slack's angle brackets
+    pub url: String,
+    /// What the user wrote besides the link
+    pub note: String,
+}
+
+impl ReviewRequest {
+    /// Detect a review request: a message with a PR or commit link that is either just
+    /// the link or mentions "review". Other messages that happen to contain a link (for
+    /// example "PR … broke the build, fix it") stay free-form.
+    pub fn detect(text: &str) -> Option<Self> {
+        let caps = GITHUB_URL_RE.captures(text)?;
+        let url = caps.get(0)?;
+
+        let subject = match (caps.name("pr"), caps.name("sha")) {
+            (Some(pr), _) => ReviewSubject::PullRequest(pr.as_str().parse().ok()?),
+            (None, Some(sha)) => ReviewSubject::Commit(sha.as_str().to_lowercase()),
+            (None, None) => return None,
+        };
+
+        let note = Self::strip_link(text, url.start(), url.end());
+        if !note.is_empty() && !note.to_lowercase().contains("review") {
+            return None;
+        }
+
+        Some(Self {
+            owner: caps["owner"].to_string(),
+            repo: caps["repo"].to_string(),
+            subject,
+            url: url.as_str().to_string(),
+            note,
+        })
+    }
+
+    /// The message text with the link (and any Slack `<…|label>` wrapping) removed
+    fn strip_link(text: &str, start: usize, end: usize) -> String {
+        let before = &text[..start];
+        let after = &text[end..];
+        let (before, after) = match before.strip_suffix('<') {
+            // `<url>` or `<url|label>`: drop everything up to the closing bracket
+            Some(before) => (
+                before,
+                after.split_once('>').map_or(after, |(_, rest)| rest),
+            ),
+            None => (before, after),
+        };
+        format!("{} {}", before.trim(), after.trim())
+            .trim()
+            .to_string()
+    }
+
+    /// Build the prompt forwarded to the repository agent
+    pub fn build_prompt(&self) -> String {
+        let repo = format!("{}/{}", self.owner, self.repo);
+        let fetch = match &self.subject {
+            ReviewSubject::PullRequest(number) => format!(
+                "Fetch it with `gh pr view {number} --repo {repo} --comments` and `gh pr diff {number} --repo {repo}`"
+            ),
+            ReviewSubject::Commit(sha) => format!(
+                "Fetch it with `gh api repos/{repo}/commits/{sha} -H \"Accept: application/vnd.github.diff\"` (or `git show {sha}` if it's in this repository)"
+            ),
+        };
+
+        let mut prompt = format!(
+            r#"Please review {subject} ({url}).
+
+1. {fetch}
+2. Read the surrounding code where the change needs context
+3. Look for bugs, unhandled edge cases and errors, missing tests, and departures from this codebase's conventions
+4. Report findings grouped by severity (blocking, should fix, nit), each with a file and line reference
+5. Finish with an overall verdict: approve, approve with nits, or request changes
+
+Don't push commits or post comments on GitHub unless asked."#,
+            subject = self,
+            url = self.url,
+        );
+        if !self.note.is_empty() {
+            prompt.push_str(&format!("\n\nThe request: {}", self.note));
+        }
+        prompt
+    }
+}
+
+impl fmt::Display for ReviewRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.subject {
+            ReviewSubject::PullRequest(number) => {
+                write!(f, "pull request {}/{}#{}", self.owner, self.repo, number)
+            }
+            ReviewSubject::Commit(sha) => write!(
+                f,
+                "commit {}/{}@{}",
+                self.owner,
+                self.repo,
+                &sha[..sha.len().min(7)]
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_pull_request() {
+        let request =
+            ReviewRequest::detect("<https://github.com/tyrchen/slack_coder/pull/42>").unwrap();
+        assert_eq!(request.owner, "tyrchen");
+        assert_eq!(request.repo, "slack_coder");
+        assert_eq!(request.subject, ReviewSubject::PullRequest(42));
+        assert_eq!(
+            request.url,
+            "https://github.com/tyrchen/slack_coder/pull/42"
+        );
+        assert!(request.note.is_empty());
+
+        // Tab links and Slack labels
+        let request = ReviewRequest::detect(
+            "can you review <https://github.com/acme/api/pull/7/files|acme/api#7> for security?",
+        )
+        .unwrap();
+        assert_eq!(request.subject, ReviewSubject::PullRequest(7));
+        assert_eq!(request.note, "can you review for security?");
+    }
+
+    #[test]
+    fn test_detect_commit() {
+        let request = ReviewRequest::detect(
+            "review https://github.com/acme/api/commit/3F2A9C1E8B7D6A5F4E3D2C1B0A9F8E7D6C5B4A39",
+        )
+        .unwrap();
+        assert_eq!(
+            request.subject,
+            ReviewSubject::Commit("3f2a9c1e8b7d6a5f4e3d2c1b0a9f8e7d6c5b4a39".to_string())
+        );
+        assert_eq!(request.to_string(), "commit acme/api@3f2a9c1");
+    }
+
+    #[test]
+    fn test_detect_ignores_other_messages() {
+        for text in [
+            "Add a README",
+            "<https://github.com/acme/api/pull/7> broke the build, please fix it",
+            "see https://github.com/acme/api/issues/7",
+            "https://github.com/acme/api",
+        ] {
+            assert_eq!(ReviewRequest::detect(text), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_build_prompt() {
+        let prompt = ReviewRequest::detect("review https://github.com/acme/api/pull/7 carefully")
+            .unwrap()
+            .build_prompt();
+        assert!(prompt.starts_with(
+            "Please review pull request acme/api#7 (https://github.com/acme/api/pull/7)."
+        ));
+        assert!(prompt.contains("`gh pr diff 7 --repo acme/api`"));
+        assert!(prompt.ends_with("The request: review carefully"));
+
+        let prompt = ReviewRequest::detect("https://github.com/acme/api/commit/3f2a9c1")
+            .unwrap()
+            .build_prompt();
+        assert!(prompt.contains("gh api repos/acme/api/commits/3f2a9c1"));
+        assert!(!prompt.contains("The request:"));
+    }
+}
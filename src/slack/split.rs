@@ -0,0 +1,198 @@
+//! Splitting long messages into Slack-sized chunks
+
+const FENCE: &str = "```";
+
+/// Bytes needed to close an open code fence at the end of a chunk
+const FENCE_CLOSE_LEN: usize = "\n```".len();
+
+/// Split text into chunks of at most `max` bytes for posting as separate Slack messages
+///
+/// Splits prefer paragraph boundaries, then line boundaries, and only fall back to
+/// splitting mid-line (always on a character boundary) when a single line is too long.
+/// A split inside a fenced code block closes the fence at the end of the chunk and
+/// re-opens it with the same language at the start of the next one.
+pub fn split_slack_message(text: &str, max: usize) -> Vec<String> {
+    if text.len() <= max {
+        return vec![text.to_string()];
+    }
+
+    let mut splitter = MessageSplitter {
+        max,
+        chunks: Vec::new(),
+        current: String::new(),
+        open_fence: None,
+    };
+
+    for line in text.split_inclusive('\n') {
+        splitter.push_line(line);
+    }
+
+    splitter.finish()
+}
+
+struct MessageSplitter {
+    max: usize,
+    chunks: Vec<String>,
+    current: String,
+    /// Opening line of the code fence we're currently inside (e.g. "```rust")
+    open_fence: Option<String>,
+}
+
+impl MessageSplitter {
+    /// Check if `extra` bytes fit in the current chunk, leaving room to close an open fence
+    fn fits(&self, extra: usize) -> bool {
+        let reserve = if self.open_fence.is_some() {
+            FENCE_CLOSE_LEN
+        } else {
+            0
+        };
+        self.current.len() + extra + reserve <= self.max
+    }
+
+    fn push_line(&mut self, line: &str) {
+        if !self.fits(line.len()) {
+            self.flush();
+        }
+
+        if self.fits(line.len()) {
+            self.current.push_str(line);
+        } else {
+            // A single line longer than a chunk - split it on character boundaries
+            for ch in line.chars() {
+                if !self.fits(ch.len_utf8()) {
+                    self.flush_hard();
+                }
+                self.current.push(ch);
+            }
+        }
+
+        self.track_fence(line);
+    }
+
+    /// Track whether we're inside a fenced code block after this line
+    fn track_fence(&mut self, line: &str) {
+        if line.matches(FENCE).count() % 2 == 0 {
+            return;
+        }
+
+        if self.open_fence.is_some() {
+            self.open_fence = None;
+        } else {
+            let trimmed = line.trim();
+            self.open_fence = Some(if trimmed.starts_with(FENCE) {
+                trimmed.to_string()
+            } else {
+                FENCE.to_string()
+            });
+        }
+    }
+
+    /// End the current chunk, preferring the last paragraph break outside code blocks
+    fn flush(&mut self) {
+        if self.open_fence.is_none()
+            && let Some(pos) = self.paragraph_break()
+        {
+            let rest = self.current.split_off(pos);
+            self.chunks.push(std::mem::replace(&mut self.current, rest));
+            return;
+        }
+
+        self.flush_hard();
+    }
+
+    /// Position just after the last paragraph break, if it's in the back half of the chunk
+    /// and not inside a code block
+    fn paragraph_break(&self) -> Option<usize> {
+        let pos = self.current.rfind("\n\n")? + 2;
+        let balanced = self.current[..pos].matches(FENCE).count() % 2 == 0;
+        (pos > self.current.len() / 2 && pos < self.current.len() && balanced).then_some(pos)
+    }
+
+    /// End the current chunk here, closing and re-opening any open code fence
+    fn flush_hard(&mut self) {
+        let reopen = self.open_fence.as_ref().map(|fence| format!("{}\n", fence));
+        if self.current.is_empty() || reopen.as_ref() == Some(&self.current) {
+            return;
+        }
+
+        let mut chunk = std::mem::take(&mut self.current);
+        if reopen.is_some() {
+            if !chunk.ends_with('\n') {
+                chunk.push('\n');
+            }
+            chunk.push_str(FENCE);
+        }
+
+        self.chunks.push(chunk);
+        self.current = reopen.unwrap_or_default();
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        let reopen = self.open_fence.as_ref().map(|fence| format!("{}\n", fence));
+        if !self.current.is_empty() && reopen.as_ref() != Some(&self.current) {
+            self.chunks.push(self.current);
+        }
+        self.chunks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_message_not_split() {
+        assert_eq!(split_slack_message("hello", 100), vec!["hello".to_string()]);
+    }
+
+    #[test]
+    fn test_splits_on_line_boundaries() {
+        let text = "line one\nline two\nline three\n";
+        let chunks = split_slack_message(text, 20);
+
+        assert_eq!(chunks, vec!["line one\nline two\n", "line three\n"]);
+        assert_eq!(chunks.concat(), text);
+    }
+
+    #[test]
+    fn test_prefers_paragraph_boundaries() {
+        let text = "first paragraph\n\nsecond para\nstill second\n";
+        let chunks = split_slack_message(text, 35);
+
+        assert_eq!(chunks[0], "first paragraph\n\n");
+        assert_eq!(chunks[1], "second para\nstill second\n");
+    }
+
+    #[test]
+    fn test_giant_code_block_reopens_fence() {
+        let mut text = String::from("Here is the code:\n\n```rust\n");
+        for i in 0..200 {
+            text.push_str(&format!("let value_{} = {};\n", i, i));
+        }
+        text.push_str("```\nDone.\n");
+
+        let max = 500;
+        let chunks = split_slack_message(&text, max);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            assert!(chunk.len() <= max, "chunk too long: {}", chunk.len());
+            // Every chunk has balanced fences
+            assert_eq!(chunk.matches("```").count() % 2, 0, "unbalanced: {}", chunk);
+        }
+        // Continuation chunks re-open with the original language
+        assert!(chunks[1].starts_with("```rust\n"));
+        assert!(chunks.last().unwrap().contains("Done."));
+    }
+
+    #[test]
+    fn test_multibyte_content_not_torn() {
+        let text = "日本語のテキスト".repeat(50);
+        let chunks = split_slack_message(&text, 100);
+
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|c| c.len() <= 100));
+        // Joining back gives the original (no replacement characters)
+        assert_eq!(chunks.concat(), text);
+    }
+}
@@ -0,0 +1,113 @@
+//! Channel topic marker shown while the agent works through a channel's queue
+//! (`BUSY_TOPIC`)
+//!
+//! The marker is a prefix on the existing topic, so the original can always be recovered
+//! by stripping it - even after a crash left the marker behind.
+
+use crate::slack::{ChannelId, SlackClient};
+
+/// Shown at the start of the topic while busy
+const BUSY_MARKER: &str = "🔧 working…";
+
+/// Separates the marker from the original topic
+const SEPARATOR: &str = " | ";
+
+/// Slack's limit on topic length
+const MAX_TOPIC_CHARS: usize = 250;
+
+/// A topic the bot changed, and what to put back
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BusyTopic {
+    original: String,
+    shown: String,
+}
+
+/// Topic to show while busy
+pub(crate) fn busy_topic(original: &str) -> String {
+    if original.is_empty() {
+        return BUSY_MARKER.to_string();
+    }
+    format!("{}{}{}", BUSY_MARKER, SEPARATOR, original)
+        .chars()
+        .take(MAX_TOPIC_CHARS)
+        .collect()
+}
+
+/// The topic without a busy marker left by an earlier run
+pub(crate) fn original_topic(topic: &str) -> &str {
+    match topic.strip_prefix(BUSY_MARKER) {
+        Some(rest) => rest.strip_prefix(SEPARATOR).unwrap_or(rest),
+        None => topic,
+    }
+}
+
+/// Put the busy marker on the channel topic. Returns what to restore, or `None` if the
+/// topic couldn't be read or set (usually a missing `channels:write.topic` scope).
+pub(crate) async fn mark_busy(
+    slack_client: &SlackClient,
+    channel: &ChannelId,
+) -> Option<BusyTopic> {
+    // Read the live topic; a cached one may be stale
+    let current = match slack_client.get_channel_info(channel.as_str()).await {
+        Ok(info) => info.topic.unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to read channel topic {}", channel.log_format());
+            return None;
+        }
+    };
+
+    let original = original_topic(&current).to_string();
+    let shown = busy_topic(&original);
+    if let Err(e) = slack_client.set_topic(channel, &shown).await {
+        tracing::warn!(error = %e, "Failed to set busy topic {}", channel.log_format());
+        return None;
+    }
+
+    Some(BusyTopic { original, shown })
+}
+
+/// Restore the topic saved by [`mark_busy`], unless someone changed it in the meantime
+pub(crate) async fn restore(slack_client: &SlackClient, channel: &ChannelId, topic: BusyTopic) {
+    match slack_client.get_channel_info(channel.as_str()).await {
+        Ok(info) if info.topic.as_deref().unwrap_or_default() != topic.shown => {
+            tracing::debug!(
+                "Channel topic changed while busy, leaving it {}",
+                channel.log_format()
+            );
+            return;
+        }
+        Ok(_) => {}
+        // Restore anyway rather than risk leaving the marker up
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to read channel topic {}", channel.log_format())
+        }
+    }
+
+    if let Err(e) = slack_client.set_topic(channel, &topic.original).await {
+        tracing::warn!(error = %e, "Failed to restore channel topic {}", channel.log_format());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_busy_topic_round_trip() {
+        assert_eq!(busy_topic(""), "🔧 working…");
+        assert_eq!(original_topic(&busy_topic("")), "");
+
+        let shown = busy_topic("Sprint 42: payments");
+        assert_eq!(shown, "🔧 working… | Sprint 42: payments");
+        assert_eq!(original_topic(&shown), "Sprint 42: payments");
+
+        // Topics without the marker are left alone
+        assert_eq!(original_topic("Sprint 42"), "Sprint 42");
+    }
+
+    #[test]
+    fn test_busy_topic_fits_slack_limit() {
+        let long = "x".repeat(MAX_TOPIC_CHARS);
+        assert_eq!(busy_topic(&long).chars().count(), MAX_TOPIC_CHARS);
+    }
+}
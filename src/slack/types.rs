@@ -1,24 +1,72 @@
+use crate::error::{Result, SlackCoderError};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Implement `Display` (the raw value) and `log_format()` (`kind=value`) for an id newtype
+macro_rules! impl_id_format {
+    ($ty:ident, $kind:literal) => {
+        impl $ty {
+            /// Format for tracing as `kind=value`
+            pub fn log_format(&self) -> String {
+                format!(concat!($kind, "={}"), self.0)
+            }
+        }
+
+        impl fmt::Display for $ty {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+    };
+}
+
+impl_id_format!(ChannelId, "channel");
+impl_id_format!(UserId, "user");
+impl_id_format!(ThreadTs, "thread_ts");
+impl_id_format!(MessageTs, "ts");
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ChannelId(pub String);
 
 impl ChannelId {
+    /// Create a channel id without validation (for ids we already trust)
     pub fn new(id: impl Into<String>) -> Self {
         Self(id.into())
     }
 
+    /// Parse a channel id from untrusted input, checking the Slack id shape:
+    /// `C` (channel), `G` (private group) or `D` (direct message) followed by
+    /// uppercase letters and digits
+    pub fn parse(id: &str) -> Result<Self> {
+        let mut chars = id.chars();
+        let valid_prefix = matches!(chars.next(), Some('C' | 'G' | 'D'));
+        let rest = chars.as_str();
+        let valid_body = !rest.is_empty()
+            && rest
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit());
+
+        if valid_prefix && valid_body {
+            Ok(Self(id.to_string()))
+        } else {
+            Err(SlackCoderError::InvalidId(format!(
+                "'{}' is not a valid Slack channel id",
+                id
+            )))
+        }
+    }
+
     pub fn as_str(&self) -> &str {
         &self.0
     }
 
-    /// Format for logging: show full ID for debugging
-    pub fn log_format(&self) -> String {
-        format!("[{}]", self.0)
+    /// Direct message (IM) conversation ids start with `D`
+    pub fn is_direct_message(&self) -> bool {
+        self.0.starts_with('D')
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct UserId(pub String);
 
 impl UserId {
@@ -31,7 +79,7 @@ impl UserId {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ThreadTs(pub String);
 
 impl ThreadTs {
@@ -39,6 +87,11 @@ impl ThreadTs {
         Self(ts.into())
     }
 
+    /// The thread rooted at a message (a thread's ts is its parent message's ts)
+    pub fn from_parent(parent: &MessageTs) -> Self {
+        Self(parent.0.clone())
+    }
+
     pub fn as_str(&self) -> &str {
         &self.0
     }
@@ -57,6 +110,14 @@ impl MessageTs {
     }
 }
 
+/// A file attached to a message, downloadable with the bot token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SharedFile {
+    pub name: String,
+    /// The file's `url_private`
+    pub url: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct SlackMessage {
     pub channel: ChannelId,
@@ -64,4 +125,67 @@ pub struct SlackMessage {
     pub text: String,
     pub thread_ts: Option<ThreadTs>,
     pub ts: MessageTs,
+    /// Files uploaded with the message
+    pub files: Vec<SharedFile>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_log_format() {
+        assert_eq!(ChannelId::new("C123").log_format(), "channel=C123");
+        assert_eq!(UserId::new("U456").log_format(), "user=U456");
+        assert_eq!(
+            ThreadTs::new("1700000000.000100").log_format(),
+            "thread_ts=1700000000.000100"
+        );
+        assert_eq!(
+            MessageTs::new("1700000000.000200").log_format(),
+            "ts=1700000000.000200"
+        );
+    }
+
+    #[test]
+    fn test_id_display() {
+        assert_eq!(format!("{}", ChannelId::new("C123")), "C123");
+        assert_eq!(format!("{}", UserId::new("U456")), "U456");
+        assert_eq!(ThreadTs::new("1.2").to_string(), "1.2");
+        assert_eq!(MessageTs::new("3.4").to_string(), "3.4");
+    }
+
+    #[test]
+    fn test_channel_id_parse_valid() {
+        for id in ["C09NU1KFXHT", "G0123ABC", "D12345"] {
+            assert_eq!(ChannelId::parse(id).unwrap().as_str(), id);
+        }
+    }
+
+    #[test]
+    fn test_is_direct_message() {
+        assert!(ChannelId::new("D12345").is_direct_message());
+        assert!(!ChannelId::new("C09NU1KFXHT").is_direct_message());
+        assert!(!ChannelId::new("G0123ABC").is_direct_message());
+    }
+
+    #[test]
+    fn test_channel_id_parse_invalid() {
+        for id in [
+            "",
+            "C",
+            "U09JDBT2MCM",    // user id
+            "1700000000.123", // message ts
+            "c09nu1kfxht",    // lowercase
+            "C09NU1KF-XHT",
+            " C09NU1KFXHT",
+            "general",
+        ] {
+            assert!(
+                ChannelId::parse(id).is_err(),
+                "expected '{}' to be rejected",
+                id
+            );
+        }
+    }
 }
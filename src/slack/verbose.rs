@@ -0,0 +1,201 @@
+//! Live feed of the agent's tool calls for channels with `/verbose on`
+
+use crate::agent::ToolAction;
+use crate::filter::{FilterResult, SecretOutputFilter};
+use crate::slack::{ChannelId, MessageTs, SlackClient, ThreadTs};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Most recent actions listed in the feed; earlier ones are only counted
+const MAX_SHOWN: usize = 5;
+
+/// The lines of one query's feed
+#[derive(Debug, Default)]
+struct FeedLines {
+    recent: VecDeque<String>,
+    total: usize,
+}
+
+impl FeedLines {
+    fn push(&mut self, line: String) {
+        self.total += 1;
+        if self.recent.len() == MAX_SHOWN {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(line);
+    }
+
+    /// The feed text; once the query is `done` the header says so
+    fn render(&self, done: bool) -> String {
+        let plural = if self.total == 1 { "" } else { "s" };
+        let mut text = if done {
+            format!("✅ _Done — {} action{}_", self.total, plural)
+        } else {
+            format!("🔧 _Working — {} action{} so far_", self.total, plural)
+        };
+        let earlier = self.total - self.recent.len();
+        if earlier > 0 {
+            text.push_str(&format!("\n_…{} earlier_", earlier));
+        }
+        for line in &self.recent {
+            text.push_str("\n• ");
+            text.push_str(line);
+        }
+        text
+    }
+}
+
+/// Posts the files the agent writes and the commands it runs while a query streams.
+///
+/// The feed is a single message edited in place, at most once per Slack's update
+/// interval; actions arriving in between are folded into the next edit.
+pub struct VerboseFeed {
+    actions: mpsc::UnboundedSender<ToolAction>,
+    task: JoinHandle<()>,
+}
+
+impl VerboseFeed {
+    pub fn spawn(
+        slack_client: Arc<SlackClient>,
+        channel: ChannelId,
+        thread_ts: Option<ThreadTs>,
+        repo_root: PathBuf,
+        output_filter: SecretOutputFilter,
+    ) -> Self {
+        let (actions, mut received) = mpsc::unbounded_channel::<ToolAction>();
+        let task = tokio::spawn(async move {
+            let interval = slack_client.limits().min_update_interval;
+            let mut lines = FeedLines::default();
+            let mut feed_ts: Option<MessageTs> = None;
+            let mut last_sent: Option<Instant> = None;
+            // Commands can carry secrets, so each line goes through the output filter
+            let push = |lines: &mut FeedLines, action: ToolAction| {
+                let line = action.describe(Some(&repo_root));
+                match output_filter.apply(&line) {
+                    FilterResult::Pass => lines.push(line),
+                    FilterResult::Rewrite(masked) => lines.push(masked),
+                    FilterResult::Block(_) => {}
+                }
+            };
+
+            let mut done = false;
+            while !done {
+                let Some(action) = received.recv().await else {
+                    break;
+                };
+                push(&mut lines, action);
+                // Fold in actions until the update interval has passed, unless the
+                // query finishes first: the final state shouldn't wait
+                if let Some(last_sent) = last_sent {
+                    let wait = tokio::time::sleep(interval.saturating_sub(last_sent.elapsed()));
+                    tokio::pin!(wait);
+                    loop {
+                        tokio::select! {
+                            _ = &mut wait => break,
+                            action = received.recv() => match action {
+                                Some(action) => push(&mut lines, action),
+                                None => {
+                                    done = true;
+                                    break;
+                                }
+                            },
+                        }
+                    }
+                }
+                while let Ok(action) = received.try_recv() {
+                    push(&mut lines, action);
+                }
+                if lines.total == 0 || done {
+                    continue;
+                }
+
+                post(
+                    &slack_client,
+                    &channel,
+                    thread_ts.as_ref(),
+                    &mut feed_ts,
+                    &lines.render(false),
+                )
+                .await;
+                last_sent = Some(Instant::now());
+            }
+
+            if lines.total > 0 {
+                post(
+                    &slack_client,
+                    &channel,
+                    thread_ts.as_ref(),
+                    &mut feed_ts,
+                    &lines.render(true),
+                )
+                .await;
+            }
+        });
+        Self { actions, task }
+    }
+
+    /// Where the response stream reports actions
+    pub fn sender(&self) -> &mpsc::UnboundedSender<ToolAction> {
+        &self.actions
+    }
+
+    /// Mark the feed done, with any actions still pending, and stop
+    pub async fn finish(self) {
+        drop(self.actions);
+        if let Err(e) = self.task.await {
+            tracing::warn!(error = %e, "Verbose feed task failed");
+        }
+    }
+}
+
+/// Post the feed, or edit it once it has been posted
+async fn post(
+    slack_client: &SlackClient,
+    channel: &ChannelId,
+    thread_ts: Option<&ThreadTs>,
+    feed_ts: &mut Option<MessageTs>,
+    text: &str,
+) {
+    let sent = match feed_ts.clone() {
+        Some(ts) => slack_client.update_message(channel, &ts, text).await,
+        None => slack_client
+            .send_message(channel, text, thread_ts)
+            .await
+            .map(|ts| *feed_ts = Some(ts)),
+    };
+    if let Err(e) = sent {
+        tracing::warn!(error = %e, "Failed to post verbose update");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feed_keeps_recent_lines() {
+        let mut lines = FeedLines::default();
+        lines.push("✏️ `src/main.rs`".to_string());
+        assert_eq!(
+            lines.render(false),
+            "🔧 _Working — 1 action so far_\n• ✏️ `src/main.rs`"
+        );
+
+        for i in 0..6 {
+            lines.push(format!("💻 `step {}`", i));
+        }
+        let text = lines.render(false);
+        assert!(text.starts_with("🔧 _Working — 7 actions so far_\n_…2 earlier_"));
+        assert!(!text.contains("src/main.rs"));
+        assert!(!text.contains("step 0"));
+        assert!(text.ends_with("• 💻 `step 5`"));
+
+        let text = lines.render(true);
+        assert!(text.starts_with("✅ _Done — 7 actions_\n_…2 earlier_"));
+        assert!(text.ends_with("• 💻 `step 5`"));
+    }
+}
@@ -0,0 +1,151 @@
+//! Response-quality feedback captured from 👍/👎 reactions on bot messages
+//!
+//! Records are appended to `~/.slack_coder/feedback.jsonl`, one JSON object per line:
+//!
+//! ```json
+//! {"channel":"C123","session_id":"session-C123-...","message_ts":"1700000000.000100","user":"U456","rating":"up","recorded_at":1700000123}
+//! ```
+//!
+//! `rating` is `up` or `down`; `recorded_at` is unix seconds. Append-only so the file can
+//! be tailed or loaded into any JSONL tool.
+
+use serde::{Deserialize, Serialize};
+
+/// A user's verdict on a bot response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Rating {
+    Up,
+    Down,
+}
+
+impl Rating {
+    /// Map a Slack reaction name to a rating, ignoring skin-tone modifiers
+    /// (`+1::skin-tone-3`). Other reactions aren't feedback.
+    pub fn from_reaction(name: &str) -> Option<Self> {
+        let base = name.split("::").next().unwrap_or(name);
+        match base {
+            "+1" | "thumbsup" => Some(Self::Up),
+            "-1" | "thumbsdown" => Some(Self::Down),
+            _ => None,
+        }
+    }
+}
+
+/// One line of `feedback.jsonl`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeedbackRecord {
+    pub channel: String,
+    /// Session the rated response ran in, if it's still remembered
+    pub session_id: Option<String>,
+    /// Timestamp of the rated bot message
+    pub message_ts: String,
+    /// User who reacted
+    pub user: String,
+    pub rating: Rating,
+    /// Unix seconds
+    pub recorded_at: u64,
+}
+
+/// Rating counts for `/feedback summary`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeedbackSummary {
+    pub up: usize,
+    pub down: usize,
+}
+
+impl FeedbackSummary {
+    /// Count the ratings in `records` for one channel
+    pub fn for_channel<'a>(
+        records: impl IntoIterator<Item = &'a FeedbackRecord>,
+        channel: &str,
+    ) -> Self {
+        records.into_iter().filter(|r| r.channel == channel).fold(
+            Self::default(),
+            |mut summary, r| {
+                match r.rating {
+                    Rating::Up => summary.up += 1,
+                    Rating::Down => summary.down += 1,
+                }
+                summary
+            },
+        )
+    }
+
+    pub fn total(&self) -> usize {
+        self.up + self.down
+    }
+
+    /// Format for Slack
+    pub fn format_slack_message(&self) -> String {
+        if self.total() == 0 {
+            return "📝 *Feedback*\n\nNo ratings yet. React with 👍 or 👎 on my replies to rate them."
+                .to_string();
+        }
+
+        let helpful_pct = self.up * 100 / self.total();
+        format!(
+            "📝 *Feedback*\n\n• 👍 {}\n• 👎 {}\n• Helpful: {}% of {} ratings",
+            self.up,
+            self.down,
+            helpful_pct,
+            self.total()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(channel: &str, rating: Rating) -> FeedbackRecord {
+        FeedbackRecord {
+            channel: channel.to_string(),
+            session_id: Some("session-C1".to_string()),
+            message_ts: "1700000000.000100".to_string(),
+            user: "U1".to_string(),
+            rating,
+            recorded_at: 1_700_000_123,
+        }
+    }
+
+    #[test]
+    fn test_rating_from_reaction() {
+        assert_eq!(Rating::from_reaction("+1"), Some(Rating::Up));
+        assert_eq!(Rating::from_reaction("thumbsup"), Some(Rating::Up));
+        assert_eq!(Rating::from_reaction("+1::skin-tone-4"), Some(Rating::Up));
+        assert_eq!(Rating::from_reaction("-1"), Some(Rating::Down));
+        assert_eq!(Rating::from_reaction("thumbsdown"), Some(Rating::Down));
+        assert_eq!(Rating::from_reaction("tada"), None);
+    }
+
+    #[test]
+    fn test_record_json_line() {
+        let line = serde_json::to_string(&record("C1", Rating::Down)).unwrap();
+        assert!(line.contains(r#""rating":"down""#));
+        assert!(!line.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<FeedbackRecord>(&line).unwrap(),
+            record("C1", Rating::Down)
+        );
+    }
+
+    #[test]
+    fn test_summary_for_channel() {
+        let records = vec![
+            record("C1", Rating::Up),
+            record("C1", Rating::Up),
+            record("C1", Rating::Down),
+            record("C2", Rating::Down),
+        ];
+        let summary = FeedbackSummary::for_channel(&records, "C1");
+        assert_eq!(summary, FeedbackSummary { up: 2, down: 1 });
+
+        let message = summary.format_slack_message();
+        assert!(message.contains("👍 2"));
+        assert!(message.contains("Helpful: 66% of 3 ratings"));
+
+        let empty = FeedbackSummary::for_channel(&records, "C3");
+        assert!(empty.format_slack_message().contains("No ratings yet"));
+    }
+}
@@ -0,0 +1,283 @@
+//! Inspection of a repository's git working tree, and switching its branch
+
+use crate::error::{Result, SlackCoderError};
+use std::path::Path;
+use std::process::Command;
+
+/// Branch and working-tree state of a repository
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitStatus {
+    /// Current branch, or `HEAD` when detached
+    pub branch: String,
+    /// Number of modified, staged, or untracked files
+    pub dirty_files: usize,
+}
+
+impl GitStatus {
+    /// Inspect the repository at `path`. Returns `None` if it isn't a git work tree.
+    pub async fn read(path: &Path) -> Result<Option<Self>> {
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || Self::read_blocking(&path))
+            .await
+            .map_err(|e| SlackCoderError::Internal(format!("git status task failed: {}", e)))?
+    }
+
+    fn read_blocking(path: &Path) -> Result<Option<Self>> {
+        if !path.is_dir() {
+            return Ok(None);
+        }
+
+        let Some(branch) = run_git(path, &["rev-parse", "--abbrev-ref", "HEAD"])? else {
+            return Ok(None);
+        };
+        let Some(porcelain) = run_git(path, &["status", "--porcelain"])? else {
+            return Ok(None);
+        };
+
+        Ok(Some(Self {
+            branch: branch.trim().to_string(),
+            dirty_files: count_dirty_files(&porcelain),
+        }))
+    }
+}
+
+/// What `checkout_branch` did
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BranchSwitch {
+    /// The branch was already checked out
+    Unchanged,
+    /// Switched to an existing local or remote branch
+    Switched,
+    /// Created the branch from the current commit
+    Created,
+}
+
+/// Check out `branch` in the repository at `path`, creating it from the current commit
+/// if neither a local nor an `origin` branch of that name exists.
+///
+/// Uncommitted changes come along when git allows it; if they would be overwritten,
+/// git refuses and the error says so.
+pub async fn checkout_branch(path: &Path, branch: &str) -> Result<BranchSwitch> {
+    let path = path.to_path_buf();
+    let branch = branch.trim().to_string();
+    tokio::task::spawn_blocking(move || checkout_branch_blocking(&path, &branch))
+        .await
+        .map_err(|e| SlackCoderError::Internal(format!("git checkout task failed: {}", e)))?
+}
+
+fn checkout_branch_blocking(path: &Path, branch: &str) -> Result<BranchSwitch> {
+    if !path.is_dir() {
+        return Err(SlackCoderError::Git(
+            "the repository clone is missing".to_string(),
+        ));
+    }
+    // Also rules out names starting with `-`, which git would read as options
+    if branch.is_empty() || run_git(path, &["check-ref-format", "--branch", branch])?.is_none() {
+        return Err(SlackCoderError::Git(format!(
+            "'{}' is not a valid branch name",
+            branch
+        )));
+    }
+
+    let current = run_git(path, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+    if current.as_deref().map(str::trim) == Some(branch) {
+        return Ok(BranchSwitch::Unchanged);
+    }
+
+    let local_ref = format!("refs/heads/{}", branch);
+    let remote_ref = format!("refs/remotes/origin/{}", branch);
+    let exists = |reference: &str| -> Result<bool> {
+        Ok(run_git(path, &["rev-parse", "--verify", "--quiet", reference])?.is_some())
+    };
+    if exists(&local_ref)? || exists(&remote_ref)? {
+        // A remote-only branch gets a local branch tracking it
+        run_git_checked(path, &["checkout", branch, "--"])?;
+        Ok(BranchSwitch::Switched)
+    } else {
+        run_git_checked(path, &["checkout", "-b", branch])?;
+        Ok(BranchSwitch::Created)
+    }
+}
+
+/// The `host/owner/repo` that the repository at `path` was cloned from (its `origin`
+/// remote). `None` if it isn't a git work tree or origin isn't a `host/owner/repo` URL.
+pub async fn origin_repo(path: &Path) -> Result<Option<String>> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        if !path.is_dir() {
+            return Ok(None);
+        }
+        let url = run_git(&path, &["remote", "get-url", "origin"])?;
+        Ok(url.as_deref().and_then(parse_remote_repo))
+    })
+    .await
+    .map_err(|e| SlackCoderError::Internal(format!("git remote task failed: {}", e)))?
+}
+
+/// Extract `host/owner/repo` from an HTTPS or SSH remote URL
+fn parse_remote_repo(url: &str) -> Option<String> {
+    let url = url.trim();
+    let (host, path) = if let Some(rest) = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .or_else(|| url.strip_prefix("ssh://git@"))
+    {
+        rest.split_once('/')?
+    } else {
+        // scp-like syntax: git@host:owner/repo
+        url.strip_prefix("git@")?.split_once(':')?
+    };
+    let path = path.trim_end_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    let (owner, repo) = path.split_once('/')?;
+    if host.is_empty() || owner.is_empty() || repo.is_empty() || repo.contains('/') {
+        return None;
+    }
+    Some(format!("{}/{}/{}", host.to_ascii_lowercase(), owner, repo))
+}
+
+/// Run a git command in `dir`, returning its stdout, or `None` if git reported an
+/// error (e.g. the directory isn't a repository)
+fn run_git(dir: &Path, args: &[&str]) -> Result<Option<String>> {
+    let output = Command::new("git").args(args).current_dir(dir).output()?;
+    if !output.status.success() {
+        tracing::debug!(
+            args = ?args,
+            stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+            "git command failed"
+        );
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&output.stdout).into_owned()))
+}
+
+/// Run a git command in `dir` that must succeed; a failure carries git's message
+fn run_git_checked(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git").args(args).current_dir(dir).output()?;
+    if !output.status.success() {
+        return Err(SlackCoderError::Git(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Count entries in `git status --porcelain` output (one line per file)
+fn count_dirty_files(porcelain: &str) -> usize {
+    porcelain.lines().filter(|l| !l.trim().is_empty()).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_dirty_files() {
+        assert_eq!(count_dirty_files(""), 0);
+        assert_eq!(
+            count_dirty_files(" M src/lib.rs\nA  new.rs\n?? notes.md\n"),
+            3
+        );
+    }
+
+    #[test]
+    fn test_parse_remote_repo() {
+        for url in [
+            "https://github.com/tyrchen/slack-coder.git\n",
+            "https://github.com/tyrchen/slack-coder",
+            "git@github.com:tyrchen/slack-coder.git",
+            "ssh://git@github.com/tyrchen/slack-coder/",
+        ] {
+            assert_eq!(
+                parse_remote_repo(url).as_deref(),
+                Some("github.com/tyrchen/slack-coder"),
+                "{url}"
+            );
+        }
+        assert_eq!(
+            parse_remote_repo("git@GHE.company.com:platform/api.git").as_deref(),
+            Some("ghe.company.com/platform/api")
+        );
+        assert_eq!(parse_remote_repo("https://github.com/tyrchen"), None);
+        assert_eq!(parse_remote_repo("/srv/git/api.git"), None);
+    }
+
+    #[tokio::test]
+    async fn test_origin_repo_missing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let origin = origin_repo(&dir.path().join("missing")).await.unwrap();
+        assert_eq!(origin, None);
+    }
+
+    /// A repository with one commit on `main`
+    fn init_repo(dir: &Path) {
+        for args in [
+            &["init", "--quiet"][..],
+            &["checkout", "--quiet", "-b", "main"],
+            &["config", "user.email", "bot@example.com"],
+            &["config", "user.name", "Bot"],
+            &["config", "commit.gpgsign", "false"],
+        ] {
+            run_git_checked(dir, args).unwrap();
+        }
+        std::fs::write(dir.join("notes.md"), "main\n").unwrap();
+        run_git_checked(dir, &["add", "."]).unwrap();
+        run_git_checked(dir, &["commit", "--quiet", "-m", "init"]).unwrap();
+    }
+
+    async fn branch(dir: &Path) -> String {
+        GitStatus::read(dir).await.unwrap().unwrap().branch
+    }
+
+    #[tokio::test]
+    async fn test_checkout_branch_creates_and_switches() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+
+        let switch = checkout_branch(dir.path(), "feature/login").await.unwrap();
+        assert_eq!(switch, BranchSwitch::Created);
+        assert_eq!(branch(dir.path()).await, "feature/login");
+
+        let switch = checkout_branch(dir.path(), "feature/login").await.unwrap();
+        assert_eq!(switch, BranchSwitch::Unchanged);
+
+        let switch = checkout_branch(dir.path(), "main").await.unwrap();
+        assert_eq!(switch, BranchSwitch::Switched);
+        assert_eq!(branch(dir.path()).await, "main");
+
+        assert!(checkout_branch(dir.path(), "-b").await.is_err());
+        assert!(checkout_branch(dir.path(), "bad..name").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_checkout_branch_refuses_to_overwrite_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        init_repo(dir.path());
+
+        // `notes.md` differs on the feature branch...
+        checkout_branch(dir.path(), "feature").await.unwrap();
+        std::fs::write(dir.path().join("notes.md"), "feature\n").unwrap();
+        run_git_checked(dir.path(), &["commit", "--quiet", "-am", "feature"]).unwrap();
+        checkout_branch(dir.path(), "main").await.unwrap();
+
+        // ...so uncommitted edits to it on main can't come along
+        std::fs::write(dir.path().join("notes.md"), "draft\n").unwrap();
+        let err = checkout_branch(dir.path(), "feature").await.unwrap_err();
+        assert!(matches!(err, SlackCoderError::Git(_)));
+        assert_eq!(branch(dir.path()).await, "main");
+
+        // A new branch takes the edits with it
+        let switch = checkout_branch(dir.path(), "draft").await.unwrap();
+        assert_eq!(switch, BranchSwitch::Created);
+        let status = GitStatus::read(dir.path()).await.unwrap().unwrap();
+        assert_eq!(status.dirty_files, 1);
+    }
+
+    #[tokio::test]
+    async fn test_read_missing_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let status = GitStatus::read(&dir.path().join("missing")).await.unwrap();
+        assert_eq!(status, None);
+    }
+}
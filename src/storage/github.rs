@@ -0,0 +1,89 @@
+//! Lightweight GitHub lookups via the `gh` CLI, used to check a repository before setup
+
+use crate::error::{Result, SlackCoderError};
+use serde::Deserialize;
+use std::process::Command;
+
+/// Fields requested from `gh repo view --json`
+const REPO_VIEW_FIELDS: &str = "nameWithOwner,visibility,defaultBranchRef,diskUsage";
+
+/// What `gh` reports about a repository
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GithubRepoInfo {
+    pub name_with_owner: String,
+    /// `PUBLIC`, `PRIVATE` or `INTERNAL`
+    pub visibility: String,
+    #[serde(default)]
+    pub default_branch_ref: Option<BranchRef>,
+    /// Approximate size in kilobytes
+    #[serde(default)]
+    pub disk_usage: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct BranchRef {
+    pub name: String,
+}
+
+impl GithubRepoInfo {
+    /// Look up `owner/repo` without cloning it. `repo_name` must already be validated.
+    pub async fn fetch(repo_name: &str) -> Result<Self> {
+        let repo_name = repo_name.to_string();
+        tokio::task::spawn_blocking(move || Self::fetch_blocking(&repo_name))
+            .await
+            .map_err(|e| SlackCoderError::Internal(format!("gh repo view task failed: {}", e)))?
+    }
+
+    fn fetch_blocking(repo_name: &str) -> Result<Self> {
+        let output = Command::new("gh")
+            .args(["repo", "view", repo_name, "--json", REPO_VIEW_FIELDS])
+            .output()?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(SlackCoderError::Config(format!(
+                "Cannot access `{}`: {}",
+                repo_name,
+                stderr.trim()
+            )));
+        }
+
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+
+    /// Default branch name, if the repository has any commits
+    pub fn default_branch(&self) -> Option<&str> {
+        self.default_branch_ref.as_ref().map(|b| b.name.as_str())
+    }
+
+    /// Approximate size in bytes
+    pub fn size_bytes(&self) -> Option<u64> {
+        self.disk_usage.map(|kb| kb * 1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_repo_view_output() {
+        let json = r#"{"defaultBranchRef":{"name":"main"},"diskUsage":2048,"nameWithOwner":"tyrchen/slack-coder","visibility":"PUBLIC"}"#;
+        let info: GithubRepoInfo = serde_json::from_str(json).unwrap();
+
+        assert_eq!(info.name_with_owner, "tyrchen/slack-coder");
+        assert_eq!(info.visibility, "PUBLIC");
+        assert_eq!(info.default_branch(), Some("main"));
+        assert_eq!(info.size_bytes(), Some(2048 * 1024));
+    }
+
+    #[test]
+    fn test_parse_empty_repo() {
+        let json = r#"{"defaultBranchRef":null,"diskUsage":0,"nameWithOwner":"o/empty","visibility":"PRIVATE"}"#;
+        let info: GithubRepoInfo = serde_json::from_str(json).unwrap();
+
+        assert_eq!(info.default_branch(), None);
+        assert_eq!(info.size_bytes(), Some(0));
+    }
+}
@@ -1,3 +1,11 @@
+mod feedback;
+mod git;
+mod github;
 mod workspace;
 
-pub use workspace::Workspace;
+pub use feedback::{FeedbackRecord, FeedbackSummary, Rating};
+pub use git::{BranchSwitch, GitStatus, checkout_branch, origin_repo};
+pub use github::{BranchRef, GithubRepoInfo};
+#[cfg(test)]
+pub(crate) use workspace::test_workspace;
+pub use workspace::{SetupStatus, SetupSteps, Workspace, format_bytes};
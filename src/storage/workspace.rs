@@ -1,12 +1,142 @@
-use crate::error::Result;
-use crate::slack::ChannelId;
+use crate::config::{ChannelConfig, RepoSpec};
+use crate::error::{Result, SlackCoderError};
+use crate::slack::{ChannelId, SessionUsage};
+use crate::storage::{FeedbackRecord, origin_repo};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+/// System directories a workspace must never be rooted at
+const FORBIDDEN_BASE_PATHS: &[&str] = &[
+    "/", "/bin", "/boot", "/dev", "/etc", "/lib", "/lib64", "/proc", "/run", "/sbin", "/sys",
+    "/usr", "/var",
+];
+
+/// Written and removed by `ensure_workspace` to prove the base path is writable
+const WRITE_PROBE_FILE: &str = ".write-probe";
 
 pub struct Workspace {
     base_path: PathBuf,
 }
 
+/// How far a channel's setup got on disk
+///
+/// A channel needs both its cloned repository and its generated system prompt. One
+/// without the other means setup was interrupted (or files were removed by hand).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupStatus {
+    /// Repository and system prompt both present
+    FullySetup,
+    /// Repository cloned, but no system prompt (analysis didn't finish)
+    RepoOnly,
+    /// System prompt present, but the repository directory is gone
+    PromptOnly,
+    /// Nothing on disk for this channel
+    NotSetup,
+}
+
+impl SetupStatus {
+    fn from_parts(repo_exists: bool, prompt_exists: bool) -> Self {
+        match (repo_exists, prompt_exists) {
+            (true, true) => Self::FullySetup,
+            (true, false) => Self::RepoOnly,
+            (false, true) => Self::PromptOnly,
+            (false, false) => Self::NotSetup,
+        }
+    }
+
+    /// Whether an agent can be created for the channel
+    pub fn is_ready(&self) -> bool {
+        *self == Self::FullySetup
+    }
+
+    /// Whether setup was started but left incomplete
+    pub fn is_partial(&self) -> bool {
+        matches!(self, Self::RepoOnly | Self::PromptOnly)
+    }
+
+    /// What's missing, in words suitable for logs and user messages (`None` when fully set up)
+    pub fn missing(&self) -> Option<&'static str> {
+        match self {
+            Self::FullySetup => None,
+            Self::RepoOnly => Some(
+                "the repository is cloned but its system prompt is missing - setup probably stopped before analysis finished",
+            ),
+            Self::PromptOnly => Some(
+                "the system prompt exists but the repository clone is missing - the repo directory may have been deleted",
+            ),
+            Self::NotSetup => Some("neither the repository nor its system prompt exist"),
+        }
+    }
+
+    /// `Ok` when fully set up, otherwise the matching not-setup/incomplete-setup error
+    pub fn ensure_ready(&self, channel_id: &ChannelId) -> Result<()> {
+        match self {
+            Self::FullySetup => Ok(()),
+            Self::NotSetup => Err(SlackCoderError::ChannelNotSetup(channel_id.to_string())),
+            Self::RepoOnly | Self::PromptOnly => Err(SlackCoderError::IncompleteSetup {
+                channel: channel_id.to_string(),
+                missing: self.missing().unwrap_or_default(),
+            }),
+        }
+    }
+}
+
+/// Which setup steps still have to run for a channel, so rerunning an interrupted
+/// setup picks up where it stopped instead of starting over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SetupSteps {
+    /// Validate and clone the repository
+    pub clone: bool,
+    /// Analyze the clone and write the system prompt
+    pub generate_prompt: bool,
+    /// Remove a leftover clone first (it's broken or from another repository)
+    pub discard_clone: bool,
+}
+
+impl SetupSteps {
+    /// Every step, for a fresh setup
+    pub const ALL: Self = Self {
+        clone: true,
+        generate_prompt: true,
+        discard_clone: false,
+    };
+
+    /// Steps left given how far setup got. Leftovers are only reused when they belong
+    /// to the requested repository (`same_repo`); a fully set up channel is set up
+    /// again from scratch, as before.
+    pub fn resume(status: SetupStatus, same_repo: bool) -> Self {
+        match status {
+            SetupStatus::RepoOnly if same_repo => Self {
+                clone: false,
+                ..Self::ALL
+            },
+            SetupStatus::RepoOnly => Self {
+                discard_clone: true,
+                ..Self::ALL
+            },
+            SetupStatus::PromptOnly if same_repo => Self {
+                generate_prompt: false,
+                ..Self::ALL
+            },
+            _ => Self::ALL,
+        }
+    }
+
+    /// Steps skipped because an earlier attempt finished them, in words for the user
+    pub fn skipped(&self) -> Vec<&'static str> {
+        let mut skipped = Vec::new();
+        if !self.clone {
+            skipped.push("cloning (the repository is already on disk)");
+        }
+        if !self.generate_prompt {
+            skipped.push("analysis (the system prompt already exists)");
+        }
+        skipped
+    }
+}
+
 impl Workspace {
     pub fn new(base_path: PathBuf) -> Self {
         Self { base_path }
@@ -30,8 +160,173 @@ impl Workspace {
             .join("system_prompt.md")
     }
 
-    /// Check if channel has an existing repository setup
-    pub async fn is_channel_setup(&self, channel_id: &ChannelId) -> bool {
+    /// Returns path to channel's config: ~/.slack_coder/system/{channel_id}/channel_config.json
+    pub fn channel_config_path(&self, channel_id: &ChannelId) -> PathBuf {
+        self.base_path
+            .join("system")
+            .join(channel_id.as_str())
+            .join("channel_config.json")
+    }
+
+    /// Returns path to channel's prompt overlay: ~/.slack_coder/system/{channel_id}/overlay.md
+    pub fn prompt_overlay_path(&self, channel_id: &ChannelId) -> PathBuf {
+        self.base_path
+            .join("system")
+            .join(channel_id.as_str())
+            .join("overlay.md")
+    }
+
+    /// Load the channel's standing instructions overlay, if present and non-empty
+    ///
+    /// A missing overlay is the normal case; an unreadable one is logged and skipped
+    /// so it never blocks agent creation.
+    pub async fn load_prompt_overlay(&self, channel_id: &ChannelId) -> Option<String> {
+        let path = self.prompt_overlay_path(channel_id);
+        match fs::read_to_string(&path).await {
+            Ok(content) if content.trim().is_empty() => None,
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                tracing::warn!(
+                    path = %path.display(),
+                    error = %e,
+                    "Failed to read prompt overlay, ignoring it"
+                );
+                None
+            }
+        }
+    }
+
+    /// Load the channel's config overrides (defaults if none saved yet)
+    pub async fn load_channel_config(&self, channel_id: &ChannelId) -> Result<ChannelConfig> {
+        let path = self.channel_config_path(channel_id);
+        match fs::read_to_string(&path).await {
+            Ok(content) => Ok(serde_json::from_str(&content)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(ChannelConfig::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Persist the channel's config overrides
+    pub async fn save_channel_config(
+        &self,
+        channel_id: &ChannelId,
+        config: &ChannelConfig,
+    ) -> Result<()> {
+        let path = self.channel_config_path(channel_id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(config)?).await?;
+        Ok(())
+    }
+
+    /// Returns path to channel's session usage totals: ~/.slack_coder/system/{channel_id}/session_usage.json
+    pub fn session_usage_path(&self, channel_id: &ChannelId) -> PathBuf {
+        self.base_path
+            .join("system")
+            .join(channel_id.as_str())
+            .join("session_usage.json")
+    }
+
+    /// Load the channel's persisted session usage, if any
+    pub async fn load_session_usage(&self, channel_id: &ChannelId) -> Result<Option<SessionUsage>> {
+        let path = self.session_usage_path(channel_id);
+        match fs::read_to_string(&path).await {
+            Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Persist the channel's session usage
+    pub async fn save_session_usage(
+        &self,
+        channel_id: &ChannelId,
+        usage: &SessionUsage,
+    ) -> Result<()> {
+        let path = self.session_usage_path(channel_id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(usage)?).await?;
+        Ok(())
+    }
+
+    /// Returns path to the channel's last startup notice time: ~/.slack_coder/system/{channel_id}/startup_notice
+    pub fn startup_notice_path(&self, channel_id: &ChannelId) -> PathBuf {
+        self.base_path
+            .join("system")
+            .join(channel_id.as_str())
+            .join("startup_notice")
+    }
+
+    /// When the channel last got an "Agent Ready" notice, if ever (or if unreadable)
+    pub async fn load_last_startup_notice(&self, channel_id: &ChannelId) -> Option<SystemTime> {
+        let content = fs::read_to_string(self.startup_notice_path(channel_id))
+            .await
+            .ok()?;
+        let secs = content.trim().parse().ok()?;
+        Some(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// Record that the channel got an "Agent Ready" notice at `at`
+    pub async fn save_last_startup_notice(
+        &self,
+        channel_id: &ChannelId,
+        at: SystemTime,
+    ) -> Result<()> {
+        let path = self.startup_notice_path(channel_id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let secs = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        fs::write(&path, secs.to_string()).await?;
+        Ok(())
+    }
+
+    /// Returns path to the response feedback log: ~/.slack_coder/feedback.jsonl
+    pub fn feedback_path(&self) -> PathBuf {
+        self.base_path.join("feedback.jsonl")
+    }
+
+    /// Append one rating to the feedback log
+    pub async fn append_feedback(&self, record: &FeedbackRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.feedback_path())
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// Load every rating from the feedback log, skipping lines that don't parse
+    pub async fn load_feedback(&self) -> Result<Vec<FeedbackRecord>> {
+        let content = match fs::read_to_string(self.feedback_path()).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(line) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    tracing::warn!(error = %e, "Skipping malformed feedback line");
+                    None
+                }
+            })
+            .collect())
+    }
+
+    /// Check how much of the channel's repository setup exists on disk
+    pub async fn is_channel_setup(&self, channel_id: &ChannelId) -> SetupStatus {
         let repo_path = self.repo_path(channel_id);
         let system_prompt_path = self.system_prompt_path(channel_id);
 
@@ -47,7 +342,61 @@ impl Workspace {
             prompt_exists
         );
 
-        repo_exists && prompt_exists
+        SetupStatus::from_parts(repo_exists, prompt_exists)
+    }
+
+    /// Work out which setup steps `repo` still needs in this channel. A leftover clone
+    /// counts if its `origin` is that repository; a leftover system prompt counts if
+    /// the channel was last set up for it.
+    pub async fn setup_steps(&self, channel_id: &ChannelId, repo: &RepoSpec) -> SetupSteps {
+        let status = self.is_channel_setup(channel_id).await;
+        let same_repo = match status {
+            SetupStatus::RepoOnly => origin_repo(&self.repo_path(channel_id))
+                .await
+                .ok()
+                .flatten()
+                .is_some_and(|origin| repo.matches_remote(&origin)),
+            SetupStatus::PromptOnly => self
+                .load_channel_config(channel_id)
+                .await
+                .ok()
+                .and_then(|config| config.repo_name)
+                .is_some_and(|saved| saved.eq_ignore_ascii_case(&repo.to_string())),
+            SetupStatus::FullySetup | SetupStatus::NotSetup => false,
+        };
+
+        let steps = SetupSteps::resume(status, same_repo);
+        tracing::debug!(status = ?status, same_repo = same_repo, steps = ?steps, "Planned setup steps");
+        steps
+    }
+
+    /// Channels with a system prompt on disk, whether or not the bot is still in them,
+    /// sorted by id. Directories that aren't valid channel ids are skipped.
+    pub async fn list_setup_channels(&self) -> Result<Vec<ChannelId>> {
+        let mut entries = match fs::read_dir(self.base_path.join("system")).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut channels = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let Some(name) = entry.file_name().to_str().map(str::to_string) else {
+                continue;
+            };
+            let Ok(channel_id) = ChannelId::parse(&name) else {
+                continue;
+            };
+            if fs::metadata(self.system_prompt_path(&channel_id))
+                .await
+                .is_ok_and(|m| m.is_file())
+            {
+                channels.push(channel_id);
+            }
+        }
+
+        channels.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        Ok(channels)
     }
 
     /// Load system prompt from disk
@@ -57,10 +406,538 @@ impl Workspace {
         Ok(content)
     }
 
-    /// Ensure workspace directories exist
+    /// Total size in bytes of all files under the channel's repository (symlinks not followed)
+    pub async fn repo_size_bytes(&self, channel_id: &ChannelId) -> Result<u64> {
+        dir_size_bytes(&self.repo_path(channel_id)).await
+    }
+
+    /// Returns the channel's system directory (prompt, config, usage): ~/.slack_coder/system/{channel_id}/
+    pub fn system_dir(&self, channel_id: &ChannelId) -> PathBuf {
+        self.base_path.join("system").join(channel_id.as_str())
+    }
+
+    /// Bytes a `remove_channel` would reclaim
+    pub async fn channel_size_bytes(&self, channel_id: &ChannelId) -> Result<u64> {
+        let mut total = 0;
+        for path in [self.repo_path(channel_id), self.system_dir(channel_id)] {
+            if fs::symlink_metadata(&path).await.is_ok() {
+                total += dir_size_bytes(&path).await?;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Delete everything stored for a channel: its repository clone and its system
+    /// directory. Returns the number of bytes reclaimed.
+    ///
+    /// Deletion is refused if either path is a symlink or resolves outside the workspace,
+    /// so nothing else on disk (including other channels' data) is touched.
+    pub async fn remove_channel(&self, channel_id: &ChannelId) -> Result<u64> {
+        let base = fs::canonicalize(&self.base_path).await?;
+        let mut reclaimed = 0;
+
+        for path in [self.repo_path(channel_id), self.system_dir(channel_id)] {
+            let metadata = match fs::symlink_metadata(&path).await {
+                Ok(metadata) => metadata,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+
+            let resolved = fs::canonicalize(&path).await?;
+            if metadata.file_type().is_symlink() || !resolved.starts_with(&base) || resolved == base
+            {
+                return Err(SlackCoderError::Internal(format!(
+                    "Refusing to delete {}: it is a symlink or resolves outside the workspace",
+                    path.display()
+                )));
+            }
+
+            reclaimed += dir_size_bytes(&resolved).await?;
+            fs::remove_dir_all(&resolved).await?;
+            tracing::info!(path = %resolved.display(), "Removed channel data");
+        }
+
+        Ok(reclaimed)
+    }
+
+    /// Remove the channel's repository directory (e.g. a partial or rejected clone)
+    pub async fn remove_repo(&self, channel_id: &ChannelId) -> Result<()> {
+        let path = self.repo_path(channel_id);
+        match fs::remove_dir_all(&path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Ensure workspace directories exist, and that the base path is a sensible,
+    /// writable place for them. Misconfiguration is reported as a `Config` error, so it
+    /// surfaces at startup rather than on the first setup.
     pub async fn ensure_workspace(&self) -> Result<()> {
-        fs::create_dir_all(self.base_path.join("repos")).await?;
-        fs::create_dir_all(self.base_path.join("system")).await?;
+        let base = &self.base_path;
+        let invalid = |reason: String| {
+            SlackCoderError::Config(format!(
+                "Invalid WORKSPACE_BASE_PATH {}: {}",
+                base.display(),
+                reason
+            ))
+        };
+
+        if !base.is_absolute() {
+            return Err(invalid("must be an absolute path".to_string()));
+        }
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        if FORBIDDEN_BASE_PATHS
+            .iter()
+            .any(|root| base == Path::new(root))
+            || home.is_some_and(|home| base == &home)
+        {
+            return Err(invalid(
+                "refusing to use a system directory or the home directory itself; \
+                 use a dedicated directory such as ~/.slack_coder"
+                    .to_string(),
+            ));
+        }
+
+        for dir in ["repos", "system"] {
+            fs::create_dir_all(base.join(dir))
+                .await
+                .map_err(|e| invalid(format!("cannot create `{}/`: {}", dir, e)))?;
+        }
+
+        let probe = base.join(WRITE_PROBE_FILE);
+        fs::write(&probe, b"")
+            .await
+            .map_err(|e| invalid(format!("not writable: {}", e)))?;
+        let _ = fs::remove_file(&probe).await;
         Ok(())
     }
 }
+
+/// Total size in bytes of all files under `root` (symlinks not followed)
+async fn dir_size_bytes(root: &Path) -> Result<u64> {
+    let mut total = 0;
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let mut entries = fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let metadata = fs::symlink_metadata(entry.path()).await?;
+            if metadata.is_dir() {
+                pending.push(entry.path());
+            } else if metadata.is_file() {
+                total += metadata.len();
+            }
+        }
+    }
+
+    Ok(total)
+}
+
+/// Format a byte count in a human-readable way
+pub fn format_bytes(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    const MB: f64 = KB * 1024.0;
+    const GB: f64 = MB * 1024.0;
+
+    let bytes_f = bytes as f64;
+    if bytes_f >= GB {
+        format!("{:.1} GB", bytes_f / GB)
+    } else if bytes_f >= MB {
+        format!("{:.1} MB", bytes_f / MB)
+    } else if bytes_f >= KB {
+        format!("{:.1} KB", bytes_f / KB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+/// A workspace in a temporary directory, with `prompt` saved as the system prompt of
+/// each of `channels`. The directory is removed when the returned `TempDir` drops.
+#[cfg(test)]
+pub(crate) async fn test_workspace(
+    channels: &[&ChannelId],
+    prompt: &str,
+) -> (tempfile::TempDir, Workspace) {
+    let dir = tempfile::tempdir().unwrap();
+    let workspace = Workspace::new(dir.path().to_path_buf());
+    for channel_id in channels {
+        let path = workspace.system_prompt_path(channel_id);
+        fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+        fs::write(&path, prompt).await.unwrap();
+    }
+    (dir, workspace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_repo_size_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new(dir.path().to_path_buf());
+        let channel = ChannelId::new("C123");
+
+        let repo = workspace.repo_path(&channel);
+        fs::create_dir_all(repo.join("src/nested")).await.unwrap();
+        fs::write(repo.join("README.md"), vec![b'a'; 100])
+            .await
+            .unwrap();
+        fs::write(repo.join("src/lib.rs"), vec![b'b'; 250])
+            .await
+            .unwrap();
+        fs::write(repo.join("src/nested/mod.rs"), vec![b'c'; 1024])
+            .await
+            .unwrap();
+
+        assert_eq!(workspace.repo_size_bytes(&channel).await.unwrap(), 1374);
+
+        workspace.remove_repo(&channel).await.unwrap();
+        assert!(fs::metadata(&repo).await.is_err());
+        // Removing again is a no-op
+        workspace.remove_repo(&channel).await.unwrap();
+    }
+
+    #[test]
+    fn test_setup_steps_resume() {
+        assert_eq!(
+            SetupSteps::resume(SetupStatus::NotSetup, false),
+            SetupSteps::ALL
+        );
+        assert_eq!(
+            SetupSteps::resume(SetupStatus::FullySetup, true),
+            SetupSteps::ALL
+        );
+
+        let repo_only = SetupSteps::resume(SetupStatus::RepoOnly, true);
+        assert!(!repo_only.clone && repo_only.generate_prompt && !repo_only.discard_clone);
+        assert_eq!(repo_only.skipped().len(), 1);
+        assert!(repo_only.skipped()[0].starts_with("cloning"));
+
+        let other_repo = SetupSteps::resume(SetupStatus::RepoOnly, false);
+        assert!(other_repo.clone && other_repo.generate_prompt && other_repo.discard_clone);
+        assert!(other_repo.skipped().is_empty());
+
+        let prompt_only = SetupSteps::resume(SetupStatus::PromptOnly, true);
+        assert!(prompt_only.clone && !prompt_only.generate_prompt);
+        assert!(prompt_only.skipped()[0].starts_with("analysis"));
+
+        assert_eq!(
+            SetupSteps::resume(SetupStatus::PromptOnly, false),
+            SetupSteps::ALL
+        );
+    }
+
+    fn spec(name: &str) -> RepoSpec {
+        RepoSpec::parse(name, crate::config::DEFAULT_GIT_HOST).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_setup_steps_from_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new(dir.path().to_path_buf());
+        let channel = ChannelId::new("C123");
+
+        // Nothing on disk
+        assert_eq!(
+            workspace.setup_steps(&channel, &spec("owner/repo")).await,
+            SetupSteps::ALL
+        );
+
+        // A prompt from an earlier setup of the same repository, clone deleted
+        let prompt = workspace.system_prompt_path(&channel);
+        fs::create_dir_all(prompt.parent().unwrap()).await.unwrap();
+        fs::write(&prompt, "prompt").await.unwrap();
+        let config = ChannelConfig {
+            repo_name: Some("owner/repo".to_string()),
+            ..Default::default()
+        };
+        workspace
+            .save_channel_config(&channel, &config)
+            .await
+            .unwrap();
+        let steps = workspace.setup_steps(&channel, &spec("Owner/Repo")).await;
+        assert!(steps.clone && !steps.generate_prompt);
+        assert_eq!(
+            workspace.setup_steps(&channel, &spec("owner/other")).await,
+            SetupSteps::ALL
+        );
+
+        // A clone that isn't a git work tree is discarded rather than reused
+        fs::remove_file(&prompt).await.unwrap();
+        fs::create_dir_all(workspace.repo_path(&channel))
+            .await
+            .unwrap();
+        let steps = workspace.setup_steps(&channel, &spec("owner/repo")).await;
+        assert!(steps.clone && steps.generate_prompt && steps.discard_clone);
+    }
+
+    #[tokio::test]
+    async fn test_load_prompt_overlay() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new(dir.path().to_path_buf());
+        let channel = ChannelId::new("C123");
+
+        assert_eq!(workspace.load_prompt_overlay(&channel).await, None);
+
+        let path = workspace.prompt_overlay_path(&channel);
+        fs::create_dir_all(path.parent().unwrap()).await.unwrap();
+        fs::write(&path, "  \n").await.unwrap();
+        assert_eq!(workspace.load_prompt_overlay(&channel).await, None);
+
+        fs::write(&path, "Always use conventional commits.")
+            .await
+            .unwrap();
+        assert_eq!(
+            workspace.load_prompt_overlay(&channel).await.as_deref(),
+            Some("Always use conventional commits.")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_session_usage_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new(dir.path().to_path_buf());
+        let channel = ChannelId::new("C123");
+
+        assert_eq!(workspace.load_session_usage(&channel).await.unwrap(), None);
+
+        let mut usage = SessionUsage::new("session-C123-1-abc");
+        usage.queries = 3;
+        usage.input_tokens = 1200;
+        workspace
+            .save_session_usage(&channel, &usage)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            workspace.load_session_usage(&channel).await.unwrap(),
+            Some(usage)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_feedback_append_and_load() {
+        use crate::storage::Rating;
+
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new(dir.path().to_path_buf());
+        assert!(workspace.load_feedback().await.unwrap().is_empty());
+
+        let record = FeedbackRecord {
+            channel: "C123".to_string(),
+            session_id: None,
+            message_ts: "1700000000.000100".to_string(),
+            user: "U1".to_string(),
+            rating: Rating::Up,
+            recorded_at: 1_700_000_123,
+        };
+        workspace.append_feedback(&record).await.unwrap();
+        workspace.append_feedback(&record).await.unwrap();
+
+        // A corrupt line doesn't lose the rest of the log
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(workspace.feedback_path())
+            .await
+            .unwrap();
+        file.write_all(b"not json\n").await.unwrap();
+
+        assert_eq!(
+            workspace.load_feedback().await.unwrap(),
+            vec![record.clone(), record]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remove_channel() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new(dir.path().to_path_buf());
+        let channel = ChannelId::new("C123");
+        let other = ChannelId::new("C999");
+
+        let repo = workspace.repo_path(&channel);
+        fs::create_dir_all(repo.join("src")).await.unwrap();
+        fs::write(repo.join("src/lib.rs"), vec![b'a'; 300])
+            .await
+            .unwrap();
+        let prompt = workspace.system_prompt_path(&channel);
+        fs::create_dir_all(prompt.parent().unwrap()).await.unwrap();
+        fs::write(&prompt, vec![b'b'; 50]).await.unwrap();
+
+        // Another channel's data must survive
+        let other_prompt = workspace.system_prompt_path(&other);
+        fs::create_dir_all(other_prompt.parent().unwrap())
+            .await
+            .unwrap();
+        fs::write(&other_prompt, "keep me").await.unwrap();
+
+        assert_eq!(workspace.channel_size_bytes(&channel).await.unwrap(), 350);
+        assert_eq!(workspace.remove_channel(&channel).await.unwrap(), 350);
+        assert!(fs::metadata(&repo).await.is_err());
+        assert!(fs::metadata(workspace.system_dir(&channel)).await.is_err());
+        assert!(fs::metadata(&other_prompt).await.is_ok());
+
+        // Nothing left to reclaim
+        assert_eq!(workspace.remove_channel(&channel).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_workspace_rejects_bad_roots() {
+        for base in ["relative/workspace", "/", "/etc/"] {
+            let error = Workspace::new(PathBuf::from(base))
+                .ensure_workspace()
+                .await
+                .unwrap_err();
+            assert!(
+                matches!(error, SlackCoderError::Config(_)),
+                "expected a config error for {}, got {:?}",
+                base,
+                error
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ensure_workspace_rejects_uncreatable_dir() {
+        // A directory can't be created under a regular file, even by root (which
+        // read-only permissions wouldn't stop)
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("not-a-directory");
+        fs::write(&file, b"").await.unwrap();
+
+        let error = Workspace::new(file.join("workspace"))
+            .ensure_workspace()
+            .await
+            .unwrap_err();
+
+        let SlackCoderError::Config(message) = error else {
+            panic!("expected a config error, got {:?}", error);
+        };
+        assert!(message.contains("Invalid WORKSPACE_BASE_PATH"));
+        assert!(message.contains("cannot create `repos/`"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_remove_channel_refuses_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("precious.txt"), "data")
+            .await
+            .unwrap();
+
+        let workspace = Workspace::new(dir.path().to_path_buf());
+        workspace.ensure_workspace().await.unwrap();
+        let channel = ChannelId::new("C123");
+        std::os::unix::fs::symlink(outside.path(), workspace.repo_path(&channel)).unwrap();
+
+        assert!(workspace.remove_channel(&channel).await.is_err());
+        assert!(
+            fs::metadata(outside.path().join("precious.txt"))
+                .await
+                .is_ok()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_is_channel_setup() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new(dir.path().to_path_buf());
+        let channel = ChannelId::new("C123");
+        let repo = workspace.repo_path(&channel);
+        let prompt = workspace.system_prompt_path(&channel);
+
+        assert_eq!(
+            workspace.is_channel_setup(&channel).await,
+            SetupStatus::NotSetup
+        );
+
+        fs::create_dir_all(&repo).await.unwrap();
+        assert_eq!(
+            workspace.is_channel_setup(&channel).await,
+            SetupStatus::RepoOnly
+        );
+
+        fs::create_dir_all(prompt.parent().unwrap()).await.unwrap();
+        fs::write(&prompt, "# Prompt").await.unwrap();
+        assert_eq!(
+            workspace.is_channel_setup(&channel).await,
+            SetupStatus::FullySetup
+        );
+
+        workspace.remove_repo(&channel).await.unwrap();
+        assert_eq!(
+            workspace.is_channel_setup(&channel).await,
+            SetupStatus::PromptOnly
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_setup_channels() {
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = Workspace::new(dir.path().to_path_buf());
+        assert!(workspace.list_setup_channels().await.unwrap().is_empty());
+
+        for channel in ["C2", "C1"] {
+            let prompt = workspace.system_prompt_path(&ChannelId::new(channel));
+            fs::create_dir_all(prompt.parent().unwrap()).await.unwrap();
+            fs::write(&prompt, "# Prompt").await.unwrap();
+        }
+        // A system dir without a prompt, and one that isn't a channel id
+        fs::create_dir_all(workspace.system_dir(&ChannelId::new("C3")))
+            .await
+            .unwrap();
+        fs::create_dir_all(dir.path().join("system").join("notes"))
+            .await
+            .unwrap();
+
+        let channels = workspace.list_setup_channels().await.unwrap();
+        assert_eq!(channels, vec![ChannelId::new("C1"), ChannelId::new("C2")]);
+    }
+
+    #[test]
+    fn test_setup_status_describes_what_is_missing() {
+        assert!(SetupStatus::FullySetup.is_ready());
+        assert_eq!(SetupStatus::FullySetup.missing(), None);
+
+        assert!(SetupStatus::RepoOnly.is_partial());
+        assert!(
+            SetupStatus::RepoOnly
+                .missing()
+                .unwrap()
+                .contains("system prompt is missing")
+        );
+
+        assert!(SetupStatus::PromptOnly.is_partial());
+        assert!(
+            SetupStatus::PromptOnly
+                .missing()
+                .unwrap()
+                .contains("clone is missing")
+        );
+
+        assert!(!SetupStatus::NotSetup.is_ready());
+        assert!(!SetupStatus::NotSetup.is_partial());
+
+        let channel = ChannelId::new("C123");
+        assert!(SetupStatus::FullySetup.ensure_ready(&channel).is_ok());
+        assert!(matches!(
+            SetupStatus::NotSetup.ensure_ready(&channel),
+            Err(SlackCoderError::ChannelNotSetup(_))
+        ));
+        assert!(matches!(
+            SetupStatus::RepoOnly.ensure_ready(&channel),
+            Err(SlackCoderError::IncompleteSetup { .. })
+        ));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(2048), "2.0 KB");
+        assert_eq!(format_bytes(5 * 1024 * 1024), "5.0 MB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 * 1024), "3.0 GB");
+    }
+}
@@ -0,0 +1,243 @@
+//! Process-wide counters exported in the Prometheus text format
+//!
+//! Hot paths bump the counters through [`metrics()`]; gauges that already have a
+//! source of truth (live agents, metadata cache stats) are read at scrape time.
+
+use crate::metadata::CacheStats;
+use crate::slack::UsageMetrics;
+use std::fmt::Write;
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::default);
+
+/// The process-wide metrics registry
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+/// Category of a failed Slack API call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlackErrorKind {
+    RateLimited,
+    Auth,
+    Api,
+}
+
+/// Counters updated by the message pipeline and the Slack client
+#[derive(Debug, Default)]
+pub struct Metrics {
+    queries: AtomicU64,
+    input_tokens: AtomicU64,
+    output_tokens: AtomicU64,
+    /// Cost in millionths of a USD, so it can live in an integer atomic
+    cost_micro_usd: AtomicU64,
+    slack_rate_limited: AtomicU64,
+    slack_auth_errors: AtomicU64,
+    slack_api_errors: AtomicU64,
+    socket_reconnects: AtomicU64,
+}
+
+impl Metrics {
+    /// Record a completed agent query
+    pub fn record_query(&self, usage: &UsageMetrics) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        self.input_tokens
+            .fetch_add(usage.input_tokens, Ordering::Relaxed);
+        self.output_tokens
+            .fetch_add(usage.output_tokens, Ordering::Relaxed);
+        if let Some(cost) = usage.cost_usd {
+            let micro_usd = (cost * 1_000_000.0).round().max(0.0) as u64;
+            self.cost_micro_usd.fetch_add(micro_usd, Ordering::Relaxed);
+        }
+    }
+
+    /// Record a failed Slack API call
+    pub fn record_slack_error(&self, kind: SlackErrorKind) {
+        let counter = match kind {
+            SlackErrorKind::RateLimited => &self.slack_rate_limited,
+            SlackErrorKind::Auth => &self.slack_auth_errors,
+            SlackErrorKind::Api => &self.slack_api_errors,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Record a Socket Mode connection reopened after the initial ones
+    pub fn record_reconnect(&self) {
+        self.socket_reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    pub fn render(&self, active_agents: usize, cache: &CacheStats) -> String {
+        let mut out = String::new();
+
+        write_metric(
+            &mut out,
+            "slack_coder_queries_total",
+            "Agent queries that produced a result",
+            "counter",
+            &[("", self.queries.load(Ordering::Relaxed) as f64)],
+        );
+        write_metric(
+            &mut out,
+            "slack_coder_tokens_total",
+            "Tokens used by agent queries",
+            "counter",
+            &[
+                (
+                    "direction=\"input\"",
+                    self.input_tokens.load(Ordering::Relaxed) as f64,
+                ),
+                (
+                    "direction=\"output\"",
+                    self.output_tokens.load(Ordering::Relaxed) as f64,
+                ),
+            ],
+        );
+        write_metric(
+            &mut out,
+            "slack_coder_cost_usd_total",
+            "Cost of agent queries in USD",
+            "counter",
+            &[(
+                "",
+                self.cost_micro_usd.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+            )],
+        );
+        write_metric(
+            &mut out,
+            "slack_coder_active_agents",
+            "Live repository agents",
+            "gauge",
+            &[("", active_agents as f64)],
+        );
+        write_metric(
+            &mut out,
+            "slack_coder_cache_hits_total",
+            "Metadata cache hits",
+            "counter",
+            &[
+                ("cache=\"channel\"", cache.channel_hits as f64),
+                ("cache=\"user\"", cache.user_hits as f64),
+                ("cache=\"negative\"", cache.negative_hits as f64),
+            ],
+        );
+        write_metric(
+            &mut out,
+            "slack_coder_cache_misses_total",
+            "Metadata cache misses",
+            "counter",
+            &[
+                ("cache=\"channel\"", cache.channel_misses as f64),
+                ("cache=\"user\"", cache.user_misses as f64),
+            ],
+        );
+        write_metric(
+            &mut out,
+            "slack_coder_cache_hit_ratio",
+            "Metadata cache hit ratio (0-1)",
+            "gauge",
+            &[
+                (
+                    "cache=\"channel\"",
+                    hit_ratio(cache.channel_hits, cache.channel_misses),
+                ),
+                (
+                    "cache=\"user\"",
+                    hit_ratio(cache.user_hits, cache.user_misses),
+                ),
+            ],
+        );
+        write_metric(
+            &mut out,
+            "slack_coder_slack_api_errors_total",
+            "Failed Slack API calls",
+            "counter",
+            &[
+                (
+                    "kind=\"rate_limited\"",
+                    self.slack_rate_limited.load(Ordering::Relaxed) as f64,
+                ),
+                (
+                    "kind=\"auth\"",
+                    self.slack_auth_errors.load(Ordering::Relaxed) as f64,
+                ),
+                (
+                    "kind=\"api\"",
+                    self.slack_api_errors.load(Ordering::Relaxed) as f64,
+                ),
+            ],
+        );
+        write_metric(
+            &mut out,
+            "slack_coder_socket_reconnects_total",
+            "Socket Mode reconnections after startup",
+            "counter",
+            &[("", self.socket_reconnects.load(Ordering::Relaxed) as f64)],
+        );
+
+        out
+    }
+}
+
+/// Append one metric family; each sample is `(labels, value)`
+fn write_metric(out: &mut String, name: &str, help: &str, kind: &str, samples: &[(&str, f64)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(out, "{} {}", name, value);
+        } else {
+            let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+        }
+    }
+}
+
+fn hit_ratio(hits: u64, misses: u64) -> f64 {
+    if hits + misses > 0 {
+        hits as f64 / (hits + misses) as f64
+    } else {
+        0.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_contains_expected_metrics() {
+        let metrics = Metrics::default();
+        metrics.record_query(&UsageMetrics {
+            input_tokens: 100,
+            output_tokens: 50,
+            cost_usd: Some(0.0125),
+            ..UsageMetrics::default()
+        });
+        metrics.record_slack_error(SlackErrorKind::RateLimited);
+        metrics.record_reconnect();
+
+        let cache = CacheStats {
+            channel_hits: 3,
+            channel_misses: 1,
+            ..CacheStats::default()
+        };
+        let output = metrics.render(2, &cache);
+
+        for name in [
+            "slack_coder_queries_total 1",
+            "slack_coder_tokens_total{direction=\"input\"} 100",
+            "slack_coder_tokens_total{direction=\"output\"} 50",
+            "slack_coder_cost_usd_total 0.0125",
+            "slack_coder_active_agents 2",
+            "slack_coder_cache_hits_total{cache=\"channel\"} 3",
+            "slack_coder_cache_misses_total{cache=\"channel\"} 1",
+            "slack_coder_cache_hit_ratio{cache=\"channel\"} 0.75",
+            "slack_coder_slack_api_errors_total{kind=\"rate_limited\"} 1",
+            "slack_coder_socket_reconnects_total 1",
+            "# TYPE slack_coder_active_agents gauge",
+        ] {
+            assert!(output.contains(name), "missing `{}` in:\n{}", name, output);
+        }
+    }
+}